@@ -0,0 +1,358 @@
+use std::{
+    ffi::OsStr,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex, MutexGuard, TryLockError,
+    },
+    time::{Duration, Instant},
+};
+
+use tauri::State;
+
+use crate::conversion::{self, ConversionTasks, RunError};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// How long a shutdown waits for the server to exit by itself.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+// What a bridge without `--server` exits with after printing its usage.
+const USAGE_EXIT_CODE: i32 = 2;
+// How many servers may run side by side.
+const SERVER_POOL_SIZE: usize = 4;
+
+/// CodeWalker bridges started with `--server` and reused by every parse, so
+/// .NET startup and index loading happen once per server instead of once
+/// per file. Each server answers one request at a time, so concurrent parses
+/// (batch workers, watcher auto-parses) each take a free server from a
+/// small pool, starting it if need be; when all are busy the parse runs the
+/// bridge one-shot instead of queueing. A request that runs past its timeout
+/// kills only its own server, and the next request on that slot starts a
+/// fresh one.
+#[derive(Default)]
+pub struct BridgeProcess {
+    servers: [Mutex<Option<Server>>; SERVER_POOL_SIZE],
+    next_id: AtomicU64,
+    // Set once a bridge turns out not to know `--server` (an older build), so
+    // later parses go straight to one-shot runs.
+    unsupported: AtomicBool,
+}
+
+struct Server {
+    exe: PathBuf,
+    child: Arc<Mutex<Child>>,
+    stdin: ChildStdin,
+    lines: mpsc::Receiver<String>,
+    answered: bool,
+}
+
+impl Server {
+    fn spawn(exe: &Path) -> Result<Self, String> {
+        let mut child = Command::new(exe)
+            .arg("--server")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start CodeWalker bridge server: {e}"))?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| "CodeWalker bridge server has no stdin.".to_string())?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| "CodeWalker bridge server has no stdout.".to_string())?;
+
+        // Responses arrive on a channel so waiting for one can time out.
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            exe: exe.to_path_buf(),
+            child: Arc::new(Mutex::new(child)),
+            stdin,
+            lines,
+            answered: false,
+        })
+    }
+
+    fn is_alive(&self) -> bool {
+        self.child
+            .lock()
+            .map(|mut child| matches!(child.try_wait(), Ok(None)))
+            .unwrap_or(false)
+    }
+
+    fn exit_code(&self) -> Option<i32> {
+        let mut child = self.child.lock().ok()?;
+        child.try_wait().ok().flatten()?.code()
+    }
+
+    fn kill(&self) {
+        if let Ok(mut child) = self.child.lock() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Reply {
+    id: Option<u64>,
+    exit_code: i32,
+    #[serde(default)]
+    stdout: String,
+    #[serde(default)]
+    stderr: String,
+}
+
+/// What one bridge request printed, from the server or a one-shot run.
+pub struct BridgeOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+pub enum ServerError {
+    /// No usable server; run the bridge one-shot instead.
+    Unavailable,
+    Run(RunError),
+}
+
+/// The server's `parse` request for one bridge command line. JSON can only
+/// carry Unicode, so this is `None` for an argument that isn't; such a path
+/// goes to a one-shot run, which passes it through untouched.
+fn parse_request(id: u64, args: &[&OsStr]) -> Option<serde_json::Value> {
+    let args = args
+        .iter()
+        .map(|arg| arg.to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(serde_json::json!({
+        "id": id,
+        "op": "parse",
+        "args": args,
+    }))
+}
+
+impl BridgeProcess {
+    /// Run one bridge command line (`args`, without `--server`) on a free
+    /// server for `exe`, registered under `key` for cancellation.
+    pub fn request(
+        &self,
+        exe: &Path,
+        args: &[&OsStr],
+        key: &str,
+        tasks: &ConversionTasks,
+        timeout: Duration,
+        cleanup: &[PathBuf],
+    ) -> Result<BridgeOutput, ServerError> {
+        if self.unsupported.load(Ordering::SeqCst) {
+            return Err(ServerError::Unavailable);
+        }
+
+        let Some(mut slot) = self.free_slot() else {
+            return Err(ServerError::Unavailable);
+        };
+        if slot
+            .as_ref()
+            .is_some_and(|server| server.exe != exe || !server.is_alive())
+        {
+            if let Some(server) = slot.take() {
+                server.kill();
+            }
+        }
+        if slot.is_none() {
+            *slot = Some(Server::spawn(exe).map_err(|_| ServerError::Unavailable)?);
+        }
+        let Some(server) = slot.as_mut() else {
+            return Err(ServerError::Unavailable);
+        };
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let Some(request) = parse_request(id, args) else {
+            return Err(ServerError::Unavailable);
+        };
+        if writeln!(server.stdin, "{request}")
+            .and_then(|_| server.stdin.flush())
+            .is_err()
+        {
+            server.kill();
+            *slot = None;
+            return Err(ServerError::Unavailable);
+        }
+
+        let cancelled = tasks.track(key, server.child.clone());
+        let started = Instant::now();
+        let result = loop {
+            match server.lines.recv_timeout(POLL_INTERVAL) {
+                Ok(line) => match serde_json::from_str::<Reply>(&line) {
+                    Ok(reply) if reply.id == Some(id) => break Ok(reply),
+                    _ => continue,
+                },
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if cancelled.load(Ordering::SeqCst) {
+                        break Err(ServerError::Run(RunError::Cancelled));
+                    }
+                    if started.elapsed() >= timeout {
+                        break Err(ServerError::Run(RunError::TimedOut(timeout)));
+                    }
+                }
+                // Cancelling kills the server, which closes its stdout.
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    if cancelled.load(Ordering::SeqCst) {
+                        break Err(ServerError::Run(RunError::Cancelled));
+                    }
+                    break Err(ServerError::Unavailable);
+                }
+            }
+        };
+        tasks.untrack(key, &server.child);
+
+        match result {
+            Ok(reply) => {
+                server.answered = true;
+                Ok(BridgeOutput {
+                    success: reply.exit_code == 0,
+                    stdout: reply.stdout,
+                    stderr: reply.stderr,
+                })
+            }
+            Err(error) => {
+                // A server that never answered and exited with the usage code
+                // doesn't know the protocol.
+                if matches!(error, ServerError::Unavailable)
+                    && !server.answered
+                    && server.exit_code() == Some(USAGE_EXIT_CODE)
+                {
+                    self.unsupported.store(true, Ordering::SeqCst);
+                }
+                server.kill();
+                *slot = None;
+                match &error {
+                    ServerError::Run(RunError::Cancelled) => {
+                        for path in cleanup {
+                            conversion::remove_partial_output(path);
+                        }
+                    }
+                    ServerError::Run(RunError::TimedOut(timeout)) => {
+                        tasks.note_timeout(key, *timeout);
+                    }
+                    _ => {}
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Ask the servers to exit, killing any that don't promptly. Waits for
+    /// requests in flight to finish first.
+    pub fn shutdown(&self) {
+        let mut servers: Vec<Server> = self
+            .servers
+            .iter()
+            .filter_map(|slot| {
+                slot.lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .take()
+            })
+            .collect();
+        for server in &mut servers {
+            let _ = writeln!(server.stdin, "{}", serde_json::json!({ "op": "shutdown" }));
+            let _ = server.stdin.flush();
+        }
+        let deadline = Instant::now() + SHUTDOWN_GRACE;
+        while servers.iter().any(Server::is_alive) && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        for server in servers {
+            server.kill();
+        }
+    }
+
+    /// A slot no other request is using, preferring one whose server is
+    /// already running. `None` when every slot is busy.
+    fn free_slot(&self) -> Option<MutexGuard<'_, Option<Server>>> {
+        let mut empty = None;
+        for slot in &self.servers {
+            let guard = match slot.try_lock() {
+                Ok(guard) => guard,
+                Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                Err(TryLockError::WouldBlock) => continue,
+            };
+            if guard.is_some() {
+                return Some(guard);
+            }
+            empty.get_or_insert(guard);
+        }
+        empty
+    }
+}
+
+/// Stop the persistent CodeWalker bridges, e.g. before the app exits. The
+/// next parse starts one again.
+#[tauri::command]
+pub fn shutdown_bridge(bridge: State<'_, BridgeProcess>) {
+    bridge.shutdown();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_ascii_paths_reach_the_server_intact() {
+        let input = Path::new("/home/тест 車/mods/адер.yft");
+        let output = Path::new("/home/тест 車/cache/ab12/model.clmesh");
+        let mut command = Command::new("CodeWalkerBridge");
+        command
+            .arg("--input")
+            .arg(input)
+            .arg("--output")
+            .arg(output)
+            .arg("--type")
+            .arg("yft");
+        let args: Vec<&OsStr> = command.get_args().collect();
+
+        let request = parse_request(7, &args).unwrap();
+        let line = request.to_string();
+        let sent: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(sent["id"], 7);
+        assert_eq!(sent["op"], "parse");
+        let sent: Vec<&str> = sent["args"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|arg| arg.as_str().unwrap())
+            .collect();
+        assert_eq!(
+            sent,
+            [
+                "--input",
+                "/home/тест 車/mods/адер.yft",
+                "--output",
+                "/home/тест 車/cache/ab12/model.clmesh",
+                "--type",
+                "yft",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_unicode_paths_skip_the_server() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let input = OsStr::from_bytes(b"/home/\xff/model.yft");
+        assert!(parse_request(1, &[OsStr::new("--input"), input]).is_none());
+    }
+}
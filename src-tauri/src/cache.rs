@@ -0,0 +1,585 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use tauri::{Manager, State};
+
+use crate::conversion::ConversionTasks;
+
+const DEFAULT_CACHE_MAX_BYTES: u64 = 2 * 1024 * 1024 * 1024; // 2 GB
+
+// Written on every cache hit so eviction goes by last use rather than by
+// when the entry was produced.
+const LAST_USED_FILE: &str = ".last_used";
+// Path of the input a conversion came from, so a later export can name the
+// files after it.
+const SOURCE_FILE: &str = "source.txt";
+// A PNG still being written is renamed into place within seconds; a partial
+// this old was left by a crash.
+const STALE_PARTIAL_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Root of the per-key YFT cache (`<app cache>/cortex-labs/yft-cache`).
+pub fn yft_cache_root(app: &crate::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve cache dir: {e}"))?
+        .join("cortex-labs")
+        .join("yft-cache"))
+}
+
+/// Scratch space for inputs that only exist in memory
+/// (`<app cache>/cortex-labs/yft-work`), kept out of the cache root so
+/// eviction never sees it.
+pub fn yft_work_root(app: &crate::AppHandle) -> Result<PathBuf, String> {
+    Ok(yft_cache_root(app)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+        .join("yft-work"))
+}
+
+/// Root of the decoded PDN cache (`<app cache>/cortex-labs/pdn-cache`),
+/// one `<key>.png` per file. Its PNGs count towards the YFT cache's budget
+/// and are evicted alongside its entries.
+pub fn pdn_cache_root(app: &crate::AppHandle) -> Result<PathBuf, String> {
+    Ok(yft_cache_root(app)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+        .join("pdn-cache"))
+}
+
+/// How a YFT's cache key is derived.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheKeyMode {
+    /// Path, mtime and size: free to compute, but a copy or an mtime bump
+    /// means a fresh conversion.
+    #[default]
+    Fast,
+    /// The file's bytes, so identical files share one entry wherever they
+    /// live.
+    Content,
+}
+
+impl CacheKeyMode {
+    pub fn parse(mode: Option<&str>) -> Result<Self, String> {
+        match mode.map(str::trim) {
+            None | Some("") | Some("fast") => Ok(Self::Fast),
+            Some("content") => Ok(Self::Content),
+            Some(other) => Err(format!(
+                "Unknown key_mode `{other}` (expected `fast` or `content`)"
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Fast => "fast",
+            Self::Content => "content",
+        }
+    }
+}
+
+/// Cache key for a YFT. In `Content` mode the file is hashed in 1 MB blocks
+/// so large inputs are never loaded whole.
+pub fn yft_cache_key(path: &str, mode: CacheKeyMode) -> Result<String, String> {
+    use std::hash::Hasher;
+    use std::io::Read;
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    match mode {
+        CacheKeyMode::Fast => {
+            let meta = std::fs::metadata(crate::paths::long(Path::new(path)))
+                .map_err(|e| format!("Failed to stat input: {e}"))?;
+            let mtime = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            hasher.write(path.as_bytes());
+            hasher.write_u64(mtime);
+            hasher.write_u64(meta.len());
+        }
+        CacheKeyMode::Content => {
+            let mut file = std::fs::File::open(crate::paths::long(Path::new(path)))
+                .map_err(|e| format!("Failed to open input: {e}"))?;
+            let mut buf = vec![0u8; 1024 * 1024];
+            hasher.write(b"content");
+            loop {
+                let read = file
+                    .read(&mut buf)
+                    .map_err(|e| format!("Failed to hash input: {e}"))?;
+                if read == 0 {
+                    break;
+                }
+                hasher.write(&buf[..read]);
+            }
+        }
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// Key for a variant of the entry under `key`, e.g. the same model parsed
+/// against a different texture dictionary.
+pub fn variant_key(key: &str, variant: &str) -> String {
+    use std::hash::Hasher;
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    hasher.write(key.as_bytes());
+    hasher.write(variant.as_bytes());
+    format!("{:016x}", hasher.finish())
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheSettings {
+    #[serde(default = "default_cache_max_bytes")]
+    max_bytes: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_CACHE_MAX_BYTES,
+        }
+    }
+}
+
+fn default_cache_max_bytes() -> u64 {
+    DEFAULT_CACHE_MAX_BYTES
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheUsage {
+    used_bytes: u64,
+    max_bytes: u64,
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearCacheReport {
+    removed_entries: u64,
+    freed_bytes: u64,
+    remaining_bytes: u64,
+    skipped: Vec<SkippedCacheEntry>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedConversion {
+    dff_path: String,
+    txd_path: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkippedCacheEntry {
+    key: String,
+    reason: String,
+}
+
+/// Total size of every file under `path`.
+pub fn dir_size(path: &Path) -> u64 {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// When a cache entry was last written, going by the newest of the directory
+/// itself and the files directly inside it (or, for a cached PNG, the file).
+fn entry_modified(path: &Path) -> Option<SystemTime> {
+    let own = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let files = std::fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().and_then(|m| m.modified()).ok());
+    own.into_iter().chain(files).max()
+}
+
+/// Everything the budget covers, as `(key, path)`: YFT entry directories
+/// and cached PDN PNGs. Stale partial PNGs are swept on the way.
+fn cache_entries(app: &crate::AppHandle) -> Vec<(String, PathBuf)> {
+    if let Ok(root) = pdn_cache_root(app) {
+        sweep_partials(&root, STALE_PARTIAL_AGE);
+    }
+    let mut entries = Vec::new();
+    let roots = [
+        (yft_cache_root(app), None),
+        (pdn_cache_root(app), Some("png")),
+    ];
+    for (root, extension) in roots {
+        let Ok(read) = root.and_then(|root| std::fs::read_dir(root).map_err(|e| e.to_string()))
+        else {
+            continue;
+        };
+        for entry in read.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let key = match extension {
+                None if path.is_dir() => entry.file_name().to_string_lossy().to_string(),
+                Some(extension)
+                    if path.is_file()
+                        && path.extension().and_then(|e| e.to_str()) == Some(extension) =>
+                {
+                    path.file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string()
+                }
+                _ => continue,
+            };
+            entries.push((key, path));
+        }
+    }
+    entries
+}
+
+/// Delete the temp files of PNG writes under `root` that were last touched
+/// more than `max_age` ago, i.e. that a crash left behind.
+fn sweep_partials(root: &Path, max_age: Duration) {
+    let Ok(read) = std::fs::read_dir(root) else {
+        return;
+    };
+    for entry in read.filter_map(|entry| entry.ok()) {
+        if !entry
+            .file_name()
+            .to_string_lossy()
+            .ends_with(crate::images::PARTIAL_SUFFIX)
+        {
+            continue;
+        }
+        let stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if stale {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Size of a cache entry directory or cached file.
+fn entry_size(path: &Path) -> u64 {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.is_file() => meta.len(),
+        _ => dir_size(path),
+    }
+}
+
+fn cache_settings_path(app: &crate::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve config dir: {e}"))?
+        .join("cortex-labs")
+        .join("cache.json"))
+}
+
+/// A missing or unreadable settings file falls back to the defaults.
+fn read_cache_settings(app: &crate::AppHandle) -> CacheSettings {
+    cache_settings_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn last_used_ms(path: &Path) -> u64 {
+    std::fs::read_to_string(path.join(LAST_USED_FILE))
+        .ok()
+        .and_then(|contents| contents.trim().parse().ok())
+        .or_else(|| {
+            entry_modified(path)
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64)
+        })
+        .unwrap_or(0)
+}
+
+/// Mark the entry for `key` as just used, then evict the least recently used
+/// entries until the cache is back under its budget. The entry for `key`
+/// itself is never evicted, nor is anything still being converted.
+pub fn record_use(app: &crate::AppHandle, key: &str) {
+    let cache_root = match yft_cache_root(app) {
+        Ok(root) => root,
+        Err(_) => return,
+    };
+    let entry_dir = cache_root.join(key);
+    if entry_dir.is_dir() {
+        let _ = std::fs::write(entry_dir.join(LAST_USED_FILE), now_ms().to_string());
+    }
+    evict(app, &entry_dir);
+}
+
+/// `record_use` for a cached PDN PNG, whose modification time is its last
+/// use.
+pub fn record_pdn_use(app: &crate::AppHandle, png: &Path) {
+    if let Ok(file) = std::fs::File::options()
+        .write(true)
+        .open(crate::paths::long(png))
+    {
+        let _ = file.set_modified(SystemTime::now());
+    }
+    evict(app, png);
+}
+
+/// Evict the least recently used entries, other than `keep`, until the
+/// cache is back under its budget.
+fn evict(app: &crate::AppHandle, keep: &Path) {
+    let max_bytes = read_cache_settings(app).max_bytes;
+    let tasks = app.state::<ConversionTasks>();
+    let mut entries: Vec<(String, PathBuf, u64, u64)> = cache_entries(app)
+        .into_iter()
+        .map(|(key, path)| {
+            let size = entry_size(&path);
+            let used = last_used_ms(&path);
+            (key, path, size, used)
+        })
+        .collect();
+
+    let mut total: u64 = entries.iter().map(|(_, _, size, _)| size).sum();
+    if total <= max_bytes {
+        return;
+    }
+
+    entries.sort_by_key(|(_, _, _, used)| *used);
+    for (entry_key, path, size, _) in entries {
+        if total <= max_bytes {
+            break;
+        }
+        if path == keep || tasks.is_running(&entry_key) {
+            continue;
+        }
+        if remove_cache_entry(&path).is_ok() {
+            total = total.saturating_sub(size);
+        }
+    }
+}
+
+/// Delete a whole cache entry. It is renamed aside first: on Windows that
+/// fails while any file inside is still open, so an entry in use is left
+/// intact instead of being half deleted.
+fn remove_cache_entry(path: &Path) -> Result<(), String> {
+    let doomed = path.with_extension("deleting");
+    std::fs::rename(path, &doomed).map_err(|e| {
+        // ERROR_SHARING_VIOLATION
+        let sharing_violation = cfg!(windows) && e.raw_os_error() == Some(32);
+        if e.kind() == std::io::ErrorKind::PermissionDenied || sharing_violation {
+            format!("in use: {e}")
+        } else {
+            e.to_string()
+        }
+    })?;
+    if doomed.is_dir() {
+        std::fs::remove_dir_all(&doomed).map_err(|e| e.to_string())
+    } else {
+        std::fs::remove_file(&doomed).map_err(|e| e.to_string())
+    }
+}
+
+pub fn record_source(entry_dir: &Path, input: &str) {
+    let _ = std::fs::write(entry_dir.join(SOURCE_FILE), input);
+}
+
+/// Copy a conversion's `model.dff`/`model.txd` into `export_dir` as
+/// `<stem>.dff`/`<stem>.txd`. Nothing is copied if a target already exists
+/// and `overwrite` is off.
+pub fn export_conversion(
+    entry_dir: &Path,
+    stem: &str,
+    export_dir: &Path,
+    overwrite: bool,
+) -> Result<ExportedConversion, String> {
+    let dff = entry_dir.join("model.dff");
+    let txd = entry_dir.join("model.txd");
+    if !dff.is_file() {
+        return Err("No converted .dff in this cache entry.".to_string());
+    }
+
+    std::fs::create_dir_all(crate::paths::long(export_dir))
+        .map_err(|e| format!("Failed to create export dir: {e}"))?;
+    let dff_target = export_dir.join(format!("{stem}.dff"));
+    let txd_target = txd
+        .is_file()
+        .then(|| export_dir.join(format!("{stem}.txd")));
+
+    if !overwrite {
+        for target in std::iter::once(&dff_target).chain(txd_target.as_ref()) {
+            if target.exists() {
+                return Err(format!(
+                    "`{}` already exists; pass overwrite to replace it.",
+                    target.to_string_lossy()
+                ));
+            }
+        }
+    }
+
+    std::fs::copy(crate::paths::long(&dff), crate::paths::long(&dff_target))
+        .map_err(|e| format!("Failed to export .dff: {e}"))?;
+    if let Some(txd_target) = &txd_target {
+        std::fs::copy(crate::paths::long(&txd), crate::paths::long(txd_target))
+            .map_err(|e| format!("Failed to export .txd: {e}"))?;
+    }
+
+    Ok(ExportedConversion {
+        dff_path: dff_target.to_string_lossy().to_string(),
+        txd_path: txd_target.map(|path| path.to_string_lossy().to_string()),
+    })
+}
+
+/// Export an already cached conversion, named after the file it was
+/// converted from (`model` if that isn't recorded).
+#[tauri::command]
+pub fn export_cached_conversion(
+    cache_key: String,
+    export_dir: String,
+    overwrite: Option<bool>,
+    app: crate::AppHandle,
+) -> Result<ExportedConversion, String> {
+    if cache_key.is_empty() || cache_key.contains(['/', '\\', '.']) {
+        return Err(format!("Invalid cache key `{cache_key}`"));
+    }
+    if export_dir.trim().is_empty() {
+        return Err("Export dir is empty".to_string());
+    }
+
+    let entry_dir = yft_cache_root(&app)?.join(&cache_key);
+    if !entry_dir.is_dir() {
+        return Err(format!("No cache entry for `{cache_key}`"));
+    }
+    let stem = std::fs::read_to_string(entry_dir.join(SOURCE_FILE))
+        .ok()
+        .and_then(|source| {
+            Path::new(source.trim())
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "model".to_string());
+
+    export_conversion(
+        &entry_dir,
+        &stem,
+        Path::new(&export_dir),
+        overwrite.unwrap_or(false),
+    )
+}
+
+/// Current cache size (YFT entries and cached PDNs) and the configured
+/// budget, for the settings gauge.
+#[tauri::command]
+pub fn get_cache_usage(app: crate::AppHandle) -> Result<CacheUsage, String> {
+    Ok(CacheUsage {
+        used_bytes: dir_size(&yft_cache_root(&app)?) + dir_size(&pdn_cache_root(&app)?),
+        max_bytes: read_cache_settings(&app).max_bytes,
+    })
+}
+
+#[tauri::command]
+pub fn set_cache_max_bytes(max_bytes: u64, app: crate::AppHandle) -> Result<(), String> {
+    let settings_path = cache_settings_path(&app)?;
+    if let Some(parent) = settings_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir: {e}"))?;
+    }
+    let json =
+        serde_json::to_string_pretty(&CacheSettings { max_bytes }).map_err(|e| e.to_string())?;
+    std::fs::write(&settings_path, json).map_err(|e| format!("Failed to write cache settings: {e}"))
+}
+
+/// Delete YFT cache entries and cached PDNs, either all of them or only
+/// those untouched for `older_than_days`. Entries that are being converted right now, are open
+/// elsewhere or can't be removed are skipped and listed in the report.
+#[tauri::command]
+pub fn clear_yft_cache(
+    older_than_days: Option<u32>,
+    app: crate::AppHandle,
+    tasks: State<ConversionTasks>,
+) -> Result<ClearCacheReport, String> {
+    let mut report = ClearCacheReport::default();
+    let cutoff = older_than_days.map(|days| {
+        SystemTime::now()
+            .checked_sub(Duration::from_secs(u64::from(days) * 24 * 60 * 60))
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    });
+
+    for (key, path) in cache_entries(&app) {
+        let size = entry_size(&path);
+
+        let expired = match cutoff {
+            Some(cutoff) => entry_modified(&path).is_some_and(|modified| modified < cutoff),
+            None => true,
+        };
+        if !expired {
+            report.remaining_bytes += size;
+            continue;
+        }
+
+        if tasks.is_running(&key) {
+            report.remaining_bytes += size;
+            report.skipped.push(SkippedCacheEntry {
+                key,
+                reason: "conversion in progress".to_string(),
+            });
+            continue;
+        }
+
+        match remove_cache_entry(&path) {
+            Ok(()) => {
+                report.removed_entries += 1;
+                report.freed_bytes += size;
+            }
+            Err(reason) => {
+                report.remaining_bytes += entry_size(&path);
+                report.skipped.push(SkippedCacheEntry { key, reason });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweeps_only_stale_partials() {
+        let dir = tempfile::tempdir().unwrap();
+        let touch = |name: &str, age: Duration| {
+            let path = dir.path().join(name);
+            let file = std::fs::File::create(&path).unwrap();
+            file.set_modified(SystemTime::now() - age).unwrap();
+            path
+        };
+        let stale = touch("a.png.x1y2z3.partial", STALE_PARTIAL_AGE * 2);
+        let writing = touch("b.png.q4r5s6.partial", Duration::ZERO);
+        let old_png = touch("c.png", STALE_PARTIAL_AGE * 2);
+
+        sweep_partials(dir.path(), STALE_PARTIAL_AGE);
+        assert!(!stale.exists());
+        assert!(writing.exists());
+        assert!(old_png.exists());
+    }
+}
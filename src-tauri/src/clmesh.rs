@@ -0,0 +1,463 @@
+use std::path::Path;
+
+use crate::paths;
+
+/// Magic at the start of every `.clmesh` written by the CodeWalker bridge.
+pub const MAGIC: &[u8; 4] = b"CLM1";
+pub const VERSION: u16 = 1;
+
+const FLAG_NORMALS: u8 = 0x1;
+const FLAG_UVS: u8 = 0x2;
+
+/// Check that a `.clmesh` is complete: the magic and version match, and the
+/// counts in every mesh header fit the bytes that follow, ending exactly at
+/// the end of the file. Catches the truncated files an interrupted bridge run
+/// leaves behind without decoding any vertex data.
+pub fn validate(path: &Path) -> Result<(), String> {
+    let data = std::fs::read(path).map_err(|e| format!("Failed to read mesh cache: {e}"))?;
+    validate_bytes(&data)
+}
+
+pub fn validate_bytes(data: &[u8]) -> Result<(), String> {
+    walk(data, |_| {})
+}
+
+/// One mesh header and the positions that follow it.
+struct MeshRecord<'a> {
+    name: &'a [u8],
+    material: &'a [u8],
+    vertex_count: usize,
+    index_count: usize,
+    flags: u8,
+    positions: &'a [u8],
+    /// Positions, normals, UVs and indices, in that order.
+    payload: &'a [u8],
+}
+
+// Check the layout, handing each complete mesh to `on_mesh` as it's reached;
+// on error, the meshes before the bad one have been seen.
+fn walk<'a>(data: &'a [u8], mut on_mesh: impl FnMut(MeshRecord<'a>)) -> Result<(), String> {
+    if data.is_empty() {
+        return Err("Mesh cache is empty.".to_string());
+    }
+    let mut cursor = Cursor { data, offset: 0 };
+    if cursor.take(4)? != MAGIC {
+        return Err("Mesh cache magic mismatch.".to_string());
+    }
+    let version = cursor.u16()?;
+    if version != VERSION {
+        return Err(format!("Unsupported mesh cache version {version}."));
+    }
+
+    let mesh_count = cursor.u16()?;
+    for _ in 0..mesh_count {
+        let name_len = cursor.u16()? as usize;
+        let name = cursor.take(name_len)?;
+        let material_len = cursor.u16()? as usize;
+        let material = cursor.take(material_len)?;
+
+        let vertex_count = cursor.u32()? as usize;
+        let index_count = cursor.u32()? as usize;
+        let flags = cursor.u8()?;
+
+        let mut floats_per_vertex = 3;
+        if flags & FLAG_NORMALS != 0 {
+            floats_per_vertex += 3;
+        }
+        if flags & FLAG_UVS != 0 {
+            floats_per_vertex += 2;
+        }
+        let payload = vertex_count
+            .checked_mul(floats_per_vertex * 4)
+            .and_then(|bytes| bytes.checked_add(index_count.checked_mul(4)?))
+            .ok_or_else(|| format!("Mesh counts overflow at offset {}.", cursor.offset))?;
+        let payload = cursor.take(payload)?;
+        on_mesh(MeshRecord {
+            name,
+            material,
+            vertex_count,
+            index_count,
+            flags,
+            positions: &payload[..vertex_count * 12],
+            payload,
+        });
+    }
+
+    if cursor.offset != data.len() {
+        return Err(format!(
+            "Mesh cache has {} trailing bytes after the last mesh.",
+            data.len() - cursor.offset
+        ));
+    }
+    Ok(())
+}
+
+fn position(p: &[u8]) -> [f32; 3] {
+    let f = |at: usize| f32::from_le_bytes([p[at], p[at + 1], p[at + 2], p[at + 3]]);
+    [f(0), f(4), f(8)]
+}
+
+/// Every position of a valid `.clmesh`, across all its meshes.
+pub fn read_positions(data: &[u8]) -> Result<Vec<[f32; 3]>, String> {
+    let mut positions = Vec::new();
+    walk(data, |mesh| {
+        positions.extend(mesh.positions.chunks_exact(12).map(position));
+    })?;
+    Ok(positions)
+}
+
+/// Every mesh of a valid `.clmesh`.
+pub fn read(data: &[u8]) -> Result<Vec<Mesh>, String> {
+    let floats = |bytes: &[u8]| {
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect::<Vec<_>>()
+    };
+    let mut meshes = Vec::new();
+    walk(data, |mesh| {
+        let count = mesh.vertex_count;
+        let mut rest = &mesh.payload[count * 12..];
+        let mut normals = None;
+        if mesh.flags & FLAG_NORMALS != 0 {
+            normals = Some(floats(&rest[..count * 12]));
+            rest = &rest[count * 12..];
+        }
+        let mut uvs = None;
+        if mesh.flags & FLAG_UVS != 0 {
+            uvs = Some(floats(&rest[..count * 8]));
+            rest = &rest[count * 8..];
+        }
+        meshes.push(Mesh {
+            name: String::from_utf8_lossy(mesh.name).to_string(),
+            material: String::from_utf8_lossy(mesh.material).to_string(),
+            positions: floats(mesh.positions),
+            normals,
+            uvs,
+            colors: None,
+            indices: rest
+                .chunks_exact(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        });
+    })?;
+    Ok(meshes)
+}
+
+/// One mesh as `inspect_clmesh` reports it.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeshInfo {
+    pub name: String,
+    pub material: String,
+    pub vertex_count: usize,
+    pub index_count: usize,
+    pub has_normals: bool,
+    pub has_uvs: bool,
+}
+
+/// Axis-aligned bounds of every position, in the file's own axes.
+#[derive(serde::Serialize, Clone, Copy)]
+pub struct BoundingBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl BoundingBox {
+    pub fn extend(bounds: &mut Option<Self>, [x, y, z]: [f32; 3]) {
+        let b = bounds.get_or_insert(Self {
+            min: [x, y, z],
+            max: [x, y, z],
+        });
+        for (axis, value) in [x, y, z].into_iter().enumerate() {
+            b.min[axis] = b.min[axis].min(value);
+            b.max[axis] = b.max[axis].max(value);
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClmeshInspection {
+    /// Format version from the header, if the file is long enough to have one.
+    pub version: Option<u16>,
+    pub vertex_count: usize,
+    pub index_count: usize,
+    pub meshes: Vec<MeshInfo>,
+    /// Distinct material names, in first-use order.
+    pub materials: Vec<String>,
+    pub bounding_box: Option<BoundingBox>,
+    pub has_normals: bool,
+    pub has_uvs: bool,
+    /// Always false; the format has no color channel yet.
+    pub has_colors: bool,
+    /// Whether every count fits the file, as `validate` checks.
+    pub valid: bool,
+    /// Why the file isn't valid. The other fields cover the meshes before
+    /// the problem.
+    pub error: Option<String>,
+}
+
+/// Read the headers and positions of a `.clmesh`, validating it on the way.
+pub fn inspect_bytes(data: &[u8]) -> ClmeshInspection {
+    let mut inspection = ClmeshInspection {
+        version: data.get(4..6).map(|v| u16::from_le_bytes([v[0], v[1]])),
+        vertex_count: 0,
+        index_count: 0,
+        meshes: Vec::new(),
+        materials: Vec::new(),
+        bounding_box: None,
+        has_normals: false,
+        has_uvs: false,
+        has_colors: false,
+        valid: false,
+        error: None,
+    };
+    let result = walk(data, |mesh| {
+        let material = String::from_utf8_lossy(mesh.material).to_string();
+        if !inspection.materials.contains(&material) {
+            inspection.materials.push(material.clone());
+        }
+        for p in mesh.positions.chunks_exact(12) {
+            BoundingBox::extend(&mut inspection.bounding_box, position(p));
+        }
+        let info = MeshInfo {
+            name: String::from_utf8_lossy(mesh.name).to_string(),
+            material,
+            vertex_count: mesh.vertex_count,
+            index_count: mesh.index_count,
+            has_normals: mesh.flags & FLAG_NORMALS != 0,
+            has_uvs: mesh.flags & FLAG_UVS != 0,
+        };
+        inspection.vertex_count += info.vertex_count;
+        inspection.index_count += info.index_count;
+        inspection.has_normals |= info.has_normals;
+        inspection.has_uvs |= info.has_uvs;
+        inspection.meshes.push(info);
+    });
+    match result {
+        Ok(()) => inspection.valid = true,
+        Err(e) => inspection.error = Some(e),
+    }
+    inspection
+}
+
+/// Inspect a `.clmesh`: format version, vertex and index counts, meshes
+/// and materials, bounding box, which attributes are present, and whether
+/// the file is intact. A damaged file still returns what was readable, with
+/// `valid: false` and the reason in `error`.
+#[tauri::command]
+pub async fn inspect_clmesh(path: String) -> Result<ClmeshInspection, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "clmesh") {
+            return Err("Only .clmesh files are supported by inspect_clmesh".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read mesh cache: {e}"))?;
+        Ok(inspect_bytes(&data))
+    })
+    .await
+    .map_err(|e| format!("Failed to join mesh inspection task: {e}"))?
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| {
+                format!(
+                    "Mesh cache is truncated at offset {} (needs {len} more bytes, file is {}).",
+                    self.offset,
+                    self.data.len()
+                )
+            })?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// One mesh of a `.clmesh`. Positions and normals are 3 floats per vertex,
+/// UVs 2, and indices are triangle lists.
+pub struct Mesh {
+    pub name: String,
+    pub material: String,
+    pub positions: Vec<f32>,
+    pub normals: Option<Vec<f32>>,
+    pub uvs: Option<Vec<f32>>,
+    /// RGBA per vertex. The format has no color channel, so these are only
+    /// carried for exports and never written.
+    pub colors: Option<Vec<u8>>,
+    pub indices: Vec<u32>,
+}
+
+/// Write `meshes` to `path` exactly as the bridge lays them out.
+pub fn write(path: &Path, meshes: &[Mesh]) -> Result<(), String> {
+    let mut data = Vec::new();
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&VERSION.to_le_bytes());
+    let mesh_count = u16::try_from(meshes.len())
+        .map_err(|_| format!("Too many meshes for a mesh cache ({}).", meshes.len()))?;
+    data.extend_from_slice(&mesh_count.to_le_bytes());
+
+    for mesh in meshes {
+        write_string(&mut data, &mesh.name);
+        write_string(&mut data, &mesh.material);
+        let vertex_count = mesh.positions.len() / 3;
+        data.extend_from_slice(&(vertex_count as u32).to_le_bytes());
+        data.extend_from_slice(&(mesh.indices.len() as u32).to_le_bytes());
+
+        let mut flags = 0;
+        if mesh.normals.is_some() {
+            flags |= FLAG_NORMALS;
+        }
+        if mesh.uvs.is_some() {
+            flags |= FLAG_UVS;
+        }
+        data.push(flags);
+
+        let floats = std::iter::once(&mesh.positions)
+            .chain(mesh.normals.as_ref())
+            .chain(mesh.uvs.as_ref())
+            .flatten();
+        for value in floats {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        for index in &mesh.indices {
+            data.extend_from_slice(&index.to_le_bytes());
+        }
+    }
+
+    std::fs::write(path, data).map_err(|e| format!("Failed to write mesh cache: {e}"))
+}
+
+// Length-prefixed UTF-8, cut to what the u16 length can describe.
+fn write_string(data: &mut Vec<u8>, value: &str) {
+    let mut end = value.len().min(u16::MAX as usize);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    data.extend_from_slice(&(end as u16).to_le_bytes());
+    data.extend_from_slice(&value.as_bytes()[..end]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/clmesh")
+            .join(name)
+    }
+
+    fn inspect(path: &Path) -> ClmeshInspection {
+        tauri::async_runtime::block_on(inspect_clmesh(path.to_string_lossy().to_string())).unwrap()
+    }
+
+    #[test]
+    fn inspects_a_valid_file() {
+        let path = fixture("valid.clmesh");
+        assert_eq!(validate(&path), Ok(()));
+
+        let inspection = inspect(&path);
+        assert!(inspection.valid);
+        assert_eq!(inspection.error, None);
+        assert_eq!(inspection.version, Some(VERSION));
+        assert_eq!(inspection.vertex_count, 7);
+        assert_eq!(inspection.index_count, 9);
+        assert_eq!(inspection.materials, ["vehicle_paint", "vehicle_glass"]);
+        let meshes: Vec<_> = inspection
+            .meshes
+            .iter()
+            .map(|m| {
+                (
+                    m.name.as_str(),
+                    m.vertex_count,
+                    m.index_count,
+                    m.has_normals,
+                    m.has_uvs,
+                )
+            })
+            .collect();
+        assert_eq!(
+            meshes,
+            [("body", 4, 6, true, true), ("glass", 3, 3, false, false)]
+        );
+        let bounds = inspection.bounding_box.unwrap();
+        assert_eq!(bounds.min, [-1.0, -2.0, 0.0]);
+        assert_eq!(bounds.max, [1.0, 2.0, 1.5]);
+        assert!(inspection.has_normals && inspection.has_uvs && !inspection.has_colors);
+    }
+
+    #[test]
+    fn inspects_a_truncated_file() {
+        let path = fixture("truncated.clmesh");
+        let error = validate(&path).unwrap_err();
+        assert!(error.contains("truncated"), "{error}");
+
+        let inspection = inspect(&path);
+        assert!(!inspection.valid);
+        assert_eq!(inspection.error, Some(error));
+        assert_eq!(inspection.version, Some(VERSION));
+        // Only the mesh before the damage is reported.
+        assert_eq!(inspection.meshes.len(), 1);
+        assert_eq!(inspection.meshes[0].name, "body");
+        assert_eq!(inspection.vertex_count, 4);
+    }
+
+    #[test]
+    fn inspect_and_validate_agree_on_every_damage() {
+        let data = std::fs::read(fixture("valid.clmesh")).unwrap();
+        let mut damaged: Vec<Vec<u8>> = (0..data.len()).map(|len| data[..len].to_vec()).collect();
+        damaged.push([data.as_slice(), &[0]].concat());
+        for offset in [0, 4, 6] {
+            let mut bad = data.clone();
+            bad[offset] ^= 0xFF;
+            damaged.push(bad);
+        }
+
+        for bytes in &damaged {
+            let error = validate_bytes(bytes).unwrap_err();
+            let inspection = inspect_bytes(bytes);
+            assert!(!inspection.valid, "{} bytes", bytes.len());
+            assert_eq!(inspection.error, Some(error));
+            assert!(read(bytes).is_err());
+        }
+    }
+
+    #[test]
+    fn written_meshes_read_back_byte_for_byte() {
+        let data = std::fs::read(fixture("valid.clmesh")).unwrap();
+        let meshes = read(&data).unwrap();
+        assert_eq!(meshes[0].uvs.as_ref().unwrap()[2..4], [1.0, 0.0]);
+        assert_eq!(meshes[1].indices, [0, 1, 2]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("round-trip.clmesh");
+        write(&path, &meshes).unwrap();
+        let written = std::fs::read(&path).unwrap();
+        assert_eq!(written, data);
+    }
+}
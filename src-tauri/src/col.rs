@@ -0,0 +1,902 @@
+use std::path::Path;
+
+use crate::{
+    cache::{self, CacheKeyMode},
+    clmesh, paths,
+};
+
+// Model header: fourcc, size of the rest of the model, 22-byte name and
+// model id.
+const HEADER_SIZE: usize = 32;
+const NAME_SIZE: usize = 22;
+// COL2+ vertices are fixed point, 1/128 of a unit.
+const VERTEX_SCALE: f32 = 128.0;
+
+/// Bounds of one collision model.
+#[derive(serde::Serialize, Clone, Copy)]
+pub struct ColBounds {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+    pub center: [f32; 3],
+    pub radius: f32,
+}
+
+/// One model of a `.col` archive.
+pub struct ColModel {
+    /// 1 for `COLL`, 2, 3 or 4 for `COL2`/`COL3`/`COL4`.
+    pub version: u8,
+    pub name: String,
+    pub model_id: u16,
+    pub bounds: ColBounds,
+    /// `(center, radius, material)`.
+    pub spheres: Vec<([f32; 3], f32, u8)>,
+    /// `(min, max, material)`.
+    pub boxes: Vec<([f32; 3], [f32; 3], u8)>,
+    pub vertices: Vec<[f32; 3]>,
+    /// `(vertex indices, material)`.
+    pub faces: Vec<([u32; 3], u8)>,
+}
+
+/// Bounds-checked reads within one model, naming the model and section in
+/// errors.
+struct Section<'a> {
+    data: &'a [u8],
+    offset: usize,
+    end: usize,
+    model: &'a str,
+    what: &'static str,
+}
+
+impl<'a> Section<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.end)
+            .ok_or_else(|| {
+                format!(
+                    "COL model `{}`: {} run past the end of the model at offset {:#x}.",
+                    self.model, self.what, self.offset
+                )
+            })?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        let b = self.take(2)?;
+        Ok(i16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        let b = self.take(4)?;
+        Ok(f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn vec3(&mut self) -> Result<[f32; 3], String> {
+        Ok([self.f32()?, self.f32()?, self.f32()?])
+    }
+
+    /// Material of a surface, skipping its flag, brightness and light bytes.
+    fn surface(&mut self) -> Result<u8, String> {
+        let surface = self.take(4)?;
+        Ok(surface[0])
+    }
+}
+
+/// Every model in a COL1/COL2/COL3 (or COL4) archive, in file order.
+pub fn parse(data: &[u8]) -> Result<Vec<ColModel>, String> {
+    let mut models = Vec::new();
+    let mut offset = 0;
+    // Archives are often padded with zeros after the last model.
+    while offset + HEADER_SIZE <= data.len() && data[offset..offset + 4] != [0; 4] {
+        let version = match &data[offset..offset + 4] {
+            b"COLL" => 1,
+            b"COL2" => 2,
+            b"COL3" => 3,
+            b"COL4" => 4,
+            other => {
+                return Err(format!(
+                    "COL model {} at offset {offset:#x} has an unknown header `{}`.",
+                    models.len(),
+                    String::from_utf8_lossy(other)
+                ))
+            }
+        };
+        let size = u32::from_le_bytes([
+            data[offset + 4],
+            data[offset + 5],
+            data[offset + 6],
+            data[offset + 7],
+        ]) as usize;
+        // Sizes and COL2+ offsets count from just past the fourcc.
+        let base = offset + 4;
+        let end = base
+            .checked_add(4 + size)
+            .filter(|end| *end <= data.len())
+            .ok_or_else(|| {
+                format!(
+                    "COL model {} at offset {offset:#x} claims {size} bytes, past the end of the file.",
+                    models.len()
+                )
+            })?;
+        let name = crate::rw::c_string(&data[offset + 8..offset + 8 + NAME_SIZE]);
+        let model_id = u16::from_le_bytes([data[offset + 30], data[offset + 31]]);
+        let model = if version == 1 {
+            parse_col1(data, offset + HEADER_SIZE, end, name, model_id)?
+        } else {
+            parse_col2(
+                data,
+                version,
+                base,
+                offset + HEADER_SIZE,
+                end,
+                name,
+                model_id,
+            )?
+        };
+        models.push(model);
+        offset = end;
+    }
+    if models.is_empty() {
+        return Err("The file has no COL models.".to_string());
+    }
+    Ok(models)
+}
+
+fn parse_col1(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    name: String,
+    model_id: u16,
+) -> Result<ColModel, String> {
+    let section = |offset: usize, what: &'static str| Section {
+        data,
+        offset,
+        end,
+        model: &name,
+        what,
+    };
+    let mut header = section(start, "the bounds");
+    let radius = header.f32()?;
+    let center = header.vec3()?;
+    let min = header.vec3()?;
+    let max = header.vec3()?;
+    let bounds = ColBounds {
+        min,
+        max,
+        center,
+        radius,
+    };
+
+    let mut reader = section(header.offset, "the spheres");
+    let mut spheres = Vec::new();
+    for _ in 0..reader.u32()? {
+        let radius = reader.f32()?;
+        let center = reader.vec3()?;
+        spheres.push((center, radius, reader.surface()?));
+    }
+    reader.what = "the lines";
+    reader.u32()?; // always 0
+    reader.what = "the boxes";
+    let mut boxes = Vec::new();
+    for _ in 0..reader.u32()? {
+        boxes.push((reader.vec3()?, reader.vec3()?, reader.surface()?));
+    }
+    reader.what = "the vertices";
+    let mut vertices = Vec::new();
+    for _ in 0..reader.u32()? {
+        vertices.push(reader.vec3()?);
+    }
+    reader.what = "the faces";
+    let mut faces = Vec::new();
+    for _ in 0..reader.u32()? {
+        let indices = [reader.u32()?, reader.u32()?, reader.u32()?];
+        faces.push((indices, reader.surface()?));
+    }
+    check_faces(&name, &faces, vertices.len())?;
+
+    Ok(ColModel {
+        version: 1,
+        name,
+        model_id,
+        bounds,
+        spheres,
+        boxes,
+        vertices,
+        faces,
+    })
+}
+
+fn parse_col2(
+    data: &[u8],
+    version: u8,
+    base: usize,
+    start: usize,
+    end: usize,
+    name: String,
+    model_id: u16,
+) -> Result<ColModel, String> {
+    let section = |offset: usize, what: &'static str| Section {
+        data,
+        offset,
+        end,
+        model: &name,
+        what,
+    };
+    let mut header = section(start, "the header");
+    let min = header.vec3()?;
+    let max = header.vec3()?;
+    let center = header.vec3()?;
+    let radius = header.f32()?;
+    let bounds = ColBounds {
+        min,
+        max,
+        center,
+        radius,
+    };
+    let sphere_count = header.u16()?;
+    let box_count = header.u16()?;
+    let face_count = header.u16()?;
+    header.u8()?; // line count
+    header.u8()?;
+    header.u32()?; // flags
+    let sphere_offset = header.u32()? as usize;
+    let box_offset = header.u32()? as usize;
+    header.u32()?; // lines
+    let vertex_offset = header.u32()? as usize;
+    let face_offset = header.u32()? as usize;
+    // Triangle planes, then COL3's shadow mesh, aren't needed for previews.
+
+    let mut spheres = Vec::new();
+    if sphere_count > 0 {
+        let mut reader = section(base + sphere_offset, "the spheres");
+        for _ in 0..sphere_count {
+            let center = reader.vec3()?;
+            let radius = reader.f32()?;
+            spheres.push((center, radius, reader.surface()?));
+        }
+    }
+    let mut boxes = Vec::new();
+    if box_count > 0 {
+        let mut reader = section(base + box_offset, "the boxes");
+        for _ in 0..box_count {
+            boxes.push((reader.vec3()?, reader.vec3()?, reader.surface()?));
+        }
+    }
+    let mut faces = Vec::new();
+    if face_count > 0 {
+        let mut reader = section(base + face_offset, "the faces");
+        for _ in 0..face_count {
+            let indices = [reader.u16()?, reader.u16()?, reader.u16()?].map(u32::from);
+            let material = reader.u8()?;
+            reader.u8()?; // light
+            faces.push((indices, material));
+        }
+    }
+    // The vertex count isn't stored; the faces reference all of them.
+    let vertex_count = faces
+        .iter()
+        .flat_map(|(indices, _)| *indices)
+        .max()
+        .map_or(0, |max| max as usize + 1);
+    let mut vertices = Vec::with_capacity(vertex_count);
+    if vertex_count > 0 {
+        let mut reader = section(base + vertex_offset, "the vertices");
+        for _ in 0..vertex_count {
+            let [x, y, z] = [reader.i16()?, reader.i16()?, reader.i16()?];
+            vertices.push([x, y, z].map(|v| f32::from(v) / VERTEX_SCALE));
+        }
+    }
+
+    Ok(ColModel {
+        version,
+        name,
+        model_id,
+        bounds,
+        spheres,
+        boxes,
+        vertices,
+        faces,
+    })
+}
+
+fn check_faces(name: &str, faces: &[([u32; 3], u8)], vertex_count: usize) -> Result<(), String> {
+    match faces
+        .iter()
+        .position(|(indices, _)| indices.iter().any(|index| *index as usize >= vertex_count))
+    {
+        Some(face) => Err(format!(
+            "COL model `{name}`: face {face} references a vertex past the {vertex_count} in the model."
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Faces and boxes as triangle meshes, for wireframe previews. Spheres are
+/// only counted.
+fn to_meshes(model: &ColModel) -> Vec<clmesh::Mesh> {
+    let mut meshes = Vec::new();
+    if !model.faces.is_empty() {
+        meshes.push(clmesh::Mesh {
+            name: format!("{}_faces", model.name),
+            material: String::new(),
+            positions: model.vertices.iter().flatten().copied().collect(),
+            normals: None,
+            uvs: None,
+            colors: None,
+            indices: model
+                .faces
+                .iter()
+                .flat_map(|(indices, _)| *indices)
+                .collect(),
+        });
+    }
+    if !model.boxes.is_empty() {
+        let mut positions = Vec::with_capacity(model.boxes.len() * 24);
+        let mut indices = Vec::with_capacity(model.boxes.len() * 36);
+        for (index, (min, max, _)) in model.boxes.iter().enumerate() {
+            for corner in 0..8 {
+                positions.extend([
+                    if corner & 1 == 0 { min[0] } else { max[0] },
+                    if corner & 2 == 0 { min[1] } else { max[1] },
+                    if corner & 4 == 0 { min[2] } else { max[2] },
+                ]);
+            }
+            let first = index as u32 * 8;
+            // Two triangles per side of the box.
+            for [a, b, c] in [
+                [0, 2, 1],
+                [1, 2, 3],
+                [4, 5, 6],
+                [5, 7, 6],
+                [0, 1, 4],
+                [1, 5, 4],
+                [2, 6, 3],
+                [3, 6, 7],
+                [0, 4, 2],
+                [2, 4, 6],
+                [1, 3, 5],
+                [3, 7, 5],
+            ] {
+                indices.extend([first + a, first + b, first + c]);
+            }
+        }
+        meshes.push(clmesh::Mesh {
+            name: format!("{}_boxes", model.name),
+            material: String::new(),
+            positions,
+            normals: None,
+            uvs: None,
+            colors: None,
+            indices,
+        });
+    }
+    meshes
+}
+
+/// One model of `parse_col`'s result.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColModelInfo {
+    pub name: String,
+    pub version: u8,
+    pub model_id: u16,
+    pub bounds: ColBounds,
+    pub sphere_count: usize,
+    pub box_count: usize,
+    pub face_count: usize,
+    pub vertex_count: usize,
+    /// Cached `.clmesh` of the faces and boxes; null when the model has
+    /// neither.
+    pub mesh_path: Option<String>,
+}
+
+/// Read a GTA SA .col archive (COL1, COL2 or COL3 models, any mix): each
+/// model's name, bounds and sphere, box and face counts. The faces and
+/// boxes of each model are cached as a `.clmesh` for the wireframe
+/// preview, returned as `meshPath`.
+#[tauri::command]
+pub async fn parse_col(path: String, app: crate::AppHandle) -> Result<Vec<ColModelInfo>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "col") {
+            return Err("Only .col files are supported by parse_col".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read COL: {e}"))?;
+        let models = parse(&data)?;
+
+        let key = cache::variant_key(&cache::yft_cache_key(&path, CacheKeyMode::Fast)?, "col");
+        let out_dir = cache::yft_cache_root(&app)?.join(&key);
+        std::fs::create_dir_all(paths::long(&out_dir))
+            .map_err(|e| format!("Failed to create output dir: {e}"))?;
+        let mut infos = Vec::with_capacity(models.len());
+        for (index, model) in models.iter().enumerate() {
+            let meshes = to_meshes(model);
+            let mesh_path = if meshes.is_empty() {
+                None
+            } else {
+                let out_mesh = out_dir.join(format!("model_{index}.clmesh"));
+                clmesh::write(&paths::long(&out_mesh), &meshes)?;
+                Some(out_mesh.to_string_lossy().to_string())
+            };
+            infos.push(ColModelInfo {
+                name: model.name.clone(),
+                version: model.version,
+                model_id: model.model_id,
+                bounds: model.bounds,
+                sphere_count: model.spheres.len(),
+                box_count: model.boxes.len(),
+                face_count: model.faces.len(),
+                vertex_count: model.vertices.len(),
+                mesh_path,
+            });
+        }
+        cache::record_use(&app, &key);
+        Ok(infos)
+    })
+    .await
+    .map_err(|e| format!("Failed to join COL parse task: {e}"))?
+}
+
+// COL3 header after the model header: bounds (40), counts (8), flags (4),
+// six section offsets (24) and the shadow mesh count and offsets (12).
+const COL3_HEADER_SIZE: usize = 88;
+// Flag for models with any spheres, boxes or faces.
+const FLAG_NOT_EMPTY: u32 = 0x2;
+const DEFAULT_MAX_FACES: usize = 10_000;
+
+/// Serialize `model` as a COL3 model.
+pub fn write_col3(model: &ColModel) -> Result<Vec<u8>, String> {
+    if model.name.len() >= NAME_SIZE {
+        return Err(format!(
+            "`{}` is too long for a COL model name ({} characters at most).",
+            model.name,
+            NAME_SIZE - 1
+        ));
+    }
+    let too_many = |what: &str, count: usize| {
+        format!(
+            "{count} {what} don't fit a COL3 model ({} at most).",
+            u16::MAX
+        )
+    };
+    let sphere_count =
+        u16::try_from(model.spheres.len()).map_err(|_| too_many("spheres", model.spheres.len()))?;
+    let box_count =
+        u16::try_from(model.boxes.len()).map_err(|_| too_many("boxes", model.boxes.len()))?;
+    let face_count =
+        u16::try_from(model.faces.len()).map_err(|_| too_many("faces", model.faces.len()))?;
+    if model.vertices.len() > usize::from(u16::MAX) + 1 {
+        return Err(too_many("vertices", model.vertices.len()));
+    }
+
+    let mut body = Vec::new();
+    let push_vec3 = |body: &mut Vec<u8>, v: [f32; 3]| {
+        for value in v {
+            body.extend_from_slice(&value.to_le_bytes());
+        }
+    };
+    // Offsets count from just past the fourcc, i.e. 4 bytes before the
+    // size field's end.
+    let offset_of = |body: &Vec<u8>| (HEADER_SIZE - 4 + COL3_HEADER_SIZE + body.len()) as u32;
+
+    let sphere_offset = if sphere_count > 0 {
+        offset_of(&body)
+    } else {
+        0
+    };
+    for (center, radius, material) in &model.spheres {
+        push_vec3(&mut body, *center);
+        body.extend_from_slice(&radius.to_le_bytes());
+        body.extend_from_slice(&[*material, 0, 0, 0]);
+    }
+    let box_offset = if box_count > 0 { offset_of(&body) } else { 0 };
+    for (min, max, material) in &model.boxes {
+        push_vec3(&mut body, *min);
+        push_vec3(&mut body, *max);
+        body.extend_from_slice(&[*material, 0, 0, 0]);
+    }
+    let vertex_offset = if model.vertices.is_empty() {
+        0
+    } else {
+        offset_of(&body)
+    };
+    for vertex in &model.vertices {
+        for value in vertex {
+            let fixed = (value * VERTEX_SCALE).round();
+            if !(f32::from(i16::MIN)..=f32::from(i16::MAX)).contains(&fixed) {
+                return Err(format!(
+                    "A vertex at {vertex:?} is outside the ±{} units COL3 vertices can hold.",
+                    f32::from(i16::MAX) / VERTEX_SCALE
+                ));
+            }
+            body.extend_from_slice(&(fixed as i16).to_le_bytes());
+        }
+    }
+    while body.len() % 4 != 0 {
+        body.push(0);
+    }
+    let face_offset = if face_count > 0 { offset_of(&body) } else { 0 };
+    for (indices, material) in &model.faces {
+        for index in indices {
+            body.extend_from_slice(&(*index as u16).to_le_bytes());
+        }
+        body.extend_from_slice(&[*material, 0]);
+    }
+
+    let mut out = Vec::with_capacity(HEADER_SIZE + COL3_HEADER_SIZE + body.len());
+    out.extend_from_slice(b"COL3");
+    out.extend_from_slice(
+        &((HEADER_SIZE - 8 + COL3_HEADER_SIZE + body.len()) as u32).to_le_bytes(),
+    );
+    let mut name = [0u8; NAME_SIZE];
+    name[..model.name.len()].copy_from_slice(model.name.as_bytes());
+    out.extend_from_slice(&name);
+    out.extend_from_slice(&model.model_id.to_le_bytes());
+    push_vec3(&mut out, model.bounds.min);
+    push_vec3(&mut out, model.bounds.max);
+    push_vec3(&mut out, model.bounds.center);
+    out.extend_from_slice(&model.bounds.radius.to_le_bytes());
+    out.extend_from_slice(&sphere_count.to_le_bytes());
+    out.extend_from_slice(&box_count.to_le_bytes());
+    out.extend_from_slice(&face_count.to_le_bytes());
+    out.extend_from_slice(&[0, 0]); // lines, padding
+    let flags = if sphere_count + box_count + face_count > 0 {
+        FLAG_NOT_EMPTY
+    } else {
+        0
+    };
+    out.extend_from_slice(&flags.to_le_bytes());
+    // Spheres, boxes, lines, vertices, faces, triangle planes, then the
+    // (empty) shadow mesh's face count and offsets.
+    for value in [
+        sphere_offset,
+        box_offset,
+        0,
+        vertex_offset,
+        face_offset,
+        0,
+        0,
+        0,
+        0,
+    ] {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+fn bounds_of(points: &[[f32; 3]]) -> ColBounds {
+    let mut bounding_box = None;
+    for p in points {
+        clmesh::BoundingBox::extend(&mut bounding_box, *p);
+    }
+    let Some(bounding_box) = bounding_box else {
+        return ColBounds {
+            min: [0.0; 3],
+            max: [0.0; 3],
+            center: [0.0; 3],
+            radius: 0.0,
+        };
+    };
+    let center: [f32; 3] =
+        std::array::from_fn(|axis| (bounding_box.min[axis] + bounding_box.max[axis]) / 2.0);
+    let radius = points
+        .iter()
+        .map(|p| distance(*p, center))
+        .fold(0.0, f32::max);
+    ColBounds {
+        min: bounding_box.min,
+        max: bounding_box.max,
+        center,
+        radius,
+    }
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// Vertices and triangles.
+type MeshData = (Vec<[f32; 3]>, Vec<[u32; 3]>);
+
+/// Merge the vertices sharing a cell of a `resolution`-per-axis grid over
+/// `bounds` into their average, dropping the triangles that collapse.
+fn cluster(
+    vertices: &[[f32; 3]],
+    faces: &[[u32; 3]],
+    bounds: &ColBounds,
+    resolution: usize,
+) -> MeshData {
+    let mut cells = std::collections::HashMap::new();
+    let mut sums: Vec<([f32; 3], f32)> = Vec::new();
+    let remap = vertices
+        .iter()
+        .map(|p| {
+            let cell: [usize; 3] = std::array::from_fn(|axis| {
+                let extent = bounds.max[axis] - bounds.min[axis];
+                let t = if extent > 0.0 {
+                    (p[axis] - bounds.min[axis]) / extent
+                } else {
+                    0.0
+                };
+                ((t * resolution as f32) as usize).min(resolution - 1)
+            });
+            let index = *cells.entry(cell).or_insert_with(|| {
+                sums.push(([0.0; 3], 0.0));
+                sums.len() - 1
+            });
+            let (sum, count) = &mut sums[index];
+            for axis in 0..3 {
+                sum[axis] += p[axis];
+            }
+            *count += 1.0;
+            index as u32
+        })
+        .collect::<Vec<_>>();
+    let merged = sums
+        .iter()
+        .map(|(sum, count)| sum.map(|value| value / count))
+        .collect();
+    let mut seen = std::collections::HashSet::new();
+    let faces = faces
+        .iter()
+        .map(|face| face.map(|index| remap[index as usize]))
+        .filter(|[a, b, c]| a != b && b != c && a != c)
+        .filter(|face| {
+            let mut key = *face;
+            key.sort_unstable();
+            seen.insert(key)
+        })
+        .collect();
+    (merged, faces)
+}
+
+/// Decimate by vertex clustering to at most `budget` faces, using the finest
+/// grid that gets there.
+fn decimate(vertices: &[[f32; 3]], faces: &[[u32; 3]], budget: usize) -> MeshData {
+    let bounds = bounds_of(vertices);
+    let (mut low, mut high) = (1, 1024);
+    let mut best = cluster(vertices, faces, &bounds, low);
+    while low < high {
+        let middle = (low + high).div_ceil(2);
+        let candidate = cluster(vertices, faces, &bounds, middle);
+        if candidate.1.len() <= budget {
+            best = candidate;
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    best
+}
+
+/// Convex hull of `points` by incremental construction, as (vertices,
+/// outward-facing triangles).
+fn convex_hull(points: &[[f32; 3]]) -> Result<MeshData, String> {
+    type V = [f64; 3];
+    let sub = |a: V, b: V| -> V { [a[0] - b[0], a[1] - b[1], a[2] - b[2]] };
+    let cross = |a: V, b: V| -> V {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+    let dot = |a: V, b: V| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let length = |a: V| dot(a, a).sqrt();
+
+    // Near-duplicate points only slow the hull down.
+    let bounds = bounds_of(points);
+    let (points, _) = cluster(points, &[], &bounds, 256);
+    let p: Vec<V> = points.iter().map(|p| p.map(f64::from)).collect();
+    let diagonal = length(sub(bounds.max.map(f64::from), bounds.min.map(f64::from)));
+    let eps = diagonal.max(1e-6) * 1e-6;
+    let too_flat = || "The model is too flat for a convex hull; use `box` mode.".to_string();
+
+    // A starting tetrahedron from far-apart points.
+    let a = (0..p.len())
+        .min_by(|i, j| p[*i][0].total_cmp(&p[*j][0]))
+        .ok_or_else(too_flat)?;
+    let farthest = |score: &dyn Fn(V) -> f64| {
+        (0..p.len())
+            .max_by(|i, j| score(p[*i]).total_cmp(&score(p[*j])))
+            .filter(|i| score(p[*i]) > eps)
+    };
+    let b = farthest(&|q| length(sub(q, p[a]))).ok_or_else(too_flat)?;
+    let c = farthest(&|q| length(cross(sub(p[b], p[a]), sub(q, p[a])))).ok_or_else(too_flat)?;
+    let normal = cross(sub(p[b], p[a]), sub(p[c], p[a]));
+    let d = farthest(&|q| dot(normal, sub(q, p[a])).abs() / length(normal)).ok_or_else(too_flat)?;
+    let mut faces: Vec<[usize; 3]> = if dot(normal, sub(p[d], p[a])) > 0.0 {
+        vec![[a, c, b], [a, b, d], [b, c, d], [c, a, d]]
+    } else {
+        vec![[a, b, c], [a, d, b], [b, d, c], [c, d, a]]
+    };
+
+    let visible = |face: &[usize; 3], q: V| {
+        let n = cross(sub(p[face[1]], p[face[0]]), sub(p[face[2]], p[face[0]]));
+        dot(n, sub(q, p[face[0]])) > eps * length(n)
+    };
+    for (index, q) in p.iter().enumerate() {
+        if [a, b, c, d].contains(&index) {
+            continue;
+        }
+        if !faces.iter().any(|face| visible(face, *q)) {
+            continue;
+        }
+        let (lit, kept): (Vec<[usize; 3]>, Vec<[usize; 3]>) = std::mem::take(&mut faces)
+            .into_iter()
+            .partition(|face| visible(face, *q));
+        // Edges of the lit region whose other side stays, joined to `q`.
+        let lit_edges = lit
+            .iter()
+            .flat_map(|f| [(f[0], f[1]), (f[1], f[2]), (f[2], f[0])])
+            .collect::<std::collections::HashSet<_>>();
+        faces = kept;
+        for &(from, to) in &lit_edges {
+            if !lit_edges.contains(&(to, from)) {
+                faces.push([from, to, index]);
+            }
+        }
+    }
+
+    let mut remap = std::collections::HashMap::new();
+    let mut vertices = Vec::new();
+    let triangles = faces
+        .iter()
+        .map(|face| {
+            face.map(|index| {
+                *remap.entry(index).or_insert_with(|| {
+                    vertices.push(points[index]);
+                    (vertices.len() - 1) as u32
+                })
+            })
+        })
+        .collect();
+    Ok((vertices, triangles))
+}
+
+/// What `generate_col` wrote.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedCol {
+    pub path: String,
+    pub name: String,
+    pub faces: usize,
+    pub vertices: usize,
+    pub boxes: usize,
+    /// Faces before decimation, when `face_budget` reduced them.
+    pub decimated_from: Option<usize>,
+}
+
+/// Build a COL3 model for a .dff. `mesh` copies the render triangles,
+/// decimated by vertex clustering to `face_budget` when given, and refuses
+/// more than `max_faces` (10 000 by default); `box` writes the bounding box;
+/// `hull` a convex hull. Every surface uses `material` (0, default, unless
+/// overridden). The model is named after the DFF.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn generate_col(
+    dff_path: String,
+    out_path: String,
+    mode: String,
+    face_budget: Option<usize>,
+    max_faces: Option<usize>,
+    material: Option<u8>,
+) -> Result<GeneratedCol, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&dff_path, "dff") {
+            return Err("Only .dff files are supported by generate_col".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&dff_path)))
+            .map_err(|e| format!("Failed to read DFF: {e}"))?;
+        let dff = crate::dff::parse(&data)?;
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        for geometry in &dff.geometries {
+            let first = vertices.len() as u32;
+            vertices.extend(geometry.positions.chunks_exact(3).map(|p| [p[0], p[1], p[2]]));
+            triangles.extend(
+                geometry
+                    .triangles
+                    .iter()
+                    .map(|triangle| triangle.vertices.map(|index| first + index)),
+            );
+        }
+        if vertices.is_empty() {
+            return Err("The DFF has no vertices.".to_string());
+        }
+        let material = material.unwrap_or(0);
+        let name = Path::new(&dff_path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut decimated_from = None;
+        let (vertices, faces, boxes) = match mode.trim() {
+            "mesh" => {
+                let (vertices, faces) = match face_budget {
+                    Some(budget) if triangles.len() > budget => {
+                        decimated_from = Some(triangles.len());
+                        decimate(&vertices, &triangles, budget)
+                    }
+                    _ => (vertices, triangles),
+                };
+                let limit = max_faces.unwrap_or(DEFAULT_MAX_FACES);
+                if faces.len() > limit {
+                    return Err(format!(
+                        "The mesh has {} faces, over the limit of {limit}; pass a face_budget to decimate it.",
+                        faces.len()
+                    ));
+                }
+                (vertices, faces, Vec::new())
+            }
+            "box" => {
+                let bounds = bounds_of(&vertices);
+                (Vec::new(), Vec::new(), vec![(bounds.min, bounds.max, material)])
+            }
+            "hull" => {
+                let (vertices, faces) = convex_hull(&vertices)?;
+                (vertices, faces, Vec::new())
+            }
+            other => {
+                return Err(format!(
+                    "Unknown mode `{other}` (expected `mesh`, `box` or `hull`)"
+                ))
+            }
+        };
+
+        let model = ColModel {
+            version: 3,
+            name: name.clone(),
+            model_id: 0,
+            bounds: if boxes.is_empty() {
+                bounds_of(&vertices)
+            } else {
+                bounds_of(&[boxes[0].0, boxes[0].1])
+            },
+            spheres: Vec::new(),
+            boxes,
+            faces: faces.into_iter().map(|face| (face, material)).collect(),
+            vertices,
+        };
+        let col = write_col3(&model)?;
+        let out_path = std::path::PathBuf::from(out_path).with_extension("col");
+        if let Some(parent) = out_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(paths::long(parent))
+                .map_err(|e| format!("Failed to create output dir: {e}"))?;
+        }
+        std::fs::write(paths::long(&out_path), col)
+            .map_err(|e| format!("Failed to write COL: {e}"))?;
+        Ok(GeneratedCol {
+            path: out_path.to_string_lossy().to_string(),
+            name,
+            faces: model.faces.len(),
+            vertices: model.vertices.len(),
+            boxes: model.boxes.len(),
+            decimated_from,
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to join COL generation task: {e}"))?
+}
@@ -0,0 +1,685 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Child, Command, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tauri::{Emitter, Manager, State};
+
+/// Error string returned by a conversion or parse that was cancelled, so the
+/// frontend can tell it apart from a real failure and ignore it.
+pub const CANCELLED: &str = "cancelled";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long the converter or bridge may run before it is killed, unless the
+/// call asks for something else.
+pub const DEFAULT_PROCESS_TIMEOUT_SECS: u64 = 120;
+
+pub fn process_timeout(timeout_secs: Option<u64>) -> Duration {
+    Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_PROCESS_TIMEOUT_SECS).max(1))
+}
+
+/// Why `ConversionTasks::run` didn't produce an `Output`.
+pub enum RunError {
+    /// Killed by `cancel_conversion`; the cleanup paths are already gone.
+    Cancelled,
+    /// Killed after running for this long. Cleanup is left to the caller so
+    /// it can salvage partial logs first.
+    TimedOut(Duration),
+    Failed(String),
+}
+
+/// Where a conversion or parse spent its time and how big its files were,
+/// returned as `stats`. Times are summed over every run of the process.
+#[derive(serde::Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionStats {
+    pub staging_ms: u64,
+    pub process_ms: u64,
+    pub reading_ms: u64,
+    pub storing_ms: u64,
+    pub input_bytes: u64,
+    /// Size of each cached output by file name.
+    pub output_bytes: BTreeMap<String, u64>,
+    /// Whether the first settings profile produced nothing and another was
+    /// tried.
+    pub retried: bool,
+}
+
+impl ConversionStats {
+    pub fn for_input(path: &Path) -> Self {
+        Self {
+            input_bytes: std::fs::metadata(crate::paths::long(path))
+                .map(|meta| meta.len())
+                .unwrap_or(0),
+            ..Default::default()
+        }
+    }
+
+    /// Record the sizes of whichever of `paths` exist.
+    pub fn record_outputs<'a>(&mut self, paths: impl IntoIterator<Item = &'a Path>) {
+        for path in paths {
+            let (Some(name), Ok(meta)) = (
+                path.file_name(),
+                std::fs::metadata(crate::paths::long(path)),
+            ) else {
+                continue;
+            };
+            self.output_bytes
+                .insert(name.to_string_lossy().to_string(), meta.len());
+        }
+    }
+}
+
+pub fn elapsed_ms(start: Instant) -> u64 {
+    start.elapsed().as_millis() as u64
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ConversionStartedPayload {
+    cache_key: String,
+    path: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ConversionStagePayload {
+    cache_key: String,
+    stage: String,
+    message: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ConversionFailedPayload {
+    cache_key: Option<String>,
+    path: String,
+    error: String,
+    /// Set when the process was killed for running too long, so the UI can
+    /// suggest a longer timeout.
+    timeout_secs: Option<u64>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ConversionLogPayload {
+    cache_key: String,
+    stream: &'static str,
+    line: String,
+}
+
+/// Where `ConversionTasks::run` forwards output lines as they arrive, as
+/// `<event>` events.
+#[derive(Clone)]
+pub struct LogSink {
+    pub app: crate::AppHandle,
+    pub event: String,
+    pub key: String,
+}
+
+#[derive(Clone)]
+struct RunningProcess {
+    child: Arc<Mutex<Child>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Converter and bridge processes currently running, keyed by cache key, so
+/// `cancel_conversion` can reach them from another command.
+#[derive(Default)]
+pub struct ConversionTasks {
+    running: Mutex<HashMap<String, RunningProcess>>,
+    // Timeout of the last run under each key that was killed for running too
+    // long, until its failure event has been emitted.
+    timed_out: Mutex<HashMap<String, u64>>,
+    // Conversions and parses in progress, by `<events>:<key>`. A second
+    // request for the same slot waits on `released` rather than running a
+    // duplicate process against the same cache directory.
+    in_flight: Mutex<HashSet<String>>,
+    released: Condvar,
+}
+
+/// Held while a conversion or parse owns its cache entry; dropping it lets
+/// the next request for the same entry through.
+pub struct InFlightGuard<'a> {
+    tasks: &'a ConversionTasks,
+    slot: String,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.tasks.in_flight.lock() {
+            in_flight.remove(&self.slot);
+        }
+        self.tasks.released.notify_all();
+    }
+}
+
+impl ConversionTasks {
+    /// Run `command` to completion like `Command::output`, but registered
+    /// under `key` so it can be killed, and killed anyway after `timeout`.
+    /// When cancelled, `cleanup` is removed (a file or a whole directory) once
+    /// the process has exited. With a `log` sink every stdout/stderr line is
+    /// also emitted live; the full output is captured either way.
+    pub fn run(
+        &self,
+        key: &str,
+        mut command: Command,
+        cleanup: &[PathBuf],
+        timeout: Duration,
+        log: Option<LogSink>,
+    ) -> Result<Output, RunError> {
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| RunError::Failed(e.to_string()))?;
+
+        // Drain both pipes on their own threads so a chatty process can't
+        // block on a full pipe while we poll for its exit.
+        let stdout = child
+            .stdout
+            .take()
+            .map(|pipe| spawn_reader(pipe, "stdout", log.clone()));
+        let stderr = child
+            .stderr
+            .take()
+            .map(|pipe| spawn_reader(pipe, "stderr", log));
+
+        let child = Arc::new(Mutex::new(child));
+        let process = RunningProcess {
+            child: child.clone(),
+            cancelled: self.track(key, child),
+        };
+
+        let started = Instant::now();
+        let mut timed_out = false;
+        let status = loop {
+            let polled = match process.child.lock() {
+                Ok(mut child) => {
+                    if !timed_out && started.elapsed() >= timeout {
+                        timed_out = true;
+                        let _ = child.kill();
+                    }
+                    child.try_wait()
+                }
+                Err(_) => break Err("process lock failed".to_string()),
+            };
+            match polled {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => std::thread::sleep(POLL_INTERVAL),
+                Err(e) => break Err(e.to_string()),
+            }
+        };
+
+        self.untrack(key, &process.child);
+
+        let stdout = stdout
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default();
+        let stderr = stderr
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default();
+
+        if process.cancelled.load(Ordering::SeqCst) {
+            for path in cleanup {
+                remove_partial_output(path);
+            }
+            return Err(RunError::Cancelled);
+        }
+        if timed_out {
+            self.note_timeout(key, timeout);
+            return Err(RunError::TimedOut(timeout));
+        }
+
+        Ok(Output {
+            status: status.map_err(RunError::Failed)?,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Register a process under `key` so `cancel_conversion` can kill it,
+    /// for processes that `run` didn't start itself. Returns the flag
+    /// cancelling sets.
+    pub fn track(&self, key: &str, child: Arc<Mutex<Child>>) -> Arc<AtomicBool> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if let Ok(mut running) = self.running.lock() {
+            running.insert(
+                key.to_string(),
+                RunningProcess {
+                    child,
+                    cancelled: cancelled.clone(),
+                },
+            );
+        }
+        cancelled
+    }
+
+    /// Undo `track`, unless something else has been registered under `key`
+    /// since.
+    pub fn untrack(&self, key: &str, child: &Arc<Mutex<Child>>) {
+        if let Ok(mut running) = self.running.lock() {
+            if running
+                .get(key)
+                .is_some_and(|current| Arc::ptr_eq(&current.child, child))
+            {
+                running.remove(key);
+            }
+        }
+    }
+
+    /// Remember that the run under `key` was killed after `timeout`, for its
+    /// failure event.
+    pub fn note_timeout(&self, key: &str, timeout: Duration) {
+        if let Ok(mut timed_out) = self.timed_out.lock() {
+            timed_out.insert(key.to_string(), timeout.as_secs());
+        }
+    }
+
+    /// Claim `slot`, first waiting for whoever holds it to finish. If it's
+    /// taken, `on_wait` runs once, under the lock, before blocking. Returns
+    /// whether it had to wait.
+    fn claim(&self, slot: &str, on_wait: impl FnOnce()) -> (InFlightGuard<'_>, bool) {
+        let mut on_wait = Some(on_wait);
+        let mut in_flight = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        while in_flight.contains(slot) {
+            if let Some(on_wait) = on_wait.take() {
+                on_wait();
+            }
+            in_flight = self
+                .released
+                .wait(in_flight)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        in_flight.insert(slot.to_string());
+        (
+            InFlightGuard {
+                tasks: self,
+                slot: slot.to_string(),
+            },
+            on_wait.is_none(),
+        )
+    }
+
+    fn take_timeout(&self, key: &str) -> Option<u64> {
+        self.timed_out.lock().ok()?.remove(key)
+    }
+
+    pub fn is_running(&self, key: &str) -> bool {
+        self.running
+            .lock()
+            .map(|running| running.contains_key(key))
+            .unwrap_or(false)
+    }
+
+    /// Kill the process registered under `key`. Returns `false` if nothing
+    /// was running for it.
+    pub fn cancel(&self, key: &str) -> Result<bool, String> {
+        let process = match self
+            .running
+            .lock()
+            .map_err(|_| "conversion task lock failed".to_string())?
+            .get(key)
+        {
+            Some(process) => process.clone(),
+            None => return Ok(false),
+        };
+
+        process.cancelled.store(true, Ordering::SeqCst);
+        let mut child = process
+            .child
+            .lock()
+            .map_err(|_| "process lock failed".to_string())?;
+        let _ = child.kill();
+        Ok(true)
+    }
+}
+
+/// One running conversion or parse: where its processes are registered for
+/// cancellation, and which lifecycle events it reports. `events` is the
+/// event prefix, `conversion` for `convert_yft` and `parse` for `parse_yft`.
+/// Every process it runs is killed after `timeout`, and with `verbose` its
+/// output lines are streamed as `<events>:log`.
+pub struct ConversionContext<'a> {
+    pub app: &'a crate::AppHandle,
+    pub tasks: &'a ConversionTasks,
+    pub key: &'a str,
+    pub events: &'static str,
+    pub timeout: Duration,
+    pub verbose: bool,
+}
+
+impl ConversionContext<'_> {
+    pub fn started(&self, path: &str) {
+        let _ = self.app.emit(
+            &format!("{}:started", self.events),
+            ConversionStartedPayload {
+                cache_key: self.key.to_string(),
+                path: path.to_string(),
+            },
+        );
+    }
+
+    /// Report a step of the pipeline, e.g. `stage("retry", "Retrying with
+    /// vehicle profile")`. `stage` is stable for code, `message` is for
+    /// display.
+    pub fn stage(&self, stage: &str, message: &str) {
+        let _ = self.app.emit(
+            &format!("{}:stage", self.events),
+            ConversionStagePayload {
+                cache_key: self.key.to_string(),
+                stage: stage.to_string(),
+                message: message.to_string(),
+            },
+        );
+    }
+
+    /// Wait until no other request is working on this cache entry, then hold
+    /// it until the guard is dropped. Whoever waited finds the other
+    /// request's output in the cache.
+    pub fn claim(&self) -> InFlightGuard<'_> {
+        let slot = format!("{}:{}", self.events, self.key);
+        let (guard, _) = self.tasks.claim(&slot, || {
+            self.stage("waiting", "Waiting for an identical request to finish")
+        });
+        guard
+    }
+
+    pub fn run(&self, command: Command, cleanup: &[PathBuf]) -> Result<Output, RunError> {
+        let log = self.verbose.then(|| LogSink {
+            app: self.app.clone(),
+            event: format!("{}:log", self.events),
+            key: self.key.to_string(),
+        });
+        self.tasks
+            .run(self.key, command, cleanup, self.timeout, log)
+    }
+}
+
+/// Emit `<events>:finished` with the command's result, or `<events>:failed`
+/// with its error. `key` is `None` when the command failed before a cache key
+/// was worked out.
+pub fn emit_outcome<T: serde::Serialize + Clone>(
+    app: &crate::AppHandle,
+    events: &str,
+    path: &str,
+    key: Option<String>,
+    result: &Result<T, String>,
+) {
+    let timeout_secs = key
+        .as_deref()
+        .and_then(|key| app.state::<ConversionTasks>().take_timeout(key));
+    let _ = match result {
+        Ok(value) => app.emit(&format!("{events}:finished"), value.clone()),
+        Err(error) => app.emit(
+            &format!("{events}:failed"),
+            ConversionFailedPayload {
+                cache_key: key,
+                path: path.to_string(),
+                error: error.clone(),
+                timeout_secs,
+            },
+        ),
+    };
+}
+
+/// `GeneralSettings.ini` switch for the `.col` the converter builds next to
+/// the `.dff`, as a `Section.key` override. Turning it off roughly halves the
+/// time on big vehicles.
+pub const COLLISION_SETTING: &str = "General.ExportCollision";
+
+/// Rewrite `key=value` lines of an INI file. An override named
+/// `Section.key` only touches that section, a bare `key` its first
+/// occurrence anywhere. Keys that aren't in the file are appended, to their
+/// section when one is named, so settings a newer converter added still work.
+pub fn apply_settings_overrides(contents: &str, overrides: &BTreeMap<String, String>) -> String {
+    let newline = if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut pending: Vec<(Option<&str>, &str, &str)> = overrides
+        .iter()
+        .map(|(name, value)| match name.split_once('.') {
+            Some((section, key)) => (Some(section.trim()), key.trim(), value.as_str()),
+            None => (None, name.trim(), value.as_str()),
+        })
+        .collect();
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut section = String::new();
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            append_section_overrides(&mut lines, &mut pending, &section);
+            section = trimmed[1..trimmed.len() - 1].trim().to_string();
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let replaced = match line.split_once('=') {
+            Some((name, _)) if !trimmed.starts_with([';', '#']) => {
+                let name = name.trim();
+                pending
+                    .iter()
+                    .position(|(target, key, _)| {
+                        key.eq_ignore_ascii_case(name)
+                            && target.is_none_or(|target| target.eq_ignore_ascii_case(&section))
+                    })
+                    .map(|index| {
+                        let (_, _, value) = pending.remove(index);
+                        // Keep the line's own spacing around `=`.
+                        let (name, old) = line.split_once('=').unwrap_or((line, ""));
+                        let spacing = &old[..old.len() - old.trim_start().len()];
+                        format!("{name}={spacing}{value}")
+                    })
+            }
+            _ => None,
+        };
+        lines.push(replaced.unwrap_or_else(|| line.to_string()));
+    }
+    append_section_overrides(&mut lines, &mut pending, &section);
+
+    // Whatever is left targets sections the file doesn't have, or had no
+    // section and matched nothing.
+    let mut last_section: Option<&str> = None;
+    for (target, key, value) in pending {
+        if let Some(target) = target {
+            if last_section != Some(target) {
+                lines.push(format!("[{target}]"));
+                last_section = Some(target);
+            }
+        }
+        lines.push(format!("{key}={value}"));
+    }
+
+    let mut merged = lines.join(newline);
+    merged.push_str(newline);
+    merged
+}
+
+/// Append the overrides aimed at `section` that no line matched.
+fn append_section_overrides(
+    lines: &mut Vec<String>,
+    pending: &mut Vec<(Option<&str>, &str, &str)>,
+    section: &str,
+) {
+    if section.is_empty() {
+        return;
+    }
+    pending.retain(|(target, key, value)| {
+        if target.is_some_and(|target| target.eq_ignore_ascii_case(section)) {
+            lines.push(format!("{key}={value}"));
+            false
+        } else {
+            true
+        }
+    });
+}
+
+fn spawn_reader<R: Read + Send + 'static>(
+    pipe: R,
+    stream: &'static str,
+    log: Option<LogSink>,
+) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let Some(log) = log else {
+            let mut pipe = pipe;
+            let _ = pipe.read_to_end(&mut buf);
+            return buf;
+        };
+
+        let mut reader = BufReader::new(pipe);
+        loop {
+            let start = buf.len();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buf[start..]);
+                    let _ = log.app.emit(
+                        &log.event,
+                        ConversionLogPayload {
+                            cache_key: log.key.clone(),
+                            stream,
+                            line: line.trim_end_matches(['\r', '\n']).to_string(),
+                        },
+                    );
+                }
+            }
+        }
+        buf
+    })
+}
+
+pub fn remove_partial_output(path: &Path) {
+    if path.is_dir() {
+        let _ = std::fs::remove_dir_all(path);
+    } else if path.exists() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Kill the converter or bridge process working on `cache_key` and discard
+/// its partial output. The cancelled command resolves with `"cancelled"`.
+#[tauri::command]
+pub fn cancel_conversion(cache_key: String, state: State<ConversionTasks>) -> Result<bool, String> {
+    state.cancel(&cache_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{atomic::AtomicU32, mpsc};
+
+    /// What one request got, and whether it waited for the slot.
+    type Outcome = (Result<u32, String>, bool);
+
+    // Stand-in for a conversion: reuse whatever the cache holds, else run
+    // `work` and cache its result, all under the claimed slot like `convert_yft`.
+    struct Cache {
+        tasks: ConversionTasks,
+        entry: Mutex<Option<u32>>,
+        runs: AtomicU32,
+    }
+
+    impl Cache {
+        fn new() -> Self {
+            Self {
+                tasks: ConversionTasks::default(),
+                entry: Mutex::new(None),
+                runs: AtomicU32::new(0),
+            }
+        }
+
+        fn convert(
+            &self,
+            on_claimed: impl FnOnce(),
+            on_wait: impl FnOnce(),
+            work: impl FnOnce() -> Result<u32, String>,
+        ) -> Outcome {
+            let (_guard, waited) = self.tasks.claim("conversion:key", on_wait);
+            on_claimed();
+            if let Some(cached) = *self.entry.lock().unwrap() {
+                return (Ok(cached), waited);
+            }
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let result = work();
+            if let Ok(value) = result {
+                *self.entry.lock().unwrap() = Some(value);
+            }
+            (result, waited)
+        }
+    }
+
+    /// Run `owner` on one thread and, once it holds the slot, a second
+    /// request for the same slot on another. `owner` only runs once the
+    /// second request is blocked on the slot. Returns both outcomes; the
+    /// owner's is `None` if it panicked.
+    fn race(
+        cache: &Cache,
+        owner: impl FnOnce() -> Result<u32, String> + Send,
+    ) -> (Option<Outcome>, Outcome) {
+        let (claimed, has_claimed) = mpsc::channel();
+        let (waiting, is_waiting) = mpsc::channel();
+        std::thread::scope(|scope| {
+            let first = scope.spawn(move || {
+                let on_claimed = move || claimed.send(()).unwrap();
+                cache.convert(
+                    on_claimed,
+                    || panic!("slot was free"),
+                    || {
+                        is_waiting.recv().unwrap();
+                        owner()
+                    },
+                )
+            });
+            has_claimed.recv().unwrap();
+            let on_wait = move || waiting.send(()).unwrap();
+            let second = scope.spawn(move || cache.convert(|| {}, on_wait, || Ok(2)));
+            (first.join().ok(), second.join().unwrap())
+        })
+    }
+
+    #[test]
+    fn second_request_waits_and_reuses_the_result() {
+        let cache = Cache::new();
+        let (first, second) = race(&cache, || Ok(1));
+
+        assert_eq!(first, Some((Ok(1), false)));
+        assert_eq!(second, (Ok(1), true));
+        assert_eq!(cache.runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn failing_owner_releases_the_waiter() {
+        let cache = Cache::new();
+        let (first, second) = race(&cache, || Err("converter crashed".to_string()));
+
+        assert_eq!(first, Some((Err("converter crashed".to_string()), false)));
+        // Nothing was cached, so the waiter runs the conversion itself.
+        assert_eq!(second, (Ok(2), true));
+        assert_eq!(cache.runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn panicking_owner_releases_the_waiter() {
+        let cache = Cache::new();
+        let (first, second) = race(&cache, || panic!("converter panicked"));
+
+        assert_eq!(first, None);
+        assert_eq!(second, (Ok(2), true));
+        assert!(cache.tasks.in_flight.lock().unwrap().is_empty());
+    }
+}
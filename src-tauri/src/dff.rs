@@ -0,0 +1,2441 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use crate::{
+    cache::{self, CacheKeyMode},
+    clmesh,
+    conversion::{self, ConversionStats},
+    paths,
+    rw::{
+        c_string, child, children, chunk_at, read_string, read_u32_le, Chunk, Cursor,
+        CHUNK_EXTENSION, CHUNK_STRING, CHUNK_STRUCT,
+    },
+};
+
+// RenderWare chunk types used by models.
+const CHUNK_TEXTURE: u32 = 0x06;
+const CHUNK_MATERIAL: u32 = 0x07;
+const CHUNK_MATERIAL_LIST: u32 = 0x08;
+const CHUNK_FRAME_LIST: u32 = 0x0E;
+const CHUNK_GEOMETRY: u32 = 0x0F;
+const CHUNK_CLUMP: u32 = 0x10;
+const CHUNK_ATOMIC: u32 = 0x14;
+const CHUNK_GEOMETRY_LIST: u32 = 0x1A;
+const CHUNK_SKIN: u32 = 0x116;
+const CHUNK_BIN_MESH: u32 = 0x50E;
+const CHUNK_NATIVE_DATA: u32 = 0x510;
+const CHUNK_2D_EFFECT: u32 = 0x253F2F8;
+const CHUNK_EXTRA_VERT_COLOUR: u32 = 0x253F2F9;
+const CHUNK_NODE_NAME: u32 = 0x253F2FE;
+
+// Chunks whose data is more chunks, rather than a struct or plugin payload.
+const CONTAINER_CHUNKS: [u32; 9] = [
+    CHUNK_CLUMP,
+    CHUNK_FRAME_LIST,
+    CHUNK_GEOMETRY_LIST,
+    CHUNK_GEOMETRY,
+    CHUNK_MATERIAL_LIST,
+    CHUNK_MATERIAL,
+    CHUNK_TEXTURE,
+    CHUNK_EXTENSION,
+    CHUNK_ATOMIC,
+];
+
+// Geometry format flags.
+const FLAG_TEXTURED: u32 = 0x04;
+const FLAG_PRELIT: u32 = 0x08;
+const FLAG_TEXTURED2: u32 = 0x80;
+const FLAG_NATIVE: u32 = 0x0100_0000;
+
+// Library versions before 3.4 (III and early VC) still store lighting
+// coefficients in the geometry struct.
+const VERSION_WITHOUT_SURFACE_PROPS: u32 = 0x3_4000;
+
+/// A clump's geometry as stored in a `.dff`.
+pub struct Dff {
+    /// Decoded library version, e.g. `0x36003` for San Andreas.
+    pub version: u32,
+    pub geometries: Vec<Geometry>,
+}
+
+pub struct Geometry {
+    pub positions: Vec<f32>,
+    pub normals: Option<Vec<f32>>,
+    /// First texture coordinate set.
+    pub uvs: Option<Vec<f32>>,
+    /// Prelit RGBA per vertex.
+    pub colors: Option<Vec<u8>>,
+    /// San Andreas' night-time RGBA per vertex, from the Extra Vert Colour
+    /// extension.
+    pub night_colors: Option<Vec<u8>>,
+    pub triangles: Vec<Triangle>,
+    pub materials: Vec<Material>,
+}
+
+/// One node of the frame hierarchy, e.g. a vehicle's `wheel_lf_dummy`.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Frame {
+    pub index: usize,
+    /// -1 for a root frame.
+    pub parent: i32,
+    /// Transform relative to the parent, as a column-major 4x4 matrix.
+    pub matrix: [f32; 16],
+    /// From the node name extension; frames without one have no name.
+    pub name: Option<String>,
+}
+
+pub struct Triangle {
+    pub vertices: [u32; 3],
+    pub material: u16,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Material {
+    /// RGBA.
+    pub color: [u8; 4],
+    pub texture_name: Option<String>,
+    pub mask_name: Option<String>,
+    /// Whether the material is translucent or alpha-masked: its color isn't
+    /// opaque or its texture has a mask.
+    pub has_alpha: bool,
+}
+
+/// Counts for one geometry, read from its struct header alone.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GeometryInfo {
+    /// Offset of the geometry chunk.
+    pub offset: usize,
+    pub flags: u32,
+    pub vertex_count: u32,
+    /// From the bin mesh when there is one, else the struct's face list.
+    pub triangle_count: u32,
+    pub material_count: u32,
+    pub uv_sets: u32,
+    /// Range of each texture coordinate set; empty for native geometry.
+    pub uv_bounds: Vec<UvBounds>,
+    /// Has a Skin PLG, i.e. bone indices and weights.
+    pub skinned: bool,
+    pub bin_mesh: Option<BinMeshInfo>,
+}
+
+// Slack for coordinates that only leave [0, 1] through float noise.
+const UV_RANGE_TOLERANCE: f32 = 1e-3;
+
+/// Range of one texture coordinate set.
+#[derive(serde::Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct UvBounds {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    /// Coordinates leave [0, 1], i.e. the texture tiles, which naive livery
+    /// mapping doesn't handle.
+    pub out_of_range: bool,
+}
+
+impl UvBounds {
+    fn of(coords: &[f32]) -> Option<Self> {
+        let mut pairs = coords.chunks_exact(2);
+        let first = pairs.next()?;
+        let mut bounds = Self {
+            min: [first[0], first[1]],
+            max: [first[0], first[1]],
+            out_of_range: false,
+        };
+        for uv in pairs {
+            bounds.merge(&Self {
+                min: [uv[0], uv[1]],
+                max: [uv[0], uv[1]],
+                out_of_range: false,
+            });
+        }
+        bounds.out_of_range = bounds.min.iter().any(|v| *v < -UV_RANGE_TOLERANCE)
+            || bounds.max.iter().any(|v| *v > 1.0 + UV_RANGE_TOLERANCE);
+        Some(bounds)
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for axis in 0..2 {
+            self.min[axis] = self.min[axis].min(other.min[axis]);
+            self.max[axis] = self.max[axis].max(other.max[axis]);
+        }
+        self.out_of_range |= other.out_of_range;
+    }
+}
+
+/// Layout of a geometry's Bin Mesh PLG extension.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BinMeshInfo {
+    /// One split per material.
+    pub splits: u32,
+    pub strips: bool,
+    pub index_count: u32,
+}
+
+/// Every geometry in a `.dff`, with totals across them.
+#[derive(serde::Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DffInspection {
+    pub geometries: Vec<GeometryInfo>,
+    pub vertex_count: u32,
+    pub triangle_count: u32,
+    /// Most texture coordinate sets any geometry has.
+    pub uv_channels: u32,
+    /// Range of each set across every geometry that has it.
+    pub uv_bounds: Vec<UvBounds>,
+    /// Whether any set leaves [0, 1].
+    pub uv_out_of_range: bool,
+}
+
+/// Vertices across all geometries of a `.dff`, or `None` if it can't be
+/// read or has no geometry.
+pub fn vertex_count(path: &Path) -> Option<u32> {
+    inspect(path)
+        .ok()
+        .filter(|inspection| !inspection.geometries.is_empty())
+        .map(|inspection| inspection.vertex_count)
+}
+
+/// Counts for each geometry of a `.dff`, and the range of its texture
+/// coordinates, without decoding positions or triangles.
+pub fn inspect(path: &Path) -> Result<DffInspection, String> {
+    let data = std::fs::read(paths::long(path)).map_err(|e| format!("Failed to read DFF: {e}"))?;
+    let mut inspection = DffInspection::default();
+    for chunk in geometry_chunks(&data) {
+        let chunk = chunk?;
+        let parts = children(&data, &chunk)?;
+        let header = child(&parts, CHUNK_STRUCT)
+            .ok_or_else(|| format!("Geometry at offset {:#x} has no struct.", chunk.offset))?;
+        let mut cursor = Cursor::new(&data, header, "Geometry struct");
+        let flags = cursor.u32()?;
+        let face_count = cursor.u32()?;
+        let vertex_count = cursor.u32()?;
+        let uv_sets = uv_set_count(flags);
+        let uv_bounds = if flags & FLAG_NATIVE != 0 {
+            Vec::new()
+        } else {
+            cursor.u32()?; // morph targets
+            if header.version < VERSION_WITHOUT_SURFACE_PROPS {
+                cursor.take(12)?;
+            }
+            if flags & FLAG_PRELIT != 0 {
+                cursor.take((vertex_count as usize).saturating_mul(4))?;
+            }
+            let mut bounds = Vec::new();
+            for _ in 0..uv_sets {
+                let coords = cursor.f32s((vertex_count as usize).saturating_mul(2))?;
+                bounds.extend(UvBounds::of(&coords));
+            }
+            bounds
+        };
+        let skinned = plugin(&data, &parts, CHUNK_SKIN)?.is_some();
+        let bin_mesh = find_bin_mesh(&data, &parts)?;
+        let triangle_count = match &bin_mesh {
+            Some(bin_mesh) => bin_mesh.triangles.len() as u32,
+            None => face_count,
+        };
+        let material_count = match child(&parts, CHUNK_MATERIAL_LIST) {
+            Some(list) => {
+                let list_parts = children(&data, list)?;
+                match child(&list_parts, CHUNK_STRUCT) {
+                    Some(list_header) => {
+                        Cursor::new(&data, list_header, "Material list struct").u32()?
+                    }
+                    None => 0,
+                }
+            }
+            None => 0,
+        };
+
+        inspection.vertex_count = inspection.vertex_count.saturating_add(vertex_count);
+        inspection.triangle_count = inspection.triangle_count.saturating_add(triangle_count);
+        inspection.uv_channels = inspection.uv_channels.max(uv_sets);
+        for (set, bounds) in uv_bounds.iter().enumerate() {
+            match inspection.uv_bounds.get_mut(set) {
+                Some(total) => total.merge(bounds),
+                None => inspection.uv_bounds.push(*bounds),
+            }
+            inspection.uv_out_of_range |= bounds.out_of_range;
+        }
+        inspection.geometries.push(GeometryInfo {
+            offset: chunk.offset,
+            flags,
+            vertex_count,
+            triangle_count,
+            material_count,
+            uv_sets,
+            uv_bounds,
+            skinned,
+            bin_mesh: bin_mesh.map(|bin_mesh| bin_mesh.info),
+        });
+    }
+    Ok(inspection)
+}
+
+/// The material list of each geometry in a `.dff`, in file order, without
+/// decoding any vertices.
+pub fn materials(path: &Path) -> Result<Vec<Vec<Material>>, String> {
+    let data = std::fs::read(paths::long(path)).map_err(|e| format!("Failed to read DFF: {e}"))?;
+    geometry_chunks(&data)
+        .map(|chunk| {
+            let chunk = chunk?;
+            match child(&children(&data, &chunk)?, CHUNK_MATERIAL_LIST) {
+                Some(list) => parse_material_list(&data, list),
+                None => Ok(Vec::new()),
+            }
+        })
+        .collect()
+}
+
+/// Every geometry chunk in `data` in file order, looking inside clumps,
+/// geometry lists and frame lists. Ends after the first malformed chunk.
+fn geometry_chunks(data: &[u8]) -> GeometryChunks<'_> {
+    GeometryChunks {
+        data,
+        ranges: vec![(0, data.len())],
+    }
+}
+
+struct GeometryChunks<'a> {
+    data: &'a [u8],
+    // (next offset, end) of each container being walked, innermost last.
+    ranges: Vec<(usize, usize)>,
+}
+
+impl Iterator for GeometryChunks<'_> {
+    type Item = Result<Chunk, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (offset, end) = *self.ranges.last()?;
+            if offset >= end {
+                self.ranges.pop();
+                continue;
+            }
+            let chunk = match chunk_at(self.data, offset, end) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    self.ranges.clear();
+                    return Some(Err(e));
+                }
+            };
+            if let Some(range) = self.ranges.last_mut() {
+                range.0 = chunk.end;
+            }
+            match chunk.kind {
+                CHUNK_GEOMETRY => return Some(Ok(chunk)),
+                CHUNK_CLUMP | CHUNK_GEOMETRY_LIST | CHUNK_FRAME_LIST => {
+                    self.ranges.push((chunk.start, chunk.end));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Texture coordinate sets a geometry stores. The count in the high flag
+/// bits wins; older files only set the textured flags.
+fn uv_set_count(flags: u32) -> u32 {
+    match (flags >> 16) & 0xFF {
+        0 if flags & FLAG_TEXTURED2 != 0 => 2,
+        0 if flags & FLAG_TEXTURED != 0 => 1,
+        count => count,
+    }
+}
+
+/// One problem `validate` found.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    /// `error` for files the game or the viewer can't load, `warning` for
+    /// ones that merely look off.
+    pub level: &'static str,
+    /// Offset of the chunk the finding is about.
+    pub offset: usize,
+    pub description: String,
+}
+
+impl Finding {
+    fn error(offset: usize, description: String) -> Self {
+        Self {
+            level: "error",
+            offset,
+            description,
+        }
+    }
+
+    fn warning(offset: usize, description: String) -> Self {
+        Self {
+            level: "warning",
+            offset,
+            description,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DffValidation {
+    /// No finding is an error.
+    pub valid: bool,
+    pub findings: Vec<Finding>,
+}
+
+/// `validate_bytes` of the `.dff` at `path`; a file that can't be read is
+/// a single error.
+pub fn validate(path: &Path) -> DffValidation {
+    match std::fs::read(paths::long(path)) {
+        Ok(data) => validate_bytes(&data),
+        Err(e) => DffValidation {
+            valid: false,
+            findings: vec![Finding::error(0, format!("Failed to read DFF: {e}"))],
+        },
+    }
+}
+
+/// Walk every chunk of a `.dff`, checking that each fits its parent and
+/// that every geometry decodes with its vertex and material indices in
+/// range. Never panics, whatever the input.
+pub fn validate_bytes(data: &[u8]) -> DffValidation {
+    let mut findings = Vec::new();
+    let mut clumps = 0;
+    let mut total_vertices = 0u64;
+    // (offset, end) of the containers still to walk.
+    let mut pending = vec![(0, data.len())];
+    while let Some((mut offset, end)) = pending.pop() {
+        while offset < end {
+            if end - offset < 12 {
+                findings.push(Finding::warning(
+                    offset,
+                    format!("{} trailing bytes after the last chunk.", end - offset),
+                ));
+                break;
+            }
+            let chunk = match chunk_at(data, offset, end) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    findings.push(Finding::error(offset, e));
+                    break;
+                }
+            };
+            offset = chunk.end;
+            match chunk.kind {
+                CHUNK_CLUMP => {
+                    clumps += 1;
+                    pending.push((chunk.start, chunk.end));
+                }
+                CHUNK_GEOMETRY => match check_geometry(data, &chunk, &mut findings) {
+                    Ok(vertices) => total_vertices += u64::from(vertices),
+                    Err(e) => findings.push(Finding::error(chunk.offset, e)),
+                },
+                kind if CONTAINER_CHUNKS.contains(&kind) => pending.push((chunk.start, chunk.end)),
+                _ => {}
+            }
+        }
+    }
+
+    if clumps == 0 {
+        findings.push(Finding::error(0, "The DFF contains no clump.".to_string()));
+    } else if total_vertices == 0 {
+        findings.push(Finding::error(0, "The DFF has no vertices.".to_string()));
+    }
+    // Errors first, each level in file order.
+    findings.sort_by_key(|finding| (finding.level != "error", finding.offset));
+    DffValidation {
+        valid: findings.iter().all(|finding| finding.level != "error"),
+        findings,
+    }
+}
+
+/// Check one geometry, adding what's wrong with it to `findings`, and
+/// return its vertex count.
+fn check_geometry(data: &[u8], chunk: &Chunk, findings: &mut Vec<Finding>) -> Result<u32, String> {
+    let parts = children(data, chunk)?;
+    let header = child(&parts, CHUNK_STRUCT)
+        .ok_or_else(|| format!("Geometry at offset {:#x} has no struct.", chunk.offset))?;
+    let mut cursor = Cursor::new(data, header, "Geometry struct");
+    let flags = cursor.u32()?;
+    cursor.u32()?; // triangle count
+    let vertex_count = cursor.u32()?;
+    if flags & FLAG_NATIVE != 0 {
+        findings.push(Finding::warning(
+            chunk.offset,
+            "Geometry is platform-native, so its contents can't be checked.".to_string(),
+        ));
+        return Ok(vertex_count);
+    }
+
+    let geometry = parse_geometry(data, chunk)?;
+    if vertex_count == 0 {
+        findings.push(Finding::warning(
+            chunk.offset,
+            "Geometry has no vertices.".to_string(),
+        ));
+    } else if geometry.positions.is_empty() {
+        findings.push(Finding::error(
+            chunk.offset,
+            "Geometry stores no vertex positions.".to_string(),
+        ));
+    }
+    if let Some(triangle) = geometry
+        .triangles
+        .iter()
+        .position(|triangle| usize::from(triangle.material) >= geometry.materials.len())
+    {
+        findings.push(Finding::error(
+            chunk.offset,
+            format!(
+                "Triangle {triangle} uses material {}, but the material list has {}.",
+                geometry.triangles[triangle].material,
+                geometry.materials.len()
+            ),
+        ));
+    }
+    Ok(vertex_count)
+}
+
+/// `0x36003` as `3.6.0.3`.
+fn format_version(version: u32) -> String {
+    format!(
+        "{}.{}.{}.{}",
+        (version >> 16) & 0xF,
+        (version >> 12) & 0xF,
+        (version >> 8) & 0xF,
+        version & 0xFF
+    )
+}
+
+/// The game a library version was used by, going by the PC releases.
+fn version_target(version: u32) -> Option<&'static str> {
+    match version {
+        0x3_0000..=0x3_2FFF => Some("GTA III (RW 3.1)"),
+        0x3_3000..=0x3_4FFF => Some("GTA Vice City (RW 3.3/3.4)"),
+        0x3_6000..=0x3_6FFF => Some("GTA San Andreas (RW 3.6)"),
+        _ => None,
+    }
+}
+
+/// Which RenderWare release wrote a `.dff`.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DffVersion {
+    /// The clump's library version, e.g. `3.6.0.3`.
+    pub version: String,
+    /// The build stamp, when the header carries one (3.1.0.1 and later).
+    pub build: Option<u32>,
+    /// The game this version targets, if it's one we know.
+    pub target: Option<&'static str>,
+    /// Every version stamped on a chunk of the file.
+    pub versions: Vec<String>,
+    /// Platform-native geometry, which only the game it was built for reads.
+    pub native_geometry: bool,
+    /// San Andreas' extra (night) vertex colors.
+    pub extra_vertex_colors: bool,
+    pub warnings: Vec<String>,
+}
+
+/// `version_report` of the `.dff` at `path`.
+pub fn read_version(path: &Path) -> Result<DffVersion, String> {
+    let data = std::fs::read(paths::long(path)).map_err(|e| format!("Failed to read DFF: {e}"))?;
+    version_report(&data)
+}
+
+/// Library version and version-specific features of a `.dff`.
+pub fn version_report(data: &[u8]) -> Result<DffVersion, String> {
+    let clump = first_clump(data)?;
+    let version = clump.version;
+    let library_id = read_u32_le(data, clump.offset + 8).unwrap_or(0);
+
+    let mut versions = BTreeSet::new();
+    let mut native_geometry = false;
+    let mut extra_vertex_colors = false;
+    let mut pending = vec![clump];
+    while let Some(chunk) = pending.pop() {
+        versions.insert(chunk.version);
+        match chunk.kind {
+            CHUNK_NATIVE_DATA => native_geometry = true,
+            CHUNK_EXTRA_VERT_COLOUR => extra_vertex_colors = true,
+            CHUNK_GEOMETRY => {
+                let parts = children(data, &chunk)?;
+                if let Some(header) = child(&parts, CHUNK_STRUCT) {
+                    let flags = Cursor::new(data, header, "Geometry struct").u32()?;
+                    native_geometry |= flags & FLAG_NATIVE != 0;
+                }
+                pending.extend(parts);
+            }
+            kind if CONTAINER_CHUNKS.contains(&kind) => pending.extend(children(data, &chunk)?),
+            _ => {}
+        }
+    }
+
+    let target = version_target(version);
+    let mut warnings = Vec::new();
+    if versions.len() > 1 {
+        warnings.push(format!(
+            "Chunks carry mixed library versions ({}); the file was likely assembled from several exports.",
+            versions.iter().copied().map(format_version).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    if target.is_none() {
+        warnings.push(format!(
+            "Library version {} isn't one GTA III, Vice City or San Andreas uses.",
+            format_version(version)
+        ));
+    }
+    if extra_vertex_colors && version < 0x3_6000 {
+        warnings.push("Extra vertex colors are a San Andreas feature.".to_string());
+    }
+    if native_geometry {
+        warnings.push(
+            "Geometry is stored in a platform-native layout, readable only by the platform it was built for."
+                .to_string(),
+        );
+    }
+
+    Ok(DffVersion {
+        version: format_version(version),
+        build: (library_id & 0xFFFF_0000 != 0).then_some(library_id & 0xFFFF),
+        target,
+        versions: versions.into_iter().map(format_version).collect(),
+        native_geometry,
+        extra_vertex_colors,
+        warnings,
+    })
+}
+
+/// Parse the geometry of the first clump in a `.dff`. Reads 3.1 to 3.6
+/// library versions (III, VC and SA), both triangle lists and strips, and
+/// prelit vertex colors. Platform-native geometry isn't supported.
+pub fn parse(data: &[u8]) -> Result<Dff, String> {
+    let clump = first_clump(data)?;
+    let parts = children(data, &clump)?;
+    let list = child(&parts, CHUNK_GEOMETRY_LIST)
+        .ok_or_else(|| format!("Clump at offset {:#x} has no geometry list.", clump.offset))?;
+    let geometries = children(data, list)?
+        .iter()
+        .filter(|chunk| chunk.kind == CHUNK_GEOMETRY)
+        .map(|chunk| parse_geometry(data, chunk))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Dff {
+        version: clump.version,
+        geometries,
+    })
+}
+
+fn first_clump(data: &[u8]) -> Result<Chunk, String> {
+    let mut offset = 0;
+    while offset < data.len() {
+        let chunk = chunk_at(data, offset, data.len())?;
+        if chunk.kind == CHUNK_CLUMP {
+            return Ok(chunk);
+        }
+        offset = chunk.end;
+    }
+    Err("The DFF contains no clump.".to_string())
+}
+
+/// The frame hierarchy of the first clump in a `.dff`, in stored order.
+pub fn frames(data: &[u8]) -> Result<Vec<Frame>, String> {
+    let clump = first_clump(data)?;
+    let parts = children(data, &clump)?;
+    let list = child(&parts, CHUNK_FRAME_LIST)
+        .ok_or_else(|| format!("Clump at offset {:#x} has no frame list.", clump.offset))?;
+    let list_parts = children(data, list)?;
+    let header = child(&list_parts, CHUNK_STRUCT)
+        .ok_or_else(|| format!("Frame list at offset {:#x} has no struct.", list.offset))?;
+
+    let mut cursor = Cursor::new(data, header, "Frame list struct");
+    let count = cursor.u32()? as usize;
+    let mut frames = Vec::with_capacity(count.min(4096));
+    for index in 0..count {
+        // Rotation rows (right, up, at), then the position.
+        let values = cursor.f32s(12)?;
+        let parent = cursor.u32()? as i32;
+        cursor.u32()?; // matrix flags
+        if parent < -1 || parent >= count as i32 {
+            return Err(format!(
+                "Frame {index} in the list at offset {:#x} has parent {parent}, but there are only {count} frames.",
+                list.offset
+            ));
+        }
+        // Each stored row becomes a column.
+        let mut matrix = [0.0; 16];
+        for (column, axis) in values.chunks_exact(3).enumerate() {
+            matrix[column * 4..column * 4 + 3].copy_from_slice(axis);
+        }
+        matrix[15] = 1.0;
+        frames.push(Frame {
+            index,
+            parent,
+            matrix,
+            name: None,
+        });
+    }
+
+    // Each frame's extension follows the struct in the same order.
+    let extensions = list_parts
+        .iter()
+        .filter(|chunk| chunk.kind == CHUNK_EXTENSION);
+    for (frame, extension) in frames.iter_mut().zip(extensions) {
+        let plugins = children(data, extension)?;
+        if let Some(name) = child(&plugins, CHUNK_NODE_NAME) {
+            frame.name = Some(read_string(data, name)).filter(|name| !name.is_empty());
+        }
+    }
+    Ok(frames)
+}
+
+fn parse_geometry(data: &[u8], chunk: &Chunk) -> Result<Geometry, String> {
+    let parts = children(data, chunk)?;
+    let header = child(&parts, CHUNK_STRUCT)
+        .ok_or_else(|| format!("Geometry at offset {:#x} has no struct.", chunk.offset))?;
+    let mut cursor = Cursor::new(data, header, "Geometry struct");
+    let flags = cursor.u32()?;
+    let triangle_count = cursor.u32()? as usize;
+    let vertex_count = cursor.u32()? as usize;
+    let morph_target_count = cursor.u32()?;
+    if flags & FLAG_NATIVE != 0 {
+        return Err(format!(
+            "Geometry at offset {:#x} is in a platform-native layout, which isn't supported.",
+            chunk.offset
+        ));
+    }
+    if header.version < VERSION_WITHOUT_SURFACE_PROPS {
+        cursor.take(12)?;
+    }
+
+    let colors = if flags & FLAG_PRELIT != 0 {
+        let len = vertex_count.checked_mul(4).ok_or_else(|| {
+            format!(
+                "Geometry at offset {:#x} has an impossible vertex count {vertex_count}.",
+                chunk.offset
+            )
+        })?;
+        Some(cursor.take(len)?.to_vec())
+    } else {
+        None
+    };
+
+    let uv_sets = uv_set_count(flags);
+    let mut uvs = None;
+    for set in 0..uv_sets {
+        let coords = cursor.f32s(vertex_count.saturating_mul(2))?;
+        if set == 0 {
+            uvs = Some(coords);
+        }
+    }
+
+    // Stored as (v1, v0, material, v2).
+    let mut triangles = Vec::with_capacity(triangle_count.min(1 << 20));
+    for _ in 0..triangle_count {
+        let [v1, v0, material, v2] = [cursor.u16()?, cursor.u16()?, cursor.u16()?, cursor.u16()?];
+        triangles.push(Triangle {
+            vertices: [u32::from(v0), u32::from(v1), u32::from(v2)],
+            material,
+        });
+    }
+
+    // Only the first morph target; the others are animation frames.
+    let (mut positions, mut normals) = (Vec::new(), None);
+    if morph_target_count > 0 {
+        cursor.take(16)?; // bounding sphere
+        let has_positions = cursor.u32()? != 0;
+        let has_normals = cursor.u32()? != 0;
+        if has_positions {
+            positions = cursor.f32s(vertex_count.saturating_mul(3))?;
+        }
+        if has_normals {
+            normals = Some(cursor.f32s(vertex_count.saturating_mul(3))?);
+        }
+    }
+
+    let materials = match child(&parts, CHUNK_MATERIAL_LIST) {
+        Some(list) => parse_material_list(data, list)?,
+        None => Vec::new(),
+    };
+
+    // The bin mesh holds the indices the game actually draws; strip
+    // geometry and some converters' output leave the face list empty or
+    // stale.
+    if let Some(bin_mesh) = find_bin_mesh(data, &parts)? {
+        triangles = bin_mesh.triangles;
+    }
+
+    for (index, triangle) in triangles.iter().enumerate() {
+        if let Some(vertex) = triangle
+            .vertices
+            .iter()
+            .find(|vertex| **vertex as usize >= vertex_count)
+        {
+            return Err(format!(
+                "Triangle {index} of the geometry at offset {:#x} uses vertex {vertex}, but there are only {vertex_count}.",
+                chunk.offset
+            ));
+        }
+    }
+
+    let night_colors = find_night_colors(data, &parts, vertex_count)?;
+
+    Ok(Geometry {
+        positions,
+        normals,
+        uvs,
+        colors,
+        night_colors,
+        triangles,
+        materials,
+    })
+}
+
+/// The `kind` plugin in the extension among `parts`, if there is one.
+fn plugin(data: &[u8], parts: &[Chunk], kind: u32) -> Result<Option<Chunk>, String> {
+    let Some(extension) = child(parts, CHUNK_EXTENSION) else {
+        return Ok(None);
+    };
+    Ok(child(&children(data, extension)?, kind).copied())
+}
+
+fn find_night_colors(
+    data: &[u8],
+    parts: &[Chunk],
+    vertex_count: usize,
+) -> Result<Option<Vec<u8>>, String> {
+    let Some(chunk) = plugin(data, parts, CHUNK_EXTRA_VERT_COLOUR)? else {
+        return Ok(None);
+    };
+    let mut cursor = Cursor::new(data, &chunk, "Extra vertex colors");
+    // A zero flag means the extension is there but holds no colors.
+    if cursor.u32()? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(cursor.take(vertex_count.saturating_mul(4))?.to_vec()))
+}
+
+fn parse_material_list(data: &[u8], list: &Chunk) -> Result<Vec<Material>, String> {
+    let parts = children(data, list)?;
+    let header = child(&parts, CHUNK_STRUCT)
+        .ok_or_else(|| format!("Material list at offset {:#x} has no struct.", list.offset))?;
+    let mut cursor = Cursor::new(data, header, "Material list struct");
+    let count = cursor.u32()? as usize;
+    let mut slots = Vec::with_capacity(count.min(4096));
+    for _ in 0..count {
+        slots.push(cursor.u32()? as i32);
+    }
+
+    let mut defined = parts.iter().filter(|chunk| chunk.kind == CHUNK_MATERIAL);
+    let mut materials: Vec<Material> = Vec::with_capacity(slots.len());
+    for (index, slot) in slots.into_iter().enumerate() {
+        // A non-negative slot reuses an earlier material.
+        let material = if slot >= 0 {
+            materials.get(slot as usize).cloned().ok_or_else(|| {
+                format!(
+                    "Material {index} in the list at offset {:#x} refers to missing material {slot}.",
+                    list.offset
+                )
+            })?
+        } else {
+            let chunk = defined.next().ok_or_else(|| {
+                format!(
+                    "Material list at offset {:#x} declares more materials than it contains.",
+                    list.offset
+                )
+            })?;
+            parse_material(data, chunk)?
+        };
+        materials.push(material);
+    }
+    Ok(materials)
+}
+
+fn parse_material(data: &[u8], chunk: &Chunk) -> Result<Material, String> {
+    let parts = children(data, chunk)?;
+    let header = child(&parts, CHUNK_STRUCT)
+        .ok_or_else(|| format!("Material at offset {:#x} has no struct.", chunk.offset))?;
+    let mut cursor = Cursor::new(data, header, "Material struct");
+    cursor.u32()?; // flags
+    let rgba = cursor.take(4)?;
+    let color = [rgba[0], rgba[1], rgba[2], rgba[3]];
+
+    // A texture holds its name and then its mask name.
+    let (texture_name, mask_name) = match child(&parts, CHUNK_TEXTURE) {
+        Some(texture) => {
+            let mut names = children(data, texture)?
+                .into_iter()
+                .filter(|chunk| chunk.kind == CHUNK_STRING)
+                .map(|chunk| read_string(data, &chunk))
+                .map(|name| Some(name).filter(|name| !name.is_empty()));
+            (names.next().flatten(), names.next().flatten())
+        }
+        None => (None, None),
+    };
+    Ok(Material {
+        color,
+        has_alpha: color[3] < 255 || mask_name.is_some(),
+        texture_name,
+        mask_name,
+    })
+}
+
+struct BinMesh {
+    info: BinMeshInfo,
+    /// Strips already unrolled into a list.
+    triangles: Vec<Triangle>,
+}
+
+/// The Bin Mesh PLG among a geometry's `parts`, if it has one.
+fn find_bin_mesh(data: &[u8], parts: &[Chunk]) -> Result<Option<BinMesh>, String> {
+    let Some(extension) = child(parts, CHUNK_EXTENSION) else {
+        return Ok(None);
+    };
+    match child(&children(data, extension)?, CHUNK_BIN_MESH) {
+        Some(chunk) => parse_bin_mesh(data, chunk).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn parse_bin_mesh(data: &[u8], chunk: &Chunk) -> Result<BinMesh, String> {
+    let mut cursor = Cursor::new(data, chunk, "Bin mesh");
+    let is_strip = cursor.u32()? & 1 != 0;
+    let split_count = cursor.u32()?;
+    let total_indices = cursor.u32()?;
+
+    let mut triangles = Vec::new();
+    for _ in 0..split_count {
+        let index_count = cursor.u32()? as usize;
+        let material = cursor.u32()? as u16;
+        let mut indices = Vec::with_capacity(index_count.min(1 << 20));
+        for _ in 0..index_count {
+            indices.push(cursor.u32()?);
+        }
+
+        if is_strip {
+            for (i, window) in indices.windows(3).enumerate() {
+                let [a, b, c] = [window[0], window[1], window[2]];
+                // Degenerate triangles only stitch strips together.
+                if a == b || b == c || a == c {
+                    continue;
+                }
+                // Every other strip triangle is wound the other way.
+                let vertices = if i % 2 == 0 { [a, b, c] } else { [b, a, c] };
+                triangles.push(Triangle { vertices, material });
+            }
+        } else {
+            for triangle in indices.chunks_exact(3) {
+                triangles.push(Triangle {
+                    vertices: [triangle[0], triangle[1], triangle[2]],
+                    material,
+                });
+            }
+        }
+    }
+    Ok(BinMesh {
+        info: BinMeshInfo {
+            splits: split_count,
+            strips: is_strip,
+            index_count: total_indices,
+        },
+        triangles,
+    })
+}
+
+/// One mesh per geometry and material, named `<base>_<material>` (with the
+/// geometry index too when there are several), holding only the vertices
+/// its triangles use. Prelit colors are carried along for exports, though
+/// `.clmesh` itself has no color channel.
+pub fn to_meshes(dff: &Dff, base_name: &str) -> Vec<clmesh::Mesh> {
+    let mut meshes = Vec::new();
+    for (geometry_index, geometry) in dff.geometries.iter().enumerate() {
+        let mut by_material: BTreeMap<u16, Vec<[u32; 3]>> = BTreeMap::new();
+        for triangle in &geometry.triangles {
+            by_material
+                .entry(triangle.material)
+                .or_default()
+                .push(triangle.vertices);
+        }
+
+        for (material, triangles) in by_material {
+            let mut remap = HashMap::new();
+            let mut mesh = clmesh::Mesh {
+                name: if dff.geometries.len() > 1 {
+                    format!("{base_name}_{geometry_index}_{material}")
+                } else {
+                    format!("{base_name}_{material}")
+                },
+                material: geometry
+                    .materials
+                    .get(material as usize)
+                    .and_then(|material| material.texture_name.clone())
+                    .unwrap_or_default(),
+                positions: Vec::new(),
+                normals: geometry.normals.as_ref().map(|_| Vec::new()),
+                uvs: geometry.uvs.as_ref().map(|_| Vec::new()),
+                colors: geometry.colors.as_ref().map(|_| Vec::new()),
+                indices: Vec::with_capacity(triangles.len() * 3),
+            };
+            for vertex in triangles.into_iter().flatten() {
+                let next = remap.len() as u32;
+                let index = *remap.entry(vertex).or_insert_with(|| {
+                    let v = vertex as usize;
+                    mesh.positions.extend_from_slice(
+                        geometry
+                            .positions
+                            .get(v * 3..v * 3 + 3)
+                            .unwrap_or(&[0.0; 3]),
+                    );
+                    if let (Some(out), Some(normals)) = (&mut mesh.normals, &geometry.normals) {
+                        out.extend_from_slice(normals.get(v * 3..v * 3 + 3).unwrap_or(&[0.0; 3]));
+                    }
+                    if let (Some(out), Some(uvs)) = (&mut mesh.uvs, &geometry.uvs) {
+                        out.extend_from_slice(uvs.get(v * 2..v * 2 + 2).unwrap_or(&[0.0; 2]));
+                    }
+                    if let (Some(out), Some(colors)) = (&mut mesh.colors, &geometry.colors) {
+                        out.extend_from_slice(colors.get(v * 4..v * 4 + 4).unwrap_or(&[255; 4]));
+                    }
+                    next
+                });
+                mesh.indices.push(index);
+            }
+            meshes.push(mesh);
+        }
+    }
+    meshes
+}
+
+/// Per-geometry vertex, triangle, material and UV set counts and flags of
+/// a .dff, whether each is skinned, with the vertex and triangle totals
+/// across the file. The range
+/// of each UV set is reported too, and `uvOutOfRange` flags tiling
+/// coordinates outside [0, 1].
+#[tauri::command]
+pub async fn inspect_dff(path: String) -> Result<DffInspection, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "dff") {
+            return Err("Only .dff files are supported by inspect_dff".to_string());
+        }
+        inspect(Path::new(&path))
+    })
+    .await
+    .map_err(|e| format!("Failed to join DFF inspection task: {e}"))?
+}
+
+/// The materials of each geometry in a .dff, as
+/// `[[{color, textureName, maskName, hasAlpha}]]` in the order the
+/// geometries and their material lists appear. Names are null when unset.
+#[tauri::command]
+pub async fn get_dff_materials(path: String) -> Result<Vec<Vec<Material>>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "dff") {
+            return Err("Only .dff files are supported by get_dff_materials".to_string());
+        }
+        materials(Path::new(&path))
+    })
+    .await
+    .map_err(|e| format!("Failed to join DFF materials task: {e}"))?
+}
+
+/// The frame hierarchy of a .dff as `[{index, parent, matrix, name}]`, where
+/// `parent` is -1 for the root, `matrix` is the column-major local
+/// transform and `name` is the node name (e.g. `chassis_dummy`), null for
+/// frames that have none.
+#[tauri::command]
+pub async fn get_dff_frames(path: String) -> Result<Vec<Frame>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "dff") {
+            return Err("Only .dff files are supported by get_dff_frames".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read DFF: {e}"))?;
+        frames(&data)
+    })
+    .await
+    .map_err(|e| format!("Failed to join DFF frames task: {e}"))?
+}
+
+/// How a geometry's vertex colors are lit.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorStats {
+    /// Vertices whose RGB is pure white, i.e. unlit.
+    pub white_count: usize,
+    /// Mean of R, G and B over every vertex, from 0 to 1.
+    pub average_brightness: f32,
+}
+
+impl ColorStats {
+    fn of(rgba: &[u8]) -> Self {
+        let (mut white_count, mut total) = (0, 0u64);
+        for color in rgba.chunks_exact(4) {
+            if color[..3] == [255; 3] {
+                white_count += 1;
+            }
+            total += u64::from(color[0]) + u64::from(color[1]) + u64::from(color[2]);
+        }
+        let channels = (rgba.len() / 4 * 3).max(1);
+        Self {
+            white_count,
+            average_brightness: total as f32 / channels as f32 / 255.0,
+        }
+    }
+}
+
+/// What `get_dff_vertex_colors` returns for one geometry.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VertexColors {
+    pub vertex_count: usize,
+    /// Prelit (day) colors, RGBA per vertex.
+    pub day_rgba_base64: Option<String>,
+    pub day: Option<ColorStats>,
+    /// Extra Vert Colour (night) colors, RGBA per vertex.
+    pub night_rgba_base64: Option<String>,
+    pub night: Option<ColorStats>,
+}
+
+/// The day (prelit) and night (Extra Vert Colour) vertex colors of
+/// geometry `geometry_index` of a .dff, as base64 RGBA with how many
+/// vertices are plain white and the average brightness. Either is null when
+/// the geometry doesn't have it.
+#[tauri::command]
+pub async fn get_dff_vertex_colors(
+    path: String,
+    geometry_index: usize,
+) -> Result<VertexColors, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "dff") {
+            return Err("Only .dff files are supported by get_dff_vertex_colors".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read DFF: {e}"))?;
+        let dff = parse(&data)?;
+        let count = dff.geometries.len();
+        let geometry = dff.geometries.get(geometry_index).ok_or_else(|| {
+            format!("Geometry {geometry_index} is out of range (the DFF has {count}).")
+        })?;
+
+        use base64::Engine;
+        let encode = |rgba: &[u8]| base64::engine::general_purpose::STANDARD.encode(rgba);
+        Ok(VertexColors {
+            vertex_count: geometry.positions.len() / 3,
+            day_rgba_base64: geometry.colors.as_deref().map(encode),
+            day: geometry.colors.as_deref().map(ColorStats::of),
+            night_rgba_base64: geometry.night_colors.as_deref().map(encode),
+            night: geometry.night_colors.as_deref().map(ColorStats::of),
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to join DFF vertex colors task: {e}"))?
+}
+
+// 2dfx entry types with a decoded body.
+const EFFECT_LIGHT: u32 = 0;
+const EFFECT_PARTICLE: u32 = 1;
+// Light entries are 76 bytes, 80 with a look direction.
+const EFFECT_LIGHT_SIZE: usize = 76;
+
+/// One entry of a geometry's 2d Effect extension.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Effect {
+    /// Index of the geometry carrying it.
+    pub geometry: usize,
+    pub position: [f32; 3],
+    pub type_id: u32,
+    /// `light`, `particle`, `pedAttractor`, `sunGlare`, `enterExit`,
+    /// `roadsign`, `triggerPoint`, `coverPoint`, `escalator` or `unknown`.
+    pub kind: &'static str,
+    pub light: Option<EffectLight>,
+    /// Particle effect name, for `particle` entries.
+    pub particle: Option<String>,
+    /// The entry's body as hex, for types without a decoded form.
+    pub raw_hex: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectLight {
+    pub color: [u8; 4],
+    pub corona_far_clip: f32,
+    pub point_light_range: f32,
+    pub corona_size: f32,
+    pub shadow_size: f32,
+    pub corona_show_mode: u8,
+    pub corona_reflection: bool,
+    pub corona_flare_type: u8,
+    pub shadow_color_multiplier: u8,
+    /// First flag byte in the low bits, second in the high.
+    pub flags: u16,
+    pub corona_texture: String,
+    pub shadow_texture: String,
+    pub shadow_z_distance: u8,
+}
+
+fn effect_kind(type_id: u32) -> &'static str {
+    match type_id {
+        EFFECT_LIGHT => "light",
+        EFFECT_PARTICLE => "particle",
+        3 => "pedAttractor",
+        4 => "sunGlare",
+        6 => "enterExit",
+        7 => "roadsign",
+        8 => "triggerPoint",
+        9 => "coverPoint",
+        10 => "escalator",
+        _ => "unknown",
+    }
+}
+
+fn parse_light(body: &[u8], offset: usize) -> Result<EffectLight, String> {
+    if body.len() < EFFECT_LIGHT_SIZE {
+        return Err(format!(
+            "2dfx light at offset {offset:#x} is {} bytes, expected at least {EFFECT_LIGHT_SIZE}.",
+            body.len()
+        ));
+    }
+    let f32_at =
+        |at: usize| f32::from_le_bytes([body[at], body[at + 1], body[at + 2], body[at + 3]]);
+    Ok(EffectLight {
+        color: [body[0], body[1], body[2], body[3]],
+        corona_far_clip: f32_at(4),
+        point_light_range: f32_at(8),
+        corona_size: f32_at(12),
+        shadow_size: f32_at(16),
+        corona_show_mode: body[20],
+        corona_reflection: body[21] != 0,
+        corona_flare_type: body[22],
+        shadow_color_multiplier: body[23],
+        flags: u16::from(body[24]) | u16::from(body[74]) << 8,
+        corona_texture: c_string(&body[25..49]),
+        shadow_texture: c_string(&body[49..73]),
+        shadow_z_distance: body[73],
+    })
+}
+
+/// Every 2dfx entry of every geometry in `data`, in file order.
+pub fn effects(data: &[u8]) -> Result<Vec<Effect>, String> {
+    let mut effects = Vec::new();
+    for (geometry, chunk) in geometry_chunks(data).enumerate() {
+        let chunk = chunk?;
+        let parts = children(data, &chunk)?;
+        let Some(effect_chunk) = plugin(data, &parts, CHUNK_2D_EFFECT)? else {
+            continue;
+        };
+        let mut cursor = Cursor::new(data, &effect_chunk, "2d Effect");
+        let count = cursor.u32()?;
+        for _ in 0..count {
+            let position = cursor.f32s(3)?;
+            let type_id = cursor.u32()?;
+            let size = cursor.u32()? as usize;
+            let offset = cursor.offset();
+            let body = cursor.take(size)?;
+            let mut effect = Effect {
+                geometry,
+                position: [position[0], position[1], position[2]],
+                type_id,
+                kind: effect_kind(type_id),
+                light: None,
+                particle: None,
+                raw_hex: None,
+            };
+            match type_id {
+                EFFECT_LIGHT => effect.light = Some(parse_light(body, offset)?),
+                EFFECT_PARTICLE => effect.particle = Some(c_string(body)),
+                _ => effect.raw_hex = Some(body.iter().map(|byte| format!("{byte:02x}")).collect()),
+            }
+            effects.push(effect);
+        }
+    }
+    Ok(effects)
+}
+
+/// The 2dfx entries (lights, particles, roadsigns, ...) of a .dff, each
+/// with the geometry it's on, its position and type. Lights are decoded
+/// (color, corona and shadow sizes and textures, flags) and particles
+/// named; other types come back as `rawHex`.
+#[tauri::command]
+pub async fn get_dff_2dfx(path: String) -> Result<Vec<Effect>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "dff") {
+            return Err("Only .dff files are supported by get_dff_2dfx".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read DFF: {e}"))?;
+        effects(&data)
+    })
+    .await
+    .map_err(|e| format!("Failed to join DFF 2dfx task: {e}"))?
+}
+
+/// How many texture references one rename touched.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextureRename {
+    pub from: String,
+    pub to: String,
+    pub references: usize,
+    /// Set when nothing matched `from`.
+    pub warning: Option<String>,
+}
+
+/// The texture name string of every material in `data`.
+fn texture_name_chunks(data: &[u8]) -> Result<Vec<Chunk>, String> {
+    let mut names = Vec::new();
+    for geometry in geometry_chunks(data) {
+        let geometry = geometry?;
+        let Some(list) = child(&children(data, &geometry)?, CHUNK_MATERIAL_LIST).copied() else {
+            continue;
+        };
+        for material in children(data, &list)? {
+            if material.kind != CHUNK_MATERIAL {
+                continue;
+            }
+            let Some(texture) = child(&children(data, &material)?, CHUNK_TEXTURE).copied() else {
+                continue;
+            };
+            names.extend(
+                children(data, &texture)?
+                    .into_iter()
+                    .find(|chunk| chunk.kind == CHUNK_STRING),
+            );
+        }
+    }
+    Ok(names)
+}
+
+/// Rename material texture references in place, matching names without
+/// regard to case. Each new name has to fit the existing name field, so no
+/// chunk changes size.
+pub fn rename_textures(
+    data: &mut [u8],
+    renames: &HashMap<String, String>,
+) -> Result<Vec<TextureRename>, String> {
+    let mut results = renames
+        .iter()
+        .map(|(from, to)| {
+            // The game reads names into 32-byte buffers.
+            if to.is_empty() || to.len() > 31 || !to.is_ascii() {
+                return Err(format!(
+                    "`{to}` isn't a valid texture name (1 to 31 ASCII characters)."
+                ));
+            }
+            Ok(TextureRename {
+                from: from.clone(),
+                to: to.clone(),
+                references: 0,
+                warning: None,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    results.sort_by(|a, b| a.from.cmp(&b.from));
+
+    for chunk in texture_name_chunks(data)? {
+        let name = read_string(data, &chunk);
+        let Some(rename) = results
+            .iter_mut()
+            .find(|rename| rename.from.eq_ignore_ascii_case(&name))
+        else {
+            continue;
+        };
+        let field = &mut data[chunk.start..chunk.end];
+        if rename.to.len() >= field.len() {
+            return Err(format!(
+                "`{}` doesn't fit the {}-byte name field of `{name}` at offset {:#x}.",
+                rename.to,
+                field.len(),
+                chunk.offset
+            ));
+        }
+        field.fill(0);
+        field[..rename.to.len()].copy_from_slice(rename.to.as_bytes());
+        rename.references += 1;
+    }
+
+    for rename in &mut results {
+        if rename.references == 0 {
+            rename.warning = Some(format!("No material uses `{}`.", rename.from));
+        }
+    }
+    Ok(results)
+}
+
+/// Rename texture references in a .dff's materials (e.g.
+/// `vehiclegrunge256` to `myskin`), matching names case-insensitively. New
+/// names are limited to 31 characters and must fit the old name's field.
+/// Writes to `out_path` when given, otherwise replaces the file and keeps
+/// the original as `<dff>.bak`. Returns how many references each rename
+/// touched, with a warning for those that matched none.
+#[tauri::command]
+pub async fn rename_dff_textures(
+    path: String,
+    renames: HashMap<String, String>,
+    out_path: Option<String>,
+) -> Result<Vec<TextureRename>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "dff") {
+            return Err("Only .dff files are supported by rename_dff_textures".to_string());
+        }
+        let mut data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read DFF: {e}"))?;
+        let results = rename_textures(&mut data, &renames)?;
+        match out_path.filter(|out| !out.trim().is_empty()) {
+            Some(out_path) => std::fs::write(paths::long(Path::new(&out_path)), &data)
+                .map_err(|e| format!("Failed to write DFF: {e}"))?,
+            None => paths::replace_file(Path::new(&path), &data, "DFF")?,
+        }
+        Ok(results)
+    })
+    .await
+    .map_err(|e| format!("Failed to join DFF rename task: {e}"))?
+}
+
+/// Scale, then rotation about X, Y and Z in that order, then translation.
+pub struct Transform {
+    pub scale: f32,
+    pub rotate_deg: [f32; 3],
+    pub translate: [f32; 3],
+}
+
+impl Transform {
+    fn is_identity(&self) -> bool {
+        self.scale == 1.0 && self.rotate_deg == [0.0; 3] && self.translate == [0.0; 3]
+    }
+
+    /// Row-major rotation matrix, Z * Y * X.
+    fn rotation(&self) -> [[f32; 3]; 3] {
+        let [x, y, z] = self.rotate_deg.map(f32::to_radians);
+        let (sx, cx) = x.sin_cos();
+        let (sy, cy) = y.sin_cos();
+        let (sz, cz) = z.sin_cos();
+        [
+            [cz * cy, cz * sy * sx - sz * cx, cz * sy * cx + sz * sx],
+            [sz * cy, sz * sy * sx + cz * cx, sz * sy * cx - cz * sx],
+            [-sy, cy * sx, cy * cx],
+        ]
+    }
+}
+
+fn rotate(m: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+fn write_f32s(data: &mut [u8], offset: usize, values: &[f32]) {
+    for (index, value) in values.iter().enumerate() {
+        let at = offset + index * 4;
+        data[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// Apply `transform` to the positions and normals of every geometry in
+/// `data` and recompute their bounding spheres. Vertex data keeps its size,
+/// so the file is patched in place. Returns the number of vertices moved.
+pub fn transform_geometry(data: &mut [u8], transform: &Transform) -> Result<usize, String> {
+    let geometries = geometry_chunks(data).collect::<Result<Vec<_>, String>>()?;
+    let rotation = transform.rotation();
+    let mut moved = 0;
+    let mut patches = Vec::new();
+    for chunk in &geometries {
+        let parts = children(data, chunk)?;
+        if plugin(data, &parts, CHUNK_SKIN)?.is_some() {
+            return Err(format!(
+                "Geometry at offset {:#x} is skinned; transforming skinned geometry isn't supported yet.",
+                chunk.offset
+            ));
+        }
+        let header = child(&parts, CHUNK_STRUCT)
+            .ok_or_else(|| format!("Geometry at offset {:#x} has no struct.", chunk.offset))?;
+        let mut cursor = Cursor::new(data, header, "Geometry struct");
+        let flags = cursor.u32()?;
+        let triangle_count = cursor.u32()? as usize;
+        let vertex_count = cursor.u32()? as usize;
+        let morph_target_count = cursor.u32()?;
+        if flags & FLAG_NATIVE != 0 {
+            return Err(format!(
+                "Geometry at offset {:#x} is in a platform-native layout, which isn't supported.",
+                chunk.offset
+            ));
+        }
+        if morph_target_count > 1 {
+            return Err(format!(
+                "Geometry at offset {:#x} has {morph_target_count} morph targets; transforming morph targets isn't supported yet.",
+                chunk.offset
+            ));
+        }
+        if morph_target_count == 0 {
+            continue;
+        }
+        if header.version < VERSION_WITHOUT_SURFACE_PROPS {
+            cursor.take(12)?;
+        }
+        if flags & FLAG_PRELIT != 0 {
+            cursor.take(vertex_count.saturating_mul(4))?;
+        }
+        for _ in 0..uv_set_count(flags) {
+            cursor.take(vertex_count.saturating_mul(8))?;
+        }
+        cursor.take(triangle_count.saturating_mul(8))?;
+
+        let sphere_offset = cursor.offset();
+        cursor.take(16)?;
+        let has_positions = cursor.u32()? != 0;
+        let has_normals = cursor.u32()? != 0;
+        if !has_positions {
+            continue;
+        }
+        let positions_offset = cursor.offset();
+        let mut positions = cursor.f32s(vertex_count.saturating_mul(3))?;
+        let normals_offset = cursor.offset();
+        let mut normals = if has_normals {
+            cursor.f32s(vertex_count.saturating_mul(3))?
+        } else {
+            Vec::new()
+        };
+
+        let mut bounds = None;
+        for p in positions.chunks_exact_mut(3) {
+            let scaled = [p[0], p[1], p[2]].map(|v| v * transform.scale);
+            let rotated = rotate(&rotation, scaled);
+            let moved_to: [f32; 3] =
+                std::array::from_fn(|axis| rotated[axis] + transform.translate[axis]);
+            p.copy_from_slice(&moved_to);
+            clmesh::BoundingBox::extend(&mut bounds, moved_to);
+        }
+        for n in normals.chunks_exact_mut(3) {
+            n.copy_from_slice(&rotate(&rotation, [n[0], n[1], n[2]]));
+        }
+        let sphere = match bounds {
+            Some(bounds) => {
+                let center: [f32; 3] =
+                    std::array::from_fn(|axis| (bounds.min[axis] + bounds.max[axis]) / 2.0);
+                let radius = positions
+                    .chunks_exact(3)
+                    .map(|p| {
+                        ((p[0] - center[0]).powi(2)
+                            + (p[1] - center[1]).powi(2)
+                            + (p[2] - center[2]).powi(2))
+                        .sqrt()
+                    })
+                    .fold(0.0, f32::max);
+                [center[0], center[1], center[2], radius]
+            }
+            None => [0.0; 4],
+        };
+        moved += vertex_count;
+        patches.push((sphere_offset, sphere.to_vec()));
+        patches.push((positions_offset, positions));
+        patches.push((normals_offset, normals));
+    }
+    for (offset, values) in patches {
+        write_f32s(data, offset, &values);
+    }
+    Ok(moved)
+}
+
+/// What `transform_dff` wrote.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformedDff {
+    pub path: String,
+    pub vertices: usize,
+}
+
+/// Bake a transform into a .dff's geometry and write it to `out_path`:
+/// positions are scaled by `scale`, rotated by `rotate_deg` (degrees about
+/// X, then Y, then Z) and moved by `translate`; normals are rotated and
+/// bounding spheres recomputed. Skinned geometry and morph targets are
+/// turned away. The identity transform writes the file unchanged.
+#[tauri::command]
+pub async fn transform_dff(
+    path: String,
+    out_path: String,
+    scale: f32,
+    rotate_deg: [f32; 3],
+    translate: [f32; 3],
+) -> Result<TransformedDff, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "dff") {
+            return Err("Only .dff files are supported by transform_dff".to_string());
+        }
+        if !crate::has_extension(&out_path, "dff") {
+            return Err(format!(
+                "transform_dff writes a .dff; `{out_path}` isn't one."
+            ));
+        }
+        if !scale.is_finite() || scale == 0.0 {
+            return Err(format!("Scale must be a non-zero number, not {scale}."));
+        }
+        let mut data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read DFF: {e}"))?;
+        let transform = Transform {
+            scale,
+            rotate_deg,
+            translate,
+        };
+        // Rewriting the floats of an identity transform would still turn
+        // -0.0 into 0.0 and re-round the spheres.
+        let vertices = if transform.is_identity() {
+            0
+        } else {
+            transform_geometry(&mut data, &transform)?
+        };
+        let out_path = PathBuf::from(out_path);
+        if let Some(parent) = out_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(paths::long(parent))
+                .map_err(|e| format!("Failed to create output dir: {e}"))?;
+        }
+        std::fs::write(paths::long(&out_path), &data)
+            .map_err(|e| format!("Failed to write DFF: {e}"))?;
+        Ok(TransformedDff {
+            path: out_path.to_string_lossy().to_string(),
+            vertices,
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to join DFF transform task: {e}"))?
+}
+
+/// A geometry's Skin PLG.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Skin {
+    pub bone_count: u8,
+    /// Bones the vertices actually reference.
+    pub used_bones: Vec<u8>,
+    pub max_weights_per_vertex: u8,
+    pub vertex_count: usize,
+    /// Four bone indices per vertex, one byte each.
+    pub bone_indices_base64: String,
+    /// Four weights per vertex, little-endian f32.
+    pub weights_base64: String,
+    /// Inverse bind matrix of each bone, column-major.
+    pub inverse_bind_matrices: Vec<[f32; 16]>,
+}
+
+/// The Skin PLG of geometry `geometry_index`, or `None` if it isn't skinned.
+pub fn skin(data: &[u8], geometry_index: usize) -> Result<Option<Skin>, String> {
+    let mut count = 0;
+    let mut geometry = None;
+    for chunk in geometry_chunks(data) {
+        let chunk = chunk?;
+        if count == geometry_index {
+            geometry = Some(chunk);
+        }
+        count += 1;
+    }
+    let geometry = geometry.ok_or_else(|| {
+        format!("Geometry {geometry_index} is out of range (the DFF has {count}).")
+    })?;
+    let parts = children(data, &geometry)?;
+    let header = child(&parts, CHUNK_STRUCT)
+        .ok_or_else(|| format!("Geometry at offset {:#x} has no struct.", geometry.offset))?;
+    let mut header_cursor = Cursor::new(data, header, "Geometry struct");
+    header_cursor.take(8)?;
+    let vertex_count = header_cursor.u32()? as usize;
+    let Some(chunk) = plugin(data, &parts, CHUNK_SKIN)? else {
+        return Ok(None);
+    };
+
+    let mut cursor = Cursor::new(data, &chunk, "Skin PLG");
+    let bone_count = cursor.u8()?;
+    let used_bone_count = cursor.u8()?;
+    let max_weights_per_vertex = cursor.u8()?;
+    cursor.u8()?;
+    let used_bones = cursor.take(usize::from(used_bone_count))?.to_vec();
+    let indices = cursor.take(vertex_count.saturating_mul(4))?;
+    let weights = cursor.take(vertex_count.saturating_mul(16))?;
+    // Files without a used-bone table put a marker before each matrix.
+    let old_format = used_bone_count == 0;
+    let mut inverse_bind_matrices = Vec::with_capacity(usize::from(bone_count));
+    for _ in 0..bone_count {
+        if old_format {
+            cursor.u32()?;
+        }
+        let values = cursor.f32s(16)?;
+        let mut matrix = [0.0; 16];
+        matrix.copy_from_slice(&values);
+        inverse_bind_matrices.push(matrix);
+    }
+
+    use base64::Engine;
+    let encode = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+    Ok(Some(Skin {
+        bone_count,
+        used_bones,
+        max_weights_per_vertex,
+        vertex_count,
+        bone_indices_base64: encode(indices),
+        weights_base64: encode(weights),
+        inverse_bind_matrices,
+    }))
+}
+
+/// The Skin PLG of geometry `geometry_index` of a .dff: bone count, the
+/// bones in use, four bone indices and weights per vertex (base64) and each
+/// bone's inverse bind matrix. Null when the geometry isn't skinned.
+#[tauri::command]
+pub async fn get_dff_skin(path: String, geometry_index: usize) -> Result<Option<Skin>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "dff") {
+            return Err("Only .dff files are supported by get_dff_skin".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read DFF: {e}"))?;
+        skin(&data, geometry_index)
+    })
+    .await
+    .map_err(|e| format!("Failed to join DFF skin task: {e}"))?
+}
+
+/// The RenderWare version a .dff was written with and the game it targets,
+/// whether it uses version-specific features (native geometry, extra vertex
+/// colors), and warnings such as chunks with mixed versions.
+#[tauri::command]
+pub async fn get_dff_version(path: String) -> Result<DffVersion, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "dff") {
+            return Err("Only .dff files are supported by get_dff_version".to_string());
+        }
+        read_version(Path::new(&path))
+    })
+    .await
+    .map_err(|e| format!("Failed to join DFF version task: {e}"))?
+}
+
+/// Check a .dff's structure: every chunk within its parent and the file,
+/// triangle vertex and material indices in range. Returns
+/// `{valid, findings: [{level, offset, description}]}`, where `valid` means
+/// no finding is an `error`.
+#[tauri::command]
+pub async fn validate_dff(path: String) -> Result<DffValidation, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "dff") {
+            return Err("Only .dff files are supported by validate_dff".to_string());
+        }
+        Ok(validate(Path::new(&path)))
+    })
+    .await
+    .map_err(|e| format!("Failed to join DFF validation task: {e}"))?
+}
+
+/// Parse a plain RenderWare .dff (III, VC or SA) natively into a cached
+/// `.clmesh`, without the bridge or converter, and return it like
+/// `parse_yft`. `meta` has the mesh, vertex, index and material counts,
+/// the texture names, the library version and whether the model is prelit.
+#[tauri::command]
+pub async fn parse_dff(
+    path: String,
+    app: crate::AppHandle,
+) -> Result<crate::ParseYftResult, String> {
+    tauri::async_runtime::spawn_blocking(move || parse_dff_blocking(&path, &app))
+        .await
+        .map_err(|e| format!("Failed to join DFF parse task: {e}"))?
+}
+
+fn parse_dff_blocking(path: &str, app: &crate::AppHandle) -> Result<crate::ParseYftResult, String> {
+    if !crate::has_extension(path, "dff") {
+        return Err("Only .dff files are supported by parse_dff".to_string());
+    }
+
+    let key = cache::variant_key(&cache::yft_cache_key(path, CacheKeyMode::Fast)?, "dff");
+    let out_dir = cache::yft_cache_root(app)?.join(&key);
+    let out_mesh = out_dir.join("model.clmesh");
+    let out_meta = out_dir.join("meta.json");
+    let mut stats = ConversionStats::for_input(Path::new(path));
+
+    let reading_started = Instant::now();
+    let cached_meta = std::fs::read_to_string(&out_meta)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+        .filter(|_| clmesh::validate(&out_mesh).is_ok());
+    let (meta, cached) = match cached_meta {
+        Some(meta) => {
+            stats.reading_ms = conversion::elapsed_ms(reading_started);
+            (meta, true)
+        }
+        None => {
+            let data = std::fs::read(paths::long(Path::new(path)))
+                .map_err(|e| format!("Failed to read DFF: {e}"))?;
+            stats.reading_ms = conversion::elapsed_ms(reading_started);
+
+            let process_started = Instant::now();
+            let dff = parse(&data)?;
+            let base_name = Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| "model".to_string());
+            let meshes = to_meshes(&dff, &base_name);
+            if meshes.iter().all(|mesh| mesh.indices.is_empty()) {
+                return Err("The DFF contains no triangles.".to_string());
+            }
+            stats.process_ms = conversion::elapsed_ms(process_started);
+
+            let storing_started = Instant::now();
+            std::fs::create_dir_all(paths::long(&out_dir))
+                .map_err(|e| format!("Failed to create output dir: {e}"))?;
+            clmesh::write(&out_mesh, &meshes)?;
+            let textures = dff
+                .geometries
+                .iter()
+                .flat_map(|geometry| &geometry.materials)
+                .filter_map(|material| material.texture_name.clone())
+                .collect::<std::collections::BTreeSet<_>>();
+            let meta = serde_json::json!({
+                "meshCount": meshes.len(),
+                "vertexCount": meshes.iter().map(|mesh| mesh.positions.len() / 3).sum::<usize>(),
+                "indexCount": meshes.iter().map(|mesh| mesh.indices.len()).sum::<usize>(),
+                "materialCount": meshes.iter().map(|mesh| &mesh.material).filter(|name| !name.is_empty()).collect::<std::collections::BTreeSet<_>>().len(),
+                "textureNames": textures,
+                "rwVersion": format!("{:#x}", dff.version),
+                "prelit": dff.geometries.iter().any(|geometry| geometry.colors.is_some()),
+            });
+            let _ = std::fs::write(&out_meta, meta.to_string());
+            stats.storing_ms = conversion::elapsed_ms(storing_started);
+            (meta, false)
+        }
+    };
+    stats.record_outputs([out_mesh.as_path()]);
+    cache::record_use(app, &key);
+
+    Ok(crate::ParseYftResult {
+        schema_version: crate::RESULT_SCHEMA_VERSION,
+        mesh_path: out_mesh.to_string_lossy().to_string(),
+        cache_key: key,
+        key_mode: CacheKeyMode::Fast.as_str(),
+        cached,
+        meta,
+        ytd_path: None,
+        lod_paths: None,
+        sibling_path: None,
+        sibling_mesh_path: None,
+        skeleton_path: None,
+        collision_path: None,
+        collision_json_path: None,
+        collision: None,
+        stats,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SA: u32 = 0x1803_FFFF;
+
+    fn chunk(kind: u32, body: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(body.len() + 12);
+        out.extend(kind.to_le_bytes());
+        out.extend((body.len() as u32).to_le_bytes());
+        out.extend(SA.to_le_bytes());
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn container(kind: u32, parts: &[Vec<u8>]) -> Vec<u8> {
+        chunk(kind, &parts.concat())
+    }
+
+    /// A null-terminated string padded to four bytes.
+    fn string(text: &str) -> Vec<u8> {
+        let mut body = text.as_bytes().to_vec();
+        body.resize((text.len() / 4 + 1) * 4, 0);
+        chunk(CHUNK_STRING, &body)
+    }
+
+    fn floats(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    fn words(values: &[u32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    /// A Bin Mesh PLG: strips or lists, and each split's material and
+    /// indices.
+    #[derive(Clone)]
+    struct Splits {
+        strips: bool,
+        splits: Vec<(u32, Vec<u32>)>,
+    }
+
+    /// One geometry of a fixture, written the way SA stores it.
+    #[derive(Clone)]
+    struct Mesh {
+        /// Extra flag bits, e.g. the UV set count in bits 16 to 23.
+        flags: u32,
+        positions: Vec<[f32; 3]>,
+        normals: Option<Vec<[f32; 3]>>,
+        /// Bounding sphere as stored: center, then radius.
+        sphere: [f32; 4],
+        uv_sets: Vec<Vec<[f32; 2]>>,
+        colors: Option<Vec<[u8; 4]>>,
+        /// The struct's face list: vertices and material.
+        triangles: Vec<([u16; 3], u16)>,
+        /// Color, texture and mask name; a `None` color reuses material 0.
+        materials: Vec<(Option<[u8; 4]>, &'static str, &'static str)>,
+        bin_mesh: Option<Splits>,
+        /// Other plugins for the geometry's extension.
+        plugins: Vec<Vec<u8>>,
+    }
+
+    impl Default for Mesh {
+        /// A textured quad with normals and one material.
+        fn default() -> Self {
+            Self {
+                flags: 0,
+                positions: vec![
+                    [-1.0, -0.0, 0.5],
+                    [1.0, 0.0, 0.5],
+                    [1.0, 2.0, 0.5],
+                    [-1.0, 2.0, 0.5],
+                ],
+                normals: Some(vec![[0.0, 0.0, 1.0]; 4]),
+                sphere: [0.0, 1.0, 0.5, 1.5],
+                uv_sets: vec![vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]],
+                colors: None,
+                triangles: vec![([0, 1, 2], 0), ([0, 2, 3], 0)],
+                materials: vec![(Some([255, 255, 255, 255]), "body", "")],
+                bin_mesh: None,
+                plugins: Vec::new(),
+            }
+        }
+    }
+
+    fn material((color, texture, mask): (Option<[u8; 4]>, &str, &str)) -> Vec<u8> {
+        let color = color.unwrap_or_default();
+        let mut header = words(&[0]);
+        header.extend(color);
+        header.extend(words(&[0, u32::from(!texture.is_empty())]));
+        header.extend(floats(&[1.0, 1.0, 1.0]));
+        let mut parts = vec![chunk(CHUNK_STRUCT, &header)];
+        if !texture.is_empty() {
+            parts.push(container(
+                CHUNK_TEXTURE,
+                &[
+                    chunk(CHUNK_STRUCT, &words(&[0x1106])),
+                    string(texture),
+                    string(mask),
+                    chunk(CHUNK_EXTENSION, &[]),
+                ],
+            ));
+        }
+        parts.push(chunk(CHUNK_EXTENSION, &[]));
+        container(CHUNK_MATERIAL, &parts)
+    }
+
+    fn geometry(mesh: &Mesh) -> Vec<u8> {
+        let vertex_count = mesh.positions.len() as u32;
+        let mut flags = mesh.flags | 0x02; // positions
+        if mesh.normals.is_some() {
+            flags |= 0x10;
+        }
+        if mesh.colors.is_some() {
+            flags |= FLAG_PRELIT;
+        }
+        if mesh.flags & 0xFF_0000 == 0 {
+            flags |= match mesh.uv_sets.len() {
+                0 => 0,
+                1 => FLAG_TEXTURED,
+                _ => FLAG_TEXTURED2,
+            };
+        }
+        let mut header = words(&[flags, mesh.triangles.len() as u32, vertex_count, 1]);
+        for color in mesh.colors.iter().flatten() {
+            header.extend(color);
+        }
+        for set in &mesh.uv_sets {
+            header.extend(floats(&set.concat()));
+        }
+        for ([v0, v1, v2], material) in &mesh.triangles {
+            for value in [v1, v0, material, v2] {
+                header.extend(value.to_le_bytes());
+            }
+        }
+        header.extend(floats(&mesh.sphere));
+        header.extend(words(&[1, u32::from(mesh.normals.is_some())]));
+        header.extend(floats(&mesh.positions.concat()));
+        if let Some(normals) = &mesh.normals {
+            header.extend(floats(&normals.concat()));
+        }
+
+        let mut list = words(&[mesh.materials.len() as u32]);
+        let mut materials = Vec::new();
+        for entry in &mesh.materials {
+            match entry.0 {
+                Some(_) => {
+                    list.extend((-1i32).to_le_bytes());
+                    materials.push(material(*entry));
+                }
+                None => list.extend(0i32.to_le_bytes()),
+            }
+        }
+        let mut list_parts = vec![chunk(CHUNK_STRUCT, &list)];
+        list_parts.extend(materials);
+
+        let mut plugins = Vec::new();
+        if let Some(Splits { strips, splits }) = &mesh.bin_mesh {
+            let total = splits.iter().map(|(_, indices)| indices.len() as u32).sum();
+            let mut body = words(&[u32::from(*strips), splits.len() as u32, total]);
+            for (material, indices) in splits {
+                body.extend(words(&[indices.len() as u32, *material]));
+                body.extend(words(indices));
+            }
+            plugins.push(chunk(CHUNK_BIN_MESH, &body));
+        }
+        plugins.extend(mesh.plugins.iter().cloned());
+        container(
+            CHUNK_GEOMETRY,
+            &[
+                chunk(CHUNK_STRUCT, &header),
+                container(CHUNK_MATERIAL_LIST, &list_parts),
+                container(CHUNK_EXTENSION, &plugins),
+            ],
+        )
+    }
+
+    /// A clump with a root frame and an atomic per mesh.
+    fn dff(meshes: &[Mesh]) -> Vec<u8> {
+        let mut frame = floats(&[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0]);
+        frame.extend((-1i32).to_le_bytes());
+        frame.extend(words(&[0]));
+        let frames = container(
+            CHUNK_FRAME_LIST,
+            &[
+                chunk(CHUNK_STRUCT, &[words(&[1]), frame].concat()),
+                container(CHUNK_EXTENSION, &[chunk(CHUNK_NODE_NAME, b"car")]),
+            ],
+        );
+        let mut geometries = vec![chunk(CHUNK_STRUCT, &words(&[meshes.len() as u32]))];
+        geometries.extend(meshes.iter().map(geometry));
+        let mut parts = vec![
+            chunk(CHUNK_STRUCT, &words(&[meshes.len() as u32, 0, 0])),
+            frames,
+            container(CHUNK_GEOMETRY_LIST, &geometries),
+        ];
+        for index in 0..meshes.len() as u32 {
+            parts.push(container(
+                CHUNK_ATOMIC,
+                &[
+                    chunk(CHUNK_STRUCT, &words(&[0, index, 5, 0])),
+                    chunk(CHUNK_EXTENSION, &[]),
+                ],
+            ));
+        }
+        parts.push(chunk(CHUNK_EXTENSION, &[]));
+        container(CHUNK_CLUMP, &parts)
+    }
+
+    /// `data` written to `dir/name`.
+    fn write_file(dir: &tempfile::TempDir, name: &str, data: &[u8]) -> PathBuf {
+        let path = dir.path().join(name);
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn transform(
+        path: &Path,
+        out: &Path,
+        scale: f32,
+        rotate_deg: [f32; 3],
+        translate: [f32; 3],
+    ) -> Result<TransformedDff, String> {
+        tauri::async_runtime::block_on(transform_dff(
+            path.to_string_lossy().to_string(),
+            out.to_string_lossy().to_string(),
+            scale,
+            rotate_deg,
+            translate,
+        ))
+    }
+
+    #[test]
+    fn identity_transform_writes_the_file_unchanged() {
+        // -0.0 in a position would turn into 0.0 if it were rewritten.
+        let data = dff(&[Mesh::default(), Mesh::default()]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_file(&dir, "identity.dff", &data);
+        let out = path.with_file_name("identity-out.dff");
+        let written = transform(&path, &out, 1.0, [0.0; 3], [0.0; 3]).unwrap();
+        assert_eq!(written.vertices, 0);
+        assert_eq!(std::fs::read(&out).unwrap(), data);
+    }
+
+    #[test]
+    fn transform_and_its_inverse_give_back_the_same_bytes() {
+        // Powers of two and dyadic offsets keep every step exact, and the
+        // stored sphere is the one the transform recomputes.
+        let mesh = Mesh {
+            positions: vec![
+                [-3.0, -4.0, 0.25],
+                [3.0, -4.0, 0.25],
+                [3.0, 4.0, 0.25],
+                [-3.0, 4.0, 0.25],
+            ],
+            sphere: [0.0, 0.0, 0.25, 5.0],
+            ..Mesh::default()
+        };
+        let data = dff(&[mesh.clone(), mesh]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("box.dff");
+        std::fs::write(&path, &data).unwrap();
+        let there = dir.path().join("there.dff");
+        let back = dir.path().join("back.dff");
+
+        let written = transform(&path, &there, 2.0, [0.0; 3], [1.5, -2.0, 0.75]).unwrap();
+        assert_eq!(written.vertices, 8);
+        assert_ne!(std::fs::read(&there).unwrap(), data);
+        let written = transform(&there, &back, 0.5, [0.0; 3], [-0.75, 1.0, -0.375]).unwrap();
+        assert_eq!(written.vertices, 8);
+        assert_eq!(std::fs::read(&back).unwrap(), data);
+    }
+
+    #[test]
+    fn transform_writes_only_dff_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("in.dff");
+        std::fs::write(&path, dff(&[Mesh::default()])).unwrap();
+        let out = dir.path().join("out.obj");
+        let error = transform(&path, &out, 2.0, [0.0; 3], [0.0; 3])
+            .err()
+            .unwrap();
+        assert!(error.contains("out.obj"), "{error}");
+        assert!(!out.exists() && !out.with_extension("dff").exists());
+    }
+
+    #[test]
+    fn transform_moves_positions_and_turns_normals() {
+        let data = dff(&[Mesh::default()]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_file(&dir, "turn.dff", &data);
+        let out = path.with_file_name("turn-out.dff");
+        // A quarter turn about Z, doubled and lifted.
+        let written = transform(&path, &out, 2.0, [0.0, 0.0, 90.0], [0.0, 0.0, 1.0]).unwrap();
+        assert_eq!(written.vertices, 4);
+        let moved = std::fs::read(&out).unwrap();
+        assert_eq!(moved.len(), data.len());
+        let geometry = &parse(&moved).unwrap().geometries[0];
+        let expected = [
+            [0.0, -2.0, 2.0],
+            [0.0, 2.0, 2.0],
+            [-4.0, 2.0, 2.0],
+            [-4.0, -2.0, 2.0],
+        ];
+        for (position, expected) in geometry.positions.chunks(3).zip(expected) {
+            for axis in 0..3 {
+                assert!(
+                    (position[axis] - expected[axis]).abs() < 1e-5,
+                    "{position:?} vs {expected:?}"
+                );
+            }
+        }
+        for normal in geometry.normals.as_ref().unwrap().chunks(3) {
+            assert!(
+                (normal[2] - 1.0).abs() < 1e-6 && normal[0].abs() < 1e-6,
+                "{normal:?}"
+            );
+        }
+        assert!(validate_bytes(&moved).valid);
+
+        // Skinned and morphing geometry is turned away.
+        let skinned = Mesh {
+            plugins: vec![chunk(CHUNK_SKIN, &[0; 4])],
+            ..Mesh::default()
+        };
+        let path = write_file(&dir, "skinned.dff", &dff(&[skinned]));
+        let error = transform(&path, &out, 2.0, [0.0; 3], [0.0; 3])
+            .err()
+            .unwrap();
+        assert!(error.contains("skinned"), "{error}");
+        let mut data = dff(&[Mesh::default()]);
+        let header = words(&[0x02 | 0x10 | FLAG_TEXTURED, 2, 4, 1]);
+        let morph_targets = data.windows(16).position(|w| w == header).unwrap() + 12;
+        data[morph_targets] = 2;
+        let path = write_file(&dir, "morph.dff", &data);
+        let error = transform(&path, &out, 2.0, [0.0; 3], [0.0; 3])
+            .err()
+            .unwrap();
+        assert!(error.contains("morph targets"), "{error}");
+    }
+
+    /// Two geometries using most of what a geometry can hold.
+    fn busy_dff() -> Vec<u8> {
+        let prelit = Mesh {
+            colors: Some(vec![[10, 20, 30, 255]; 4]),
+            materials: vec![(Some([255, 0, 0, 255]), "body", ""), (None, "", "")],
+            triangles: vec![([0, 1, 2], 0), ([0, 2, 3], 1)],
+            ..Mesh::default()
+        };
+        let stripped = Mesh {
+            triangles: Vec::new(),
+            bin_mesh: Some(Splits {
+                strips: true,
+                splits: vec![(0, vec![0, 1, 3, 2])],
+            }),
+            ..Mesh::default()
+        };
+        dff(&[prelit, stripped])
+    }
+
+    #[test]
+    fn validate_flags_every_truncation_and_survives_bit_flips() {
+        let data = busy_dff();
+        let validation = validate_bytes(&data);
+        assert!(
+            validation.valid,
+            "{:?}",
+            validation
+                .findings
+                .iter()
+                .map(|f| &f.description)
+                .collect::<Vec<_>>()
+        );
+
+        for len in 0..data.len() {
+            assert!(!validate_bytes(&data[..len]).valid, "truncated to {len}");
+        }
+        for at in 0..data.len() {
+            for bit in 0..8 {
+                let mut flipped = data.clone();
+                flipped[at] ^= 1 << bit;
+                let _ = validate_bytes(&flipped);
+                let _ = parse(&flipped);
+            }
+        }
+        let mut seed = 0x9E37_79B9_7F4A_7C15u64;
+        for _ in 0..500 {
+            let garbage: Vec<u8> = (0..seed % 512)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    seed as u8
+                })
+                .collect();
+            assert!(!validate_bytes(&garbage).valid);
+            seed = seed.wrapping_add(1);
+        }
+    }
+
+    #[test]
+    fn validate_reports_bad_indices() {
+        let errors = |mesh: Mesh| {
+            validate_bytes(&dff(&[mesh]))
+                .findings
+                .into_iter()
+                .filter(|finding| finding.level == "error")
+                .map(|finding| finding.description)
+                .collect::<Vec<_>>()
+        };
+        let vertex = errors(Mesh {
+            triangles: vec![([0, 1, 9], 0)],
+            ..Mesh::default()
+        });
+        assert!(
+            vertex.iter().any(|e| e.contains("uses vertex 9")),
+            "{vertex:?}"
+        );
+        let material = errors(Mesh {
+            triangles: vec![([0, 1, 2], 3)],
+            ..Mesh::default()
+        });
+        assert!(
+            material.iter().any(|e| e.contains("uses material 3")),
+            "{material:?}"
+        );
+        let empty = errors(Mesh {
+            positions: Vec::new(),
+            normals: None,
+            uv_sets: Vec::new(),
+            triangles: Vec::new(),
+            ..Mesh::default()
+        });
+        assert_eq!(empty, ["The DFF has no vertices."]);
+        assert!(!validate_bytes(&chunk(CHUNK_STRUCT, &[0; 4])).valid);
+    }
+
+    #[test]
+    fn inspect_reports_both_uv_sets_and_their_bounds() {
+        let second = vec![[0.0, 0.0], [2.0, 0.0], [2.0, 1.0], [0.0, 1.0]];
+        // The set count from the high flag bits, and from the textured flag
+        // alone.
+        let counted = Mesh {
+            flags: 2 << 16,
+            uv_sets: vec![Mesh::default().uv_sets[0].clone(), second.clone()],
+            ..Mesh::default()
+        };
+        let flagged = Mesh {
+            uv_sets: vec![vec![[0.25, 0.5]; 4], second],
+            ..Mesh::default()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_file(&dir, "uvs.dff", &dff(&[counted, flagged]));
+        let inspection = inspect(&path).unwrap();
+        assert_eq!(inspection.uv_channels, 2);
+        assert!(inspection
+            .geometries
+            .iter()
+            .all(|geometry| geometry.uv_sets == 2));
+        let [first, second] = &inspection.uv_bounds[..] else {
+            panic!("{} UV sets", inspection.uv_bounds.len());
+        };
+        assert_eq!((first.min, first.max), ([0.0, 0.0], [1.0, 1.0]));
+        assert!(!first.out_of_range);
+        assert_eq!((second.min, second.max), ([0.0, 0.0], [2.0, 1.0]));
+        assert!(second.out_of_range);
+        assert!(inspection.uv_out_of_range);
+        // The first set is the one the geometry decodes.
+        let geometry = &parse(&std::fs::read(&path).unwrap()).unwrap().geometries[1];
+        assert_eq!(geometry.uvs.as_deref().unwrap()[..2], [0.25, 0.5]);
+    }
+
+    #[test]
+    fn lists_the_materials_of_each_geometry() {
+        let textured = Mesh {
+            materials: vec![
+                (Some([255, 0, 0, 255]), "body", ""),
+                (Some([255, 255, 255, 128]), "glass", ""),
+                (Some([255, 255, 255, 255]), "decal", "decala"),
+                (None, "", ""),
+            ],
+            ..Mesh::default()
+        };
+        let plain = Mesh {
+            materials: vec![(Some([1, 2, 3, 255]), "", "")],
+            ..Mesh::default()
+        };
+        let mut data = dff(&[textured, plain]);
+        // Leftovers of a longer name after the terminator are padding.
+        let at = data.windows(8).position(|w| w == b"body\0\0\0\0").unwrap();
+        data[at..at + 8].copy_from_slice(b"body\0ygr");
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_file(&dir, "materials.dff", &data);
+
+        let geometries = materials(&path).unwrap();
+        assert_eq!(geometries.len(), 2);
+        let summary: Vec<_> = geometries[0]
+            .iter()
+            .map(|material| {
+                (
+                    material.color,
+                    material.texture_name.as_deref(),
+                    material.mask_name.as_deref(),
+                    material.has_alpha,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ([255, 0, 0, 255], Some("body"), None, false),
+                ([255, 255, 255, 128], Some("glass"), None, true),
+                ([255, 255, 255, 255], Some("decal"), Some("decala"), true),
+                ([255, 0, 0, 255], Some("body"), None, false),
+            ]
+        );
+        let plain = &geometries[1][0];
+        assert_eq!(plain.color, [1, 2, 3, 255]);
+        assert!(plain.texture_name.is_none() && plain.mask_name.is_none());
+    }
+
+    #[test]
+    fn prefers_the_bin_mesh_over_the_face_list() {
+        let face_list = Mesh::default();
+        // A stale face list, with the real triangles split by material.
+        let lists = Mesh {
+            triangles: vec![([0, 1, 2], 0)],
+            materials: vec![(Some([255; 4]), "body", ""), (Some([255; 4]), "trim", "")],
+            bin_mesh: Some(Splits {
+                strips: false,
+                splits: vec![(0, vec![0, 1, 2]), (1, vec![0, 2, 3])],
+            }),
+            ..Mesh::default()
+        };
+        // An empty face list, and a strip ending in degenerate triangles.
+        let strips = Mesh {
+            triangles: Vec::new(),
+            bin_mesh: Some(Splits {
+                strips: true,
+                splits: vec![(0, vec![0, 1, 3, 2, 2, 2])],
+            }),
+            ..Mesh::default()
+        };
+        let data = dff(&[face_list, lists, strips]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_file(&dir, "bin-mesh.dff", &data);
+
+        let inspection = inspect(&path).unwrap();
+        let counts: Vec<_> = inspection
+            .geometries
+            .iter()
+            .map(|geometry| geometry.triangle_count)
+            .collect();
+        assert_eq!(counts, [2, 2, 2]);
+        assert_eq!(inspection.triangle_count, 6);
+        assert!(inspection.geometries[0].bin_mesh.is_none());
+        let lists_info = inspection.geometries[1].bin_mesh.as_ref().unwrap();
+        assert_eq!(
+            (lists_info.splits, lists_info.strips, lists_info.index_count),
+            (2, false, 6)
+        );
+        let strips_info = inspection.geometries[2].bin_mesh.as_ref().unwrap();
+        assert_eq!(
+            (
+                strips_info.splits,
+                strips_info.strips,
+                strips_info.index_count
+            ),
+            (1, true, 6)
+        );
+
+        let dff = parse(&data).unwrap();
+        let triangles = |geometry: &Geometry| {
+            geometry
+                .triangles
+                .iter()
+                .map(|triangle| (triangle.vertices, triangle.material))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            triangles(&dff.geometries[0]),
+            [([0, 1, 2], 0), ([0, 2, 3], 0)]
+        );
+        assert_eq!(
+            triangles(&dff.geometries[1]),
+            [([0, 1, 2], 0), ([0, 2, 3], 1)]
+        );
+        // Every other strip triangle is flipped back to the same winding.
+        assert_eq!(
+            triangles(&dff.geometries[2]),
+            [([0, 1, 3], 0), ([3, 1, 2], 0)]
+        );
+
+        // Exports get the bin mesh's triangles, a mesh per material.
+        let meshes = to_meshes(&dff, "car");
+        let indices: Vec<_> = meshes
+            .iter()
+            .map(|mesh| (mesh.name.as_str(), mesh.indices.len()))
+            .collect();
+        assert_eq!(
+            indices,
+            [
+                ("car_0_0", 6),
+                ("car_1_0", 3),
+                ("car_1_1", 3),
+                ("car_2_0", 6)
+            ]
+        );
+    }
+}
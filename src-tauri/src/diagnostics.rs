@@ -0,0 +1,210 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use crate::cache;
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    path: String,
+    exists: bool,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolFile {
+    name: &'static str,
+    path: String,
+    exists: bool,
+    size_bytes: Option<u64>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BridgeDiagnostics {
+    candidates: Vec<Candidate>,
+    selected: Option<String>,
+    version: Option<String>,
+    version_error: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverterDiagnostics {
+    candidates: Vec<Candidate>,
+    selected: Option<String>,
+    files: Vec<ToolFile>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheDiagnostics {
+    dir: String,
+    usage: Option<cache::CacheUsage>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolDiagnostics {
+    platform: &'static str,
+    bridge: BridgeDiagnostics,
+    converter: ConverterDiagnostics,
+    cache: CacheDiagnostics,
+    /// Exact path of every required file that isn't there.
+    missing: Vec<String>,
+}
+
+/// Everything support needs to know about the external tools: every path
+/// the bridge and converter are looked for at, which ones were picked, the
+/// bridge's `--version`, the converter's files with their sizes, and the
+/// cache directory with its usage. `missing` lists the expected path of
+/// each required file that isn't there.
+#[tauri::command]
+pub async fn get_tool_diagnostics(app: crate::AppHandle) -> Result<ToolDiagnostics, String> {
+    tauri::async_runtime::spawn_blocking(move || collect(&app))
+        .await
+        .map_err(|e| format!("Failed to join diagnostics task: {e}"))?
+}
+
+fn collect(app: &crate::AppHandle) -> Result<ToolDiagnostics, String> {
+    let mut missing = Vec::new();
+
+    let bridge_candidates = crate::codewalker_bridge_candidates(app);
+    let bridge = bridge_candidates.iter().find(|path| path.exists()).cloned();
+    let (version, version_error) = match &bridge {
+        Some(path) => match bridge_version(path) {
+            Ok(version) => (Some(version), None),
+            Err(e) => (None, Some(e)),
+        },
+        None => {
+            if let Some(expected) = bridge_candidates.first() {
+                missing.push(display(expected));
+            }
+            (None, None)
+        }
+    };
+
+    let converter_candidates = crate::converter_dir_candidates(app);
+    let converter_dir = converter_candidates
+        .iter()
+        .find(|path| path.exists())
+        .cloned();
+    // Without a folder, report the files where the first candidate expects
+    // them.
+    let files = converter_dir
+        .as_ref()
+        .or(converter_candidates.first())
+        .map(|dir| {
+            [
+                crate::CONVERTER_EXE,
+                "GeneralSettings.ini",
+                "GeneralSettings.vehicle.ini",
+                crate::CONVERTER_MATERIALS,
+                crate::CONVERTER_DLL,
+            ]
+            .into_iter()
+            .map(|name| {
+                let path = dir.join(name);
+                let size_bytes = std::fs::metadata(&path).ok().map(|meta| meta.len());
+                ToolFile {
+                    name,
+                    path: display(&path),
+                    exists: size_bytes.is_some(),
+                    size_bytes,
+                }
+            })
+            .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    missing.extend(
+        files
+            .iter()
+            .filter(|file| !file.exists)
+            .map(|file| file.path.clone()),
+    );
+
+    let cache_dir = cache::yft_cache_root(app)?;
+    Ok(ToolDiagnostics {
+        platform: std::env::consts::OS,
+        bridge: BridgeDiagnostics {
+            candidates: candidates(&bridge_candidates),
+            selected: bridge.as_deref().map(display),
+            version,
+            version_error,
+        },
+        converter: ConverterDiagnostics {
+            candidates: candidates(&converter_candidates),
+            selected: converter_dir.as_deref().map(display),
+            files,
+        },
+        cache: CacheDiagnostics {
+            dir: display(&cache_dir),
+            usage: cache::get_cache_usage(app.clone()).ok(),
+        },
+        missing,
+    })
+}
+
+fn candidates(paths: &[PathBuf]) -> Vec<Candidate> {
+    paths
+        .iter()
+        .map(|path| Candidate {
+            path: display(path),
+            exists: path.exists(),
+        })
+        .collect()
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// First line the bridge prints for `--version`. Killed if it doesn't
+/// answer within `VERSION_TIMEOUT`.
+fn bridge_version(bridge: &Path) -> Result<String, String> {
+    let mut child = Command::new(bridge)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run bridge: {e}"))?;
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if started.elapsed() >= VERSION_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "Bridge did not answer --version within {}s.",
+                    VERSION_TIMEOUT.as_secs()
+                ));
+            }
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(format!("Failed to wait for bridge: {e}")),
+        }
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to read bridge output: {e}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.lines().next().unwrap_or_default().trim();
+    if !output.status.success() || version.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Bridge doesn't report a version (exit={:?}): {}",
+            output.status.code(),
+            stderr.trim()
+        ));
+    }
+    Ok(version.to_string())
+}
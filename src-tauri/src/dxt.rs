@@ -0,0 +1,413 @@
+/// Decompress DXT1/3/5 blocks into `rgba`, cropping blocks that overhang
+/// textures whose sides aren't multiples of four.
+pub fn decode(blocks: &[u8], format: &str, width: usize, height: usize, rgba: &mut [u8]) {
+    let block_size = if format == "DXT1" { 8 } else { 16 };
+    let blocks_wide = width.div_ceil(4);
+    for (index, block) in blocks
+        .chunks_exact(block_size)
+        .take(blocks_wide * height.div_ceil(4))
+        .enumerate()
+    {
+        let (color_block, alpha_block) = if format == "DXT1" {
+            (block, None)
+        } else {
+            (&block[8..], Some(&block[..8]))
+        };
+        let mut alphas = [255u8; 16];
+        match alpha_block {
+            Some(alpha) if format == "DXT3" => {
+                for (i, alpha_value) in alphas.iter_mut().enumerate() {
+                    let nibble = (alpha[i / 2] >> ((i % 2) * 4)) & 0xF;
+                    *alpha_value = nibble * 17;
+                }
+            }
+            Some(alpha) => {
+                let (a0, a1) = (u32::from(alpha[0]), u32::from(alpha[1]));
+                let table: [u32; 8] = if a0 > a1 {
+                    [
+                        a0,
+                        a1,
+                        (6 * a0 + a1) / 7,
+                        (5 * a0 + 2 * a1) / 7,
+                        (4 * a0 + 3 * a1) / 7,
+                        (3 * a0 + 4 * a1) / 7,
+                        (2 * a0 + 5 * a1) / 7,
+                        (a0 + 6 * a1) / 7,
+                    ]
+                } else {
+                    [
+                        a0,
+                        a1,
+                        (4 * a0 + a1) / 5,
+                        (3 * a0 + 2 * a1) / 5,
+                        (2 * a0 + 3 * a1) / 5,
+                        (a0 + 4 * a1) / 5,
+                        0,
+                        255,
+                    ]
+                };
+                let bits = alpha[2..8]
+                    .iter()
+                    .rev()
+                    .fold(0u64, |bits, byte| (bits << 8) | u64::from(*byte));
+                for (i, alpha_value) in alphas.iter_mut().enumerate() {
+                    *alpha_value = table[((bits >> (i * 3)) & 0x7) as usize] as u8;
+                }
+            }
+            None => {}
+        }
+
+        let c0 = u16::from_le_bytes([color_block[0], color_block[1]]);
+        let c1 = u16::from_le_bytes([color_block[2], color_block[3]]);
+        let [r0, g0, b0] = rgb565(c0);
+        let [r1, g1, b1] = rgb565(c1);
+        let mix = |a: u32, b: u32, wa: u32, wb: u32| ((a * wa + b * wb) / (wa + wb)) as u8;
+        // Only DXT1 has the three-color mode with transparent black.
+        let colors: [[u8; 4]; 4] = if c0 > c1 || format != "DXT1" {
+            [
+                [r0 as u8, g0 as u8, b0 as u8, 255],
+                [r1 as u8, g1 as u8, b1 as u8, 255],
+                [mix(r0, r1, 2, 1), mix(g0, g1, 2, 1), mix(b0, b1, 2, 1), 255],
+                [mix(r0, r1, 1, 2), mix(g0, g1, 1, 2), mix(b0, b1, 1, 2), 255],
+            ]
+        } else {
+            [
+                [r0 as u8, g0 as u8, b0 as u8, 255],
+                [r1 as u8, g1 as u8, b1 as u8, 255],
+                [mix(r0, r1, 1, 1), mix(g0, g1, 1, 1), mix(b0, b1, 1, 1), 255],
+                [0, 0, 0, 0],
+            ]
+        };
+        let indices = u32::from_le_bytes([
+            color_block[4],
+            color_block[5],
+            color_block[6],
+            color_block[7],
+        ]);
+
+        let (block_x, block_y) = ((index % blocks_wide) * 4, (index / blocks_wide) * 4);
+        for i in 0..16 {
+            let (x, y) = (block_x + i % 4, block_y + i / 4);
+            if x >= width || y >= height {
+                continue;
+            }
+            let mut color = colors[((indices >> (i * 2)) & 0x3) as usize];
+            if alpha_block.is_some() {
+                color[3] = alphas[i];
+            }
+            let pixel = (y * width + x) * 4;
+            rgba[pixel..pixel + 4].copy_from_slice(&color);
+        }
+    }
+}
+
+/// A 5:6:5 color widened to 8 bits per channel.
+fn rgb565(color: u16) -> [u32; 3] {
+    let (r, g, b) = (
+        u32::from(color >> 11),
+        u32::from((color >> 5) & 0x3F),
+        u32::from(color & 0x1F),
+    );
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+/// Block formats `encode` can write.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Opaque or 1-bit alpha, 8 bytes per block.
+    Dxt1,
+    /// Interpolated alpha, 16 bytes per block.
+    Dxt5,
+}
+
+/// Compress RGBA pixels into DXT blocks. Endpoints are the corners of each
+/// block's color (and alpha) bounding box, which is quick and good enough
+/// for game textures. In DXT1, pixels with alpha below 128 become
+/// transparent.
+pub fn encode(rgba: &[u8], width: usize, height: usize, compression: Compression) -> Vec<u8> {
+    let (blocks_wide, blocks_high) = (width.div_ceil(4), height.div_ceil(4));
+    let block_size = if compression == Compression::Dxt1 {
+        8
+    } else {
+        16
+    };
+    let mut out = Vec::with_capacity(blocks_wide * blocks_high * block_size);
+    for block_y in 0..blocks_high {
+        for block_x in 0..blocks_wide {
+            // Blocks past the edge repeat the last row or column.
+            let mut block = [[0u8; 4]; 16];
+            for (i, pixel) in block.iter_mut().enumerate() {
+                let x = (block_x * 4 + i % 4).min(width - 1);
+                let y = (block_y * 4 + i / 4).min(height - 1);
+                let at = (y * width + x) * 4;
+                pixel.copy_from_slice(&rgba[at..at + 4]);
+            }
+            match compression {
+                Compression::Dxt1 => out.extend_from_slice(&encode_color(&block, true)),
+                Compression::Dxt5 => {
+                    out.extend_from_slice(&encode_alpha(&block));
+                    out.extend_from_slice(&encode_color(&block, false));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn encode_color(block: &[[u8; 4]; 16], punch_through: bool) -> [u8; 8] {
+    let transparent = |pixel: &[u8; 4]| punch_through && pixel[3] < 128;
+    let (mut low, mut high) = ([255u8; 3], [0u8; 3]);
+    for pixel in block.iter().filter(|pixel| !transparent(pixel)) {
+        for channel in 0..3 {
+            low[channel] = low[channel].min(pixel[channel]);
+            high[channel] = high[channel].max(pixel[channel]);
+        }
+    }
+    let any_transparent = block.iter().any(transparent);
+    if block.iter().all(transparent) {
+        // Three-color mode, every pixel index 3.
+        return [0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+    }
+
+    // The box's main diagonal only fits colors where green and blue rise
+    // with red; flip the channels that fall as red rises instead.
+    let opaque = block.iter().filter(|pixel| !transparent(pixel));
+    let count = opaque.clone().count() as i32;
+    let mean = |channel: usize| {
+        opaque
+            .clone()
+            .map(|pixel| i32::from(pixel[channel]))
+            .sum::<i32>()
+            / count
+    };
+    let means = [mean(0), mean(1), mean(2)];
+    for channel in 1..3 {
+        let covariance: i32 = opaque
+            .clone()
+            .map(|pixel| {
+                (i32::from(pixel[0]) - means[0]) * (i32::from(pixel[channel]) - means[channel])
+            })
+            .sum();
+        if covariance < 0 {
+            std::mem::swap(&mut low[channel], &mut high[channel]);
+        }
+    }
+
+    let (a, b) = (to_rgb565(high), to_rgb565(low));
+    // Four colors need c0 > c1; three colors plus transparency c0 <= c1.
+    let (c0, c1) = if any_transparent {
+        (a.min(b), a.max(b))
+    } else {
+        (a.max(b), a.min(b))
+    };
+    let [r0, g0, b0] = rgb565(c0);
+    let [r1, g1, b1] = rgb565(c1);
+    let mix = |a: u32, b: u32, wa: u32, wb: u32| (a * wa + b * wb) / (wa + wb);
+    let palette: Vec<[u32; 3]> = if c0 > c1 {
+        vec![
+            [r0, g0, b0],
+            [r1, g1, b1],
+            [mix(r0, r1, 2, 1), mix(g0, g1, 2, 1), mix(b0, b1, 2, 1)],
+            [mix(r0, r1, 1, 2), mix(g0, g1, 1, 2), mix(b0, b1, 1, 2)],
+        ]
+    } else {
+        vec![
+            [r0, g0, b0],
+            [r1, g1, b1],
+            [mix(r0, r1, 1, 1), mix(g0, g1, 1, 1), mix(b0, b1, 1, 1)],
+        ]
+    };
+
+    let mut indices = 0u32;
+    for (i, pixel) in block.iter().enumerate() {
+        let index = if transparent(pixel) {
+            3
+        } else {
+            nearest(&palette, |color| {
+                (0..3)
+                    .map(|channel| {
+                        let d = color[channel] as i32 - i32::from(pixel[channel]);
+                        (d * d) as u32
+                    })
+                    .sum()
+            })
+        };
+        indices |= index << (i * 2);
+    }
+    let [c0_lo, c0_hi] = c0.to_le_bytes();
+    let [c1_lo, c1_hi] = c1.to_le_bytes();
+    let [i0, i1, i2, i3] = indices.to_le_bytes();
+    [c0_lo, c0_hi, c1_lo, c1_hi, i0, i1, i2, i3]
+}
+
+fn encode_alpha(block: &[[u8; 4]; 16]) -> [u8; 8] {
+    let a0 = block
+        .iter()
+        .map(|pixel| u32::from(pixel[3]))
+        .max()
+        .unwrap_or(255);
+    let a1 = block
+        .iter()
+        .map(|pixel| u32::from(pixel[3]))
+        .min()
+        .unwrap_or(255);
+    // a0 > a1 selects the eight-value mode; equal endpoints only need index 0.
+    let table: Vec<u32> = (0..8)
+        .map(|i| match i {
+            0 => a0,
+            1 => a1,
+            i => ((8 - i) * a0 + (i - 1) * a1) / 7,
+        })
+        .collect();
+    let mut bits = 0u64;
+    if a0 > a1 {
+        for (i, pixel) in block.iter().enumerate() {
+            let alpha = u32::from(pixel[3]);
+            let index = nearest(&table, |value| value.abs_diff(alpha));
+            bits |= u64::from(index) << (i * 3);
+        }
+    }
+    let bits = bits.to_le_bytes();
+    [
+        a0 as u8, a1 as u8, bits[0], bits[1], bits[2], bits[3], bits[4], bits[5],
+    ]
+}
+
+/// Index of the entry of `palette` with the smallest `distance`.
+fn nearest<T>(palette: &[T], distance: impl Fn(&T) -> u32) -> u32 {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| distance(entry))
+        .map_or(0, |(index, _)| index as u32)
+}
+
+fn to_rgb565([r, g, b]: [u8; 3]) -> u16 {
+    (u16::from(r >> 3) << 11) | (u16::from(g >> 2) << 5) | u16::from(b >> 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(rgba: &[u8], width: usize, height: usize, compression: Compression) -> Vec<u8> {
+        let blocks = encode(rgba, width, height, compression);
+        let block_size = if compression == Compression::Dxt1 {
+            8
+        } else {
+            16
+        };
+        assert_eq!(
+            blocks.len(),
+            width.div_ceil(4) * height.div_ceil(4) * block_size
+        );
+        let format = if compression == Compression::Dxt1 {
+            "DXT1"
+        } else {
+            "DXT5"
+        };
+        let mut decoded = vec![0; rgba.len()];
+        decode(&blocks, format, width, height, &mut decoded);
+        decoded
+    }
+
+    #[test]
+    fn colors_representable_in_565_round_trip_exactly() {
+        // One color per 4x4 block, each exactly representable in 5:6:5.
+        let colors = [[0, 0, 0], [255, 255, 255], [248, 4, 0], [8, 252, 132]];
+        let (width, height) = (8, 8);
+        let mut rgba = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let [r, g, b] = colors[(y / 4) * 2 + x / 4];
+                let [r, g, b] = rgb565(to_rgb565([r, g, b])).map(|c| c as u8);
+                rgba.extend([r, g, b, 255]);
+            }
+        }
+        assert_eq!(round_trip(&rgba, width, height, Compression::Dxt1), rgba);
+        assert_eq!(round_trip(&rgba, width, height, Compression::Dxt5), rgba);
+    }
+
+    #[test]
+    fn gradients_stay_within_tolerance() {
+        // Gray ramps along each block's diagonal, and a falling alpha.
+        let (width, height) = (16, 16);
+        let rgba: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                let gray = ((x + y) * 8) as u8;
+                [gray, gray, gray, 255 - (x * 8) as u8]
+            })
+            .collect();
+        let decoded = round_trip(&rgba, width, height, Compression::Dxt5);
+        let mut total = 0;
+        for (pixel, (got, want)) in decoded.chunks(4).zip(rgba.chunks(4)).enumerate() {
+            for channel in 0..4 {
+                let error = got[channel].abs_diff(want[channel]);
+                let limit = if channel == 3 { 4 } else { 12 };
+                assert!(
+                    error <= limit,
+                    "pixel {pixel} channel {channel}: {got:?} vs {want:?}"
+                );
+                total += u32::from(error);
+            }
+        }
+        assert!(
+            total / (rgba.len() as u32) < 4,
+            "mean error {}",
+            total / rgba.len() as u32
+        );
+    }
+
+    #[test]
+    fn dxt1_punches_through_low_alpha() {
+        let (width, height) = (4, 4);
+        let rgba: Vec<u8> = (0..16)
+            .flat_map(|i| {
+                if i % 2 == 0 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 40]
+                }
+            })
+            .collect();
+        let decoded = round_trip(&rgba, width, height, Compression::Dxt1);
+        for (i, pixel) in decoded.chunks(4).enumerate() {
+            let expected = if i % 2 == 0 {
+                [255, 0, 0, 255]
+            } else {
+                [0, 0, 0, 0]
+            };
+            assert_eq!(pixel, expected, "pixel {i}");
+        }
+        let transparent = vec![0u8; 16 * 4];
+        assert_eq!(
+            round_trip(&transparent, 4, 4, Compression::Dxt1),
+            transparent
+        );
+    }
+
+    #[test]
+    fn sizes_that_are_not_multiples_of_four_crop() {
+        let (width, height) = (6, 5);
+        let rgba: Vec<u8> = (0..width * height)
+            .flat_map(|_| [64, 128, 192, 255])
+            .collect();
+        let decoded = round_trip(&rgba, width, height, Compression::Dxt5);
+        assert_eq!(decoded.len(), rgba.len());
+        // Within 5:6:5 quantization.
+        for pixel in decoded.chunks(4) {
+            assert!(
+                pixel
+                    .iter()
+                    .zip([64u8, 128, 192, 255])
+                    .all(|(a, b)| a.abs_diff(b) <= 8),
+                "{pixel:?}"
+            );
+        }
+    }
+}
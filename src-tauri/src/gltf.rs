@@ -0,0 +1,578 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{clmesh, dff, paths, textures};
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const CHUNK_JSON: &[u8; 4] = b"JSON";
+const CHUNK_BIN: &[u8; 4] = b"BIN\0";
+// Accessor component types and buffer view targets.
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// What `export_gltf` wrote.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GltfExport {
+    pub path: String,
+    pub primitives: usize,
+    pub vertices: usize,
+    pub triangles: usize,
+    pub materials: usize,
+    /// Textures embedded in the file.
+    pub embedded_textures: Vec<String>,
+    /// Textures the materials name but no image was found for.
+    pub missing_textures: Vec<String>,
+}
+
+/// The binary chunk being built, with the JSON entries describing it.
+#[derive(Default)]
+struct Builder {
+    bin: Vec<u8>,
+    buffer_views: Vec<serde_json::Value>,
+    accessors: Vec<serde_json::Value>,
+}
+
+impl Builder {
+    fn view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        while !self.bin.len().is_multiple_of(4) {
+            self.bin.push(0);
+        }
+        let mut view = serde_json::json!({
+            "buffer": 0,
+            "byteOffset": self.bin.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = target.into();
+        }
+        self.bin.extend_from_slice(bytes);
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    fn accessor(
+        &mut self,
+        bytes: &[u8],
+        target: u32,
+        component_type: u32,
+        kind: &str,
+        count: usize,
+    ) -> usize {
+        let view = self.view(bytes, Some(target));
+        self.accessors.push(serde_json::json!({
+            "bufferView": view,
+            "componentType": component_type,
+            "count": count,
+            "type": kind,
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn floats(&mut self, values: &[f32], kind: &str, width: usize) -> usize {
+        let bytes = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        self.accessor(&bytes, ARRAY_BUFFER, FLOAT, kind, values.len() / width)
+    }
+}
+
+/// GTA models are Z-up, glTF is Y-up.
+fn to_y_up(values: &[f32]) -> Vec<f32> {
+    values
+        .chunks_exact(3)
+        .flat_map(|v| [v[0], v[2], -v[1]])
+        .collect()
+}
+
+/// The texture's PNG: a recorded file or `<name>.png` beside the mesh or
+/// the output, as long as it really is a PNG.
+fn find_png(
+    name: &str,
+    recorded: &[(String, PathBuf)],
+    mesh_dir: &Path,
+    out_dir: &Path,
+) -> Option<Vec<u8>> {
+    let path = textures::find_file(name, recorded, &[mesh_dir, out_dir])?;
+    let data = std::fs::read(paths::long(&path)).ok()?;
+    data.starts_with(b"\x89PNG").then_some(data)
+}
+
+/// Write `meshes` as a binary glTF at `out_path`, one primitive per mesh.
+/// `colors` gives material base colors by texture name.
+pub fn write_glb(
+    meshes: &[clmesh::Mesh],
+    colors: &BTreeMap<String, [u8; 4]>,
+    mesh_dir: &Path,
+    embed_textures: bool,
+    out_path: &Path,
+) -> Result<GltfExport, String> {
+    let mut builder = Builder::default();
+    let recorded = if embed_textures {
+        textures::recorded_files(mesh_dir)
+    } else {
+        Vec::new()
+    };
+    let out_dir = out_path.parent().unwrap_or(Path::new(""));
+
+    let mut material_indices = BTreeMap::new();
+    let (mut materials, mut images, mut image_textures) = (Vec::new(), Vec::new(), Vec::new());
+    let (mut embedded_textures, mut missing_textures) = (Vec::new(), Vec::new());
+    for mesh in meshes {
+        if material_indices.contains_key(&mesh.material) {
+            continue;
+        }
+        let [r, g, b, a] = colors.get(&mesh.material).copied().unwrap_or([255; 4]);
+        let base_color = [r, g, b, a].map(|channel| f32::from(channel) / 255.0);
+        let mut pbr = serde_json::json!({
+            "baseColorFactor": base_color,
+            "metallicFactor": 0.0,
+            "roughnessFactor": 1.0,
+        });
+        if embed_textures && !mesh.material.is_empty() {
+            match find_png(&mesh.material, &recorded, mesh_dir, out_dir) {
+                Some(png) => {
+                    let view = builder.view(&png, None);
+                    images.push(serde_json::json!({
+                        "name": mesh.material,
+                        "bufferView": view,
+                        "mimeType": "image/png",
+                    }));
+                    image_textures
+                        .push(serde_json::json!({ "source": images.len() - 1, "sampler": 0 }));
+                    pbr["baseColorTexture"] =
+                        serde_json::json!({ "index": image_textures.len() - 1 });
+                    embedded_textures.push(mesh.material.clone());
+                }
+                None => missing_textures.push(mesh.material.clone()),
+            }
+        }
+        let name = if mesh.material.is_empty() {
+            "untextured"
+        } else {
+            &mesh.material
+        };
+        materials.push(serde_json::json!({ "name": name, "pbrMetallicRoughness": pbr }));
+        material_indices.insert(mesh.material.clone(), materials.len() - 1);
+    }
+
+    let (mut primitives, mut vertices, mut triangles) = (Vec::new(), 0, 0);
+    for mesh in meshes {
+        let count = mesh.positions.len() / 3;
+        if count == 0 || mesh.indices.is_empty() {
+            continue;
+        }
+        let positions = to_y_up(&mesh.positions);
+        let position = builder.floats(&positions, "VEC3", 3);
+        let mut bounds = None;
+        for p in positions.chunks_exact(3) {
+            clmesh::BoundingBox::extend(&mut bounds, [p[0], p[1], p[2]]);
+        }
+        if let Some(bounds) = bounds {
+            builder.accessors[position]["min"] = serde_json::json!(bounds.min);
+            builder.accessors[position]["max"] = serde_json::json!(bounds.max);
+        }
+        let mut attributes = serde_json::json!({ "POSITION": position });
+        if let Some(normals) = &mesh.normals {
+            attributes["NORMAL"] = builder.floats(&to_y_up(normals), "VEC3", 3).into();
+        }
+        // RenderWare and glTF both put V = 0 at the top of the image.
+        if let Some(uvs) = &mesh.uvs {
+            attributes["TEXCOORD_0"] = builder.floats(uvs, "VEC2", 2).into();
+        }
+        if let Some(colors) = &mesh.colors {
+            let accessor = builder.accessor(colors, ARRAY_BUFFER, UNSIGNED_BYTE, "VEC4", count);
+            builder.accessors[accessor]["normalized"] = true.into();
+            attributes["COLOR_0"] = accessor.into();
+        }
+        // 0xFFFF is the primitive restart index, so u16 stops just short.
+        let indices = if count < usize::from(u16::MAX) {
+            let bytes = mesh
+                .indices
+                .iter()
+                .flat_map(|index| (*index as u16).to_le_bytes())
+                .collect::<Vec<_>>();
+            builder.accessor(
+                &bytes,
+                ELEMENT_ARRAY_BUFFER,
+                UNSIGNED_SHORT,
+                "SCALAR",
+                mesh.indices.len(),
+            )
+        } else {
+            let bytes = mesh
+                .indices
+                .iter()
+                .flat_map(|index| index.to_le_bytes())
+                .collect::<Vec<_>>();
+            builder.accessor(
+                &bytes,
+                ELEMENT_ARRAY_BUFFER,
+                UNSIGNED_INT,
+                "SCALAR",
+                mesh.indices.len(),
+            )
+        };
+        primitives.push(serde_json::json!({
+            "attributes": attributes,
+            "indices": indices,
+            "material": material_indices[&mesh.material],
+        }));
+        vertices += count;
+        triangles += mesh.indices.len() / 3;
+    }
+    if primitives.is_empty() {
+        return Err("The mesh has no triangles to export.".to_string());
+    }
+
+    let primitive_count = primitives.len();
+    let name = out_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "model".to_string());
+    while !builder.bin.len().is_multiple_of(4) {
+        builder.bin.push(0);
+    }
+    let mut json = serde_json::json!({
+        "asset": { "version": "2.0", "generator": "Cortex Studio" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "name": name, "mesh": 0 }],
+        "meshes": [{ "name": name, "primitives": primitives }],
+        "materials": materials,
+        "buffers": [{ "byteLength": builder.bin.len() }],
+        "bufferViews": builder.buffer_views,
+        "accessors": builder.accessors,
+    });
+    if !images.is_empty() {
+        json["images"] = images.into();
+        json["textures"] = image_textures.into();
+        // Linear filtering with mipmaps, repeating.
+        json["samplers"] = serde_json::json!([{
+            "magFilter": 9729,
+            "minFilter": 9987,
+            "wrapS": 10497,
+            "wrapT": 10497,
+        }]);
+    }
+
+    // JSON is padded with spaces, the binary chunk with zeros.
+    let mut json = json.to_string().into_bytes();
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+    let total = 12 + 8 + json.len() + 8 + builder.bin.len();
+    let mut glb = Vec::with_capacity(total);
+    glb.extend_from_slice(GLB_MAGIC);
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&(total as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(CHUNK_JSON);
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(builder.bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(CHUNK_BIN);
+    glb.extend_from_slice(&builder.bin);
+
+    if let Some(parent) = out_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(paths::long(parent))
+            .map_err(|e| format!("Failed to create output dir: {e}"))?;
+    }
+    std::fs::write(paths::long(out_path), glb).map_err(|e| format!("Failed to write glTF: {e}"))?;
+
+    Ok(GltfExport {
+        path: out_path.to_string_lossy().to_string(),
+        primitives: primitive_count,
+        vertices,
+        triangles,
+        materials: materials.len(),
+        embedded_textures,
+        missing_textures,
+    })
+}
+
+/// Export a cached `.clmesh` (or a `.dff`, read natively) as a binary glTF
+/// 2.0 `.glb` at `out_path`: positions and normals converted to Y-up, UVs,
+/// vertex colors (DFF prelighting), one primitive per mesh and a material
+/// per texture name. With `embed_textures` each material's PNG is embedded
+/// as its base color texture, taken from the `textureFiles` of the parse
+/// that wrote the mesh or `<texture>.png` beside it or the output; those
+/// not found are listed in `missingTextures`.
+#[tauri::command]
+pub async fn export_gltf(
+    mesh_path: String,
+    out_path: String,
+    embed_textures: Option<bool>,
+) -> Result<GltfExport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&mesh_path);
+        let data = std::fs::read(paths::long(path))
+            .map_err(|e| format!("Failed to read {mesh_path}: {e}"))?;
+        let mut colors = BTreeMap::new();
+        let meshes = if crate::has_extension(&mesh_path, "clmesh") {
+            clmesh::read(&data)?
+        } else if crate::has_extension(&mesh_path, "dff") {
+            let dff = dff::parse(&data)?;
+            // The first material using each texture decides its color.
+            for material in dff
+                .geometries
+                .iter()
+                .flat_map(|geometry| &geometry.materials)
+            {
+                colors
+                    .entry(material.texture_name.clone().unwrap_or_default())
+                    .or_insert(material.color);
+            }
+            let base_name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| "model".to_string());
+            dff::to_meshes(&dff, &base_name)
+        } else {
+            return Err("Only .clmesh and .dff files are supported by export_gltf".to_string());
+        };
+        if !crate::has_extension(&out_path, "glb") {
+            return Err(format!(
+                "export_gltf writes a .glb; `{out_path}` isn't one."
+            ));
+        }
+        let mesh_dir = path.parent().unwrap_or(Path::new(""));
+        let out_path = PathBuf::from(out_path);
+        write_glb(
+            &meshes,
+            &colors,
+            mesh_dir,
+            embed_textures.unwrap_or(false),
+            &out_path,
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to join glTF export task: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(data: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+    }
+
+    /// The GLB's JSON and binary chunk, checking the header and chunk
+    /// lengths on the way and the whole file against the glTF schema.
+    fn chunks(glb: &[u8]) -> (serde_json::Value, &[u8]) {
+        validate(glb);
+        assert_eq!(&glb[..4], GLB_MAGIC);
+        assert_eq!(u32_at(glb, 4), 2);
+        assert_eq!(u32_at(glb, 8) as usize, glb.len());
+        let json_len = u32_at(glb, 12) as usize;
+        assert_eq!(&glb[16..20], CHUNK_JSON);
+        assert_eq!(json_len % 4, 0);
+        let bin_at = 20 + json_len;
+        let bin_len = u32_at(glb, bin_at) as usize;
+        assert_eq!(&glb[bin_at + 4..bin_at + 8], CHUNK_BIN);
+        assert_eq!(bin_len % 4, 0);
+        assert_eq!(bin_at + 8 + bin_len, glb.len());
+        let json = serde_json::from_slice(&glb[20..bin_at]).unwrap();
+        (json, &glb[bin_at + 8..])
+    }
+
+    /// Load `glb` with the `gltf` crate, which validates every index and
+    /// accessor, and read each primitive's positions back through it.
+    fn validate(glb: &[u8]) {
+        let gltf = gltf::Gltf::from_slice(glb).unwrap_or_else(|e| panic!("invalid glTF: {e}"));
+        let blob = gltf.blob.as_deref().unwrap();
+        for primitive in gltf.meshes().flat_map(|mesh| mesh.primitives()) {
+            let reader = primitive.reader(|_| Some(blob));
+            let positions = reader.read_positions().unwrap().count();
+            let indices = reader.read_indices().unwrap().into_u32();
+            assert!(indices
+                .into_iter()
+                .all(|index| (index as usize) < positions));
+        }
+    }
+
+    /// An accessor's data as f32s, or its indices widened to u32.
+    fn read_accessor(
+        json: &serde_json::Value,
+        bin: &[u8],
+        accessor: &serde_json::Value,
+    ) -> Vec<f64> {
+        let view = &json["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+        let offset = view["byteOffset"].as_u64().unwrap() as usize;
+        let bytes = &bin[offset..offset + view["byteLength"].as_u64().unwrap() as usize];
+        match accessor["componentType"].as_u64().unwrap() as u32 {
+            FLOAT => bytes
+                .chunks_exact(4)
+                .map(|b| f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+                .collect(),
+            UNSIGNED_SHORT => bytes
+                .chunks_exact(2)
+                .map(|b| f64::from(u16::from_le_bytes([b[0], b[1]])))
+                .collect(),
+            UNSIGNED_INT => bytes
+                .chunks_exact(4)
+                .map(|b| f64::from(u32_at(b, 0)))
+                .collect(),
+            _ => bytes.iter().map(|b| f64::from(*b)).collect(),
+        }
+    }
+
+    fn triangle(material: &str) -> clmesh::Mesh {
+        clmesh::Mesh {
+            name: format!("car_{material}"),
+            material: material.to_string(),
+            positions: vec![0.0, 0.0, 0.0, 1.0, -2.0, 0.5, -1.0, 3.0, 2.0],
+            normals: Some(vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0]),
+            uvs: Some(vec![0.0, 0.0, 1.0, 0.0, 0.5, 1.0]),
+            colors: Some(vec![255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 128]),
+            indices: vec![0, 1, 2],
+        }
+    }
+
+    #[test]
+    fn writes_padded_chunks_and_accessors_matching_the_mesh() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        // A PNG whose length isn't a multiple of four, embedded first.
+        let png = b"\x89PNG\r\n\x1a\nxyz".to_vec();
+        std::fs::write(dir.join("body.png"), &png).unwrap();
+        let plain = clmesh::Mesh {
+            normals: None,
+            uvs: None,
+            colors: None,
+            ..triangle("")
+        };
+        let meshes = [triangle("body"), plain, triangle("decal")];
+        let colors = BTreeMap::from([("body".to_string(), [255, 0, 0, 255])]);
+        let out = dir.join("car.glb");
+        let export = write_glb(&meshes, &colors, dir, true, &out).unwrap();
+        assert_eq!(
+            (
+                export.primitives,
+                export.vertices,
+                export.triangles,
+                export.materials
+            ),
+            (3, 9, 3, 3)
+        );
+        assert_eq!(export.embedded_textures, ["body"]);
+        assert_eq!(export.missing_textures, ["decal"]);
+
+        let glb = std::fs::read(&out).unwrap();
+        let (json, bin) = chunks(&glb);
+        assert_eq!(
+            json["buffers"][0]["byteLength"].as_u64().unwrap() as usize,
+            bin.len()
+        );
+        for view in json["bufferViews"].as_array().unwrap() {
+            let offset = view["byteOffset"].as_u64().unwrap() as usize;
+            assert_eq!(offset % 4, 0, "{view}");
+            assert!(offset + view["byteLength"].as_u64().unwrap() as usize <= bin.len());
+        }
+        let image =
+            &json["bufferViews"][json["images"][0]["bufferView"].as_u64().unwrap() as usize];
+        let at = image["byteOffset"].as_u64().unwrap() as usize;
+        assert_eq!(&bin[at..at + png.len()], &png[..]);
+        assert_eq!(
+            json["materials"][0]["pbrMetallicRoughness"]["baseColorFactor"],
+            serde_json::json!([1.0, 0.0, 0.0, 1.0])
+        );
+
+        let primitives = json["meshes"][0]["primitives"].as_array().unwrap();
+        assert_eq!(primitives.len(), 3);
+        let accessor =
+            |index: &serde_json::Value| &json["accessors"][index.as_u64().unwrap() as usize];
+        for (primitive, mesh) in primitives.iter().zip(&meshes) {
+            let attributes = &primitive["attributes"];
+            let position = accessor(&attributes["POSITION"]);
+            assert_eq!(position["count"], 3);
+            // Z-up to Y-up: (x, y, z) becomes (x, z, -y).
+            let expected: Vec<f64> = mesh
+                .positions
+                .chunks(3)
+                .flat_map(|p| [p[0], p[2], -p[1]].map(f64::from))
+                .collect();
+            assert_eq!(read_accessor(&json, bin, position), expected);
+            assert_eq!(position["min"], serde_json::json!([-1.0, 0.0, -3.0]));
+            assert_eq!(position["max"], serde_json::json!([1.0, 2.0, 2.0]));
+            let indices = accessor(&primitive["indices"]);
+            assert_eq!(indices["componentType"], UNSIGNED_SHORT);
+            assert_eq!(read_accessor(&json, bin, indices), [0.0, 1.0, 2.0]);
+            assert_eq!(attributes.get("NORMAL").is_some(), mesh.normals.is_some());
+            if let Some(colors) = &mesh.colors {
+                let color = accessor(&attributes["COLOR_0"]);
+                assert_eq!(
+                    (color["count"].as_u64(), &color["normalized"]),
+                    (Some(3), &serde_json::json!(true))
+                );
+                let expected: Vec<f64> = colors.iter().map(|c| f64::from(*c)).collect();
+                assert_eq!(read_accessor(&json, bin, color), expected);
+                assert_eq!(
+                    read_accessor(&json, bin, accessor(&attributes["TEXCOORD_0"])),
+                    [0.0, 0.0, 1.0, 0.0, 0.5, 1.0]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn large_meshes_use_32_bit_indices() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let count = usize::from(u16::MAX);
+        let mesh = clmesh::Mesh {
+            name: "big".to_string(),
+            material: String::new(),
+            positions: (0..count * 3).map(|i| i as f32).collect(),
+            normals: None,
+            uvs: None,
+            colors: None,
+            indices: vec![0, 1, count as u32 - 1],
+        };
+        let out = dir.join("big.glb");
+        write_glb(&[mesh], &BTreeMap::new(), dir, false, &out).unwrap();
+        let glb = std::fs::read(&out).unwrap();
+        let (json, bin) = chunks(&glb);
+        let indices = &json["accessors"][json["meshes"][0]["primitives"][0]["indices"]
+            .as_u64()
+            .unwrap() as usize];
+        assert_eq!(indices["componentType"], UNSIGNED_INT);
+        assert_eq!(
+            read_accessor(&json, bin, indices),
+            [0.0, 1.0, (count - 1) as f64]
+        );
+        assert!(write_glb(&[], &BTreeMap::new(), dir, false, &out).is_err());
+    }
+
+    #[test]
+    fn export_writes_only_glb_files() {
+        let mesh = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/clmesh/valid.clmesh");
+        let dir = tempfile::tempdir().unwrap();
+        let export = |out: &Path| {
+            tauri::async_runtime::block_on(export_gltf(
+                mesh.to_string_lossy().to_string(),
+                out.to_string_lossy().to_string(),
+                None,
+            ))
+        };
+
+        let gltf = dir.path().join("car.gltf");
+        let error = export(&gltf).err().unwrap();
+        assert!(error.contains("car.gltf"), "{error}");
+        assert!(std::fs::read_dir(dir.path()).unwrap().next().is_none());
+
+        let glb = dir.path().join("car.glb");
+        assert_eq!(export(&glb).unwrap().path, glb.to_string_lossy());
+        validate(&std::fs::read(&glb).unwrap());
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    path::PathBuf,
+    sync::Mutex,
+    time::{Instant, SystemTime},
+};
+
+use tauri::Manager;
+
+use crate::conversion;
+
+const HISTORY_FILE: &str = "conversions.log";
+const MAX_ENTRIES: usize = 1000;
+
+// Appends happen on their own threads; this keeps two of them from
+// trimming the file at the same time.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// One finished `parse_yft` or `convert_yft`, a line of `conversions.log`.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    /// Unix time in milliseconds when the command finished.
+    pub timestamp: u64,
+    /// `parse` or `conversion`, like the events the command emits.
+    pub kind: String,
+    pub path: String,
+    pub cache_key: Option<String>,
+    pub duration_ms: u64,
+    pub vertex_count: Option<u64>,
+    pub profile_used: Option<String>,
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn finished(
+        kind: &str,
+        path: &str,
+        cache_key: Option<String>,
+        started: Instant,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            kind: kind.to_string(),
+            path: path.to_string(),
+            cache_key,
+            duration_ms: conversion::elapsed_ms(started),
+            vertex_count: None,
+            profile_used: None,
+            error,
+        }
+    }
+}
+
+fn history_path(app: &crate::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve data dir: {e}"))?
+        .join("cortex-labs")
+        .join(HISTORY_FILE))
+}
+
+/// Append `entry` to the log in the background, keeping the newest
+/// `MAX_ENTRIES`. A log that can't be written is skipped silently.
+pub fn record(app: &crate::AppHandle, entry: HistoryEntry) {
+    let Ok(path) = history_path(app) else {
+        return;
+    };
+    std::thread::spawn(move || {
+        let Ok(line) = serde_json::to_string(&entry) else {
+            return;
+        };
+        let _guard = WRITE_LOCK.lock();
+        let existing = std::fs::read_to_string(&path).unwrap_or_default();
+        let mut lines = existing
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>();
+        lines.push(&line);
+        let keep = &lines[lines.len().saturating_sub(MAX_ENTRIES)..];
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = std::fs::write(&path, keep.join("\n") + "\n");
+    });
+}
+
+/// The last `limit` parses and conversions, newest first. Lines that no
+/// longer parse are skipped.
+#[tauri::command]
+pub fn get_conversion_history(
+    limit: u32,
+    app: crate::AppHandle,
+) -> Result<Vec<HistoryEntry>, String> {
+    let path = history_path(&app)?;
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read conversion history: {e}")),
+    };
+    Ok(contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit as usize)
+        .collect())
+}
+
+#[tauri::command]
+pub fn clear_conversion_history(app: crate::AppHandle) -> Result<(), String> {
+    let path = history_path(&app)?;
+    let _guard = WRITE_LOCK.lock();
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to clear conversion history: {e}")),
+    }
+}
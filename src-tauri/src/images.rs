@@ -0,0 +1,220 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::paths;
+
+/// Write 8-bit RGBA pixels as a PNG.
+pub fn write_png(path: &Path, width: usize, height: usize, rgba: &[u8]) -> Result<(), String> {
+    let file = File::create(paths::long(path)).map_err(|e| format!("Failed to create PNG: {e}"))?;
+    encode_png(file, width, height, rgba)
+}
+
+/// Like `write_png`, but into a temp file of its own next to `path` that is
+/// renamed over it once complete. Readers never see a half-written PNG, and
+/// concurrent writers of the same path don't interleave; the last rename wins.
+pub fn write_png_atomic(
+    path: &Path,
+    width: usize,
+    height: usize,
+    rgba: &[u8],
+) -> Result<(), String> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let partial = tempfile::Builder::new()
+        .prefix(&format!("{name}."))
+        .suffix(PARTIAL_SUFFIX)
+        .tempfile_in(paths::long(dir))
+        .map_err(|e| format!("Failed to create PNG: {e}"))?;
+    encode_png(partial.as_file(), width, height, rgba)?;
+    partial
+        .persist(paths::long(path))
+        .map_err(|e| format!("Failed to write PNG: {}", e.error))?;
+    Ok(())
+}
+
+/// Suffix of the temp files `write_png_atomic` renames into place; one left
+/// behind belongs to a write that never finished.
+pub const PARTIAL_SUFFIX: &str = ".partial";
+
+fn encode_png(file: impl Write, width: usize, height: usize, rgba: &[u8]) -> Result<(), String> {
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("Failed to write PNG: {e}"))?;
+    writer
+        .write_image_data(rgba)
+        .map_err(|e| format!("Failed to write PNG: {e}"))?;
+    writer
+        .finish()
+        .map_err(|e| format!("Failed to write PNG: {e}"))
+}
+
+/// Pixels for the frontend as one binary IPC response, which it reads as an
+/// ArrayBuffer: a 4-byte little-endian length, that much JSON describing
+/// the image (`width`, `height`, ...), then the raw RGBA. Large images
+/// otherwise spend longer in base64 and JSON than in decoding.
+pub fn rgba_response(meta: &serde_json::Value, rgba: Vec<u8>) -> tauri::ipc::Response {
+    let meta = meta.to_string();
+    let mut body = Vec::with_capacity(4 + meta.len() + rgba.len());
+    body.extend_from_slice(&(meta.len() as u32).to_le_bytes());
+    body.extend_from_slice(meta.as_bytes());
+    body.extend_from_slice(&rgba);
+    tauri::ipc::Response::new(body)
+}
+
+// DDS header flags and pixel format flags.
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PITCH: u32 = 0x8;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDSD_LINEARSIZE: u32 = 0x80000;
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDSCAPS_COMPLEX: u32 = 0x8;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS_MIPMAP: u32 = 0x40_0000;
+
+/// What a DDS file holds.
+pub enum DdsPixels<'a> {
+    /// DXT blocks for each mipmap level, largest first, under `fourcc`.
+    Compressed {
+        fourcc: &'a [u8; 4],
+        levels: &'a [&'a [u8]],
+    },
+    /// A single level of 8-bit RGBA.
+    Rgba(&'a [u8]),
+}
+
+/// Write a DDS with a plain (pre-DX10) header.
+pub fn write_dds(
+    path: &Path,
+    width: usize,
+    height: usize,
+    pixels: DdsPixels,
+) -> Result<(), String> {
+    let mut header = [0u32; 31];
+    header[0] = 124; // header size
+    header[2] = height as u32;
+    header[3] = width as u32;
+    header[18] = 32; // pixel format size
+    let mut caps = DDSCAPS_TEXTURE;
+    let mut body = Vec::new();
+    match pixels {
+        DdsPixels::Compressed { fourcc, levels } => {
+            header[1] = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT | DDSD_LINEARSIZE;
+            header[4] = levels.first().map_or(0, |level| level.len() as u32);
+            if levels.len() > 1 {
+                header[1] |= DDSD_MIPMAPCOUNT;
+                header[6] = levels.len() as u32;
+                caps |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+            }
+            header[19] = DDPF_FOURCC;
+            header[20] = u32::from_le_bytes(*fourcc);
+            for level in levels {
+                body.extend_from_slice(level);
+            }
+        }
+        DdsPixels::Rgba(rgba) => {
+            header[1] = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT | DDSD_PITCH;
+            header[4] = (width * 4) as u32;
+            header[19] = DDPF_RGB | DDPF_ALPHAPIXELS;
+            header[21] = 32;
+            // Masks for RGBA byte order.
+            header[22] = 0x0000_00FF;
+            header[23] = 0x0000_FF00;
+            header[24] = 0x00FF_0000;
+            header[25] = 0xFF00_0000;
+            body.extend_from_slice(rgba);
+        }
+    }
+    header[26] = caps;
+
+    let mut file = Vec::with_capacity(128 + body.len());
+    file.extend_from_slice(b"DDS ");
+    for word in header {
+        file.extend_from_slice(&word.to_le_bytes());
+    }
+    file.extend_from_slice(&body);
+    std::fs::write(paths::long(path), file).map_err(|e| format!("Failed to write DDS: {e}"))
+}
+
+/// Decode a PNG or TGA into 8-bit RGBA: `(width, height, pixels)`. TGAs
+/// have no signature, so they're told apart by extension.
+pub fn read_image(path: &Path) -> Result<(usize, usize, Vec<u8>), String> {
+    let data =
+        std::fs::read(paths::long(path)).map_err(|e| format!("Failed to read image: {e}"))?;
+    let (format, label) = if data.starts_with(b"\x89PNG") {
+        (image::ImageFormat::Png, "PNG")
+    } else if crate::has_extension(&path.to_string_lossy(), "tga") {
+        (image::ImageFormat::Tga, "TGA")
+    } else {
+        return Err(format!(
+            "{} is neither a PNG nor a TGA.",
+            path.to_string_lossy()
+        ));
+    };
+    let rgba = image::load_from_memory_with_format(&data, format)
+        .map_err(|e| format!("Failed to read {label}: {e}"))?
+        .into_rgba8();
+    Ok((
+        rgba.width() as usize,
+        rgba.height() as usize,
+        rgba.into_raw(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_atomic_writes_leave_one_whole_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.png");
+        std::thread::scope(|scope| {
+            for shade in 0..8u8 {
+                let path = &path;
+                scope.spawn(move || {
+                    let rgba = vec![shade * 30; 64 * 64 * 4];
+                    write_png_atomic(path, 64, 64, &rgba).unwrap();
+                });
+            }
+        });
+
+        let mut reader = png::Decoder::new(File::open(&path).unwrap())
+            .read_info()
+            .unwrap();
+        let mut rgba = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut rgba).unwrap();
+        // Every pixel comes from the same writer.
+        assert!(rgba.iter().all(|&value| value == rgba[0]), "mixed writes");
+        let names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["key.png"]);
+    }
+
+    #[test]
+    fn reads_colour_mapped_tgas() {
+        // 2x1, top-down, 8-bit indices into a two-entry BGR palette.
+        let mut tga = vec![0, 1, 1, 0, 0, 2, 0, 24, 0, 0, 0, 0, 2, 0, 1, 0, 8, 0x20];
+        tga.extend([0, 0, 255, 255, 0, 0]);
+        tga.extend([1, 0]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("palette.tga");
+        std::fs::write(&path, tga).unwrap();
+
+        let (width, height, rgba) = read_image(&path).unwrap();
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(rgba, [0, 0, 255, 255, 255, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,571 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::paths;
+
+// Offsets and sizes in IMG directories are in 2 KB sectors.
+const SECTOR_SIZE: u64 = 2048;
+const ENTRY_SIZE: usize = 32;
+const NAME_SIZE: usize = 24;
+
+/// One file inside an IMG archive.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImgEntry {
+    pub name: String,
+    /// Byte offset in the `.img`.
+    pub offset: u64,
+    /// Bytes the entry occupies, a whole number of sectors.
+    pub size: u64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImgListing {
+    /// 1 for III/VC archives with a separate `.dir`, 2 for SA's `VER2`.
+    pub version: u8,
+    pub entries: Vec<ImgEntry>,
+}
+
+/// Read the directory of an IMG archive: the `VER2` header of an SA
+/// archive, or the `.dir` next to an older one. Only the directory is
+/// read, never the entries' data.
+pub fn list(img_path: &Path) -> Result<ImgListing, String> {
+    let mut img =
+        File::open(paths::long(img_path)).map_err(|e| format!("Failed to open IMG: {e}"))?;
+    read_directory(&mut img, img_path)
+}
+
+// Through an already open `img`, so a locked archive can still be read.
+fn read_directory(img: &mut File, img_path: &Path) -> Result<ImgListing, String> {
+    img.seek(SeekFrom::Start(0))
+        .map_err(|e| format!("Failed to seek in IMG: {e}"))?;
+    let mut file = BufReader::new(img);
+    let mut magic = [0u8; 4];
+    let is_ver2 = file.read_exact(&mut magic).is_ok() && &magic == b"VER2";
+
+    let (version, entry_count, mut directory): (u8, Option<usize>, Box<dyn Read>) = if is_ver2 {
+        let mut count = [0u8; 4];
+        file.read_exact(&mut count)
+            .map_err(|e| format!("Failed to read IMG header: {e}"))?;
+        (2, Some(u32::from_le_bytes(count) as usize), Box::new(file))
+    } else {
+        let dir_path = img_path.with_extension("dir");
+        let dir = File::open(paths::long(&dir_path)).map_err(|_| {
+            format!(
+                "{} has no VER2 header and no {} next to it; only GTA III, VC and SA archives are supported.",
+                img_path.to_string_lossy(),
+                dir_path.to_string_lossy()
+            )
+        })?;
+        (1, None, Box::new(BufReader::new(dir)))
+    };
+
+    let mut entries = Vec::with_capacity(entry_count.unwrap_or(0).min(1 << 16));
+    let mut record = [0u8; ENTRY_SIZE];
+    loop {
+        if entry_count.is_some_and(|count| entries.len() >= count) {
+            break;
+        }
+        match directory.read_exact(&mut record) {
+            Ok(()) => {}
+            // A `.dir` is just the records, so its end is the last one.
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof && entry_count.is_none() => {
+                break
+            }
+            Err(e) => {
+                return Err(format!(
+                    "Failed to read IMG directory entry {}: {e}",
+                    entries.len()
+                ))
+            }
+        }
+        let offset = u32::from_le_bytes([record[0], record[1], record[2], record[3]]);
+        let sectors = if version == 2 {
+            // Streaming size, or the size in the archive when that's unset.
+            match u16::from_le_bytes([record[4], record[5]]) {
+                0 => u32::from(u16::from_le_bytes([record[6], record[7]])),
+                streaming => u32::from(streaming),
+            }
+        } else {
+            u32::from_le_bytes([record[4], record[5], record[6], record[7]])
+        };
+        entries.push(ImgEntry {
+            name: crate::rw::c_string(&record[8..8 + NAME_SIZE]),
+            offset: u64::from(offset) * SECTOR_SIZE,
+            size: u64::from(sectors) * SECTOR_SIZE,
+        });
+    }
+    Ok(ImgListing { version, entries })
+}
+
+/// Copy the entry named `entry_name` (any case) out of an IMG archive to
+/// `dest_path`, seeking to it rather than reading the archive. Returns the
+/// bytes written.
+pub fn extract(img_path: &Path, entry_name: &str, dest_path: &Path) -> Result<u64, String> {
+    let listing = list(img_path)?;
+    let entry = listing
+        .entries
+        .iter()
+        .find(|entry| entry.name.eq_ignore_ascii_case(entry_name.trim()))
+        .ok_or_else(|| format!("The IMG has no entry named `{entry_name}`."))?;
+
+    let mut img =
+        File::open(paths::long(img_path)).map_err(|e| format!("Failed to open IMG: {e}"))?;
+    let length = img
+        .metadata()
+        .map_err(|e| format!("Failed to stat IMG: {e}"))?
+        .len();
+    if entry.offset + entry.size > length {
+        return Err(format!(
+            "`{}` runs past the end of the IMG ({} bytes at {:#x}, archive is {length}).",
+            entry.name, entry.size, entry.offset
+        ));
+    }
+    img.seek(SeekFrom::Start(entry.offset))
+        .map_err(|e| format!("Failed to seek in IMG: {e}"))?;
+
+    if let Some(parent) = dest_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(paths::long(parent))
+            .map_err(|e| format!("Failed to create output dir: {e}"))?;
+    }
+    let mut out =
+        File::create(paths::long(dest_path)).map_err(|e| format!("Failed to create file: {e}"))?;
+    std::io::copy(&mut img.take(entry.size), &mut out)
+        .map_err(|e| format!("Failed to extract `{}`: {e}", entry.name))
+}
+
+/// What `replace_img_entry` did.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplacedEntry {
+    #[serde(flatten)]
+    pub entry: ImgEntry,
+    /// The archive had no entry by that name.
+    pub added: bool,
+    /// The data went to the end of the archive rather than its old slot.
+    pub appended: bool,
+    /// Where the directory was backed up.
+    pub backup_path: String,
+}
+
+fn sectors(bytes: u64) -> u64 {
+    bytes.div_ceil(SECTOR_SIZE)
+}
+
+fn directory_record(version: u8, entry: &ImgEntry) -> Result<[u8; ENTRY_SIZE], String> {
+    let mut record = [0u8; ENTRY_SIZE];
+    let offset = u32::try_from(entry.offset / SECTOR_SIZE)
+        .map_err(|_| "The IMG is too large for its directory.".to_string())?;
+    record[..4].copy_from_slice(&offset.to_le_bytes());
+    let size = sectors(entry.size);
+    if version == 2 {
+        // Only the streaming size is set; the archive size stays 0.
+        let size = u16::try_from(size)
+            .map_err(|_| format!("`{}` is too large for a VER2 entry.", entry.name))?;
+        record[4..6].copy_from_slice(&size.to_le_bytes());
+    } else {
+        record[4..8].copy_from_slice(&(size as u32).to_le_bytes());
+    }
+    record[8..8 + entry.name.len()].copy_from_slice(entry.name.as_bytes());
+    Ok(record)
+}
+
+fn open_locked(path: &Path, what: &str) -> Result<File, String> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(paths::long(path))
+        .map_err(|e| format!("Failed to open {what}: {e}"))?;
+    file.try_lock()
+        .map_err(|_| format!("The {what} is in use by another program."))?;
+    Ok(file)
+}
+
+fn write_at(file: &mut File, offset: u64, bytes: &[u8]) -> Result<(), String> {
+    file.seek(SeekFrom::Start(offset))
+        .and_then(|_| file.write_all(bytes))
+        .map_err(|e| format!("Failed to write IMG: {e}"))
+}
+
+/// Write `data` as the entry `entry_name` of an IMG archive. It reuses the
+/// entry's slot when it fits, else goes to the end of the archive, padded
+/// to whole sectors; a name the archive doesn't have yet is added. The
+/// archive (and a v1 `.dir`) is held exclusively meanwhile, and the
+/// directory is first copied to `<archive>.dir.bak`.
+pub fn replace(img_path: &Path, entry_name: &str, data: &[u8]) -> Result<ReplacedEntry, String> {
+    let name = entry_name.trim();
+    if name.is_empty() || name.len() >= NAME_SIZE || !name.is_ascii() {
+        return Err(format!(
+            "`{name}` isn't a valid IMG entry name (1 to {} ASCII characters).",
+            NAME_SIZE - 1
+        ));
+    }
+    let mut img = open_locked(img_path, "IMG")?;
+    let listing = read_directory(&mut img, img_path)?;
+    let version = listing.version;
+    let mut entries = listing.entries;
+    let dir_path = img_path.with_extension("dir");
+    let mut dir = if version == 1 {
+        Some(open_locked(&dir_path, "IMG directory")?)
+    } else {
+        None
+    };
+
+    // The whole directory region, as it is before anything changes.
+    let backup_path = PathBuf::from(format!(
+        "{}.dir.bak",
+        img_path.with_extension("").to_string_lossy()
+    ));
+    let directory_len = 8 + entries.len() * ENTRY_SIZE;
+    let mut original = Vec::new();
+    match dir.as_mut() {
+        Some(dir) => dir.read_to_end(&mut original).map(|_| ()),
+        None => {
+            original.resize(directory_len, 0);
+            img.seek(SeekFrom::Start(0))
+                .and_then(|_| img.read_exact(&mut original))
+        }
+    }
+    .map_err(|e| format!("Failed to read IMG directory: {e}"))?;
+    std::fs::write(paths::long(&backup_path), &original)
+        .map_err(|e| format!("Failed to back up IMG directory: {e}"))?;
+
+    let padded_len = sectors(data.len() as u64) * SECTOR_SIZE;
+    let mut padded = data.to_vec();
+    padded.resize(padded_len as usize, 0);
+    let mut end = entries
+        .iter()
+        .map(|entry| entry.offset + entry.size)
+        .max()
+        .unwrap_or(0)
+        .max(
+            sectors(
+                img.metadata()
+                    .map_err(|e| format!("Failed to stat IMG: {e}"))?
+                    .len(),
+            ) * SECTOR_SIZE,
+        );
+
+    let existing = entries
+        .iter()
+        .position(|entry| entry.name.eq_ignore_ascii_case(name));
+    let added = existing.is_none();
+    let index = match existing {
+        Some(index) => index,
+        None => {
+            entries.push(ImgEntry {
+                name: name.to_string(),
+                offset: 0,
+                size: 0,
+            });
+            entries.len() - 1
+        }
+    };
+
+    // A VER2 directory grows into the data after it; entries in the way
+    // move to the end.
+    if version == 2 {
+        let directory_end = (8 + entries.len() * ENTRY_SIZE) as u64;
+        for (other, entry) in entries.iter_mut().enumerate() {
+            if other == index || entry.size == 0 || entry.offset >= directory_end {
+                continue;
+            }
+            let mut moved = vec![0; entry.size as usize];
+            img.seek(SeekFrom::Start(entry.offset))
+                .and_then(|_| img.read_exact(&mut moved))
+                .map_err(|e| format!("Failed to read `{}` from the IMG: {e}", entry.name))?;
+            write_at(&mut img, end, &moved)?;
+            entry.offset = end;
+            end += moved.len() as u64;
+        }
+    }
+
+    let appended = added || padded_len > entries[index].size;
+    if appended {
+        entries[index].offset = end;
+    } else {
+        // Clear the rest of the old slot.
+        padded.resize(entries[index].size as usize, 0);
+    }
+    write_at(&mut img, entries[index].offset, &padded)?;
+    entries[index].size = padded_len;
+
+    let mut records = Vec::with_capacity(entries.len() * ENTRY_SIZE);
+    for entry in &entries {
+        records.extend_from_slice(&directory_record(version, entry)?);
+    }
+    match dir.as_mut() {
+        Some(dir) => {
+            dir.set_len(0)
+                .and_then(|_| dir.seek(SeekFrom::Start(0)))
+                .and_then(|_| dir.write_all(&records))
+                .map_err(|e| format!("Failed to write IMG directory: {e}"))?;
+        }
+        None => {
+            let mut header = b"VER2".to_vec();
+            header.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+            header.extend_from_slice(&records);
+            write_at(&mut img, 0, &header)?;
+        }
+    }
+
+    Ok(ReplacedEntry {
+        entry: entries[index].clone(),
+        added,
+        appended,
+        backup_path: backup_path.to_string_lossy().to_string(),
+    })
+}
+
+/// List an IMG archive (GTA III/VC with a `.dir`, or SA's `VER2`): each
+/// entry's name, byte offset and size. Only the directory is read, so
+/// multi-GB archives are fine.
+#[tauri::command]
+pub async fn list_img(path: String) -> Result<ImgListing, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "img") {
+            return Err("Only .img files are supported by list_img".to_string());
+        }
+        list(Path::new(&path))
+    })
+    .await
+    .map_err(|e| format!("Failed to join IMG listing task: {e}"))?
+}
+
+/// Copy one entry of an IMG archive, matched without regard to case like the
+/// game does, to `dest_path` (a folder gets the entry's own name). The data
+/// is sector-aligned, so it may end in padding. Returns the path written.
+#[tauri::command]
+pub async fn extract_img_entry(
+    img_path: String,
+    entry_name: String,
+    dest_path: String,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&img_path, "img") {
+            return Err("Only .img files are supported by extract_img_entry".to_string());
+        }
+        let mut dest = PathBuf::from(dest_path);
+        if dest.is_dir() {
+            dest = dest.join(entry_name.trim());
+        }
+        extract(Path::new(&img_path), &entry_name, &dest)?;
+        Ok(dest.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| format!("Failed to join IMG extract task: {e}"))?
+}
+
+/// Put `file_path` into an IMG archive as `entry_name` (matched without
+/// regard to case), replacing the entry of that name or adding one. The
+/// old slot is reused when the file fits, otherwise the data is appended;
+/// either way it's padded to 2 KB sectors and the directory updated. The
+/// directory is backed up to `<archive>.dir.bak` first, and the archive is
+/// locked while it's written.
+#[tauri::command]
+pub async fn replace_img_entry(
+    img_path: String,
+    entry_name: String,
+    file_path: String,
+) -> Result<ReplacedEntry, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&img_path, "img") {
+            return Err("Only .img files are supported by replace_img_entry".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&file_path)))
+            .map_err(|e| format!("Failed to read {file_path}: {e}"))?;
+        replace(Path::new(&img_path), &entry_name, &data)
+    })
+    .await
+    .map_err(|e| format!("Failed to join IMG replace task: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(offset: u32, sectors: u32, name: &str, version: u8) -> Vec<u8> {
+        let mut record = vec![0; ENTRY_SIZE];
+        record[..4].copy_from_slice(&offset.to_le_bytes());
+        if version == 2 {
+            record[4..6].copy_from_slice(&(sectors as u16).to_le_bytes());
+        } else {
+            record[4..8].copy_from_slice(&sectors.to_le_bytes());
+        }
+        record[8..8 + name.len()].copy_from_slice(name.as_bytes());
+        record
+    }
+
+    /// An archive holding `entries` (name, sector count, fill byte) back to
+    /// back from `first_sector`, with its directory in a `.dir` (version 1)
+    /// or a `VER2` header.
+    fn archive(dir: &Path, version: u8, first_sector: u32, entries: &[(&str, u32, u8)]) -> PathBuf {
+        let img_path = dir.join("test.img");
+        let mut img = vec![0; first_sector as usize * SECTOR_SIZE as usize];
+        let mut records = Vec::new();
+        for (name, sectors, fill) in entries {
+            let offset = (img.len() as u64 / SECTOR_SIZE) as u32;
+            records.extend(record(offset, *sectors, name, version));
+            img.resize(img.len() + (*sectors as u64 * SECTOR_SIZE) as usize, *fill);
+        }
+        if version == 2 {
+            let mut header = b"VER2".to_vec();
+            header.extend((entries.len() as u32).to_le_bytes());
+            header.extend(records);
+            img[..header.len()].copy_from_slice(&header);
+        } else {
+            std::fs::write(img_path.with_extension("dir"), records).unwrap();
+        }
+        std::fs::write(&img_path, img).unwrap();
+        img_path
+    }
+
+    fn extracted(img_path: &Path, name: &str) -> Vec<u8> {
+        let out = img_path.with_file_name(format!("out-{name}"));
+        let written = extract(img_path, name, &out).unwrap();
+        let data = std::fs::read(&out).unwrap();
+        assert_eq!(written, data.len() as u64);
+        data
+    }
+
+    /// `data`, zero padded to whole sectors as the archive stores it.
+    fn padded(data: &[u8]) -> Vec<u8> {
+        let mut padded = data.to_vec();
+        padded.resize((sectors(data.len() as u64) * SECTOR_SIZE) as usize, 0);
+        padded
+    }
+
+    fn round_trip(version: u8) {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let first = if version == 2 { 1 } else { 0 };
+        let img_path = archive(
+            dir,
+            version,
+            first,
+            &[("a.dff", 2, 0xAA), ("b.txd", 1, 0xBB)],
+        );
+        let directory = |img_path: &Path| match version {
+            2 => std::fs::read(img_path).unwrap()[..8 + 2 * ENTRY_SIZE].to_vec(),
+            _ => std::fs::read(img_path.with_extension("dir")).unwrap(),
+        };
+        let original = directory(&img_path);
+        let start = u64::from(first) * SECTOR_SIZE;
+
+        // Smaller than the slot: written in place, the rest of it cleared.
+        let small = vec![1; 100];
+        let replaced = replace(&img_path, "A.DFF", &small).unwrap();
+        assert!(!replaced.added && !replaced.appended);
+        assert_eq!(replaced.entry.offset, start);
+        assert_eq!(replaced.entry.size, SECTOR_SIZE);
+        assert_eq!(
+            std::fs::read(&replaced.backup_path).unwrap(),
+            original,
+            "the backup is the directory as it was"
+        );
+        assert_eq!(
+            replaced.backup_path,
+            dir.join("test.dir.bak").to_string_lossy()
+        );
+        let mut img = std::fs::read(&img_path).unwrap();
+        assert!(
+            img[(start + SECTOR_SIZE) as usize..][..SECTOR_SIZE as usize]
+                .iter()
+                .all(|byte| *byte == 0)
+        );
+
+        // Larger than the slot: appended after the last entry.
+        let before = directory(&img_path);
+        let large: Vec<u8> = (0..5000).map(|i| i as u8).collect();
+        let replaced = replace(&img_path, "b.txd", &large).unwrap();
+        assert!(!replaced.added && replaced.appended);
+        assert_eq!(replaced.entry.offset, start + 3 * SECTOR_SIZE);
+        assert_eq!(replaced.entry.size, 3 * SECTOR_SIZE);
+        assert_eq!(std::fs::read(&replaced.backup_path).unwrap(), before);
+
+        // A new name: added and appended.
+        let added = replace(&img_path, "c.col", b"col").unwrap();
+        assert!(added.added && added.appended);
+        assert_eq!(added.entry.offset, start + 6 * SECTOR_SIZE);
+
+        let listing = list(&img_path).unwrap();
+        assert_eq!(listing.version, version);
+        let names: Vec<_> = listing
+            .entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(names, ["a.dff", "b.txd", "c.col"]);
+        assert_eq!(extracted(&img_path, "a.dff"), padded(&small));
+        assert_eq!(extracted(&img_path, "b.txd"), padded(&large));
+        assert_eq!(extracted(&img_path, "c.col"), padded(b"col"));
+
+        // Appending leaves the old slot alone; nothing else moved.
+        img = std::fs::read(&img_path).unwrap();
+        assert_eq!(img.len() as u64, start + 7 * SECTOR_SIZE);
+        assert!(
+            img[(start + 2 * SECTOR_SIZE) as usize..][..SECTOR_SIZE as usize]
+                .iter()
+                .all(|byte| *byte == 0xBB)
+        );
+    }
+
+    #[test]
+    fn replaces_entries_of_v1_archives() {
+        round_trip(1);
+    }
+
+    #[test]
+    fn replaces_entries_of_ver2_archives() {
+        round_trip(2);
+    }
+
+    #[test]
+    fn moves_entries_out_of_a_growing_ver2_directory() {
+        // 63 entries fill the first sector's directory; a 64th overruns it.
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let names: Vec<String> = (0..63).map(|i| format!("e{i}.dat")).collect();
+        let entries: Vec<_> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), 1, i as u8 + 1))
+            .collect();
+        let img_path = archive(dir, 2, 1, &entries);
+
+        let added = replace(&img_path, "new.dat", b"new").unwrap();
+        assert!(added.added);
+        let listing = list(&img_path).unwrap();
+        assert_eq!(listing.entries.len(), 64);
+        let moved = &listing.entries[0];
+        assert_eq!(moved.offset, 64 * SECTOR_SIZE);
+        assert_eq!(added.entry.offset, 65 * SECTOR_SIZE);
+        assert_eq!(
+            extracted(&img_path, "e0.dat"),
+            vec![1; SECTOR_SIZE as usize]
+        );
+        assert_eq!(
+            extracted(&img_path, "e62.dat"),
+            vec![63; SECTOR_SIZE as usize]
+        );
+        assert_eq!(extracted(&img_path, "new.dat"), padded(b"new"));
+    }
+
+    #[test]
+    fn rejects_names_the_directory_cannot_hold() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let img_path = archive(dir, 2, 1, &[("a.dff", 1, 0)]);
+        for name in ["", "  ", "a_name_of_24_characters_", "ümlaut.dff"] {
+            assert!(replace(&img_path, name, b"x").is_err(), "{name:?}");
+        }
+        assert!(!dir.join("test.dir.bak").exists());
+    }
+}
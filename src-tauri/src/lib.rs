@@ -1,25 +1,70 @@
 use std::{
     path::{Path, PathBuf},
     process::Command,
-    sync::Mutex,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
 };
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
 
-use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use tauri::{Emitter, Manager, State};
 
-const MAX_PDN_FILE_BYTES: u64 = 128 * 1024 * 1024; // 128 MB
-const MAX_PDN_DIMENSION: u32 = 16_384;
-const MAX_PDN_OUTPUT_BYTES: usize = 384 * 1024 * 1024; // 384 MB RGBA
-const MAX_PDN_INFLATED_CHUNK_BYTES: usize = 64 * 1024 * 1024; // 64 MB/chunk
-const MAX_PDN_TOTAL_INFLATED_BYTES: usize = 768 * 1024 * 1024; // 768 MB total
+mod bridge;
+mod cache;
+mod clmesh;
+mod col;
+mod conversion;
+mod dff;
+mod diagnostics;
+mod dxt;
+mod gltf;
+mod history;
+mod images;
+mod img;
+mod meshdiff;
+mod nrbf;
+mod obj;
+mod paths;
+mod pdn;
+mod rpf;
+mod rsc7;
+mod rw;
+mod tasks;
+mod textures;
+mod txd;
+mod watch;
+
+use bridge::{BridgeOutput, BridgeProcess, ServerError};
+use cache::CacheKeyMode;
+use conversion::{ConversionContext, ConversionStats, ConversionTasks, RunError};
+use rsc7::YftClass;
+use watch::WatcherRegistry;
+
+/// Runtime the app is built on. Unit tests run on Tauri's mock runtime, so
+/// code that needs an `AppHandle` can be driven without a window system.
+#[cfg(not(test))]
+pub type Runtime = tauri::Wry;
+#[cfg(test)]
+pub type Runtime = tauri::test::MockRuntime;
+
+pub type AppHandle = tauri::AppHandle<Runtime>;
+
+// 30 + 60 + 120 + 240 + 480 ms: roughly a second of backoff in total.
+const READ_RETRY_ATTEMPTS: u32 = 6;
+const READ_RETRY_DELAY: Duration = Duration::from_millis(30);
 
 fn is_yft(path: &str) -> bool {
+    has_extension(path, "yft")
+}
+
+fn has_extension(path: &str, extension: &str) -> bool {
     Path::new(path)
         .extension()
         .and_then(|ext| ext.to_str())
-        .map(|ext| ext.eq_ignore_ascii_case("yft"))
+        .map(|ext| ext.eq_ignore_ascii_case(extension))
         .unwrap_or(false)
 }
 
@@ -30,8 +75,10 @@ fn is_supported_open_model(path: &str) -> bool {
         .map(|ext| {
             ext.eq_ignore_ascii_case("yft")
                 || ext.eq_ignore_ascii_case("ydd")
+                || ext.eq_ignore_ascii_case("ydr")
                 || ext.eq_ignore_ascii_case("dff")
                 || ext.eq_ignore_ascii_case("clmesh")
+                || ext.eq_ignore_ascii_case("rpf")
         })
         .unwrap_or(false)
 }
@@ -43,7 +90,7 @@ fn normalize_open_file_arg(raw: &str) -> Option<String> {
     }
 
     if let Some(rest) = candidate.strip_prefix("file://") {
-        let mut normalized = rest.replace("%20", " ");
+        let mut normalized = percent_decode(rest);
         if cfg!(windows) {
             if normalized.starts_with('/') && normalized.chars().nth(2) == Some(':') {
                 normalized = normalized.chars().skip(1).collect();
@@ -65,7 +112,7 @@ fn extract_open_file_arg(args: &[String]) -> Option<String> {
         })
 }
 
-fn queue_open_file(app: &tauri::AppHandle, file_path: String) {
+fn queue_open_file(app: &crate::AppHandle, file_path: String) {
     if !is_supported_open_model(&file_path) {
         return;
     }
@@ -83,54 +130,34 @@ fn queue_open_file(app: &tauri::AppHandle, file_path: String) {
     }
 }
 
-fn read_u32_le(data: &[u8], offset: usize) -> Option<u32> {
-    if offset + 4 > data.len() {
-        return None;
-    }
-    Some(u32::from_le_bytes([
-        data[offset],
-        data[offset + 1],
-        data[offset + 2],
-        data[offset + 3],
-    ]))
-}
-
-fn find_geometry_chunk(data: &[u8], start: usize, end: usize) -> Option<usize> {
-    let mut offset = start;
-    while offset + 12 <= end {
-        let chunk_id = read_u32_le(data, offset)?;
-        let size = read_u32_le(data, offset + 4)? as usize;
-        let data_start = offset + 12;
-        let data_end = data_start + size;
-        if data_end > end || data_end > data.len() {
-            return None;
-        }
-        if chunk_id == 0x0F {
-            return Some(offset);
-        }
-        if chunk_id == 0x10 || chunk_id == 0x1A || chunk_id == 0x0E {
-            if let Some(found) = find_geometry_chunk(data, data_start, data_end) {
-                return Some(found);
-            }
-        }
-        offset = data_end;
+/// Whether a read failed because another process still holds the file, e.g.
+/// Paint.NET or an exporter that hasn't closed it yet after a save.
+fn is_file_locked_error(error: &std::io::Error) -> bool {
+    if error.kind() == std::io::ErrorKind::PermissionDenied {
+        return true;
     }
-    None
+    // ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION
+    cfg!(windows) && matches!(error.raw_os_error(), Some(32) | Some(33))
 }
 
-fn read_dff_vertex_count(path: &Path) -> Option<u32> {
-    let data = std::fs::read(path).ok()?;
-    let geom_offset = find_geometry_chunk(&data, 0, data.len())?;
-    let geom_start = geom_offset + 12;
-    if geom_start + 24 > data.len() {
-        return None;
-    }
-    let struct_size = read_u32_le(&data, geom_start + 4)? as usize;
-    let struct_data = geom_start + 12;
-    if struct_data + struct_size > data.len() || struct_data + 12 > data.len() {
-        return None;
+/// Read a file that a watcher has just reported as changed, retrying with
+/// exponential backoff while the writer still has it locked.
+fn read_file_with_retry(path: &Path, attempts: u32, delay: Duration) -> Result<Vec<u8>, String> {
+    let attempts = attempts.max(1);
+    let mut delay = delay;
+    let mut attempt = 1;
+    loop {
+        match std::fs::read(path) {
+            Ok(data) => return Ok(data),
+            Err(e) if attempt < attempts && is_file_locked_error(&e) => {
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) if attempt > 1 => return Err(format!("{e} (after {attempt} attempts)")),
+            Err(e) => return Err(e.to_string()),
+        }
     }
-    read_u32_le(&data, struct_data + 8)
 }
 
 fn find_first_file_with_ext(dir: &Path, ext: &str) -> Option<PathBuf> {
@@ -145,7 +172,23 @@ fn find_first_file_with_ext(dir: &Path, ext: &str) -> Option<PathBuf> {
         })
 }
 
-fn run_yft_converter(
+/// Files and timings from one converter run in its work dir.
+struct ConverterRun {
+    dff: PathBuf,
+    txd: Option<PathBuf>,
+    /// Collision the converter built, unless it was told not to.
+    col: Option<PathBuf>,
+    output: std::process::Output,
+    log: String,
+    staging_ms: u64,
+    process_ms: u64,
+    reading_ms: u64,
+}
+
+/// Copy everything the converter needs into a fresh `work_dir`, under the
+/// fixed names it looks for, and return the staged converter.
+#[allow(clippy::too_many_arguments)]
+fn stage_converter_files(
     work_dir: &Path,
     settings: &Path,
     col_materials: &Path,
@@ -153,12 +196,14 @@ fn run_yft_converter(
     zlib: &Path,
     input_yft: &Path,
     input_ytd: Option<&Path>,
-) -> Result<(PathBuf, Option<PathBuf>, std::process::Output, String), String> {
+    settings_overrides: &BTreeMap<String, String>,
+) -> Result<PathBuf, String> {
     if work_dir.exists() {
-        std::fs::remove_dir_all(work_dir)
+        std::fs::remove_dir_all(paths::long(work_dir))
             .map_err(|e| format!("Failed to reset working dir: {e}"))?;
     }
-    std::fs::create_dir_all(work_dir).map_err(|e| format!("Failed to create working dir: {e}"))?;
+    std::fs::create_dir_all(paths::long(work_dir))
+        .map_err(|e| format!("Failed to create working dir: {e}"))?;
 
     let staged_settings = work_dir.join("GeneralSettings.ini");
     let staged_col = work_dir.join("col_gen_materials.dat");
@@ -167,34 +212,97 @@ fn run_yft_converter(
         converter_exe
             .file_name()
             .and_then(|n| n.to_str())
-            .unwrap_or("ytdydryddyft2txddffcol.exe"),
+            .unwrap_or(CONVERTER_EXE),
     );
     let staged_zlib = work_dir.join(
         zlib.file_name()
             .and_then(|n| n.to_str())
-            .unwrap_or("zlib1.dll"),
+            .unwrap_or(CONVERTER_DLL),
     );
 
-    std::fs::copy(settings, &staged_settings)
-        .map_err(|e| format!("Failed to stage settings: {e}"))?;
-    std::fs::copy(col_materials, &staged_col)
+    if settings_overrides.is_empty() {
+        std::fs::copy(paths::long(settings), paths::long(&staged_settings))
+            .map_err(|e| format!("Failed to stage settings: {e}"))?;
+    } else {
+        let contents = std::fs::read_to_string(paths::long(settings))
+            .map_err(|e| format!("Failed to read settings: {e}"))?;
+        let merged = conversion::apply_settings_overrides(&contents, settings_overrides);
+        std::fs::write(paths::long(&staged_settings), merged)
+            .map_err(|e| format!("Failed to stage settings: {e}"))?;
+    }
+    std::fs::copy(paths::long(col_materials), paths::long(&staged_col))
         .map_err(|e| format!("Failed to stage material file: {e}"))?;
-    std::fs::copy(converter_exe, &staged_exe)
+    std::fs::copy(paths::long(converter_exe), paths::long(&staged_exe))
         .map_err(|e| format!("Failed to stage converter: {e}"))?;
-    std::fs::copy(zlib, &staged_zlib).map_err(|e| format!("Failed to stage zlib: {e}"))?;
-    std::fs::copy(input_yft, &staged_yft).map_err(|e| format!("Failed to stage YFT: {e}"))?;
+    std::fs::copy(paths::long(zlib), paths::long(&staged_zlib))
+        .map_err(|e| format!("Failed to stage zlib: {e}"))?;
+    let yft_bytes = read_file_with_retry(
+        &paths::long(input_yft),
+        READ_RETRY_ATTEMPTS,
+        READ_RETRY_DELAY,
+    )
+    .map_err(|e| format!("Failed to read YFT: {e}"))?;
+    std::fs::write(paths::long(&staged_yft), yft_bytes)
+        .map_err(|e| format!("Failed to stage YFT: {e}"))?;
 
     if let Some(ytd) = input_ytd {
         let staged_ytd = work_dir.join("model.ytd");
-        let _ = std::fs::copy(ytd, &staged_ytd);
+        let _ = std::fs::copy(paths::long(ytd), paths::long(&staged_ytd));
     }
 
-    let output = std::process::Command::new(&staged_exe)
-        .current_dir(work_dir)
-        .output()
-        .map_err(|e| format!("Failed to run converter: {e}"))?;
+    Ok(staged_exe)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_yft_converter(
+    ctx: &ConversionContext,
+    work_dir: &Path,
+    settings: &Path,
+    col_materials: &Path,
+    converter_exe: &Path,
+    zlib: &Path,
+    input_yft: &Path,
+    input_ytd: Option<&Path>,
+    settings_overrides: &BTreeMap<String, String>,
+) -> Result<ConverterRun, String> {
+    ctx.stage("staging", "Staging converter files");
+    let staging_started = Instant::now();
+    let staged_exe = stage_converter_files(
+        work_dir,
+        settings,
+        col_materials,
+        converter_exe,
+        zlib,
+        input_yft,
+        input_ytd,
+        settings_overrides,
+    )?;
+    let staging_ms = conversion::elapsed_ms(staging_started);
 
+    ctx.stage("converting", "Running YFT converter");
+    let process_started = Instant::now();
+    let mut command = std::process::Command::new(&staged_exe);
+    command.current_dir(work_dir);
     let log_path = work_dir.join("log.txt");
+    let output = ctx
+        .run(command, &[work_dir.to_path_buf()])
+        .map_err(|e| match e {
+            RunError::Cancelled => conversion::CANCELLED.to_string(),
+            RunError::TimedOut(timeout) => {
+                let partial_log = std::fs::read_to_string(&log_path).unwrap_or_default();
+                conversion::remove_partial_output(work_dir);
+                format!(
+                    "YFT converter timed out after {}s and was stopped. Very large models may need a longer timeout.\nLOG:\n{}",
+                    timeout.as_secs(),
+                    partial_log.trim()
+                )
+            }
+            RunError::Failed(e) => format!("Failed to run converter: {e}"),
+        })?;
+
+    let process_ms = conversion::elapsed_ms(process_started);
+
+    let reading_started = Instant::now();
     let log_contents = std::fs::read_to_string(&log_path).unwrap_or_default();
 
     let mut produced_dff = work_dir.join("model.dff");
@@ -219,41 +327,25 @@ fn run_yft_converter(
     if !produced_txd.exists() {
         produced_txd = find_first_file_with_ext(work_dir, "txd").unwrap_or(produced_txd);
     }
+    let mut produced_col = work_dir.join("model.col");
+    if !produced_col.exists() {
+        produced_col = find_first_file_with_ext(work_dir, "col").unwrap_or(produced_col);
+    }
 
-    Ok((
-        produced_dff,
-        if produced_txd.exists() {
+    Ok(ConverterRun {
+        dff: produced_dff,
+        txd: if produced_txd.exists() {
             Some(produced_txd)
         } else {
             None
         },
+        col: produced_col.exists().then_some(produced_col),
         output,
-        log_contents,
-    ))
-}
-
-#[derive(Default)]
-struct WatchState {
-    watcher: Mutex<Option<RecommendedWatcher>>,
-    path: Mutex<Option<PathBuf>>,
-}
-
-#[derive(Default)]
-struct WindowWatchState {
-    watcher: Mutex<Option<RecommendedWatcher>>,
-    path: Mutex<Option<PathBuf>>,
-}
-
-#[derive(Default)]
-struct MultiWatchState {
-    watcher: Mutex<Option<RecommendedWatcher>>,
-    paths: Mutex<Vec<PathBuf>>,
-}
-
-#[derive(Default)]
-struct ModelWatchState {
-    watcher: Mutex<Option<RecommendedWatcher>>,
-    path: Mutex<Option<PathBuf>>,
+        log: log_contents,
+        staging_ms,
+        process_ms,
+        reading_ms: conversion::elapsed_ms(reading_started),
+    })
 }
 
 #[derive(Default)]
@@ -261,12 +353,6 @@ struct PendingOpenFileState {
     path: Mutex<Option<String>>,
 }
 
-#[derive(serde::Serialize, Clone)]
-struct WatchPayload {
-    path: String,
-    kind: String,
-}
-
 #[derive(serde::Deserialize)]
 struct UpdaterFeedPlatform {
     url: Option<String>,
@@ -349,359 +435,1218 @@ async fn inspect_updater_release(endpoint: String) -> Result<serde_json::Value,
     .map_err(|e| format!("Failed to join updater inspection task: {e}"))?
 }
 
-#[tauri::command]
-fn start_watch(
-    path: String,
-    app: tauri::AppHandle,
-    state: State<WatchState>,
-) -> Result<(), String> {
-    let mut watcher_guard = state
-        .watcher
-        .lock()
-        .map_err(|_| "watcher lock failed".to_string())?;
-    let mut path_guard = state
-        .path
-        .lock()
-        .map_err(|_| "path lock failed".to_string())?;
+/// Resource types the CodeWalker bridge can parse (its `--type`).
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BridgeModel {
+    Yft,
+    Ydd,
+    Ydr,
+}
 
-    if let Some(mut existing) = watcher_guard.take() {
-        if let Some(prev_path) = path_guard.take() {
-            let _ = existing.unwatch(&prev_path);
+impl BridgeModel {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Yft => "yft",
+            Self::Ydd => "ydd",
+            Self::Ydr => "ydr",
         }
     }
 
-    let app_handle = app.clone();
-    let mut watcher =
-        notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
-            if let Ok(event) = res {
-                let payload = WatchPayload {
-                    path: event
-                        .paths
-                        .get(0)
-                        .map(|p| p.to_string_lossy().to_string())
-                        .unwrap_or_default(),
-                    kind: format!("{:?}", event.kind),
-                };
-                let _ = app_handle.emit("texture:update", payload);
-            }
-        })
-        .map_err(|e| e.to_string())?;
+    fn label(self) -> &'static str {
+        match self {
+            Self::Yft => "YFT",
+            Self::Ydd => "YDD",
+            Self::Ydr => "YDR",
+        }
+    }
+}
 
-    let path_buf = PathBuf::from(&path);
-    watcher
-        .watch(&path_buf, RecursiveMode::NonRecursive)
-        .map_err(|e| e.to_string())?;
+#[derive(Clone, Default)]
+struct ParseOptions {
+    key_mode: CacheKeyMode,
+    /// Texture dictionary to use instead of the sibling `.ytd`, e.g. a shared
+    /// `vehshare.ytd`.
+    ytd_path: Option<String>,
+    /// Single drawable of a `.ydd` to parse; all of them when unset.
+    drawable_name: Option<String>,
+    /// Also write one `model_<level>.clmesh` per LOD next to the combined
+    /// mesh.
+    include_lods: bool,
+    /// Parse the other half of a `_hi` pair (`adder_hi.yft` / `adder.yft`)
+    /// alongside the input.
+    merge_hi: bool,
+    /// Also dump the fragment's bounds to `collision.clmesh` and
+    /// `collision.json`.
+    include_collision: bool,
+    /// Also write the fragment's damaged drawables as `model_damaged.clmesh`.
+    include_damaged: bool,
+    /// Extract the textures of the `.ytd` used into the cache as PNGs.
+    extract_textures: bool,
+    /// Bridge timeout; `conversion::DEFAULT_PROCESS_TIMEOUT_SECS` when unset.
+    timeout_secs: Option<u64>,
+    /// Stream the bridge's output as `parse:log` events.
+    verbose: bool,
+}
+
+#[derive(Clone, Default)]
+struct ConvertYftOptions {
+    key_mode: CacheKeyMode,
+    /// Where to copy the converted files, named after the input.
+    export_dir: Option<String>,
+    /// Replace files already in `export_dir`.
+    overwrite: bool,
+    /// `GeneralSettings.ini` entries to change, as `key` or `Section.key`.
+    settings_overrides: BTreeMap<String, String>,
+    /// Leave out the collision the converter generates, for quick previews.
+    skip_collision: bool,
+    /// Converter profile (`GeneralSettings.<name>.ini`) to use instead of the
+    /// default-then-vehicle fallback.
+    profile: Option<String>,
+    /// Converter timeout; `conversion::DEFAULT_PROCESS_TIMEOUT_SECS` when
+    /// unset.
+    timeout_secs: Option<u64>,
+    /// Stream the converter's output as `conversion:log` events.
+    verbose: bool,
+}
 
-    *path_guard = Some(path_buf);
-    *watcher_guard = Some(watcher);
+/// Version of the result shapes below. Bump it whenever a field is added,
+/// renamed or changes meaning, so the frontend can tell. 2 added
+/// `resource` to conversions, 3 `workDir`, 4 `geometries`, 5 `rwVersion`,
+/// 6 `validation`, 7 `uvChannels`/`uvBounds`/`uvOutOfRange`, 8
+/// `geometries[].skinned`.
+const RESULT_SCHEMA_VERSION: u32 = 8;
 
-    Ok(())
+/// What `parse_yft` (and `parse_ydd`/`parse_ydr`) return. Paths that may
+/// not exist are `None`, serialized as null.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ParseYftResult {
+    schema_version: u32,
+    mesh_path: String,
+    cache_key: String,
+    key_mode: &'static str,
+    cached: bool,
+    meta: serde_json::Value,
+    ytd_path: Option<String>,
+    lod_paths: Option<serde_json::Map<String, serde_json::Value>>,
+    sibling_path: Option<String>,
+    sibling_mesh_path: Option<String>,
+    skeleton_path: Option<String>,
+    collision_path: Option<String>,
+    collision_json_path: Option<String>,
+    collision: Option<serde_json::Value>,
+    stats: ConversionStats,
+}
+
+/// What `convert_yft` returns. `classification` and `retried` are only
+/// known when the converter ran, and `exitCode` only then too.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ConvertYftResult {
+    schema_version: u32,
+    dff_path: String,
+    txd_path: Option<String>,
+    cache_key: String,
+    key_mode: &'static str,
+    cached: bool,
+    exit_code: Option<i32>,
+    /// Summed across every geometry in the DFF.
+    vertex_count: u32,
+    /// Per-geometry counts behind `vertex_count`.
+    geometries: Vec<dff::GeometryInfo>,
+    /// Most texture coordinate sets any geometry has; livery templates
+    /// often need the second.
+    uv_channels: u32,
+    /// Range of each texture coordinate set across the geometries.
+    uv_bounds: Vec<dff::UvBounds>,
+    /// Whether any coordinates leave [0, 1], i.e. the texture tiles.
+    uv_out_of_range: bool,
+    /// The output's RenderWare version, when it could be read.
+    rw_version: Option<dff::DffVersion>,
+    /// Structural checks of the output; only outputs without errors are
+    /// cached, so this only ever holds warnings.
+    validation: dff::DffValidation,
+    exported: Option<cache::ExportedConversion>,
+    settings_overrides: BTreeMap<String, String>,
+    collision_generated: bool,
+    profile_used: Option<String>,
+    classification: Option<&'static str>,
+    retried: Option<bool>,
+    /// Where the converter ran; only set when it did.
+    work_dir: Option<String>,
+    /// The input's RSC7 header.
+    resource: rsc7::ResourceHeader,
+    stats: ConversionStats,
 }
 
+/// Parse a .yft through the CodeWalker bridge into a cached `.clmesh`,
+/// reporting `parse:started`/`parse:stage`/`parse:finished`/`parse:failed`.
+/// Runs on the blocking pool so other commands stay responsive meanwhile.
+///
+/// `key_mode` is `fast` (path + mtime + size, the default) or `content`
+/// (hash of the file bytes, shared across copies of the same file).
+/// `ytd_path` overrides the sibling `.ytd` lookup; if it doesn't exist the
+/// lookup runs as usual and `meta.ytdOverrideMissing` says so.
+///
+/// `meta.lods` always lists per-LOD counts. With `include_lods` each level is
+/// also cached as its own mesh and returned in `lodPaths`.
+///
+/// With `merge_hi` (the default) the sibling half of a `_hi` pair is parsed
+/// too and returned as `siblingMeshPath`; `meta.hiMeshPath` and
+/// `meta.baseMeshPath` say which is which, or `meta.siblingMissing` is set.
+///
+/// With `include_collision` the fragment's bounds are cached as
+/// `collision.clmesh` (triangles) and `collision.json` (primitives and
+/// materials), returned as `collisionPath`/`collisionJsonPath` with per-type
+/// counts in `collision`.
+///
+/// With `include_damaged` the damaged drawables are cached as
+/// `model_damaged.clmesh` and `meta.damagedMeshPath` points at it, or is null
+/// when the fragment has none.
+///
+/// With `extract_textures` the textures of the `.ytd` used are extracted
+/// like `extract_ytd` does and listed in `meta.textureFiles`; a failed
+/// extraction leaves the model as is and sets `meta.texturesError`.
+///
+/// `meta.skeleton` lists the bones (name, parent index, local translation and
+/// rotation, tag) when the model has a skeleton; per-vertex bone indices and
+/// weights are in the `model.skel.json` returned as `skeletonPath`.
+///
+/// Files without an RSC7 header are turned away before the bridge runs,
+/// with a hint to extract them first when they look encrypted. The header
+/// read is returned as `meta.resource` (version, flags, segment sizes).
+///
+/// The bridge is killed after `timeout_secs` (120 by default). With
+/// `verbose` its output is streamed line by line as `parse:log`. `stats`
+/// has the time spent per step and the input and output sizes.
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
-fn stop_watch(state: State<WatchState>) -> Result<(), String> {
-    let mut watcher_guard = state
-        .watcher
-        .lock()
-        .map_err(|_| "watcher lock failed".to_string())?;
-    let mut path_guard = state
-        .path
-        .lock()
-        .map_err(|_| "path lock failed".to_string())?;
+async fn parse_yft(
+    path: String,
+    key_mode: Option<String>,
+    ytd_path: Option<String>,
+    include_lods: Option<bool>,
+    merge_hi: Option<bool>,
+    include_collision: Option<bool>,
+    include_damaged: Option<bool>,
+    extract_textures: Option<bool>,
+    timeout_secs: Option<u64>,
+    verbose: Option<bool>,
+    app: crate::AppHandle,
+) -> Result<ParseYftResult, String> {
+    let options = ParseOptions {
+        key_mode: CacheKeyMode::parse(key_mode.as_deref())?,
+        ytd_path,
+        include_lods: include_lods.unwrap_or(false),
+        merge_hi: merge_hi.unwrap_or(true),
+        include_collision: include_collision.unwrap_or(false),
+        include_damaged: include_damaged.unwrap_or(false),
+        extract_textures: extract_textures.unwrap_or(false),
+        timeout_secs,
+        verbose: verbose.unwrap_or(false),
+        ..Default::default()
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        parse_model_blocking(BridgeModel::Yft, path, options, &app)
+    })
+    .await
+    .map_err(|e| format!("Failed to join YFT parse task: {e}"))?
+}
+
+/// Parse a .yft that only exists in memory, e.g. from an archive or the
+/// clipboard. The bytes are the raw request body (no JSON round trip for
+/// 100 MB+ models) and the file name is the `x-file-name` header,
+/// percent-encoded if it isn't ASCII.
+///
+/// The bytes are staged in the cache work area, parsed like `parse_yft` with
+/// a content key and no sibling lookups, then removed. `ytdPath` is always
+/// null.
+#[tauri::command]
+async fn parse_yft_bytes(
+    request: tauri::ipc::Request<'_>,
+    app: crate::AppHandle,
+) -> Result<ParseYftResult, String> {
+    let tauri::ipc::InvokeBody::Raw(data) = request.body() else {
+        return Err("parse_yft_bytes expects the file as a raw request body.".to_string());
+    };
+    let name = request
+        .headers()
+        .get("x-file-name")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| percent_decode(value.trim()))
+        .ok_or_else(|| "parse_yft_bytes requires an `x-file-name` header.".to_string())?;
+    let file_name = Path::new(&name)
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .filter(|file_name| is_yft(file_name))
+        .ok_or_else(|| format!("`{name}` is not a .yft file name."))?;
+
+    // Each call gets its own directory so nothing lands next to the staged
+    // file for the sibling .ytd lookup to find.
+    static NEXT_STAGE: AtomicU64 = AtomicU64::new(0);
+    let stage_dir = cache::yft_work_root(&app)?.join(format!(
+        "bytes-{}-{}",
+        std::process::id(),
+        NEXT_STAGE.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(paths::long(&stage_dir))
+        .map_err(|e| format!("Failed to create staging dir: {e}"))?;
+    let staged = stage_dir.join(file_name);
+    // Written straight from the request so the body isn't copied first.
+    if let Err(e) = std::fs::write(paths::long(&staged), data) {
+        let _ = std::fs::remove_dir_all(paths::long(&stage_dir));
+        return Err(format!("Failed to stage YFT bytes: {e}"));
+    }
+
+    let options = ParseOptions {
+        key_mode: CacheKeyMode::Content,
+        ..Default::default()
+    };
+    let staged_path = staged.to_string_lossy().to_string();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        parse_model_blocking(BridgeModel::Yft, staged_path, options, &app)
+    })
+    .await;
+    let _ = std::fs::remove_dir_all(paths::long(&stage_dir));
+    result.map_err(|e| format!("Failed to join YFT parse task: {e}"))?
+}
 
-    if let Some(mut watcher) = watcher_guard.take() {
-        if let Some(prev_path) = path_guard.take() {
-            let _ = watcher.unwatch(&prev_path);
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
         }
     }
+    String::from_utf8_lossy(&decoded).to_string()
+}
 
-    Ok(())
+/// Parse a .ydd drawable dictionary like `parse_yft`. With `drawable_name`
+/// only that drawable is parsed, otherwise all of them into one mesh;
+/// `meta.drawables` lists every name in the dictionary either way.
+#[tauri::command]
+async fn parse_ydd(
+    path: String,
+    drawable_name: Option<String>,
+    key_mode: Option<String>,
+    app: crate::AppHandle,
+) -> Result<ParseYftResult, String> {
+    let options = ParseOptions {
+        key_mode: CacheKeyMode::parse(key_mode.as_deref())?,
+        drawable_name: drawable_name.filter(|name| !name.trim().is_empty()),
+        ..Default::default()
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        parse_model_blocking(BridgeModel::Ydd, path, options, &app)
+    })
+    .await
+    .map_err(|e| format!("Failed to join YDD parse task: {e}"))?
 }
 
+/// Parse a .ydr prop like `parse_yft`, into the same cache layout.
+/// `meta.embeddedTextures` is set when the drawable carries its own textures.
 #[tauri::command]
-fn start_window_watch(
+async fn parse_ydr(
     path: String,
-    app: tauri::AppHandle,
-    state: State<WindowWatchState>,
-) -> Result<(), String> {
-    let mut watcher_guard = state
-        .watcher
-        .lock()
-        .map_err(|_| "watcher lock failed".to_string())?;
-    let mut path_guard = state
-        .path
-        .lock()
-        .map_err(|_| "path lock failed".to_string())?;
+    key_mode: Option<String>,
+    app: crate::AppHandle,
+) -> Result<ParseYftResult, String> {
+    let options = ParseOptions {
+        key_mode: CacheKeyMode::parse(key_mode.as_deref())?,
+        ..Default::default()
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        parse_model_blocking(BridgeModel::Ydr, path, options, &app)
+    })
+    .await
+    .map_err(|e| format!("Failed to join YDR parse task: {e}"))?
+}
 
-    if let Some(mut existing) = watcher_guard.take() {
-        if let Some(prev_path) = path_guard.take() {
-            let _ = existing.unwatch(&prev_path);
+/// Import a Wavefront .obj, such as a work-in-progress export from Blender,
+/// as a mesh to overlay on a parsed model. The result has the shape of
+/// `parse_yft`'s so the viewer loads it the same way: the faces are cached
+/// as `model.clmesh` under a content key, one mesh per `g`/`o` group, with
+/// the axes and UVs flipped back from what `export_dff_to_obj` writes.
+/// Materials are ignored. `meta` has the vertex, triangle and mesh counts,
+/// the bounding box and `flatNormals`, the faces that had no normals.
+#[tauri::command]
+async fn import_obj(path: String, app: crate::AppHandle) -> Result<ParseYftResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !has_extension(&path, "obj") {
+            return Err("Only .obj files are supported by import_obj".to_string());
         }
-    }
-
-    let app_handle = app.clone();
-    let mut watcher =
-        notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
-            if let Ok(event) = res {
-                let payload = WatchPayload {
-                    path: event
-                        .paths
-                        .get(0)
-                        .map(|p| p.to_string_lossy().to_string())
-                        .unwrap_or_default(),
-                    kind: format!("{:?}", event.kind),
-                };
-                let _ = app_handle.emit("texture:update", payload);
+        let key = cache::variant_key(&cache::yft_cache_key(&path, CacheKeyMode::Content)?, "obj");
+        let out_dir = cache::yft_cache_root(&app)?.join(&key);
+        let out_mesh = out_dir.join("model.clmesh");
+        let out_meta = out_dir.join("meta.json");
+
+        let mut stats = ConversionStats::for_input(Path::new(&path));
+        let reading_started = Instant::now();
+        let cached_meta = clmesh::validate(&out_mesh)
+            .ok()
+            .and_then(|_| std::fs::read(paths::long(&out_meta)).ok())
+            .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+            .filter(meta_schema_current);
+        stats.reading_ms = conversion::elapsed_ms(reading_started);
+        let cached = cached_meta.is_some();
+        let meta = match cached_meta {
+            Some(meta) => meta,
+            None => {
+                let process_started = Instant::now();
+                let data = std::fs::read(paths::long(Path::new(&path)))
+                    .map_err(|e| format!("Failed to read OBJ: {e}"))?;
+                let base_name = Path::new(&path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "model".to_string());
+                let imported = obj::import(&String::from_utf8_lossy(&data), &base_name)?;
+                stats.process_ms = conversion::elapsed_ms(process_started);
+
+                let storing_started = Instant::now();
+                std::fs::create_dir_all(paths::long(&out_dir))
+                    .map_err(|e| format!("Failed to create output dir: {e}"))?;
+                clmesh::write(&paths::long(&out_mesh), &imported.meshes)?;
+                let mut bounding_box = None;
+                for mesh in &imported.meshes {
+                    for p in mesh.positions.chunks_exact(3) {
+                        clmesh::BoundingBox::extend(&mut bounding_box, [p[0], p[1], p[2]]);
+                    }
+                }
+                let meta = serde_json::json!({
+                    "schemaVersion": META_SCHEMA_VERSION,
+                    "source": "obj",
+                    "vertexCount": imported.vertex_count,
+                    "triangleCount": imported.triangle_count,
+                    "meshCount": imported.meshes.len(),
+                    "meshNames": imported.meshes.iter().map(|mesh| &mesh.name).collect::<Vec<_>>(),
+                    "boundingBox": bounding_box,
+                    "flatNormals": imported.flat_normals,
+                });
+                let _ = std::fs::write(&out_meta, meta.to_string());
+                stats.storing_ms = conversion::elapsed_ms(storing_started);
+                meta
             }
+        };
+        stats.record_outputs([out_mesh.as_path()]);
+        cache::record_use(&app, &key);
+
+        Ok(ParseYftResult {
+            schema_version: RESULT_SCHEMA_VERSION,
+            mesh_path: out_mesh.to_string_lossy().to_string(),
+            cache_key: key,
+            key_mode: CacheKeyMode::Content.as_str(),
+            cached,
+            meta,
+            ytd_path: None,
+            lod_paths: None,
+            sibling_path: None,
+            sibling_mesh_path: None,
+            skeleton_path: None,
+            collision_path: None,
+            collision_json_path: None,
+            collision: None,
+            stats,
         })
-        .map_err(|e| e.to_string())?;
-
-    let path_buf = PathBuf::from(&path);
-    watcher
-        .watch(&path_buf, RecursiveMode::NonRecursive)
-        .map_err(|e| e.to_string())?;
+    })
+    .await
+    .map_err(|e| format!("Failed to join OBJ import task: {e}"))?
+}
 
-    *path_guard = Some(path_buf);
-    *watcher_guard = Some(watcher);
+/// What `get_model_stats` could find out about a model file.
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ModelStats {
+    /// The file's extension, lowercased.
+    format: String,
+    file_size: Option<u64>,
+    vertex_count: Option<usize>,
+    triangle_count: Option<usize>,
+    material_count: Option<usize>,
+    bounding_box: Option<clmesh::BoundingBox>,
+    /// Levels in `meta.lods`, for models parsed through the bridge.
+    lod_count: Option<usize>,
+    /// Why the counts are missing, when they are.
+    error: Option<String>,
+}
 
+fn fill_clmesh_stats(stats: &mut ModelStats, mesh: &Path) -> Result<(), String> {
+    let data =
+        std::fs::read(paths::long(mesh)).map_err(|e| format!("Failed to read mesh cache: {e}"))?;
+    let inspection = clmesh::inspect_bytes(&data);
+    if let Some(error) = inspection.error {
+        return Err(error);
+    }
+    stats.vertex_count = Some(inspection.vertex_count);
+    stats.triangle_count = Some(inspection.index_count / 3);
+    stats.material_count = Some(inspection.materials.len());
+    stats.bounding_box = inspection.bounding_box;
     Ok(())
 }
 
-#[tauri::command]
-fn stop_window_watch(state: State<WindowWatchState>) -> Result<(), String> {
-    let mut watcher_guard = state
-        .watcher
-        .lock()
-        .map_err(|_| "watcher lock failed".to_string())?;
-    let mut path_guard = state
-        .path
-        .lock()
-        .map_err(|_| "path lock failed".to_string())?;
-
-    if let Some(mut watcher) = watcher_guard.take() {
-        if let Some(prev_path) = path_guard.take() {
-            let _ = watcher.unwatch(&prev_path);
+fn fill_model_stats(
+    stats: &mut ModelStats,
+    path: &str,
+    allow_convert: bool,
+    app: &crate::AppHandle,
+) -> Result<(), String> {
+    let model = match stats.format.as_str() {
+        "clmesh" => return fill_clmesh_stats(stats, Path::new(path)),
+        "dff" => {
+            let data = std::fs::read(paths::long(Path::new(path)))
+                .map_err(|e| format!("Failed to read DFF: {e}"))?;
+            let dff = dff::parse(&data)?;
+            let mut bounding_box = None;
+            let (mut vertices, mut triangles, mut materials) = (0, 0, 0);
+            for geometry in &dff.geometries {
+                for p in geometry.positions.chunks_exact(3) {
+                    clmesh::BoundingBox::extend(&mut bounding_box, [p[0], p[1], p[2]]);
+                }
+                vertices += geometry.positions.len() / 3;
+                triangles += geometry.triangles.len();
+                materials += geometry.materials.len();
+            }
+            stats.vertex_count = Some(vertices);
+            stats.triangle_count = Some(triangles);
+            stats.material_count = Some(materials);
+            stats.bounding_box = bounding_box;
+            return Ok(());
         }
-    }
+        "yft" => BridgeModel::Yft,
+        "ydd" => BridgeModel::Ydd,
+        "ydr" => BridgeModel::Ydr,
+        other => {
+            return Err(format!(
+                "`.{other}` files aren't supported by get_model_stats"
+            ))
+        }
+    };
 
-    Ok(())
+    // The same entry a plain `parse_<format>` call would use.
+    let options = ParseOptions {
+        merge_hi: true,
+        ..Default::default()
+    };
+    let out_dir = cache::yft_cache_root(app)?.join(parse_cache_key(model, path, &options)?.key);
+    let out_mesh = out_dir.join("model.clmesh");
+    let meta = if clmesh::validate(&out_mesh).is_ok() {
+        std::fs::read(paths::long(&out_dir.join("meta.json")))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+    } else if allow_convert {
+        Some(parse_model_blocking(model, path.to_string(), options, app)?.meta)
+    } else {
+        return Err(format!(
+            "Not parsed yet; parse the {} or pass allow_convert.",
+            model.label()
+        ));
+    };
+    stats.lod_count = meta
+        .as_ref()
+        .and_then(|meta| meta.get("lods")?.as_array().map(Vec::len));
+    fill_clmesh_stats(stats, &out_mesh)
 }
 
+/// Size and mesh statistics of whatever model is open, in one shape for
+/// every format: `.clmesh` and `.dff` are read directly, `.yft`/`.ydd`/`.ydr`
+/// from their cached parse. An unparsed model is parsed first only with
+/// `allow_convert`. Files that can't be read still return their format and
+/// size, with the reason in `error`.
 #[tauri::command]
-fn start_multi_watch(
-    paths: Vec<String>,
-    app: tauri::AppHandle,
-    state: State<MultiWatchState>,
-) -> Result<(), String> {
-    let mut watcher_guard = state
-        .watcher
-        .lock()
-        .map_err(|_| "watcher lock failed".to_string())?;
-    let mut paths_guard = state
-        .paths
-        .lock()
-        .map_err(|_| "path lock failed".to_string())?;
-
-    if let Some(mut existing) = watcher_guard.take() {
-        for prev_path in paths_guard.drain(..) {
-            let _ = existing.unwatch(&prev_path);
+async fn get_model_stats(
+    path: String,
+    allow_convert: Option<bool>,
+    app: crate::AppHandle,
+) -> Result<ModelStats, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut stats = ModelStats {
+            format: Path::new(&path)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+            file_size: std::fs::metadata(paths::long(Path::new(&path)))
+                .ok()
+                .map(|meta| meta.len()),
+            ..Default::default()
+        };
+        if let Err(e) = fill_model_stats(&mut stats, &path, allow_convert.unwrap_or(false), &app) {
+            stats.error = Some(e);
         }
-    }
+        stats
+    })
+    .await
+    .map_err(|e| format!("Failed to join model stats task: {e}"))
+}
 
-    let mut unique: HashSet<PathBuf> = HashSet::new();
-    for raw in paths {
-        let trimmed = raw.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        unique.insert(PathBuf::from(trimmed));
+fn parse_model_blocking(
+    model: BridgeModel,
+    path: String,
+    options: ParseOptions,
+    app: &crate::AppHandle,
+) -> Result<ParseYftResult, String> {
+    let started = Instant::now();
+    let task = tasks::start(app, "parse", Some(&path));
+    let result = parse_model_with_progress(model, path.clone(), options, app, &task);
+    let key = task.cache_key();
+    if let (Ok(_), Some(key)) = (&result, &key) {
+        cache::record_use(app, key);
+    }
+    let mut entry = history::HistoryEntry::finished(
+        "parse",
+        &path,
+        key.clone(),
+        started,
+        result.as_ref().err().cloned(),
+    );
+    if let Ok(parsed) = &result {
+        entry.vertex_count = parsed.meta.get("vertexCount").and_then(|v| v.as_u64());
     }
+    history::record(app, entry);
+    conversion::emit_outcome(app, "parse", &path, key, &result);
+    result
+}
 
-    if unique.is_empty() {
-        return Ok(());
+fn parse_model_with_progress(
+    model: BridgeModel,
+    path: String,
+    options: ParseOptions,
+    app: &crate::AppHandle,
+    task: &tasks::TaskHandle,
+) -> Result<ParseYftResult, String> {
+    let extension = model.extension();
+    if !has_extension(&path, extension) {
+        return Err(format!(
+            "Only .{extension} files are supported by parse_{extension}"
+        ));
     }
 
-    let app_handle = app.clone();
-    let mut watcher =
-        notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
-            if let Ok(event) = res {
-                let payload = WatchPayload {
-                    path: event
-                        .paths
-                        .get(0)
-                        .map(|p| p.to_string_lossy().to_string())
-                        .unwrap_or_default(),
-                    kind: format!("{:?}", event.kind),
-                };
-                let _ = app_handle.emit("texture:update", payload);
-            }
-        })
-        .map_err(|e| e.to_string())?;
-
-    for path_buf in unique.iter() {
-        watcher
-            .watch(path_buf, RecursiveMode::NonRecursive)
-            .map_err(|e| e.to_string())?;
+    if !cfg!(target_os = "windows") {
+        return Err(format!(
+            "{} parsing is only supported on Windows in this build.",
+            model.label()
+        ));
     }
 
-    *paths_guard = unique.into_iter().collect();
-    *watcher_guard = Some(watcher);
-
-    Ok(())
+    // Encrypted or foreign files only get a stack trace out of the bridge.
+    let resource = rsc7::sniff(Path::new(&path))?;
+    let bridge = find_codewalker_bridge(app)?;
+    let cache_root = cache::yft_cache_root(app)?;
+    parse_with_bridge(
+        model,
+        path,
+        options,
+        app,
+        task,
+        resource,
+        &bridge,
+        &cache_root,
+    )
 }
 
-#[tauri::command]
-fn stop_multi_watch(state: State<MultiWatchState>) -> Result<(), String> {
-    let mut watcher_guard = state
-        .watcher
-        .lock()
-        .map_err(|_| "watcher lock failed".to_string())?;
-    let mut paths_guard = state
-        .paths
-        .lock()
-        .map_err(|_| "path lock failed".to_string())?;
+/// The platform-independent part of a parse: serve `path` from the entry
+/// under `cache_root`, or run `bridge` to fill it.
+#[allow(clippy::too_many_arguments)]
+fn parse_with_bridge(
+    model: BridgeModel,
+    path: String,
+    options: ParseOptions,
+    app: &crate::AppHandle,
+    task: &tasks::TaskHandle,
+    resource: rsc7::ResourceHeader,
+    bridge: &Path,
+    cache_root: &Path,
+) -> Result<ParseYftResult, String> {
+    let extension = model.extension();
+    std::fs::create_dir_all(paths::long(cache_root))
+        .map_err(|e| format!("Failed to create cache dir: {e}"))?;
+
+    let ParseKey {
+        key,
+        ytd_override,
+        ytd_override_missing,
+        merge_hi,
+        sibling,
+    } = parse_cache_key(model, &path, &options)?;
+
+    task.attach_cache_key(&key);
+    let tasks = app.state::<ConversionTasks>();
+    let ctx = ConversionContext {
+        app,
+        tasks: &tasks,
+        key: &key,
+        events: "parse",
+        timeout: conversion::process_timeout(options.timeout_secs),
+        verbose: options.verbose,
+    };
+    ctx.started(&path);
+    let _in_flight = ctx.claim();
+
+    let out_dir = cache_root.join(&key);
+    std::fs::create_dir_all(paths::long(&out_dir))
+        .map_err(|e| format!("Failed to create output dir: {e}"))?;
+    let out_mesh = out_dir.join("model.clmesh");
+    let out_meta = out_dir.join("meta.json");
+    let sibling_mesh = out_dir.join("sibling.clmesh");
+    let sibling_meta = out_dir.join("sibling_meta.json");
+    let out_skeleton = out_dir.join("model.skel.json");
+    let out_collision = out_dir.join("collision.clmesh");
+    let out_collision_json = out_dir.join("collision.json");
+    let out_damaged = out_dir.join("model_damaged.clmesh");
+
+    let found_ytd = ytd_override.or_else(|| find_sibling_ytd(&path));
+    let pair = HiPair {
+        requested: merge_hi,
+        sibling: sibling.as_ref(),
+        out_mesh: &out_mesh,
+        sibling_mesh: &sibling_mesh,
+    };
 
-    if let Some(mut watcher) = watcher_guard.take() {
-        for prev_path in paths_guard.drain(..) {
-            let _ = watcher.unwatch(&prev_path);
+    let collision_outputs = || {
+        if options.include_collision {
+            (
+                existing_path(&out_collision),
+                existing_path(&out_collision_json),
+                collision_counts(&out_collision_json),
+            )
+        } else {
+            (None, None, None)
         }
-    }
+    };
+    let attach_textures = |meta: &mut serde_json::Value| -> Result<(), String> {
+        let Some(ytd) = found_ytd.as_deref().filter(|_| options.extract_textures) else {
+            return Ok(());
+        };
+        ctx.stage("textures", "Extracting textures");
+        let format = textures::TextureFormat::Png;
+        let extracted = textures::texture_cache_dir(app, ytd, format)
+            .and_then(|(_, dir)| textures::extract(&ctx, bridge, ytd, &dir, format, true));
+        let (field, value) = match extracted {
+            Ok(list) => ("textureFiles", list),
+            Err(e) if e == conversion::CANCELLED => return Err(e),
+            Err(e) => ("texturesError", serde_json::Value::String(e)),
+        };
+        if let Some(meta) = meta.as_object_mut() {
+            meta.insert(field.to_string(), value);
+        }
+        Ok(())
+    };
 
-    Ok(())
-}
+    let mut stats = ConversionStats::for_input(Path::new(&path));
+    let mut outputs = vec![
+        out_mesh.clone(),
+        sibling_mesh.clone(),
+        out_skeleton.clone(),
+        out_collision.clone(),
+        out_collision_json.clone(),
+        out_damaged.clone(),
+    ];
+    outputs.extend(
+        LOD_LEVELS
+            .iter()
+            .map(|level| out_dir.join(lod_file_name(level))),
+    );
 
-#[tauri::command]
-fn start_model_watch(
-    path: String,
-    app: tauri::AppHandle,
-    state: State<ModelWatchState>,
-) -> Result<(), String> {
-    let mut watcher_guard = state
-        .watcher
-        .lock()
-        .map_err(|_| "watcher lock failed".to_string())?;
-    let mut path_guard = state
-        .path
-        .lock()
-        .map_err(|_| "path lock failed".to_string())?;
+    // A truncated mesh from an interrupted run would break every later open,
+    // so a cache hit is only served once the files check out.
+    let mut cache_invalidated = false;
+    let reading_started = Instant::now();
+    if out_mesh.exists() && (sibling.is_none() || sibling_mesh.exists()) {
+        let intact = clmesh::validate(&out_mesh).is_ok()
+            && (sibling.is_none() || clmesh::validate(&sibling_mesh).is_ok())
+            && (!out_collision.exists() || clmesh::validate(&out_collision).is_ok())
+            && (!out_damaged.exists() || clmesh::validate(&out_damaged).is_ok());
+        if !intact {
+            for stale in [
+                &out_mesh,
+                &out_meta,
+                &sibling_mesh,
+                &sibling_meta,
+                &out_skeleton,
+                &out_collision,
+                &out_collision_json,
+                &out_damaged,
+            ] {
+                let _ = std::fs::remove_file(stale);
+            }
+            for level in LOD_LEVELS {
+                let _ = std::fs::remove_file(out_dir.join(lod_file_name(level)));
+            }
+            cache_invalidated = true;
+        }
+    }
 
-    if let Some(mut existing) = watcher_guard.take() {
-        if let Some(prev_path) = path_guard.take() {
-            let _ = existing.unwatch(&prev_path);
+    if out_mesh.exists() && (sibling.is_none() || sibling_mesh.exists()) {
+        let mut meta_json = if out_meta.exists() {
+            std::fs::read_to_string(&out_meta)
+                .ok()
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                .unwrap_or_else(|| serde_json::json!({}))
+        } else {
+            serde_json::json!({})
+        };
+        // An entry parsed without LODs, collision or damaged meshes can't
+        // serve a request for them, and one written by an older bridge lacks
+        // fields callers now expect.
+        let lod_paths = cached_lod_paths(&out_dir, &meta_json);
+        let has_collision = out_collision.exists() && out_collision_json.exists();
+        let has_damaged = meta_json
+            .get("includeDamaged")
+            .and_then(|flag| flag.as_bool())
+            .unwrap_or(false);
+        if (!options.include_lods || lod_paths.is_some())
+            && (!options.include_collision || has_collision)
+            && (!options.include_damaged || has_damaged)
+            && meta_schema_current(&meta_json)
+        {
+            stats.reading_ms = conversion::elapsed_ms(reading_started);
+            stats.record_outputs(outputs.iter().map(PathBuf::as_path));
+            note_missing_ytd(&mut meta_json, ytd_override_missing.as_deref());
+            pair.annotate(&mut meta_json);
+            note_damaged_mesh(&mut meta_json, options.include_damaged, &out_damaged);
+            note_resource(&mut meta_json, &resource);
+            attach_textures(&mut meta_json)?;
+            let (collision_path, collision_json_path, collision) = collision_outputs();
+            return Ok(ParseYftResult {
+                schema_version: RESULT_SCHEMA_VERSION,
+                mesh_path: out_mesh.to_string_lossy().to_string(),
+                cache_key: key.clone(),
+                key_mode: options.key_mode.as_str(),
+                cached: true,
+                meta: meta_json,
+                ytd_path: found_ytd.clone(),
+                lod_paths: lod_paths.filter(|_| options.include_lods),
+                sibling_path: pair.sibling_path().map(str::to_string),
+                sibling_mesh_path: pair.sibling_mesh_path(),
+                skeleton_path: existing_path(&out_skeleton),
+                collision_path,
+                collision_json_path,
+                collision,
+                stats,
+            });
         }
     }
 
-    let app_handle = app.clone();
-    let mut watcher =
-        notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
-            if let Ok(event) = res {
-                let payload = WatchPayload {
-                    path: event
-                        .paths
-                        .get(0)
-                        .map(|p| p.to_string_lossy().to_string())
-                        .unwrap_or_default(),
-                    kind: format!("{:?}", event.kind),
-                };
-                let _ = app_handle.emit("model:update", payload);
-            }
-        })
-        .map_err(|e| e.to_string())?;
+    stats.reading_ms = conversion::elapsed_ms(reading_started);
+    ctx.stage("parsing", "Running CodeWalker bridge");
+    let mut command = Command::new(bridge);
+    command
+        .arg("--input")
+        .arg(&path)
+        .arg("--output")
+        .arg(&out_mesh)
+        .arg("--type")
+        .arg(extension)
+        .arg("--skeleton");
+    if let Some(ytd) = &found_ytd {
+        command.arg("--ytd").arg(ytd);
+    }
+    if let Some(name) = &options.drawable_name {
+        command.arg("--drawable").arg(name);
+    }
+    if options.include_lods {
+        command.arg("--lods");
+    }
+    if options.include_collision {
+        command.arg("--collision");
+    }
+    if options.include_damaged {
+        command.arg("--damaged");
+    }
+    let mut cleanup = vec![
+        out_mesh.clone(),
+        out_meta.clone(),
+        out_skeleton.clone(),
+        out_collision.clone(),
+        out_collision_json.clone(),
+        out_damaged.clone(),
+    ];
+    cleanup.extend(
+        LOD_LEVELS
+            .iter()
+            .map(|level| out_dir.join(lod_file_name(level))),
+    );
+    let process_started = Instant::now();
+    task.check()?;
+    let mut meta_json = run_codewalker_bridge(&ctx, command, &cleanup)?;
 
-    let path_buf = PathBuf::from(&path);
-    watcher
-        .watch(&path_buf, RecursiveMode::NonRecursive)
-        .map_err(|e| e.to_string())?;
+    if !out_mesh.exists() {
+        return Err("CodeWalker bridge did not produce mesh cache output.".to_string());
+    }
 
-    *path_guard = Some(path_buf);
-    *watcher_guard = Some(watcher);
+    if let Some((sibling_path, _)) = &sibling {
+        task.check()?;
+        ctx.stage("parsing", "Running CodeWalker bridge on the paired model");
+        let mut command = Command::new(bridge);
+        command
+            .arg("--input")
+            .arg(sibling_path)
+            .arg("--output")
+            .arg(&sibling_mesh)
+            .arg("--type")
+            .arg(extension);
+        if let Some(ytd) = &found_ytd {
+            command.arg("--ytd").arg(ytd);
+        }
+        let sibling_meta_json = run_codewalker_bridge(
+            &ctx,
+            command,
+            &[out_mesh.clone(), sibling_mesh.clone(), sibling_meta.clone()],
+        )?;
+        if !sibling_mesh.exists() {
+            return Err("CodeWalker bridge did not produce mesh cache output.".to_string());
+        }
+        let _ = std::fs::write(&sibling_meta, sibling_meta_json.to_string());
+    }
+    stats.process_ms = conversion::elapsed_ms(process_started);
 
-    Ok(())
+    ctx.stage("storing", "Writing mesh metadata");
+    let storing_started = Instant::now();
+    if let Some(meta) = meta_json.as_object_mut() {
+        meta.insert(
+            "schemaVersion".to_string(),
+            serde_json::Value::from(META_SCHEMA_VERSION),
+        );
+        // Lets a later request for damaged meshes tell a run that found none
+        // from one that never looked.
+        if options.include_damaged {
+            meta.insert("includeDamaged".to_string(), serde_json::Value::Bool(true));
+        }
+    }
+    let _ = std::fs::write(&out_meta, meta_json.to_string());
+    stats.storing_ms = conversion::elapsed_ms(storing_started);
+    stats.record_outputs(outputs.iter().map(PathBuf::as_path));
+    note_missing_ytd(&mut meta_json, ytd_override_missing.as_deref());
+    pair.annotate(&mut meta_json);
+    note_damaged_mesh(&mut meta_json, options.include_damaged, &out_damaged);
+    note_resource(&mut meta_json, &resource);
+    attach_textures(&mut meta_json)?;
+    if cache_invalidated {
+        if let Some(meta) = meta_json.as_object_mut() {
+            meta.insert(
+                "cacheInvalidated".to_string(),
+                serde_json::Value::Bool(true),
+            );
+        }
+    }
+    let lod_paths = if options.include_lods {
+        cached_lod_paths(&out_dir, &meta_json)
+    } else {
+        None
+    };
+    let (collision_path, collision_json_path, collision) = collision_outputs();
+
+    Ok(ParseYftResult {
+        schema_version: RESULT_SCHEMA_VERSION,
+        mesh_path: out_mesh.to_string_lossy().to_string(),
+        cache_key: key.clone(),
+        key_mode: options.key_mode.as_str(),
+        cached: false,
+        meta: meta_json,
+        ytd_path: found_ytd.clone(),
+        lod_paths,
+        sibling_path: pair.sibling_path().map(str::to_string),
+        sibling_mesh_path: pair.sibling_mesh_path(),
+        skeleton_path: existing_path(&out_skeleton),
+        collision_path,
+        collision_json_path,
+        collision,
+        stats,
+    })
 }
 
-#[tauri::command]
-fn stop_model_watch(state: State<ModelWatchState>) -> Result<(), String> {
-    let mut watcher_guard = state
-        .watcher
-        .lock()
-        .map_err(|_| "watcher lock failed".to_string())?;
-    let mut path_guard = state
-        .path
-        .lock()
-        .map_err(|_| "path lock failed".to_string())?;
-
-    if let Some(mut watcher) = watcher_guard.take() {
-        if let Some(prev_path) = path_guard.take() {
-            let _ = watcher.unwatch(&prev_path);
-        }
+/// Add the RSC7 header read before parsing as `meta.resource`.
+fn note_resource(meta: &mut serde_json::Value, resource: &rsc7::ResourceHeader) {
+    if let Some(meta) = meta.as_object_mut() {
+        meta.insert("resource".to_string(), serde_json::json!(resource));
     }
+}
 
-    Ok(())
+/// The cache key of a parse and what went into it.
+struct ParseKey {
+    key: String,
+    /// `options.ytd_path` when it exists.
+    ytd_override: Option<String>,
+    /// `options.ytd_path` when it doesn't.
+    ytd_override_missing: Option<String>,
+    merge_hi: bool,
+    /// The other half of a `_hi` pair and whether the input is the `_hi` one.
+    sibling: Option<(String, bool)>,
 }
 
-#[tauri::command]
-fn parse_yft(path: String, app: tauri::AppHandle) -> Result<serde_json::Value, String> {
-    if !is_yft(&path) {
-        return Err("Only .yft files are supported by parse_yft".to_string());
-    }
+fn parse_cache_key(
+    model: BridgeModel,
+    path: &str,
+    options: &ParseOptions,
+) -> Result<ParseKey, String> {
+    // An explicit dictionary wins over auto-discovery, and is part of the
+    // key so switching dictionaries re-parses.
+    let (ytd_override, ytd_override_missing) = match options.ytd_path.as_deref() {
+        Some(ytd) if !ytd.trim().is_empty() => {
+            if Path::new(ytd).is_file() {
+                (Some(ytd.to_string()), None)
+            } else {
+                (None, Some(ytd.to_string()))
+            }
+        }
+        _ => (None, None),
+    };
 
-    if !cfg!(target_os = "windows") {
-        return Err("YFT parsing is only supported on Windows in this build.".to_string());
+    let mut key = cache::yft_cache_key(path, options.key_mode)?;
+    if let Some(ytd) = &ytd_override {
+        key = cache::variant_key(&key, ytd);
+    }
+    if let Some(name) = &options.drawable_name {
+        key = cache::variant_key(&key, &format!("drawable:{name}"));
     }
 
-    let exe_name = if cfg!(target_os = "windows") {
-        "CodeWalkerBridge.exe"
+    // The pair shares one entry keyed on both files, so editing either half
+    // re-parses.
+    let merge_hi = options.merge_hi && model == BridgeModel::Yft;
+    let sibling = if merge_hi {
+        find_hi_sibling(path)
     } else {
-        "CodeWalkerBridge"
+        None
     };
+    if let Some((sibling_path, _)) = &sibling {
+        let sibling_key = cache::yft_cache_key(sibling_path, options.key_mode)?;
+        key = cache::variant_key(&key, &format!("pair:{sibling_key}"));
+    }
 
-    let mut candidates: Vec<PathBuf> = Vec::new();
-    candidates.push(
-        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("bin")
-            .join("codewalker-bridge")
-            .join(exe_name),
-    );
-    candidates.push(
-        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("..")
-            .join("tools")
-            .join("codewalker-bridge")
-            .join("bin")
-            .join("Release")
-            .join("net10.0")
-            .join(exe_name),
-    );
-    candidates.push(
-        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    Ok(ParseKey {
+        key,
+        ytd_override,
+        ytd_override_missing,
+        merge_hi,
+        sibling,
+    })
+}
+
+fn note_damaged_mesh(meta: &mut serde_json::Value, requested: bool, damaged_mesh: &Path) {
+    if !requested {
+        return;
+    }
+    if let Some(meta) = meta.as_object_mut() {
+        meta.insert(
+            "damagedMeshPath".to_string(),
+            serde_json::json!(existing_path(damaged_mesh)),
+        );
+    }
+}
+
+/// Per-type primitive counts from a cached `collision.json`.
+fn collision_counts(path: &Path) -> Option<serde_json::Value> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let json = serde_json::from_str::<serde_json::Value>(&contents).ok()?;
+    json.get("counts").cloned()
+}
+
+/// Version of the `meta.json` written next to a parsed mesh. Bump it when the
+/// bridge starts emitting something callers rely on, so older entries are
+/// re-parsed instead of served without it. 2 added `skeleton`.
+const META_SCHEMA_VERSION: u64 = 2;
+
+fn meta_schema_current(meta: &serde_json::Value) -> bool {
+    meta.get("schemaVersion")
+        .and_then(|version| version.as_u64())
+        .is_some_and(|version| version >= META_SCHEMA_VERSION)
+}
+
+fn existing_path(path: &Path) -> Option<String> {
+    path.exists().then(|| path.to_string_lossy().to_string())
+}
+
+/// Run the bridge and return the meta JSON it prints on stdout. Goes
+/// through a free persistent `--server` bridge when one can be used, and
+/// runs the command one-shot otherwise (including whenever output is
+/// streamed or every server is busy).
+fn run_codewalker_bridge(
+    ctx: &ConversionContext,
+    command: Command,
+    cleanup: &[PathBuf],
+) -> Result<serde_json::Value, String> {
+    let map_error = |e| match e {
+        RunError::Cancelled => conversion::CANCELLED.to_string(),
+        RunError::TimedOut(timeout) => {
+            for path in cleanup {
+                conversion::remove_partial_output(path);
+            }
+            format!(
+                "CodeWalker bridge timed out after {}s and was stopped. Very large models may need a longer timeout.",
+                timeout.as_secs()
+            )
+        }
+        RunError::Failed(e) => format!("Failed to run CodeWalker bridge: {e}"),
+    };
+
+    let served = if ctx.verbose {
+        Err(ServerError::Unavailable)
+    } else {
+        let args = command.get_args().collect::<Vec<_>>();
+        ctx.app.state::<BridgeProcess>().request(
+            Path::new(command.get_program()),
+            &args,
+            ctx.key,
+            ctx.tasks,
+            ctx.timeout,
+            cleanup,
+        )
+    };
+    let output = match served {
+        Ok(output) => output,
+        Err(ServerError::Run(e)) => return Err(map_error(e)),
+        Err(ServerError::Unavailable) => {
+            let output = ctx.run(command, cleanup).map_err(map_error)?;
+            BridgeOutput {
+                success: output.status.success(),
+                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            }
+        }
+    };
+
+    if !output.success {
+        return Err(format!(
+            "CodeWalker bridge failed.\nSTDERR:\n{}\nSTDOUT:\n{}",
+            output.stderr.trim(),
+            output.stdout.trim()
+        ));
+    }
+
+    Ok(
+        serde_json::from_str::<serde_json::Value>(output.stdout.trim())
+            .unwrap_or_else(|_| serde_json::json!({})),
+    )
+}
+
+/// The other half of a `_hi` pair, `adder_hi.yft` for `adder.yft` and vice
+/// versa, if it exists. The flag is whether the input is the `_hi` half.
+fn find_hi_sibling(path: &str) -> Option<(String, bool)> {
+    let path_buf = PathBuf::from(path);
+    let file_stem = path_buf.file_stem()?.to_str()?;
+    let extension = path_buf.extension()?.to_str()?;
+    let (sibling_stem, input_is_hi) = match file_stem.strip_suffix("_hi") {
+        Some(base) => (base.to_string(), true),
+        None => (format!("{file_stem}_hi"), false),
+    };
+    let sibling = path_buf.with_file_name(format!("{sibling_stem}.{extension}"));
+    sibling
+        .is_file()
+        .then(|| (sibling.to_string_lossy().to_string(), input_is_hi))
+}
+
+/// How a `_hi` pair ended up in a parse result.
+struct HiPair<'a> {
+    requested: bool,
+    sibling: Option<&'a (String, bool)>,
+    out_mesh: &'a Path,
+    sibling_mesh: &'a Path,
+}
+
+impl HiPair<'_> {
+    fn sibling_path(&self) -> Option<&str> {
+        self.sibling.map(|(path, _)| path.as_str())
+    }
+
+    fn sibling_mesh_path(&self) -> Option<String> {
+        self.sibling
+            .map(|_| self.sibling_mesh.to_string_lossy().to_string())
+    }
+
+    /// Add `hiMeshPath`/`baseMeshPath`, or `siblingMissing` when a merge was
+    /// asked for but there was nothing to merge with.
+    fn annotate(&self, meta: &mut serde_json::Value) {
+        let Some(meta) = meta.as_object_mut() else {
+            return;
+        };
+        match self.sibling {
+            Some((_, input_is_hi)) => {
+                let input = self.out_mesh.to_string_lossy().to_string();
+                let sibling = self.sibling_mesh.to_string_lossy().to_string();
+                let (hi, base) = if *input_is_hi {
+                    (input, sibling)
+                } else {
+                    (sibling, input)
+                };
+                meta.insert("hiMeshPath".to_string(), serde_json::Value::String(hi));
+                meta.insert("baseMeshPath".to_string(), serde_json::Value::String(base));
+            }
+            None if self.requested => {
+                meta.insert("siblingMissing".to_string(), serde_json::Value::Bool(true));
+            }
+            None => {}
+        }
+    }
+}
+
+/// LOD levels the bridge knows, highest detail first.
+const LOD_LEVELS: [&str; 4] = ["high", "med", "low", "vlow"];
+
+fn lod_file_name(level: &str) -> String {
+    format!("model_{level}.clmesh")
+}
+
+/// Per-LOD mesh paths for the levels listed in `meta.lods`, or `None` if the
+/// entry has no LOD list or any of the files is missing.
+fn cached_lod_paths(
+    out_dir: &Path,
+    meta: &serde_json::Value,
+) -> Option<serde_json::Map<String, serde_json::Value>> {
+    let mut paths = serde_json::Map::new();
+    for lod in meta.get("lods")?.as_array()? {
+        let level = lod.get("level")?.as_str()?;
+        let path = out_dir.join(lod_file_name(level));
+        if !path.is_file() {
+            return None;
+        }
+        paths.insert(
+            level.to_string(),
+            serde_json::Value::String(path.to_string_lossy().to_string()),
+        );
+    }
+    Some(paths)
+}
+
+/// Everywhere the CodeWalker bridge is looked for, in order of preference.
+fn codewalker_bridge_candidates(app: &crate::AppHandle) -> Vec<PathBuf> {
+    let exe_name = if cfg!(target_os = "windows") {
+        "CodeWalkerBridge.exe"
+    } else {
+        "CodeWalkerBridge"
+    };
+
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let local_build = |configuration: &str, framework: &str| {
+        manifest_dir
             .join("..")
             .join("tools")
             .join("codewalker-bridge")
             .join("bin")
-            .join("Debug")
-            .join("net10.0")
-            .join(exe_name),
-    );
-    candidates.push(
-        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("..")
-            .join("tools")
-            .join("codewalker-bridge")
+            .join(configuration)
+            .join(framework)
+            .join(exe_name)
+    };
+    let mut candidates = vec![
+        manifest_dir
             .join("bin")
-            .join("Release")
-            .join("net8.0")
-            .join(exe_name),
-    );
-    candidates.push(
-        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("..")
-            .join("tools")
             .join("codewalker-bridge")
-            .join("bin")
-            .join("Debug")
-            .join("net8.0")
             .join(exe_name),
-    );
+        local_build("Release", "net10.0"),
+        local_build("Debug", "net10.0"),
+        local_build("Release", "net8.0"),
+        local_build("Debug", "net8.0"),
+    ];
     if let Ok(resource_dir) = app.path().resource_dir() {
         candidates.push(
             resource_dir
@@ -711,42 +1656,20 @@ fn parse_yft(path: String, app: tauri::AppHandle) -> Result<serde_json::Value, S
         );
     }
 
-    let bridge = candidates.into_iter().find(|p| p.exists()).ok_or_else(|| {
+    candidates
+}
+
+fn find_codewalker_bridge(app: &crate::AppHandle) -> Result<PathBuf, String> {
+    codewalker_bridge_candidates(app).into_iter().find(|p| p.exists()).ok_or_else(|| {
         "Missing CodeWalker bridge executable. Build it with `dotnet publish -c Release` in `tools/codewalker-bridge`."
             .to_string()
-    })?;
-
-    let cache_root = app
-        .path()
-        .app_cache_dir()
-        .map_err(|e| format!("Failed to resolve cache dir: {e}"))?
-        .join("cortex-labs")
-        .join("yft-cache");
-    std::fs::create_dir_all(&cache_root).map_err(|e| format!("Failed to create cache dir: {e}"))?;
-
-    let meta = std::fs::metadata(&path).map_err(|e| format!("Failed to stat input: {e}"))?;
-    let mtime = meta
-        .modified()
-        .ok()
-        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-        .map(|d| d.as_millis() as u64)
-        .unwrap_or(0);
-    let size = meta.len();
-
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    use std::hash::Hasher;
-    hasher.write(path.as_bytes());
-    hasher.write_u64(mtime);
-    hasher.write_u64(size);
-    let key = format!("{:016x}", hasher.finish());
-
-    let out_dir = cache_root.join(&key);
-    std::fs::create_dir_all(&out_dir).map_err(|e| format!("Failed to create output dir: {e}"))?;
-    let out_mesh = out_dir.join("model.clmesh");
-    let out_meta = out_dir.join("meta.json");
+    })
+}
 
-    // Check for sibling YTD file (auto-discovery)
-    let path_buf = PathBuf::from(&path);
+/// Sibling texture dictionary for a model: `model.ytd` next to `model.yft`,
+/// falling back to the name without a `_hi`/`+hi` suffix.
+fn find_sibling_ytd(path: &str) -> Option<String> {
+    let path_buf = PathBuf::from(path);
     let file_stem = path_buf.file_stem().and_then(|s| s.to_str()).unwrap_or("");
     let parent = path_buf
         .parent()
@@ -765,68 +1688,126 @@ fn parse_yft(path: String, app: tauri::AppHandle) -> Result<serde_json::Value, S
         }
     }
 
-    let found_ytd = if ytd_path_buf.exists() {
+    if ytd_path_buf.exists() {
         Some(ytd_path_buf.to_string_lossy().to_string())
     } else {
         None
-    };
-
-    if out_mesh.exists() {
-        let meta_json = if out_meta.exists() {
-            std::fs::read_to_string(&out_meta)
-                .ok()
-                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-                .unwrap_or_else(|| serde_json::json!({}))
-        } else {
-            serde_json::json!({})
-        };
-        return Ok(serde_json::json!({
-            "meshPath": out_mesh.to_string_lossy().to_string(),
-            "cacheKey": key,
-            "cached": true,
-            "meta": meta_json,
-            "ytdPath": found_ytd
-        }));
-    }
-
-    let output = Command::new(&bridge)
-        .arg("--input")
-        .arg(&path)
-        .arg("--output")
-        .arg(&out_mesh)
-        .output()
-        .map_err(|e| format!("Failed to run CodeWalker bridge: {e}"))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        return Err(format!(
-            "CodeWalker bridge failed.\nSTDERR:\n{}\nSTDOUT:\n{}",
-            stderr.trim(),
-            stdout.trim()
-        ));
     }
+}
 
-    if !out_mesh.exists() {
-        return Err("CodeWalker bridge did not produce mesh cache output.".to_string());
+/// Record in the returned meta that the requested `ytd_path` wasn't found
+/// and auto-discovery was used instead.
+fn note_missing_ytd(meta: &mut serde_json::Value, missing: Option<&str>) {
+    if let (Some(missing), Some(meta)) = (missing, meta.as_object_mut()) {
+        meta.insert(
+            "ytdOverrideMissing".to_string(),
+            serde_json::Value::String(missing.to_string()),
+        );
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let meta_json = serde_json::from_str::<serde_json::Value>(stdout.trim())
-        .unwrap_or_else(|_| serde_json::json!({}));
-    let _ = std::fs::write(&out_meta, meta_json.to_string());
+/// Convert a .yft to a cached .dff/.txd pair, reporting
+/// `conversion:started`/`conversion:stage`/`conversion:finished`/
+/// `conversion:failed` along the way. Runs on the blocking pool, so several
+/// conversions can be in flight at once. `key_mode` works as in `parse_yft`.
+///
+/// With `export_dir` the results are also copied there as `<stem>.dff` and
+/// `<stem>.txd` (listed in `exported`); existing files are only replaced
+/// when `overwrite` is set.
+///
+/// `settings_overrides` rewrites entries of the converter's
+/// `GeneralSettings.ini` for this run (`key` or `Section.key`; unknown keys
+/// are appended). The applied set is echoed back as `settingsOverrides`.
+///
+/// `profile` picks one of `list_converter_profiles`; without it the default
+/// profile runs first and the vehicle one is tried if the DFF comes out
+/// empty. `profileUsed` names the profile the result came from.
+///
+/// Without a profile the YFT is first classified as `vehicle` or `generic`
+/// from its contents so the matching profile runs first; `classification`
+/// and `retried` (whether the fallback was needed) report how that went.
+/// Both are null for explicit profiles and cache hits.
+///
+/// The input's RSC7 header is checked first, as in `parse_yft`, and
+/// returned as `resource`.
+///
+/// The converter runs in a short temp folder, since it can't handle paths
+/// past MAX_PATH; `workDir` says which one.
+///
+/// `generate_collision: false` skips building the collision for a faster
+/// preview conversion, cached separately from the full one;
+/// `collisionGenerated` says whether a `.col` actually came out.
+///
+/// The converter is killed after `timeout_secs` (120 by default). With
+/// `verbose` its output is streamed line by line as `conversion:log`.
+/// `stats` has the time spent per step and the input and output sizes.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn convert_yft(
+    path: String,
+    key_mode: Option<String>,
+    export_dir: Option<String>,
+    overwrite: Option<bool>,
+    settings_overrides: Option<HashMap<String, String>>,
+    generate_collision: Option<bool>,
+    profile: Option<String>,
+    timeout_secs: Option<u64>,
+    verbose: Option<bool>,
+    app: crate::AppHandle,
+) -> Result<ConvertYftResult, String> {
+    let options = ConvertYftOptions {
+        key_mode: CacheKeyMode::parse(key_mode.as_deref())?,
+        export_dir: export_dir.filter(|dir| !dir.trim().is_empty()),
+        overwrite: overwrite.unwrap_or(false),
+        settings_overrides: settings_overrides
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(key, _)| !key.trim().is_empty())
+            .collect(),
+        skip_collision: !generate_collision.unwrap_or(true),
+        profile: profile.filter(|name| !name.trim().is_empty()),
+        timeout_secs,
+        verbose: verbose.unwrap_or(false),
+    };
+    tauri::async_runtime::spawn_blocking(move || convert_yft_blocking(path, options, &app))
+        .await
+        .map_err(|e| format!("Failed to join YFT conversion task: {e}"))?
+}
 
-    Ok(serde_json::json!({
-        "meshPath": out_mesh.to_string_lossy().to_string(),
-        "cacheKey": key,
-        "cached": false,
-        "meta": meta_json,
-        "ytdPath": found_ytd
-    }))
+fn convert_yft_blocking(
+    path: String,
+    options: ConvertYftOptions,
+    app: &crate::AppHandle,
+) -> Result<ConvertYftResult, String> {
+    let started = Instant::now();
+    let task = tasks::start(app, "conversion", Some(&path));
+    let result = convert_yft_with_progress(path.clone(), options, app, &task);
+    let key = task.cache_key();
+    if let (Ok(_), Some(key)) = (&result, &key) {
+        cache::record_use(app, key);
+    }
+    let mut entry = history::HistoryEntry::finished(
+        "conversion",
+        &path,
+        key.clone(),
+        started,
+        result.as_ref().err().cloned(),
+    );
+    if let Ok(converted) = &result {
+        entry.vertex_count = Some(u64::from(converted.vertex_count));
+        entry.profile_used = converted.profile_used.clone();
+    }
+    history::record(app, entry);
+    conversion::emit_outcome(app, "conversion", &path, key, &result);
+    result
 }
 
-#[tauri::command]
-fn convert_yft(path: String, app: tauri::AppHandle) -> Result<serde_json::Value, String> {
+fn convert_yft_with_progress(
+    path: String,
+    options: ConvertYftOptions,
+    app: &crate::AppHandle,
+    task: &tasks::TaskHandle,
+) -> Result<ConvertYftResult, String> {
     if !is_yft(&path) {
         return Err("Only .yft files are supported by convert_yft".to_string());
     }
@@ -835,32 +1816,20 @@ fn convert_yft(path: String, app: tauri::AppHandle) -> Result<serde_json::Value,
         return Err("YFT conversion is only supported on Windows in this build.".to_string());
     }
 
-    let converter_folder = "yft-converter";
-    let converter_exe = "ytdydryddyft2txddffcol.exe";
-    let converter_dll = "zlib1.dll";
-    let mut candidates: Vec<PathBuf> = Vec::new();
-    candidates.push(
-        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("bin")
-            .join(converter_folder),
-    );
-    if let Ok(resource_dir) = app.path().resource_dir() {
-        candidates.push(resource_dir.join("bin").join(converter_folder));
-    }
+    let resource = rsc7::sniff(Path::new(&path))?;
 
-    let converter_dir = candidates.into_iter().find(|p| p.exists()).ok_or_else(|| {
-        format!(
-            "Missing YFT converter folder.\n\
-Place it at `src-tauri/bin/{}` for dev builds, or bundle it as a resource.\n\
-See `THIRD_PARTY_NOTICES.md` for credits.",
-            converter_folder
-        )
-    })?;
+    let converter_dll = CONVERTER_DLL;
+    let converter_dir = find_converter_dir(app)?;
 
-    let converter = converter_dir.join(converter_exe);
-    let settings = converter_dir.join("GeneralSettings.ini");
-    let vehicle_settings = converter_dir.join("GeneralSettings.vehicle.ini");
-    let col_materials = converter_dir.join("col_gen_materials.dat");
+    let converter = converter_dir.join(CONVERTER_EXE);
+    let settings = converter_dir.join(profile_file_name(DEFAULT_PROFILE));
+    let vehicle_settings = converter_dir.join(profile_file_name(VEHICLE_PROFILE));
+    // An explicit profile replaces the default/vehicle fallback.
+    let explicit_profile = match options.profile.as_deref() {
+        Some(requested) => Some(resolve_converter_profile(&converter_dir, requested)?),
+        None => None,
+    };
+    let col_materials = converter_dir.join(CONVERTER_MATERIALS);
     let zlib = converter_dir.join(converter_dll);
 
     if !converter.exists() {
@@ -869,7 +1838,7 @@ See `THIRD_PARTY_NOTICES.md` for credits.",
             converter.to_string_lossy()
         ));
     }
-    if !settings.exists() {
+    if explicit_profile.is_none() && !settings.exists() {
         return Err(format!(
             "Missing converter settings file at `{}`.",
             settings.to_string_lossy()
@@ -889,56 +1858,95 @@ See `THIRD_PARTY_NOTICES.md` for credits.",
         ));
     }
 
-    let cache_root = app
-        .path()
-        .app_cache_dir()
-        .map_err(|e| format!("Failed to resolve cache dir: {e}"))?
-        .join("cortex-labs")
-        .join("yft-cache");
-    std::fs::create_dir_all(&cache_root).map_err(|e| format!("Failed to create cache dir: {e}"))?;
+    let cache_root = cache::yft_cache_root(app)?;
+    std::fs::create_dir_all(paths::long(&cache_root))
+        .map_err(|e| format!("Failed to create cache dir: {e}"))?;
 
-    let meta = std::fs::metadata(&path).map_err(|e| format!("Failed to stat input: {e}"))?;
-    let mtime = meta
-        .modified()
-        .ok()
-        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-        .map(|d| d.as_millis() as u64)
-        .unwrap_or(0);
-    let size = meta.len();
-
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    use std::hash::Hasher;
-    hasher.write(path.as_bytes());
-    hasher.write_u64(mtime);
-    hasher.write_u64(size);
-    let key = format!("{:016x}", hasher.finish());
+    let key = convert_cache_key(
+        &path,
+        &options,
+        explicit_profile.as_ref().map(|(name, _)| name.as_str()),
+    )?;
+    // Applied on top of the caller's overrides, so a preview conversion
+    // stays one whatever they set.
+    let mut settings_overrides = options.settings_overrides.clone();
+    if options.skip_collision {
+        settings_overrides.insert(
+            conversion::COLLISION_SETTING.to_string(),
+            "false".to_string(),
+        );
+    }
+
+    task.attach_cache_key(&key);
+    let tasks = app.state::<ConversionTasks>();
+    let ctx = ConversionContext {
+        app,
+        tasks: &tasks,
+        key: &key,
+        events: "conversion",
+        timeout: conversion::process_timeout(options.timeout_secs),
+        verbose: options.verbose,
+    };
+    ctx.started(&path);
+    let _in_flight = ctx.claim();
 
     let out_dir = cache_root.join(&key);
-    std::fs::create_dir_all(&out_dir).map_err(|e| format!("Failed to create output dir: {e}"))?;
+    std::fs::create_dir_all(paths::long(&out_dir))
+        .map_err(|e| format!("Failed to create output dir: {e}"))?;
     let out_dff = out_dir.join("model.dff");
     let out_txd = out_dir.join("model.txd");
+    let out_col = out_dir.join("model.col");
+    let out_profile = out_dir.join(PROFILE_FILE);
 
+    let mut stats = ConversionStats::for_input(Path::new(&path));
     if out_dff.exists() {
-        let cached_vertices = read_dff_vertex_count(&out_dff).unwrap_or(0);
-        if cached_vertices > 0 {
-            return Ok(serde_json::json!({
-                "dffPath": out_dff.to_string_lossy().to_string(),
-                "txdPath": if out_txd.exists() { out_txd.to_string_lossy().to_string() } else { "".to_string() },
-                "cacheKey": key,
-                "cached": true,
-                "vertexCount": cached_vertices
-            }));
+        let reading_started = Instant::now();
+        let cached = dff::inspect(&out_dff).unwrap_or_default();
+        let validation = dff::validate(&out_dff);
+        stats.reading_ms = conversion::elapsed_ms(reading_started);
+        if cached.vertex_count > 0 && validation.valid {
+            stats.record_outputs([out_dff.as_path(), out_txd.as_path(), out_col.as_path()]);
+            cache::record_source(&out_dir, &path);
+            let exported = export_if_requested(&options, &out_dir, &path)?;
+            return Ok(ConvertYftResult {
+                schema_version: RESULT_SCHEMA_VERSION,
+                dff_path: out_dff.to_string_lossy().to_string(),
+                txd_path: existing_path(&out_txd),
+                cache_key: key.clone(),
+                key_mode: options.key_mode.as_str(),
+                cached: true,
+                exit_code: None,
+                vertex_count: cached.vertex_count,
+                geometries: cached.geometries,
+                uv_channels: cached.uv_channels,
+                uv_bounds: cached.uv_bounds,
+                uv_out_of_range: cached.uv_out_of_range,
+                rw_version: dff::read_version(&out_dff).ok(),
+                validation,
+                exported,
+                settings_overrides: options.settings_overrides,
+                collision_generated: out_col.exists(),
+                profile_used: std::fs::read_to_string(&out_profile)
+                    .ok()
+                    .map(|name| name.trim().to_string()),
+                classification: None,
+                retried: None,
+                work_dir: None,
+                resource,
+                stats,
+            });
         }
         let _ = std::fs::remove_file(&out_dff);
         let _ = std::fs::remove_file(&out_txd);
+        let _ = std::fs::remove_file(&out_col);
     }
 
     let short_key = if key.len() > 12 { &key[..12] } else { &key };
-    let temp_root = std::env::temp_dir().join("cl-yft");
-    let mut work_dir = temp_root.join(short_key);
-    if std::fs::create_dir_all(&temp_root).is_err() {
-        work_dir = out_dir.join("work");
-    }
+    let work_dir = converter_work_dir(&out_dir, short_key)?;
+    ctx.stage(
+        "staging",
+        &format!("Using work dir {}", work_dir.to_string_lossy()),
+    );
 
     let input_ytd = Path::new(&path).with_extension("ytd");
     let input_ytd_ref = if input_ytd.exists() {
@@ -947,8 +1955,33 @@ See `THIRD_PARTY_NOTICES.md` for credits.",
         None
     };
 
-    let mut settings_path = settings;
+    // Vehicles only convert with the vehicle profile, so a YFT that looks
+    // like one starts there and the default profile becomes the fallback.
+    let classification = match &explicit_profile {
+        Some(_) => None,
+        None => rsc7::classify_yft(Path::new(&path)).ok(),
+    };
+    let attempts: Vec<(String, PathBuf)> = match explicit_profile.clone() {
+        Some(profile) => vec![profile],
+        None => {
+            let default = (DEFAULT_PROFILE.to_string(), settings);
+            let vehicle = vehicle_settings
+                .exists()
+                .then(|| (VEHICLE_PROFILE.to_string(), vehicle_settings));
+            match (classification, vehicle) {
+                (Some(YftClass::Vehicle), Some(vehicle)) => vec![vehicle, default],
+                (_, vehicle) => std::iter::once(default).chain(vehicle).collect(),
+            }
+        }
+    };
+    let mut attempts = attempts.into_iter();
+
+    let (mut profile_used, settings_path) = attempts
+        .next()
+        .ok_or_else(|| "No converter profile to run.".to_string())?;
+    task.check()?;
     let mut run = run_yft_converter(
+        &ctx,
         &work_dir,
         &settings_path,
         &col_materials,
@@ -956,48 +1989,470 @@ See `THIRD_PARTY_NOTICES.md` for credits.",
         &zlib,
         Path::new(&path),
         input_ytd_ref,
+        &settings_overrides,
     )?;
 
-    let mut vertex_count = read_dff_vertex_count(&run.0).unwrap_or(0);
-    if vertex_count == 0 && vehicle_settings.exists() {
-        settings_path = vehicle_settings;
-        run = run_yft_converter(
-            &work_dir,
-            &settings_path,
-            &col_materials,
-            &converter,
-            &zlib,
-            Path::new(&path),
-            input_ytd_ref,
-        )?;
-        vertex_count = read_dff_vertex_count(&run.0).unwrap_or(0);
+    let (mut inspection, mut validation) = tally_converter_run(&run, &mut stats);
+    let mut retried = false;
+    if !validation.valid {
+        if let Some((name, settings_path)) = attempts.next() {
+            task.check()?;
+            ctx.stage("retry", &format!("Retrying with {name} profile"));
+            retried = true;
+            profile_used = name;
+            run = run_yft_converter(
+                &ctx,
+                &work_dir,
+                &settings_path,
+                &col_materials,
+                &converter,
+                &zlib,
+                Path::new(&path),
+                input_ytd_ref,
+                &settings_overrides,
+            )?;
+            (inspection, validation) = tally_converter_run(&run, &mut stats);
+        }
     }
+    stats.retried = retried;
 
-    if vertex_count == 0 {
-        let stderr = String::from_utf8_lossy(&run.2.stderr);
-        let stdout = String::from_utf8_lossy(&run.2.stdout);
+    if inspection.vertex_count == 0 {
+        let stderr = String::from_utf8_lossy(&run.output.stderr);
+        let stdout = String::from_utf8_lossy(&run.output.stdout);
         return Err(format!(
             "YFT converter produced a DFF with 0 vertices. This YFT likely uses a newer vertex format not supported by the bundled converter.\n\
 STDERR:\n{}\nSTDOUT:\n{}\nLOG:\n{}",
             stderr.trim(),
             stdout.trim(),
-            run.3.trim()
+            run.log.trim()
         ));
     }
+    if !validation.valid {
+        let errors = validation
+            .findings
+            .iter()
+            .filter(|finding| finding.level == "error")
+            .map(|finding| format!("{:#x}: {}", finding.offset, finding.description))
+            .collect::<Vec<_>>();
+        return Err(format!(
+            "YFT converter produced a malformed DFF:\n{}",
+            errors.join("\n")
+        ));
+    }
+
+    ctx.stage("storing", "Copying converted files to cache");
+    let storing_started = Instant::now();
+    std::fs::copy(paths::long(&run.dff), paths::long(&out_dff))
+        .map_err(|e| format!("Failed to store .dff: {e}"))?;
+    if let Some(txd) = run.txd.as_ref() {
+        let _ = std::fs::copy(paths::long(txd), paths::long(&out_txd));
+    }
+    if let Some(col) = run.col.as_ref() {
+        let _ = std::fs::copy(paths::long(col), paths::long(&out_col));
+    }
+    let _ = std::fs::write(&out_profile, &profile_used);
+    stats.storing_ms = conversion::elapsed_ms(storing_started);
+    stats.record_outputs([out_dff.as_path(), out_txd.as_path(), out_col.as_path()]);
+    cache::record_source(&out_dir, &path);
+    let exported = export_if_requested(&options, &out_dir, &path)?;
+
+    Ok(ConvertYftResult {
+        schema_version: RESULT_SCHEMA_VERSION,
+        dff_path: out_dff.to_string_lossy().to_string(),
+        txd_path: existing_path(&out_txd),
+        cache_key: key.clone(),
+        key_mode: options.key_mode.as_str(),
+        cached: false,
+        exit_code: run.output.status.code(),
+        vertex_count: inspection.vertex_count,
+        geometries: inspection.geometries,
+        uv_channels: inspection.uv_channels,
+        uv_bounds: inspection.uv_bounds,
+        uv_out_of_range: inspection.uv_out_of_range,
+        rw_version: dff::read_version(&out_dff).ok(),
+        validation,
+        exported,
+        settings_overrides: options.settings_overrides,
+        collision_generated: out_col.exists(),
+        profile_used: Some(profile_used),
+        classification: classification.map(YftClass::as_str),
+        retried: Some(retried),
+        work_dir: Some(work_dir.to_string_lossy().to_string()),
+        resource,
+        stats,
+    })
+}
 
-    std::fs::copy(&run.0, &out_dff).map_err(|e| format!("Failed to store .dff: {e}"))?;
-    if let Some(txd) = run.1.as_ref() {
-        let _ = std::fs::copy(txd, &out_txd);
+// The converter can't open paths past MAX_PATH itself, so its work dir has
+// to leave room for the file names it writes there.
+const WORK_DIR_MAX_CHARS: usize = 200;
+
+/// Where the converter runs for the entry keyed `short_key`: the temp dir
+/// normally, the cache entry when that can't be created, and a folder at the
+/// root of the system drive when both are too deep or not plain ASCII.
+fn converter_work_dir(out_dir: &Path, short_key: &str) -> Result<PathBuf, String> {
+    let mut candidates = vec![
+        std::env::temp_dir().join("cl-yft").join(short_key),
+        out_dir.join("work"),
+    ];
+    // `SystemDrive` is just `C:`, which alone would be relative.
+    if let Some(mut root) = std::env::var_os("SystemDrive") {
+        root.push("\\");
+        candidates.push(PathBuf::from(root).join("cl-yft").join(short_key));
+    }
+    candidates
+        .into_iter()
+        // The converter also mangles non-ASCII paths, e.g. a temp dir under a
+        // Cyrillic user name.
+        .filter(|dir| {
+            dir.to_str()
+                .is_some_and(|dir| dir.is_ascii() && dir.len() <= WORK_DIR_MAX_CHARS)
+        })
+        .find(|dir| {
+            dir.parent()
+                .is_some_and(|parent| std::fs::create_dir_all(paths::long(parent)).is_ok())
+        })
+        .ok_or_else(|| {
+            format!(
+                "No ASCII working directory for the converter is shorter than {WORK_DIR_MAX_CHARS} characters."
+            )
+        })
+}
+
+/// Cache key of a conversion of `path` with `options`, run with the
+/// explicit `profile` if one was picked.
+fn convert_cache_key(
+    path: &str,
+    options: &ConvertYftOptions,
+    profile: Option<&str>,
+) -> Result<String, String> {
+    let mut key = cache::yft_cache_key(path, options.key_mode)?;
+    if let Some(name) = profile {
+        key = cache::variant_key(&key, &format!("profile:{name}"));
+    }
+    if !options.settings_overrides.is_empty() {
+        let overrides = options
+            .settings_overrides
+            .iter()
+            .map(|(name, value)| format!("{name}={value}\n"))
+            .collect::<String>();
+        key = cache::variant_key(&key, &format!("settings:{overrides}"));
     }
+    if options.skip_collision {
+        key = cache::variant_key(&key, "collision:off");
+    }
+    Ok(key)
+}
 
-    Ok(serde_json::json!({
-        "dffPath": out_dff.to_string_lossy().to_string(),
-        "txdPath": if out_txd.exists() { out_txd.to_string_lossy().to_string() } else { "".to_string() },
-        "cacheKey": key,
-        "cached": false,
-        "exitCode": run.2.status.code(),
-        "vertexCount": vertex_count
-    }))
+// Physical-segment bytes per vertex for `estimate_yft`: vertex and index
+// buffers plus the textures sharing the segment.
+const PHYSICAL_BYTES_PER_VERTEX: u64 = 64;
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct YftEstimate {
+    file_bytes: u64,
+    resource: rsc7::ResourceHeader,
+    /// The `.ytd` `parse_yft` would pick up, if any.
+    ytd_path: Option<String>,
+    /// Cache key and state of a default `parse_yft`.
+    parse_cache_key: String,
+    parse_cached: bool,
+    /// Cache key and state of a default `convert_yft`.
+    convert_cache_key: String,
+    convert_cached: bool,
+    /// Rough guess from the size of the resource's physical segment.
+    estimated_vertices: u64,
+}
+
+/// What opening a .yft would cost, from header reads and cache lookups
+/// only: its size, RSC7 header, sibling `.ytd`, a rough vertex count, and
+/// whether a default `parse_yft` and `convert_yft` would be served from the
+/// cache. `key_mode` works as in `parse_yft`.
+#[tauri::command]
+async fn estimate_yft(
+    path: String,
+    key_mode: Option<String>,
+    app: crate::AppHandle,
+) -> Result<YftEstimate, String> {
+    let key_mode = CacheKeyMode::parse(key_mode.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if !is_yft(&path) {
+            return Err("Only .yft files are supported by estimate_yft".to_string());
+        }
+        let file_bytes = std::fs::metadata(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to stat YFT: {e}"))?
+            .len();
+        let resource = rsc7::sniff(Path::new(&path))?;
+        let cache_root = cache::yft_cache_root(&app)?;
+
+        let parse_options = ParseOptions {
+            key_mode,
+            merge_hi: true,
+            ..Default::default()
+        };
+        let parse_key = parse_cache_key(BridgeModel::Yft, &path, &parse_options)?;
+        let parse_dir = cache_root.join(&parse_key.key);
+        let parse_cached = parse_dir.join("model.clmesh").is_file()
+            && (parse_key.sibling.is_none() || parse_dir.join("sibling.clmesh").is_file())
+            && std::fs::read_to_string(parse_dir.join("meta.json"))
+                .ok()
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                .is_some_and(|meta| meta_schema_current(&meta));
+
+        let convert_options = ConvertYftOptions {
+            key_mode,
+            ..Default::default()
+        };
+        let convert_key = convert_cache_key(&path, &convert_options, None)?;
+        let convert_cached = dff::vertex_count(&cache_root.join(&convert_key).join("model.dff"))
+            .is_some_and(|vertices| vertices > 0);
+
+        Ok(YftEstimate {
+            file_bytes,
+            resource,
+            ytd_path: find_sibling_ytd(&path),
+            parse_cache_key: parse_key.key,
+            parse_cached,
+            convert_cache_key: convert_key,
+            convert_cached,
+            estimated_vertices: resource.physical_size / PHYSICAL_BYTES_PER_VERTEX,
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to join YFT estimate task: {e}"))?
+}
+
+/// The geometries in a run's DFF (none if it can't be read) and its
+/// validation, adding the run's timings to `stats`.
+fn tally_converter_run(
+    run: &ConverterRun,
+    stats: &mut ConversionStats,
+) -> (dff::DffInspection, dff::DffValidation) {
+    let reading_started = Instant::now();
+    let inspection = dff::inspect(&run.dff).unwrap_or_default();
+    let validation = dff::validate(&run.dff);
+    stats.staging_ms += run.staging_ms;
+    stats.process_ms += run.process_ms;
+    stats.reading_ms += run.reading_ms + conversion::elapsed_ms(reading_started);
+    (inspection, validation)
+}
+
+const CONVERTER_FOLDER: &str = "yft-converter";
+const CONVERTER_EXE: &str = "ytdydryddyft2txddffcol.exe";
+const CONVERTER_DLL: &str = "zlib1.dll";
+const CONVERTER_MATERIALS: &str = "col_gen_materials.dat";
+const DEFAULT_PROFILE: &str = "default";
+const VEHICLE_PROFILE: &str = "vehicle";
+// Profile a cached conversion came from, for `profileUsed` on a cache hit.
+const PROFILE_FILE: &str = "profile.txt";
+
+/// Everywhere the converter folder is looked for, in order of preference.
+fn converter_dir_candidates(app: &crate::AppHandle) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    candidates.push(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("bin")
+            .join(CONVERTER_FOLDER),
+    );
+    if let Ok(resource_dir) = app.path().resource_dir() {
+        candidates.push(resource_dir.join("bin").join(CONVERTER_FOLDER));
+    }
+    candidates
+}
+
+fn find_converter_dir(app: &crate::AppHandle) -> Result<PathBuf, String> {
+    converter_dir_candidates(app)
+        .into_iter()
+        .find(|p| p.exists())
+        .ok_or_else(|| {
+            format!(
+                "Missing YFT converter folder.\n\
+Place it at `src-tauri/bin/{}` for dev builds, or bundle it as a resource.\n\
+See `THIRD_PARTY_NOTICES.md` for credits.",
+                CONVERTER_FOLDER
+            )
+        })
+}
+
+/// `GeneralSettings.ini` is the `default` profile and
+/// `GeneralSettings.<name>.ini` the profile `name`.
+fn profile_file_name(name: &str) -> String {
+    if name == DEFAULT_PROFILE {
+        "GeneralSettings.ini".to_string()
+    } else {
+        format!("GeneralSettings.{name}.ini")
+    }
+}
+
+fn profile_name(file_name: &str) -> Option<String> {
+    let prefix = "GeneralSettings";
+    let head = file_name.get(..prefix.len())?;
+    let rest = file_name.get(prefix.len()..)?;
+    let name = rest.get(..rest.len().checked_sub(".ini".len())?)?;
+    if !head.eq_ignore_ascii_case(prefix) || !rest[name.len()..].eq_ignore_ascii_case(".ini") {
+        return None;
+    }
+    match name {
+        "" => Some(DEFAULT_PROFILE.to_string()),
+        _ => name
+            .strip_prefix('.')
+            .filter(|name| !name.is_empty())
+            .map(str::to_string),
+    }
+}
+
+fn converter_profiles(converter_dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut profiles: Vec<(String, PathBuf)> = std::fs::read_dir(converter_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.path().is_file())
+                .filter_map(|entry| {
+                    let name = profile_name(&entry.file_name().to_string_lossy())?;
+                    Some((name, entry.path()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // Default first, the rest by name.
+    profiles.sort_by(|(a, _), (b, _)| {
+        (a != DEFAULT_PROFILE, a.to_ascii_lowercase())
+            .cmp(&(b != DEFAULT_PROFILE, b.to_ascii_lowercase()))
+    });
+    profiles
+}
+
+/// Match a requested profile by name or file name, case-insensitively.
+fn resolve_converter_profile(
+    converter_dir: &Path,
+    requested: &str,
+) -> Result<(String, PathBuf), String> {
+    let requested = requested.trim();
+    let wanted = profile_name(requested).unwrap_or_else(|| requested.to_string());
+    let profiles = converter_profiles(converter_dir);
+    profiles
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(&wanted))
+        .cloned()
+        .ok_or_else(|| {
+            let available = profiles
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("Unknown converter profile `{requested}` (available: {available})")
+        })
+}
+
+/// Names of the converter's settings profiles (`GeneralSettings*.ini`), for
+/// `convert_yft`'s `profile`. `default` is `GeneralSettings.ini`.
+#[tauri::command]
+fn list_converter_profiles(app: crate::AppHandle) -> Result<Vec<String>, String> {
+    let converter_dir = find_converter_dir(&app)?;
+    Ok(converter_profiles(&converter_dir)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect())
+}
+
+fn export_if_requested(
+    options: &ConvertYftOptions,
+    out_dir: &Path,
+    input: &str,
+) -> Result<Option<cache::ExportedConversion>, String> {
+    let Some(export_dir) = &options.export_dir else {
+        return Ok(None);
+    };
+    let stem = Path::new(input)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("model");
+    cache::export_conversion(out_dir, stem, Path::new(export_dir), options.overwrite).map(Some)
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BatchConversionItem {
+    index: usize,
+    path: String,
+    ok: bool,
+    vertex_count: Option<u32>,
+    error: Option<String>,
+    result: Option<ConvertYftResult>,
+}
+
+/// Convert several YFTs with at most `max_parallel` running at once. Every
+/// item is attempted regardless of earlier failures; `conversion:batch_progress`
+/// fires as each one finishes and the per-item results come back in input
+/// order. Items use their own cache-keyed work dirs, so they don't collide.
+///
+/// The batch is a `batch` task; cancelling it fails the items not started
+/// yet with `cancelled`.
+#[tauri::command]
+async fn convert_yft_batch(
+    paths: Vec<String>,
+    max_parallel: u32,
+    key_mode: Option<String>,
+    app: crate::AppHandle,
+) -> Result<Vec<BatchConversionItem>, String> {
+    let options = ConvertYftOptions {
+        key_mode: CacheKeyMode::parse(key_mode.as_deref())?,
+        ..Default::default()
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let batch = tasks::start(&app, "batch", None);
+        let workers = (max_parallel.max(1) as usize).min(paths.len().max(1));
+        let next = std::sync::atomic::AtomicUsize::new(0);
+        let results: Mutex<Vec<BatchConversionItem>> = Mutex::new(Vec::new());
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    let Some(path) = paths.get(index) else {
+                        break;
+                    };
+
+                    // Items already running are tasks of their own; a
+                    // cancelled batch just stops starting new ones.
+                    let converted = batch
+                        .check()
+                        .and_then(|_| convert_yft_blocking(path.clone(), options.clone(), &app));
+                    let item = match converted {
+                        Ok(result) => BatchConversionItem {
+                            index,
+                            path: path.clone(),
+                            ok: true,
+                            vertex_count: Some(result.vertex_count),
+                            error: None,
+                            result: Some(result),
+                        },
+                        Err(error) => BatchConversionItem {
+                            index,
+                            path: path.clone(),
+                            ok: false,
+                            vertex_count: None,
+                            error: Some(error),
+                            result: None,
+                        },
+                    };
+                    let _ = app.emit("conversion:batch_progress", item.clone());
+                    if let Ok(mut results) = results.lock() {
+                        results.push(item);
+                    }
+                });
+            }
+        });
+
+        let mut results = results
+            .into_inner()
+            .map_err(|_| "batch result lock failed".to_string())?;
+        results.sort_by_key(|item| item.index);
+        Ok(results)
+    })
+    .await
+    .map_err(|e| format!("Failed to join YFT batch task: {e}"))?
 }
 
 #[tauri::command]
@@ -1011,11 +2466,18 @@ fn consume_pending_open_file(state: State<PendingOpenFileState>) -> Option<Strin
 
 #[tauri::command]
 fn ensure_dir(path: String) -> Result<(), String> {
+    create_dir(&path).map(|_| ())
+}
+
+/// Create `path` and any missing parents, as `ensure_dir` does for the UI.
+fn create_dir(path: &str) -> Result<PathBuf, String> {
     if path.trim().is_empty() {
         return Err("Path is empty".to_string());
     }
 
-    std::fs::create_dir_all(PathBuf::from(path)).map_err(|e| e.to_string())
+    let path = PathBuf::from(path);
+    std::fs::create_dir_all(&path).map_err(|e| e.to_string())?;
+    Ok(path)
 }
 
 #[tauri::command]
@@ -1064,390 +2526,14 @@ fn open_folder_fallback(path: String) -> Result<(), String> {
     Ok(())
 }
 
-/// Decode a Paint.NET (.pdn) file into raw RGBA pixel data.
-/// Returns base64-encoded RGBA pixels plus width/height.
-#[tauri::command]
-fn decode_pdn(path: String) -> Result<serde_json::Value, String> {
-    use flate2::read::GzDecoder;
-    use std::io::Read;
-
-    let file_meta = std::fs::metadata(&path).map_err(|e| format!("Failed to stat PDN file: {e}"))?;
-    if file_meta.len() > MAX_PDN_FILE_BYTES {
-        return Err(format!(
-            "PDN file exceeds safety limit ({} bytes > {} bytes).",
-            file_meta.len(),
-            MAX_PDN_FILE_BYTES
-        ));
-    }
-
-    let data = std::fs::read(&path).map_err(|e| format!("Failed to read PDN file: {e}"))?;
-
-    // Validate PDN3 magic
-    if data.len() < 24 || &data[0..4] != b"PDN3" {
-        return Err("Not a valid Paint.NET file (missing PDN3 magic).".to_string());
-    }
-
-    // Find image dimensions by scanning for "width" and "height" field names
-    // in the .NET BinaryFormatter stream
-    let (width, height) = extract_pdn_dimensions(&data)
-        .ok_or_else(|| "Could not extract image dimensions from PDN file.".to_string())?;
-
-    if width == 0 || height == 0 || width > MAX_PDN_DIMENSION || height > MAX_PDN_DIMENSION {
-        return Err(format!("Invalid PDN dimensions: {width}x{height}"));
-    }
-    let initial_expected_size = (width as usize).saturating_mul(height as usize).saturating_mul(4);
-    if initial_expected_size == 0 || initial_expected_size > MAX_PDN_OUTPUT_BYTES {
-        return Err(format!(
-            "PDN image dimensions exceed safety output limit ({} bytes > {} bytes).",
-            initial_expected_size, MAX_PDN_OUTPUT_BYTES
-        ));
-    }
-
-    // Find and decompress all gzip chunks
-    let mut gzip_offsets = Vec::new();
-    let mut i = 24;
-    while i + 2 < data.len() {
-        if data[i] == 0x1F && data[i + 1] == 0x8B && i + 2 < data.len() && data[i + 2] == 0x08 {
-            gzip_offsets.push(i);
-            i += 18; // skip minimum gzip overhead
-        } else {
-            i += 1;
-        }
-    }
-
-    if gzip_offsets.is_empty() {
-        return Err("No compressed pixel data found in PDN file.".to_string());
-    }
-
-    // Decompress all chunks first.
-    let mut inflated_chunks: Vec<Vec<u8>> = Vec::new();
-    let mut total_inflated_bytes: usize = 0;
-
-    for offset in &gzip_offsets {
-        let slice = &data[*offset..];
-        let mut decoder = GzDecoder::new(slice);
-        let mut inflated = Vec::new();
-        let mut chunk_buf = [0u8; 8192];
-
-        loop {
-            let bytes_read = match decoder.read(&mut chunk_buf) {
-                Ok(n) => n,
-                Err(_) => {
-                    inflated.clear();
-                    break;
-                }
-            };
-
-            if bytes_read == 0 {
-                break;
-            }
-
-            if inflated.len().saturating_add(bytes_read) > MAX_PDN_INFLATED_CHUNK_BYTES {
-                return Err(format!(
-                    "PDN chunk exceeded inflate safety limit ({} bytes).",
-                    MAX_PDN_INFLATED_CHUNK_BYTES
-                ));
-            }
-
-            if total_inflated_bytes.saturating_add(bytes_read) > MAX_PDN_TOTAL_INFLATED_BYTES {
-                return Err(format!(
-                    "PDN decode exceeded total inflate safety limit ({} bytes).",
-                    MAX_PDN_TOTAL_INFLATED_BYTES
-                ));
-            }
-
-            inflated.extend_from_slice(&chunk_buf[..bytes_read]);
-            total_inflated_bytes += bytes_read;
-        }
-
-        if inflated.is_empty() {
-            continue;
-        }
-
-        inflated_chunks.push(inflated);
-    }
-
-    if inflated_chunks.is_empty() {
-        return Err("Failed to decompress any pixel data from PDN file.".to_string());
-    }
-
-    // Width/height extraction can occasionally return swapped values.
-    // If chunk alignment strongly suggests a swap, correct it.
-    let mut width_usize = width as usize;
-    let mut height_usize = height as usize;
-    maybe_swap_pdn_dimensions_by_alignment(&inflated_chunks, &mut width_usize, &mut height_usize);
-
-    let expected_size = width_usize.saturating_mul(height_usize).saturating_mul(4);
-    if expected_size == 0 || expected_size > MAX_PDN_OUTPUT_BYTES {
-        return Err("PDN decode produced invalid output dimensions.".to_string());
-    }
-
-    let row_bytes = width_usize.saturating_mul(4);
-    let has_row_misalignment = row_bytes > 0
-        && inflated_chunks
-            .iter()
-            .any(|chunk| chunk.len() >= row_bytes && chunk.len() % row_bytes != 0);
-
-    // If chunks are not row-aligned, strip small per-chunk metadata/header bytes.
-    let mut layers = assemble_pdn_layers(
-        &inflated_chunks,
-        width_usize,
-        height_usize,
-        has_row_misalignment,
-    );
-    if layers.is_empty() {
-        // Fallback to raw concatenation if trimmed assembly yielded nothing.
-        layers = assemble_pdn_layers(&inflated_chunks, width_usize, height_usize, false);
-    }
-    if layers.is_empty() {
-        return Err("Failed to reconstruct any PDN layers from decompressed data.".to_string());
-    }
-
-    // Composite layers bottom-to-top, converting BGRA → RGBA
-    let mut rgba = vec![0u8; expected_size];
-    for layer in &layers {
-        let len = std::cmp::min(layer.len(), expected_size);
-        let mut j = 0;
-        while j + 3 < len {
-            let pix = (j / 4) * 4;
-            if pix + 3 >= rgba.len() {
-                break;
-            }
-
-            let src_b = layer[j];
-            let src_g = layer[j + 1];
-            let src_r = layer[j + 2];
-            let src_a = layer[j + 3];
-
-            if src_a == 0 {
-                j += 4;
-                continue;
-            }
-
-            let dst_a = rgba[pix + 3];
-            if src_a == 255 || dst_a == 0 {
-                rgba[pix] = src_r;
-                rgba[pix + 1] = src_g;
-                rgba[pix + 2] = src_b;
-                rgba[pix + 3] = src_a;
-            } else {
-                let sa = src_a as f32 / 255.0;
-                let da = dst_a as f32 / 255.0;
-                let out_a = sa + da * (1.0 - sa);
-                if out_a > 0.0 {
-                    rgba[pix] =
-                        ((src_r as f32 * sa + rgba[pix] as f32 * da * (1.0 - sa)) / out_a) as u8;
-                    rgba[pix + 1] = ((src_g as f32 * sa + rgba[pix + 1] as f32 * da * (1.0 - sa))
-                        / out_a) as u8;
-                    rgba[pix + 2] = ((src_b as f32 * sa + rgba[pix + 2] as f32 * da * (1.0 - sa))
-                        / out_a) as u8;
-                    rgba[pix + 3] = (out_a * 255.0) as u8;
-                }
-            }
-
-            j += 4;
-        }
-    }
-
-    // Encode as base64 for transfer to frontend
-    use base64::Engine;
-    let encoded = base64::engine::general_purpose::STANDARD.encode(&rgba);
-
-    Ok(serde_json::json!({
-        "width": width_usize,
-        "height": height_usize,
-        "rgba_base64": encoded
-    }))
-}
-
-fn maybe_swap_pdn_dimensions_by_alignment(
-    chunks: &[Vec<u8>],
-    width: &mut usize,
-    height: &mut usize,
-) {
-    if *width == 0 || *height == 0 || *width == *height {
-        return;
-    }
-
-    let row_a = width.saturating_mul(4);
-    let row_b = height.saturating_mul(4);
-    if row_a == 0 || row_b == 0 {
-        return;
-    }
-
-    let aligned_a = chunks
-        .iter()
-        .filter(|chunk| chunk.len() >= row_a && chunk.len() % row_a == 0)
-        .count();
-    let aligned_b = chunks
-        .iter()
-        .filter(|chunk| chunk.len() >= row_b && chunk.len() % row_b == 0)
-        .count();
-
-    if aligned_b > aligned_a && aligned_b >= chunks.len().saturating_div(2) {
-        std::mem::swap(width, height);
-    }
-}
-
-fn assemble_pdn_layers(
-    chunks: &[Vec<u8>],
-    width: usize,
-    height: usize,
-    trim_chunk_headers: bool,
-) -> Vec<Vec<u8>> {
-    let expected_size = width.saturating_mul(height).saturating_mul(4);
-    let row_bytes = width.saturating_mul(4);
-    if expected_size == 0 {
-        return Vec::new();
-    }
-
-    let mut layers: Vec<Vec<u8>> = Vec::new();
-    let mut current_layer: Vec<u8> = Vec::with_capacity(expected_size);
-
-    for chunk in chunks {
-        let payload = if trim_chunk_headers {
-            select_pdn_chunk_payload(chunk, row_bytes)
-        } else {
-            (0, chunk.len())
-        };
-        if payload.1 <= payload.0 || payload.1 > chunk.len() {
-            continue;
-        }
-
-        current_layer.extend_from_slice(&chunk[payload.0..payload.1]);
-
-        while current_layer.len() >= expected_size {
-            let layer = current_layer.drain(..expected_size).collect::<Vec<u8>>();
-            layers.push(layer);
-        }
-    }
-
-    // Keep non-empty trailing data as a partial layer and pad to full image size.
-    if current_layer.len() >= row_bytes.max(1) {
-        current_layer.resize(expected_size, 0);
-        layers.push(current_layer);
-    }
-
-    layers
-}
-
-fn select_pdn_chunk_payload(chunk: &[u8], row_bytes: usize) -> (usize, usize) {
-    if row_bytes == 0 || chunk.len() <= row_bytes {
-        return (0, chunk.len());
-    }
-
-    let max_trim = std::cmp::min(64, chunk.len().saturating_sub(1));
-    let mut best: Option<(usize, usize, usize)> = None;
-
-    for lead in 0..=max_trim {
-        for trail in 0..=max_trim {
-            if lead + trail >= chunk.len() {
-                continue;
-            }
-            let payload_len = chunk.len() - lead - trail;
-            if payload_len < row_bytes || payload_len % row_bytes != 0 {
-                continue;
-            }
-            let rows = payload_len / row_bytes;
-            if rows == 0 || rows > 8192 {
-                continue;
-            }
-            let discarded = lead + trail;
-            match best {
-                None => best = Some((lead, chunk.len() - trail, discarded)),
-                Some((best_start, best_end, best_discarded)) => {
-                    let best_payload_len = best_end.saturating_sub(best_start);
-                    if discarded < best_discarded
-                        || (discarded == best_discarded && payload_len > best_payload_len)
-                    {
-                        best = Some((lead, chunk.len() - trail, discarded));
-                    }
-                }
-            }
-        }
-    }
-
-    if let Some((start, end, _)) = best {
-        return (start, end);
-    }
-
-    let aligned_len = chunk.len() - (chunk.len() % row_bytes);
-    if aligned_len >= row_bytes {
-        return (0, aligned_len);
-    }
-
-    (0, chunk.len())
-}
-
-/// Extract width and height from a PDN file's .NET BinaryFormatter stream.
-fn extract_pdn_dimensions(data: &[u8]) -> Option<(u32, u32)> {
-    let mut width: u32 = 0;
-    let mut height: u32 = 0;
-
-    // Scan for "width" and "height" length-prefixed strings
-    let width_needle = b"width";
-    let height_needle = b"height";
-
-    for i in 0..data.len().saturating_sub(10) {
-        // .NET BinaryFormatter writes field names as length-prefixed strings
-        if data[i] == width_needle.len() as u8 && i + 1 + width_needle.len() < data.len() {
-            if &data[i + 1..i + 1 + width_needle.len()] == width_needle {
-                // Scan forward for a reasonable Int32 value
-                width = scan_for_dimension(data, i + 1 + width_needle.len());
-            }
-        }
-        if data[i] == height_needle.len() as u8 && i + 1 + height_needle.len() < data.len() {
-            if &data[i + 1..i + 1 + height_needle.len()] == height_needle {
-                height = scan_for_dimension(data, i + 1 + height_needle.len());
-            }
-        }
-        if width > 0 && height > 0 {
-            return Some((width, height));
-        }
-    }
-
-    // Fallback: scan for dimension pair in the header area
-    for i in 24..std::cmp::min(data.len().saturating_sub(8), 4096) {
-        let a = u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
-        let b = u32::from_le_bytes([data[i + 4], data[i + 5], data[i + 6], data[i + 7]]);
-        if (16..=16384).contains(&a)
-            && (16..=16384).contains(&b)
-            && a.is_power_of_two()
-            && b.is_power_of_two()
-        {
-            return Some((a, b));
-        }
-    }
-
-    None
-}
-
-/// Scan forward from a position to find a reasonable dimension value.
-fn scan_for_dimension(data: &[u8], from: usize) -> u32 {
-    for off in from..std::cmp::min(from + 64, data.len().saturating_sub(4)) {
-        let val = u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]]);
-        if (1..=65536).contains(&val) && (val.is_power_of_two() || val % 64 == 0 || val % 100 == 0)
-        {
-            return val;
-        }
-    }
-    for off in from..std::cmp::min(from + 64, data.len().saturating_sub(4)) {
-        let val = u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]]);
-        if (16..=65536).contains(&val) {
-            return val;
-        }
-    }
-    0
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
-        .manage(WatchState::default())
-        .manage(WindowWatchState::default())
-        .manage(MultiWatchState::default())
-        .manage(ModelWatchState::default())
+    tauri::Builder::<Runtime>::new()
+        .manage(WatcherRegistry::default())
         .manage(PendingOpenFileState::default())
+        .manage(ConversionTasks::default())
+        .manage(BridgeProcess::default())
+        .manage(tasks::TaskRegistry::default())
         .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
             if let Some(file_path) = extract_open_file_arg(&args) {
                 queue_open_file(app, file_path);
@@ -1464,21 +2550,93 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .invoke_handler(tauri::generate_handler![
             inspect_updater_release,
-            start_watch,
-            stop_watch,
-            start_window_watch,
-            stop_window_watch,
-            start_multi_watch,
-            stop_multi_watch,
-            start_model_watch,
-            stop_model_watch,
+            watch::start_watch,
+            watch::stop_watch,
+            watch::stop_all_watches,
+            watch::list_active_watches,
+            watch::get_watch_stats,
+            watch::reset_watch_stats,
+            watch::pause_watch,
+            watch::resume_watch,
+            watch::start_window_watch,
+            watch::stop_window_watch,
+            watch::start_multi_watch,
+            watch::stop_multi_watch,
+            watch::add_watch_paths,
+            watch::remove_watch_paths,
+            watch::list_multi_watch_paths,
+            watch::start_model_watch,
+            watch::stop_model_watch,
+            watch::start_model_dir_watch,
+            watch::stop_model_dir_watch,
+            watch::start_pdn_live_watch,
+            watch::stop_pdn_live_watch,
+            watch::start_ytd_watch,
+            watch::stop_ytd_watch,
+            watch::save_watch_session,
+            watch::restore_watch_session,
             parse_yft,
+            parse_yft_bytes,
+            list_converter_profiles,
+            estimate_yft,
+            dff::parse_dff,
+            dff::inspect_dff,
+            dff::get_dff_materials,
+            dff::get_dff_frames,
+            dff::get_dff_version,
+            dff::validate_dff,
+            dff::get_dff_vertex_colors,
+            dff::get_dff_2dfx,
+            dff::get_dff_skin,
+            dff::rename_dff_textures,
+            dff::transform_dff,
+            col::parse_col,
+            col::generate_col,
+            img::list_img,
+            img::extract_img_entry,
+            img::replace_img_entry,
+            gltf::export_gltf,
+            obj::export_clmesh_obj,
+            obj::export_dff_to_obj,
+            txd::list_txd,
+            txd::decode_txd_texture,
+            txd::export_txd_textures,
+            txd::replace_txd_texture,
+            txd::build_txd,
+            clmesh::inspect_clmesh,
+            meshdiff::diff_meshes,
+            get_model_stats,
+            rpf::list_rpf,
+            rpf::extract_rpf_entry,
+            parse_ydd,
+            parse_ydr,
+            import_obj,
             convert_yft,
+            convert_yft_batch,
+            conversion::cancel_conversion,
+            bridge::shutdown_bridge,
+            diagnostics::get_tool_diagnostics,
+            textures::extract_ytd,
+            history::get_conversion_history,
+            history::clear_conversion_history,
+            tasks::list_tasks,
+            tasks::cancel_task,
+            cache::clear_yft_cache,
+            cache::get_cache_usage,
+            cache::set_cache_max_bytes,
+            cache::export_cached_conversion,
             consume_pending_open_file,
             ensure_dir,
             open_folder_fallback,
-            decode_pdn
+            pdn::decode_pdn,
+            pdn::decode_pdn_to_png,
+            pdn::decode_pdn_layers
         ])
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                watch::on_window_destroyed(window);
+            }
+        })
         .setup(|app| {
             // On Windows, "Open With" passes the file path as a CLI argument.
             // Queue it so the frontend can consume it once listeners are mounted.
@@ -1488,8 +2646,343 @@ pub fn run() {
                     *pending = Some(file_path);
                 }
             }
+            watch::auto_restore_watch_session(app.handle());
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                app.state::<BridgeProcess>().shutdown();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The frontend reads these results by field name, so renaming one
+    // without bumping `RESULT_SCHEMA_VERSION` breaks it silently.
+    fn assert_golden(result: impl serde::Serialize, name: &str) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/results")
+            .join(name);
+        let golden: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display())),
+        )
+        .unwrap();
+        let actual = serde_json::to_value(result).unwrap();
+        assert_eq!(
+            actual,
+            golden,
+            "{name} changed; bump RESULT_SCHEMA_VERSION and update the golden:\n{}",
+            serde_json::to_string_pretty(&actual).unwrap()
+        );
+    }
+
+    fn stats() -> ConversionStats {
+        ConversionStats {
+            staging_ms: 1,
+            process_ms: 2,
+            reading_ms: 3,
+            storing_ms: 4,
+            input_bytes: 5,
+            output_bytes: BTreeMap::from([("model.clmesh".to_string(), 6)]),
+            retried: false,
+        }
+    }
+
+    #[test]
+    fn parse_result_matches_golden() {
+        let mut lod_paths = serde_json::Map::new();
+        lod_paths.insert("high".to_string(), "cache/model_high.clmesh".into());
+        assert_golden(
+            ParseYftResult {
+                schema_version: RESULT_SCHEMA_VERSION,
+                mesh_path: "cache/model.clmesh".to_string(),
+                cache_key: "abc123".to_string(),
+                key_mode: CacheKeyMode::Fast.as_str(),
+                cached: true,
+                meta: serde_json::json!({ "drawables": 1 }),
+                ytd_path: Some("adder.ytd".to_string()),
+                lod_paths: Some(lod_paths),
+                sibling_path: Some("adder_hi.yft".to_string()),
+                sibling_mesh_path: Some("cache/model_hi.clmesh".to_string()),
+                skeleton_path: None,
+                collision_path: Some("cache/collision.clmesh".to_string()),
+                collision_json_path: Some("cache/collision.json".to_string()),
+                collision: None,
+                stats: stats(),
+            },
+            "parse-yft.json",
+        );
+    }
+
+    #[test]
+    fn convert_result_matches_golden() {
+        let bounds = dff::UvBounds {
+            min: [0.0, 0.0],
+            max: [1.0, 0.5],
+            out_of_range: false,
+        };
+        assert_golden(
+            ConvertYftResult {
+                schema_version: RESULT_SCHEMA_VERSION,
+                dff_path: "cache/model.dff".to_string(),
+                txd_path: Some("cache/model.txd".to_string()),
+                cache_key: "abc123".to_string(),
+                key_mode: CacheKeyMode::Content.as_str(),
+                cached: false,
+                exit_code: Some(0),
+                vertex_count: 4,
+                geometries: vec![dff::GeometryInfo {
+                    offset: 12,
+                    flags: 0x76,
+                    vertex_count: 4,
+                    triangle_count: 2,
+                    material_count: 1,
+                    uv_sets: 1,
+                    uv_bounds: vec![bounds],
+                    skinned: false,
+                    bin_mesh: Some(dff::BinMeshInfo {
+                        splits: 1,
+                        strips: false,
+                        index_count: 6,
+                    }),
+                }],
+                uv_channels: 1,
+                uv_bounds: vec![bounds],
+                uv_out_of_range: false,
+                rw_version: Some(dff::DffVersion {
+                    version: "3.6.0.3".to_string(),
+                    build: Some(0xFFFF),
+                    target: Some("San Andreas"),
+                    versions: vec!["3.6.0.3".to_string()],
+                    native_geometry: false,
+                    extra_vertex_colors: false,
+                    warnings: Vec::new(),
+                }),
+                validation: dff::DffValidation {
+                    valid: true,
+                    findings: vec![dff::Finding {
+                        level: "warning",
+                        offset: 12,
+                        description: "Unused material.".to_string(),
+                    }],
+                },
+                exported: None,
+                settings_overrides: BTreeMap::from([(
+                    "General.ExportCollision".to_string(),
+                    "false".to_string(),
+                )]),
+                collision_generated: false,
+                profile_used: Some("vehicle".to_string()),
+                classification: Some("ok"),
+                retried: Some(false),
+                work_dir: Some("cache/work".to_string()),
+                resource: rsc7::ResourceHeader {
+                    version: 162,
+                    system_flags: 1,
+                    graphics_flags: 2,
+                    virtual_size: 0x2000,
+                    physical_size: 0x4000,
+                },
+                stats: stats(),
+            },
+            "convert-yft.json",
+        );
+    }
+
+    /// An empty temp dir whose name starts with `prefix`.
+    fn temp_dir_named(prefix: &str) -> tempfile::TempDir {
+        tempfile::Builder::new().prefix(prefix).tempdir().unwrap()
+    }
+
+    // Percent-encode everything but unreserved characters and separators, as
+    // a file manager does when it hands over a file URL.
+    fn file_url(path: &Path) -> String {
+        let mut path = path.to_str().unwrap().replace('\\', "/");
+        if !path.starts_with('/') {
+            path.insert(0, '/');
+        }
+        let mut url = "file://".to_string();
+        for byte in path.bytes() {
+            if byte.is_ascii_alphanumeric() || b"/-._~:".contains(&byte) {
+                url.push(byte as char);
+            } else {
+                url.push_str(&format!("%{byte:02X}"));
+            }
+        }
+        url
+    }
+
+    #[test]
+    fn non_ascii_file_urls_decode_to_the_path() {
+        let temp = temp_dir_named("тест 車");
+        let dir = temp.path();
+        let model = dir.join("адер.yft");
+        std::fs::write(&model, b"RSC7").unwrap();
+
+        let url = file_url(&model);
+        assert!(url.is_ascii(), "{url}");
+        let expected = model.to_string_lossy().to_string();
+        assert_eq!(normalize_open_file_arg(&url), Some(expected.clone()));
+        assert_eq!(
+            extract_open_file_arg(&["cortex".to_string(), format!("\"{url}\"")]),
+            Some(expected)
+        );
+        // A stray `%` that isn't an escape is kept as is.
+        assert_eq!(percent_decode("100%25 %zz%"), "100% %zz%");
+    }
+
+    #[test]
+    fn converter_stages_non_ascii_inputs_in_an_ascii_work_dir() {
+        let temp = temp_dir_named("тест 車 staging");
+        let dir = temp.path();
+        let write = |name: &str, contents: &[u8]| {
+            let path = dir.join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        let settings = write("GeneralSettings.ini", b"[General]\nExportCollision=true\n");
+        let col = write("col_gen_materials.dat", b"materials");
+        let exe = write(CONVERTER_EXE, b"exe");
+        let zlib = write(CONVERTER_DLL, b"dll");
+        let yft = write("адер.yft", b"RSC7 yft");
+        let ytd = write("адер.ytd", b"RSC7 ytd");
+
+        let short_key = format!("test{}", std::process::id());
+        let work_dir = converter_work_dir(&dir.join("cache"), &short_key).unwrap();
+        assert!(
+            work_dir.to_str().is_some_and(str::is_ascii),
+            "{}",
+            work_dir.display()
+        );
+
+        let overrides = BTreeMap::from([(
+            conversion::COLLISION_SETTING.to_string(),
+            "false".to_string(),
+        )]);
+        let staged_exe = stage_converter_files(
+            &work_dir,
+            &settings,
+            &col,
+            &exe,
+            &zlib,
+            &yft,
+            Some(&ytd),
+            &overrides,
+        )
+        .unwrap();
+        let read = |name: &str| std::fs::read(work_dir.join(name)).unwrap();
+        assert_eq!(staged_exe, work_dir.join(CONVERTER_EXE));
+        assert_eq!(read("model.yft"), b"RSC7 yft");
+        assert_eq!(read("model.ytd"), b"RSC7 ytd");
+        assert_eq!(read(CONVERTER_DLL), b"dll");
+        assert_eq!(read("col_gen_materials.dat"), b"materials");
+        assert_eq!(
+            String::from_utf8(read("GeneralSettings.ini")).unwrap(),
+            "[General]\nExportCollision=false\n"
+        );
+
+        // The work dir sits outside `dir`, under the shared temp dir.
+        std::fs::remove_dir_all(&work_dir).unwrap();
+    }
+
+    /// A bridge that turns down `--server`, so parses run it one-shot, and
+    /// that blocks on the `gate` FIFO before writing a valid mesh. Every run
+    /// leaves a line in `runs`.
+    #[cfg(unix)]
+    fn gated_bridge(dir: &Path) -> (PathBuf, PathBuf, PathBuf) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let gate = dir.join("gate");
+        let runs = dir.join("runs");
+        let mesh = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/clmesh/valid.clmesh");
+        assert!(Command::new("mkfifo")
+            .arg(&gate)
+            .status()
+            .unwrap()
+            .success());
+        let bridge = dir.join("CodeWalkerBridge");
+        std::fs::write(
+            &bridge,
+            format!(
+                "#!/bin/sh\n\
+                 [ \"$1\" = --server ] && exit 2\n\
+                 echo run >> '{runs}'\n\
+                 cat '{gate}' > /dev/null\n\
+                 cp '{mesh}' \"$4\"\n\
+                 echo '{{}}'\n",
+                runs = runs.display(),
+                gate = gate.display(),
+                mesh = mesh.display(),
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&bridge, std::fs::Permissions::from_mode(0o755)).unwrap();
+        (bridge, gate, runs)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn concurrent_parses_run_the_bridge_once() {
+        use tauri::Listener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let (bridge, gate, runs) = gated_bridge(dir.path());
+        let cache_root = dir.path().join("cache");
+        let model = dir.path().join("adder.yft");
+        let mut header = rsc7::MAGIC.to_le_bytes().to_vec();
+        header.resize(64, 0);
+        std::fs::write(&model, header).unwrap();
+        let model = model.to_string_lossy().to_string();
+
+        let app = tauri::test::mock_builder()
+            .manage(ConversionTasks::default())
+            .manage(BridgeProcess::default())
+            .manage(tasks::TaskRegistry::default())
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .unwrap();
+        let (stage_tx, stages) = std::sync::mpsc::channel();
+        app.listen("parse:stage", move |event| {
+            let payload: serde_json::Value = serde_json::from_str(event.payload()).unwrap();
+            let _ = stage_tx.send(payload["stage"].as_str().unwrap().to_string());
+        });
+        let parse = || {
+            let app = app.handle().clone();
+            let (model, bridge, cache_root) = (model.clone(), bridge.clone(), cache_root.clone());
+            std::thread::spawn(move || {
+                let task = tasks::start(&app, "parse", Some(&model));
+                let resource = rsc7::sniff(Path::new(&model)).unwrap();
+                parse_with_bridge(
+                    BridgeModel::Yft,
+                    model,
+                    ParseOptions::default(),
+                    &app,
+                    &task,
+                    resource,
+                    &bridge,
+                    &cache_root,
+                )
+            })
+        };
+        let next_stage = || stages.recv_timeout(Duration::from_secs(10)).unwrap();
+
+        // The first parse holds the entry while its bridge sits on the gate,
+        // and the second one blocks behind it.
+        let first = parse();
+        while next_stage() != "parsing" {}
+        let second = parse();
+        assert_eq!(next_stage(), "waiting");
+        std::fs::write(&gate, b"go").unwrap();
+
+        let first = first.join().unwrap().unwrap();
+        let second = second.join().unwrap().unwrap();
+        assert!(!first.cached);
+        assert!(second.cached);
+        assert_eq!(second.mesh_path, first.mesh_path);
+        assert_eq!(std::fs::read_to_string(&runs).unwrap(), "run\n");
+    }
 }
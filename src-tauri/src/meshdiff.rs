@@ -0,0 +1,248 @@
+use std::{collections::BTreeSet, path::Path};
+
+use crate::{
+    clmesh::{self, BoundingBox},
+    dff, paths,
+};
+
+// Sorted vertex matching is only meaningful when the counts are close; past
+// this ratio the voxel comparison is used instead.
+const MAX_COUNT_RATIO: f64 = 1.1;
+// Above this many vertices per side sorting is skipped, as it needs a copy
+// of every position.
+const MAX_SORTED_VERTICES: usize = 4_000_000;
+// Cells per axis of the occupancy grid.
+const VOXEL_RESOLUTION: usize = 64;
+
+/// One side of a `diff_meshes` comparison.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeshSide {
+    pub path: String,
+    /// `clmesh` or `dff`.
+    pub format: &'static str,
+    pub vertex_count: usize,
+    pub triangle_count: usize,
+    pub bounding_box: Option<BoundingBox>,
+    pub materials: Vec<String>,
+}
+
+/// How far apart the two meshes' vertices are.
+#[derive(serde::Serialize)]
+#[serde(tag = "method", rename_all = "camelCase")]
+pub enum GeometryDiff {
+    /// Both position lists sorted on (x, y, z) and compared pairwise over
+    /// the shorter one.
+    #[serde(rename_all = "camelCase")]
+    SortedVertices {
+        matched: usize,
+        max_distance: f32,
+        mean_distance: f32,
+    },
+    /// Occupied cells of a grid over both bounding boxes.
+    #[serde(rename_all = "camelCase")]
+    Voxels {
+        resolution: usize,
+        shared: usize,
+        only_a: usize,
+        only_b: usize,
+    },
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeshDiff {
+    pub a: MeshSide,
+    pub b: MeshSide,
+    /// `b` minus `a`.
+    pub vertex_delta: i64,
+    pub triangle_delta: i64,
+    /// Per-axis change of `min` and `max`, when both meshes have vertices.
+    pub bounding_box_delta: Option<BoundingBox>,
+    /// Materials in `b` but not `a`.
+    pub materials_added: Vec<String>,
+    /// Materials in `a` but not `b`.
+    pub materials_removed: Vec<String>,
+    pub geometry: GeometryDiff,
+}
+
+fn load(path: &str) -> Result<(MeshSide, Vec<[f32; 3]>), String> {
+    let data = std::fs::read(paths::long(Path::new(path)))
+        .map_err(|e| format!("Failed to read {path}: {e}"))?;
+    if crate::has_extension(path, "clmesh") {
+        let inspection = clmesh::inspect_bytes(&data);
+        if let Some(error) = inspection.error {
+            return Err(format!("{path}: {error}"));
+        }
+        let side = MeshSide {
+            path: path.to_string(),
+            format: "clmesh",
+            vertex_count: inspection.vertex_count,
+            triangle_count: inspection.index_count / 3,
+            bounding_box: inspection.bounding_box,
+            materials: inspection.materials,
+        };
+        Ok((side, clmesh::read_positions(&data)?))
+    } else if crate::has_extension(path, "dff") {
+        let dff = dff::parse(&data).map_err(|e| format!("{path}: {e}"))?;
+        let mut positions = Vec::new();
+        let mut bounding_box = None;
+        let mut materials = BTreeSet::new();
+        let mut triangle_count = 0;
+        for geometry in &dff.geometries {
+            for p in geometry.positions.chunks_exact(3) {
+                BoundingBox::extend(&mut bounding_box, [p[0], p[1], p[2]]);
+                positions.push([p[0], p[1], p[2]]);
+            }
+            triangle_count += geometry.triangles.len();
+            materials.extend(
+                geometry
+                    .materials
+                    .iter()
+                    .map(|material| material.texture_name.clone().unwrap_or_default()),
+            );
+        }
+        let side = MeshSide {
+            path: path.to_string(),
+            format: "dff",
+            vertex_count: positions.len(),
+            triangle_count,
+            bounding_box,
+            materials: materials.into_iter().collect(),
+        };
+        Ok((side, positions))
+    } else {
+        Err(format!("{path} is neither a .clmesh nor a .dff"))
+    }
+}
+
+fn sorted_vertices(mut a: Vec<[f32; 3]>, mut b: Vec<[f32; 3]>) -> GeometryDiff {
+    let order = |p: &[f32; 3], q: &[f32; 3]| {
+        p[0].total_cmp(&q[0])
+            .then(p[1].total_cmp(&q[1]))
+            .then(p[2].total_cmp(&q[2]))
+    };
+    a.sort_unstable_by(order);
+    b.sort_unstable_by(order);
+    let (mut max_distance, mut total) = (0f32, 0f64);
+    for (p, q) in a.iter().zip(&b) {
+        let distance =
+            ((p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2) + (p[2] - q[2]).powi(2)).sqrt();
+        max_distance = max_distance.max(distance);
+        total += f64::from(distance);
+    }
+    let matched = a.len().min(b.len());
+    GeometryDiff::SortedVertices {
+        matched,
+        max_distance,
+        mean_distance: if matched == 0 {
+            0.0
+        } else {
+            (total / matched as f64) as f32
+        },
+    }
+}
+
+fn voxels(a: &[[f32; 3]], b: &[[f32; 3]]) -> GeometryDiff {
+    let mut bounds = None;
+    for p in a.iter().chain(b) {
+        BoundingBox::extend(&mut bounds, *p);
+    }
+    let cells = VOXEL_RESOLUTION.pow(3);
+    let occupancy = |positions: &[[f32; 3]]| {
+        let mut grid = vec![0u64; cells.div_ceil(64)];
+        let Some(bounds) = bounds else {
+            return grid;
+        };
+        for p in positions {
+            let mut cell = 0;
+            for (axis, value) in p.iter().enumerate() {
+                let extent = bounds.max[axis] - bounds.min[axis];
+                let t = if extent > 0.0 {
+                    (value - bounds.min[axis]) / extent
+                } else {
+                    0.0
+                };
+                let index = ((t * VOXEL_RESOLUTION as f32) as usize).min(VOXEL_RESOLUTION - 1);
+                cell = cell * VOXEL_RESOLUTION + index;
+            }
+            grid[cell / 64] |= 1 << (cell % 64);
+        }
+        grid
+    };
+    let (grid_a, grid_b) = (occupancy(a), occupancy(b));
+    let (mut shared, mut only_a, mut only_b) = (0, 0, 0);
+    for (x, y) in grid_a.iter().zip(&grid_b) {
+        shared += (x & y).count_ones() as usize;
+        only_a += (x & !y).count_ones() as usize;
+        only_b += (!x & y).count_ones() as usize;
+    }
+    GeometryDiff::Voxels {
+        resolution: VOXEL_RESOLUTION,
+        shared,
+        only_a,
+        only_b,
+    }
+}
+
+/// Compare two meshes, each a `.clmesh` or `.dff`.
+pub fn diff(path_a: &str, path_b: &str) -> Result<MeshDiff, String> {
+    let (a, positions_a) = load(path_a)?;
+    let (b, positions_b) = load(path_b)?;
+
+    let (small, large) = if a.vertex_count < b.vertex_count {
+        (a.vertex_count, b.vertex_count)
+    } else {
+        (b.vertex_count, a.vertex_count)
+    };
+    let geometry = if small > 0
+        && large <= MAX_SORTED_VERTICES
+        && large as f64 / small as f64 <= MAX_COUNT_RATIO
+    {
+        sorted_vertices(positions_a, positions_b)
+    } else {
+        voxels(&positions_a, &positions_b)
+    };
+
+    let bounding_box_delta = a
+        .bounding_box
+        .zip(b.bounding_box)
+        .map(|(a, b)| BoundingBox {
+            min: std::array::from_fn(|axis| b.min[axis] - a.min[axis]),
+            max: std::array::from_fn(|axis| b.max[axis] - a.max[axis]),
+        });
+    let materials_added = b
+        .materials
+        .iter()
+        .filter(|material| !a.materials.contains(material))
+        .cloned()
+        .collect();
+    let materials_removed = a
+        .materials
+        .iter()
+        .filter(|material| !b.materials.contains(material))
+        .cloned()
+        .collect();
+    Ok(MeshDiff {
+        vertex_delta: b.vertex_count as i64 - a.vertex_count as i64,
+        triangle_delta: b.triangle_count as i64 - a.triangle_count as i64,
+        bounding_box_delta,
+        materials_added,
+        materials_removed,
+        geometry,
+        a,
+        b,
+    })
+}
+
+/// Compare two converted meshes (`.clmesh` or `.dff`, in any mix): vertex
+/// and triangle deltas, bounding-box change, materials added and removed,
+/// and a coarse geometric difference. Meshes with similar vertex counts are
+/// matched vertex by vertex after sorting; otherwise occupancy of a
+/// 64x64x64 grid is compared.
+#[tauri::command]
+pub async fn diff_meshes(path_a: String, path_b: String) -> Result<MeshDiff, String> {
+    tauri::async_runtime::spawn_blocking(move || diff(&path_a, &path_b))
+        .await
+        .map_err(|e| format!("Failed to join mesh diff task: {e}"))?
+}
@@ -0,0 +1,999 @@
+use std::collections::HashMap;
+
+// Record types of the .NET BinaryFormatter (NRBF) format.
+const RECORD_STREAM_HEADER: u8 = 0;
+const RECORD_CLASS_WITH_ID: u8 = 1;
+const RECORD_SYSTEM_CLASS_WITH_MEMBERS: u8 = 2;
+const RECORD_CLASS_WITH_MEMBERS: u8 = 3;
+const RECORD_SYSTEM_CLASS_WITH_MEMBERS_AND_TYPES: u8 = 4;
+const RECORD_CLASS_WITH_MEMBERS_AND_TYPES: u8 = 5;
+const RECORD_OBJECT_STRING: u8 = 6;
+const RECORD_BINARY_ARRAY: u8 = 7;
+const RECORD_MEMBER_PRIMITIVE_TYPED: u8 = 8;
+const RECORD_MEMBER_REFERENCE: u8 = 9;
+const RECORD_OBJECT_NULL: u8 = 10;
+const RECORD_MESSAGE_END: u8 = 11;
+const RECORD_LIBRARY: u8 = 12;
+const RECORD_OBJECT_NULL_MULTIPLE_256: u8 = 13;
+const RECORD_OBJECT_NULL_MULTIPLE: u8 = 14;
+const RECORD_ARRAY_SINGLE_PRIMITIVE: u8 = 15;
+const RECORD_ARRAY_SINGLE_OBJECT: u8 = 16;
+const RECORD_ARRAY_SINGLE_STRING: u8 = 17;
+
+// Member and element types.
+const TYPE_PRIMITIVE: u8 = 0;
+const TYPE_SYSTEM_CLASS: u8 = 3;
+const TYPE_CLASS: u8 = 4;
+const TYPE_PRIMITIVE_ARRAY: u8 = 7;
+
+// Binary array shapes that carry lower bounds.
+const ARRAY_SINGLE_OFFSET: u8 = 3;
+
+// Guards against lengths from a damaged stream.
+const MAX_MEMBERS: usize = 4096;
+const MAX_ARRAY_LENGTH: usize = 1 << 24;
+// Objects written inline inside other objects; real documents nest a few
+// levels, anything deeper is a damaged or hostile stream.
+const MAX_DEPTH: usize = 64;
+
+/// The start of every stream: the header record with root id 1, header id
+/// -1 and format version 1.0.
+pub const STREAM_HEADER: [u8; 17] = [
+    RECORD_STREAM_HEADER,
+    1,
+    0,
+    0,
+    0,
+    0xFF,
+    0xFF,
+    0xFF,
+    0xFF,
+    1,
+    0,
+    0,
+    0,
+    0,
+    0,
+    0,
+    0,
+];
+
+/// A member or array element.
+#[derive(Clone)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    String(String),
+    /// An object, string or array recorded in the stream, by id. Value
+    /// types written inline, such as enums, are recorded under their own id
+    /// as well.
+    Reference(i32),
+    /// Floating-point numbers, which nothing here reads.
+    Float,
+}
+
+impl Value {
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// A class instance: its full .NET type name and members in record order.
+/// Inherited fields are named `Base+field`.
+pub struct Object {
+    pub class_name: String,
+    pub members: Vec<(String, Value)>,
+}
+
+impl Object {
+    pub fn member(&self, name: &str) -> Option<&Value> {
+        self.members
+            .iter()
+            .find(|(member, _)| member == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Anything the stream gives an id.
+pub enum Record {
+    Object(Object),
+    String(String),
+    Array(Vec<Value>),
+}
+
+/// A whole stream, up to its `MessageEnd` record.
+pub struct Stream {
+    pub root_id: i32,
+    pub records: HashMap<i32, Record>,
+    /// Offset just past the stream; PDN writes the layer pixels from here.
+    pub end: usize,
+}
+
+impl Stream {
+    pub fn root(&self) -> Option<&Object> {
+        match self.records.get(&self.root_id)? {
+            Record::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// The object a member refers to.
+    pub fn object<'a>(&'a self, value: &'a Value) -> Option<&'a Object> {
+        match value {
+            Value::Reference(id) => match self.records.get(id)? {
+                Record::Object(object) => Some(object),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The elements of the array a member refers to.
+    pub fn array(&self, value: &Value) -> Option<&[Value]> {
+        match self.records.get(&match value {
+            Value::Reference(id) => *id,
+            _ => return None,
+        })? {
+            Record::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// The string a member holds, inline or by reference.
+    pub fn string<'a>(&'a self, value: &'a Value) -> Option<&'a str> {
+        match value {
+            Value::String(text) => Some(text),
+            Value::Reference(id) => match self.records.get(id)? {
+                Record::String(text) => Some(text),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// The member list of a class, from its first record; later instances
+/// point back at it by id.
+#[derive(Clone)]
+struct ClassLayout {
+    class_name: String,
+    names: Vec<String>,
+    /// Member type and, for primitives and primitive arrays, the primitive
+    /// type.
+    types: Vec<(u8, u8)>,
+}
+
+/// A member list or array element read from the stream; runs of nulls
+/// count as several elements.
+enum Element {
+    Value(Value),
+    Nulls(usize),
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    layouts: HashMap<i32, ClassLayout>,
+    records: HashMap<i32, Record>,
+    /// How many inline records the current one sits inside.
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, what: &str) -> String {
+        format!(
+            "BinaryFormatter stream: {what} at offset {:#x}.",
+            self.offset
+        )
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| self.error(&format!("truncated (needs {len} more bytes)")))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.bytes()?))
+    }
+
+    fn count(&mut self, limit: usize, what: &str) -> Result<usize, String> {
+        let count = self.i32()?;
+        usize::try_from(count)
+            .ok()
+            .filter(|count| *count <= limit)
+            .ok_or_else(|| self.error(&format!("impossible {what} {count}")))
+    }
+
+    /// A string with a 7-bit variable-length size prefix.
+    fn string(&mut self) -> Result<String, String> {
+        let mut len = 0usize;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            len |= usize::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(String::from_utf8_lossy(self.take(len)?).to_string());
+            }
+        }
+        Err(self.error("string length runs past 5 bytes"))
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    fn primitive(&mut self, primitive: u8) -> Result<Value, String> {
+        Ok(match primitive {
+            1 => Value::Bool(self.u8()? != 0),
+            2 => Value::Int(i64::from(self.u8()?)),
+            // Char, as UTF-8: the lead byte gives the length.
+            3 => {
+                let lead = self.u8()?;
+                let extra = match lead {
+                    0xF0.. => 3,
+                    0xE0.. => 2,
+                    0xC0.. => 1,
+                    _ => 0,
+                };
+                let mut bytes = vec![lead];
+                bytes.extend_from_slice(self.take(extra)?);
+                Value::String(String::from_utf8_lossy(&bytes).to_string())
+            }
+            // Decimal, written as its text.
+            5 => Value::String(self.string()?),
+            6 => {
+                self.take(8)?;
+                Value::Float
+            }
+            7 => Value::Int(i64::from(i16::from_le_bytes(self.bytes()?))),
+            8 => Value::Int(i64::from(self.i32()?)),
+            // Int64, TimeSpan ticks, DateTime, and UInt64 kept as its bits.
+            9 | 12 | 13 | 16 => Value::Int(i64::from_le_bytes(self.bytes()?)),
+            10 => Value::Int(i64::from(self.u8()? as i8)),
+            11 => {
+                self.take(4)?;
+                Value::Float
+            }
+            14 => Value::Int(i64::from(u16::from_le_bytes(self.bytes()?))),
+            15 => Value::Int(i64::from(u32::from_le_bytes(self.bytes()?))),
+            17 => Value::Null,
+            18 => Value::String(self.string()?),
+            other => return Err(self.error(&format!("unknown primitive type {other}"))),
+        })
+    }
+
+    /// The class name and member names shared by every class record.
+    fn class_info(&mut self) -> Result<(i32, String, Vec<String>), String> {
+        let id = self.i32()?;
+        let class_name = self.string()?;
+        let count = self.count(MAX_MEMBERS, "member count")?;
+        let names = (0..count)
+            .map(|_| self.string())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((id, class_name, names))
+    }
+
+    /// The type and extra type info of `count` members or array elements.
+    fn type_info(&mut self, kinds: &[u8]) -> Result<Vec<(u8, u8)>, String> {
+        let mut types = Vec::with_capacity(kinds.len());
+        for kind in kinds {
+            let primitive = match *kind {
+                TYPE_PRIMITIVE | TYPE_PRIMITIVE_ARRAY => self.u8()?,
+                TYPE_SYSTEM_CLASS => {
+                    self.string()?;
+                    0
+                }
+                TYPE_CLASS => {
+                    self.string()?;
+                    self.i32()?;
+                    0
+                }
+                _ => 0,
+            };
+            types.push((*kind, primitive));
+        }
+        Ok(types)
+    }
+
+    /// A class record with its member types, after the record type byte;
+    /// returns the object's id and the object.
+    fn class_with_types(&mut self, record: u8) -> Result<(i32, Object), String> {
+        let (id, class_name, names) = self.class_info()?;
+        let kinds = self.take(names.len())?.to_vec();
+        let types = self.type_info(&kinds)?;
+        if record == RECORD_CLASS_WITH_MEMBERS_AND_TYPES {
+            self.i32()?; // library id
+        }
+        let layout = ClassLayout {
+            class_name,
+            names,
+            types,
+        };
+        self.layouts.insert(id, layout.clone());
+        let object = self.instance(&layout)?;
+        Ok((id, object))
+    }
+
+    fn instance(&mut self, layout: &ClassLayout) -> Result<Object, String> {
+        let mut members = Vec::with_capacity(layout.names.len());
+        let mut nulls = 0;
+        for (name, (kind, primitive)) in layout.names.iter().zip(&layout.types) {
+            let value = if nulls > 0 {
+                nulls -= 1;
+                Value::Null
+            } else if *kind == TYPE_PRIMITIVE {
+                self.primitive(*primitive)?
+            } else {
+                match self.element()? {
+                    Element::Value(value) => value,
+                    Element::Nulls(count) => {
+                        nulls = count.saturating_sub(1);
+                        Value::Null
+                    }
+                }
+            };
+            members.push((name.clone(), value));
+        }
+        Ok(Object {
+            class_name: layout.class_name.clone(),
+            members,
+        })
+    }
+
+    /// One record in a member or element position. Objects other than
+    /// strings are usually references to later records, but value types
+    /// come inline; they are recorded under their id and referred to like
+    /// the rest.
+    fn element(&mut self) -> Result<Element, String> {
+        let record = self.u8()?;
+        let value = match record {
+            RECORD_OBJECT_NULL => Value::Null,
+            RECORD_OBJECT_NULL_MULTIPLE_256 | RECORD_OBJECT_NULL_MULTIPLE => {
+                let count = if record == RECORD_OBJECT_NULL_MULTIPLE_256 {
+                    usize::from(self.u8()?)
+                } else {
+                    self.count(MAX_ARRAY_LENGTH, "null count")?
+                };
+                if count == 0 {
+                    return Err(self.error("empty run of nulls"));
+                }
+                return Ok(Element::Nulls(count));
+            }
+            RECORD_MEMBER_REFERENCE => Value::Reference(self.i32()?),
+            RECORD_MEMBER_PRIMITIVE_TYPED => {
+                let primitive = self.u8()?;
+                self.primitive(primitive)?
+            }
+            RECORD_OBJECT_STRING => {
+                let id = self.i32()?;
+                let text = self.string()?;
+                self.records.insert(id, Record::String(text.clone()));
+                Value::String(text)
+            }
+            _ => {
+                // Anything else with an id, written inline.
+                if self.depth >= MAX_DEPTH {
+                    return Err(self.error("records nested too deeply"));
+                }
+                self.offset -= 1;
+                self.depth += 1;
+                let inline = self.record();
+                self.depth -= 1;
+                match inline? {
+                    Some((id, inline)) => {
+                        self.records.insert(id, inline);
+                        Value::Reference(id)
+                    }
+                    None => return Err(self.error(&format!("record {record} out of place"))),
+                }
+            }
+        };
+        Ok(Element::Value(value))
+    }
+
+    fn elements(&mut self, length: usize, kind: u8, primitive: u8) -> Result<Vec<Value>, String> {
+        // Every element takes at least a byte, except runs of nulls.
+        let mut values = Vec::with_capacity(length.min(self.data.len() - self.offset));
+        while values.len() < length {
+            if kind == TYPE_PRIMITIVE {
+                values.push(self.primitive(primitive)?);
+                continue;
+            }
+            match self.element()? {
+                Element::Value(value) => values.push(value),
+                Element::Nulls(count) => {
+                    let count = count.min(length - values.len());
+                    values.extend(std::iter::repeat_n(Value::Null, count));
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    /// The next record that gives something an id, or `None` for the
+    /// bookkeeping records (libraries, the header, the end).
+    fn record(&mut self) -> Result<Option<(i32, Record)>, String> {
+        let record = self.u8()?;
+        Ok(Some(match record {
+            RECORD_STREAM_HEADER => {
+                self.take(16)?;
+                return Ok(None);
+            }
+            RECORD_LIBRARY => {
+                self.i32()?;
+                self.string()?;
+                return Ok(None);
+            }
+            RECORD_MESSAGE_END => return Ok(None),
+            RECORD_CLASS_WITH_ID => {
+                let id = self.i32()?;
+                let metadata_id = self.i32()?;
+                let layout =
+                    self.layouts.get(&metadata_id).cloned().ok_or_else(|| {
+                        self.error(&format!("unknown class layout {metadata_id}"))
+                    })?;
+                (id, Record::Object(self.instance(&layout)?))
+            }
+            RECORD_SYSTEM_CLASS_WITH_MEMBERS_AND_TYPES | RECORD_CLASS_WITH_MEMBERS_AND_TYPES => {
+                let (id, object) = self.class_with_types(record)?;
+                (id, Record::Object(object))
+            }
+            RECORD_SYSTEM_CLASS_WITH_MEMBERS | RECORD_CLASS_WITH_MEMBERS => {
+                return Err(self.error("members without types are not supported"));
+            }
+            RECORD_OBJECT_STRING => {
+                let id = self.i32()?;
+                (id, Record::String(self.string()?))
+            }
+            RECORD_ARRAY_SINGLE_PRIMITIVE => {
+                let id = self.i32()?;
+                let length = self.count(MAX_ARRAY_LENGTH, "array length")?;
+                let primitive = self.u8()?;
+                (
+                    id,
+                    Record::Array(self.elements(length, TYPE_PRIMITIVE, primitive)?),
+                )
+            }
+            RECORD_ARRAY_SINGLE_OBJECT | RECORD_ARRAY_SINGLE_STRING => {
+                let id = self.i32()?;
+                let length = self.count(MAX_ARRAY_LENGTH, "array length")?;
+                (id, Record::Array(self.elements(length, TYPE_CLASS, 0)?))
+            }
+            RECORD_BINARY_ARRAY => {
+                let id = self.i32()?;
+                let shape = self.u8()?;
+                let rank = self.count(32, "array rank")?;
+                let mut length = 1usize;
+                for _ in 0..rank {
+                    length = length
+                        .checked_mul(self.count(MAX_ARRAY_LENGTH, "array length")?)
+                        .filter(|length| *length <= MAX_ARRAY_LENGTH)
+                        .ok_or_else(|| self.error("array too large"))?;
+                }
+                if shape >= ARRAY_SINGLE_OFFSET {
+                    self.take(rank * 4)?; // lower bounds
+                }
+                let kind = self.u8()?;
+                let (kind, primitive) = self.type_info(&[kind])?[0];
+                (id, Record::Array(self.elements(length, kind, primitive)?))
+            }
+            other => return Err(self.error(&format!("unsupported record type {other}"))),
+        }))
+    }
+}
+
+/// Read the stream starting at `offset` (its header record) through its
+/// `MessageEnd`, keeping every object, string and array by id.
+pub fn parse(data: &[u8], offset: usize) -> Result<Stream, String> {
+    if !data[offset.min(data.len())..].starts_with(&STREAM_HEADER[..1]) {
+        return Err(format!(
+            "BinaryFormatter stream: no header record at offset {offset:#x}."
+        ));
+    }
+    let mut reader = Reader {
+        data,
+        offset: offset + 1,
+        layouts: HashMap::new(),
+        records: HashMap::new(),
+        depth: 0,
+    };
+    let root_id = reader.i32()?;
+    reader.take(12)?;
+    loop {
+        if reader.data.get(reader.offset) == Some(&RECORD_MESSAGE_END) {
+            reader.offset += 1;
+            break;
+        }
+        if let Some((id, record)) = reader.record()? {
+            reader.records.insert(id, record);
+        }
+    }
+    Ok(Stream {
+        root_id,
+        records: reader.records,
+        end: reader.offset,
+    })
+}
+
+/// Builds BinaryFormatter streams for the tests.
+#[cfg(test)]
+mod fixtures {
+    use super::*;
+
+    /// A member's type in a class record.
+    pub enum Member {
+        Primitive(u8),
+        String,
+        Object,
+        SystemClass(&'static str),
+        Class(&'static str, i32),
+        ObjectArray,
+        PrimitiveArray(u8),
+    }
+
+    /// Writes records one after another, starting with the stream header.
+    pub struct Writer {
+        pub bytes: Vec<u8>,
+    }
+
+    impl Writer {
+        pub fn new() -> Self {
+            Self {
+                bytes: STREAM_HEADER.to_vec(),
+            }
+        }
+
+        pub fn u8(&mut self, value: u8) -> &mut Self {
+            self.bytes.push(value);
+            self
+        }
+
+        pub fn i32(&mut self, value: i32) -> &mut Self {
+            self.bytes.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        pub fn raw(&mut self, bytes: &[u8]) -> &mut Self {
+            self.bytes.extend_from_slice(bytes);
+            self
+        }
+
+        pub fn string(&mut self, text: &str) -> &mut Self {
+            let mut len = text.len();
+            loop {
+                let byte = (len & 0x7F) as u8;
+                len >>= 7;
+                if len == 0 {
+                    self.bytes.push(byte);
+                    break;
+                }
+                self.bytes.push(byte | 0x80);
+            }
+            self.raw(text.as_bytes())
+        }
+
+        pub fn library(&mut self, id: i32, name: &str) -> &mut Self {
+            self.u8(RECORD_LIBRARY).i32(id).string(name)
+        }
+
+        /// A `ClassWithMembersAndTypes` record (or the `SystemClass...`
+        /// one when `library` is `None`), up to the member values.
+        pub fn class(
+            &mut self,
+            id: i32,
+            class_name: &str,
+            members: &[(&str, Member)],
+            library: Option<i32>,
+        ) -> &mut Self {
+            self.u8(match library {
+                Some(_) => RECORD_CLASS_WITH_MEMBERS_AND_TYPES,
+                None => RECORD_SYSTEM_CLASS_WITH_MEMBERS_AND_TYPES,
+            })
+            .i32(id)
+            .string(class_name)
+            .i32(members.len() as i32);
+            for (name, _) in members {
+                self.string(name);
+            }
+            for (_, member) in members {
+                self.u8(match member {
+                    Member::Primitive(_) => TYPE_PRIMITIVE,
+                    Member::String => 1,
+                    Member::Object => 2,
+                    Member::SystemClass(_) => TYPE_SYSTEM_CLASS,
+                    Member::Class(..) => TYPE_CLASS,
+                    Member::ObjectArray => 5,
+                    Member::PrimitiveArray(_) => TYPE_PRIMITIVE_ARRAY,
+                });
+            }
+            for (_, member) in members {
+                match member {
+                    Member::Primitive(primitive) | Member::PrimitiveArray(primitive) => {
+                        self.u8(*primitive);
+                    }
+                    Member::SystemClass(name) => {
+                        self.string(name);
+                    }
+                    Member::Class(name, library) => {
+                        self.string(name).i32(*library);
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(library) = library {
+                self.i32(library);
+            }
+            self
+        }
+
+        /// A `ClassWithId` record reusing the layout of `metadata_id`, up to
+        /// the member values.
+        pub fn class_with_id(&mut self, id: i32, metadata_id: i32) -> &mut Self {
+            self.u8(RECORD_CLASS_WITH_ID).i32(id).i32(metadata_id)
+        }
+
+        pub fn object_string(&mut self, id: i32, text: &str) -> &mut Self {
+            self.u8(RECORD_OBJECT_STRING).i32(id).string(text)
+        }
+
+        pub fn reference(&mut self, id: i32) -> &mut Self {
+            self.u8(RECORD_MEMBER_REFERENCE).i32(id)
+        }
+
+        pub fn null(&mut self) -> &mut Self {
+            self.u8(RECORD_OBJECT_NULL)
+        }
+
+        pub fn object_array(&mut self, id: i32, length: i32) -> &mut Self {
+            self.u8(RECORD_ARRAY_SINGLE_OBJECT).i32(id).i32(length)
+        }
+
+        pub fn end(&mut self) -> Vec<u8> {
+            self.u8(RECORD_MESSAGE_END);
+            std::mem::take(&mut self.bytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::{Member, Writer};
+    use super::*;
+
+    fn object(stream: &Stream, id: i32) -> &Object {
+        match stream.records.get(&id) {
+            Some(Record::Object(object)) => object,
+            _ => panic!("no object {id}"),
+        }
+    }
+
+    fn array(stream: &Stream, id: i32) -> &[Value] {
+        stream.array(&Value::Reference(id)).expect("array")
+    }
+
+    /// One of everything the reader supports, rooted at object 1.
+    fn every_record() -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer
+            .library(2, "Lib")
+            .class(
+                1,
+                "Root",
+                &[
+                    ("flag", Member::Primitive(1)),
+                    ("byte", Member::Primitive(2)),
+                    ("char", Member::Primitive(3)),
+                    ("double", Member::Primitive(6)),
+                    ("short", Member::Primitive(7)),
+                    ("int", Member::Primitive(8)),
+                    ("long", Member::Primitive(9)),
+                    ("sbyte", Member::Primitive(10)),
+                    ("single", Member::Primitive(11)),
+                    ("ushort", Member::Primitive(14)),
+                    ("uint", Member::Primitive(15)),
+                    ("name", Member::String),
+                    ("boxed", Member::Object),
+                    ("version", Member::SystemClass("System.Version")),
+                    ("child", Member::Class("Child", 2)),
+                    ("sibling", Member::Class("Child", 2)),
+                    ("a", Member::Object),
+                    ("b", Member::Object),
+                    ("c", Member::Object),
+                    ("d", Member::Object),
+                    ("e", Member::Object),
+                    ("bytes", Member::PrimitiveArray(2)),
+                    ("items", Member::ObjectArray),
+                    ("names", Member::Object),
+                    ("grid", Member::Object),
+                ],
+                Some(2),
+            )
+            .u8(1)
+            .u8(200)
+            .raw("é".as_bytes())
+            .raw(&1.5f64.to_le_bytes())
+            .raw(&(-2i16).to_le_bytes())
+            .i32(-3)
+            .raw(&(1i64 << 40).to_le_bytes())
+            .u8(0xFF)
+            .raw(&2.5f32.to_le_bytes())
+            .raw(&65535u16.to_le_bytes())
+            .raw(&u32::MAX.to_le_bytes())
+            .object_string(3, "root");
+        // A boxed primitive.
+        writer.u8(RECORD_MEMBER_PRIMITIVE_TYPED).u8(8).i32(42);
+        // An inline system class.
+        writer
+            .class(
+                4,
+                "System.Version",
+                &[
+                    ("_Major", Member::Primitive(8)),
+                    ("_Minor", Member::Primitive(8)),
+                ],
+                None,
+            )
+            .i32(4)
+            .i32(5);
+        // An inline class, and a second instance reusing its layout.
+        writer
+            .class(5, "Child", &[("value", Member::Primitive(8))], Some(2))
+            .i32(7);
+        writer.class_with_id(6, 5).i32(8);
+        // Members a..e: a reference, nulls, and runs of nulls covering the
+        // rest.
+        writer.reference(5).null();
+        writer.u8(RECORD_OBJECT_NULL_MULTIPLE_256).u8(2);
+        writer.u8(RECORD_OBJECT_NULL_MULTIPLE).i32(1);
+        // bytes, items, names and grid, all by reference.
+        writer.reference(7).reference(8).reference(9).reference(10);
+
+        writer
+            .u8(RECORD_ARRAY_SINGLE_PRIMITIVE)
+            .i32(7)
+            .i32(3)
+            .u8(2)
+            .raw(&[1, 2, 3]);
+        writer.object_array(8, 4).reference(3).null();
+        writer.u8(RECORD_OBJECT_NULL_MULTIPLE_256).u8(2);
+        writer
+            .u8(RECORD_ARRAY_SINGLE_STRING)
+            .i32(9)
+            .i32(2)
+            .object_string(11, "x")
+            .reference(11);
+        // A 2x2 rectangular array of ints with lower bounds.
+        writer
+            .u8(RECORD_BINARY_ARRAY)
+            .i32(10)
+            .u8(ARRAY_SINGLE_OFFSET + 2)
+            .i32(2)
+            .i32(2)
+            .i32(2)
+            .i32(0)
+            .i32(0)
+            .u8(TYPE_PRIMITIVE)
+            .u8(8);
+        for value in 1..=4 {
+            writer.i32(value);
+        }
+        writer.end()
+    }
+
+    #[test]
+    fn reads_every_supported_record() {
+        let data = every_record();
+        let stream = parse(&data, 0).expect("parse");
+        assert_eq!(stream.end, data.len());
+        let root = stream.root().expect("root");
+        assert_eq!(root.class_name, "Root");
+
+        let int = |name: &str| root.member(name).and_then(Value::as_int);
+        assert_eq!(root.member("flag").and_then(Value::as_bool), Some(true));
+        assert_eq!(int("byte"), Some(200));
+        assert!(matches!(root.member("char"), Some(Value::String(c)) if c == "é"));
+        assert!(matches!(root.member("double"), Some(Value::Float)));
+        assert_eq!(int("short"), Some(-2));
+        assert_eq!(int("int"), Some(-3));
+        assert_eq!(int("long"), Some(1 << 40));
+        assert_eq!(int("sbyte"), Some(-1));
+        assert!(matches!(root.member("single"), Some(Value::Float)));
+        assert_eq!(int("ushort"), Some(65535));
+        assert_eq!(int("uint"), Some(i64::from(u32::MAX)));
+        assert_eq!(stream.string(root.member("name").unwrap()), Some("root"));
+        assert_eq!(int("boxed"), Some(42));
+
+        let version = stream.object(root.member("version").unwrap()).unwrap();
+        assert_eq!(version.class_name, "System.Version");
+        assert_eq!(version.member("_Minor").and_then(Value::as_int), Some(5));
+        let child = stream.object(root.member("child").unwrap()).unwrap();
+        assert_eq!(child.member("value").and_then(Value::as_int), Some(7));
+        let sibling = stream.object(root.member("sibling").unwrap()).unwrap();
+        assert_eq!(sibling.class_name, "Child");
+        assert_eq!(sibling.member("value").and_then(Value::as_int), Some(8));
+        assert_eq!(object(&stream, 5).class_name, "Child");
+
+        assert!(matches!(root.member("a"), Some(Value::Reference(5))));
+        for name in ["b", "c", "d", "e"] {
+            assert!(matches!(root.member(name), Some(Value::Null)), "{name}");
+        }
+
+        let bytes = array(&stream, 7);
+        assert_eq!(
+            bytes.iter().filter_map(Value::as_int).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        let items = array(&stream, 8);
+        assert_eq!(items.len(), 4);
+        assert_eq!(stream.string(&items[0]), Some("root"));
+        assert!(items[1..].iter().all(|item| matches!(item, Value::Null)));
+        let names = array(&stream, 9);
+        assert!(names.iter().all(|name| stream.string(name) == Some("x")));
+        let grid = array(&stream, 10);
+        assert_eq!(
+            grid.iter().filter_map(Value::as_int).collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn starts_at_an_offset_and_stops_at_message_end() {
+        let mut data = b"PDN3junk".to_vec();
+        data.extend(every_record());
+        let end = data.len();
+        data.extend_from_slice(b"pixels");
+        assert_eq!(parse(&data, 8).expect("parse").end, end);
+        assert!(parse(&data, 4).is_err());
+        assert!(parse(&data, data.len() + 10).is_err());
+    }
+
+    #[test]
+    fn every_truncation_is_an_error() {
+        let data = every_record();
+        for len in 0..data.len() {
+            assert!(parse(&data[..len], 0).is_err(), "truncated to {len}");
+        }
+    }
+
+    #[test]
+    fn garbage_is_an_error_or_a_stream_but_never_a_panic() {
+        let valid = every_record();
+        let mut seed = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        for _ in 0..2000 {
+            // Random bytes after a valid header, and the valid stream with
+            // a few bytes flipped.
+            let mut data = STREAM_HEADER.to_vec();
+            data.extend((0..next() % 256).map(|_| next() as u8));
+            let _ = parse(&data, 0);
+
+            let mut data = valid.clone();
+            for _ in 0..4 {
+                let at = STREAM_HEADER.len() + next() as usize % (data.len() - STREAM_HEADER.len());
+                data[at] ^= next() as u8 | 1;
+            }
+            let _ = parse(&data, 0);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_records() {
+        let with_record = |bytes: &[u8]| {
+            let mut writer = Writer::new();
+            writer.raw(bytes);
+            writer.end()
+        };
+        // Members without types.
+        for record in [RECORD_SYSTEM_CLASS_WITH_MEMBERS, RECORD_CLASS_WITH_MEMBERS] {
+            let mut writer = Writer::new();
+            writer.u8(record).i32(1).string("Untyped").i32(0);
+            assert!(parse(&writer.end(), 0).is_err());
+        }
+        // An unknown record type, an unknown primitive and a class layout
+        // that was never defined.
+        assert!(parse(&with_record(&[0x30]), 0).is_err());
+        let mut writer = Writer::new();
+        writer.u8(RECORD_MEMBER_PRIMITIVE_TYPED);
+        assert!(parse(&writer.end(), 0).is_err());
+        let mut writer = Writer::new();
+        writer.u8(RECORD_MEMBER_PRIMITIVE_TYPED).u8(4).i32(0);
+        assert!(parse(&writer.end(), 0).is_err());
+        let mut writer = Writer::new();
+        writer.class_with_id(1, 99);
+        assert!(parse(&writer.end(), 0).is_err());
+        // Negative and oversized lengths.
+        let mut writer = Writer::new();
+        writer.object_array(1, -1);
+        assert!(parse(&writer.end(), 0).is_err());
+        let mut writer = Writer::new();
+        writer.object_array(1, i32::MAX);
+        assert!(parse(&writer.end(), 0).is_err());
+        // A string length prefix that never ends.
+        let mut writer = Writer::new();
+        writer.u8(RECORD_OBJECT_STRING).i32(1).raw(&[0xFF; 6]);
+        assert!(parse(&writer.end(), 0).is_err());
+    }
+
+    #[test]
+    fn rejects_empty_runs_of_nulls() {
+        for run in [
+            vec![RECORD_OBJECT_NULL_MULTIPLE_256, 0],
+            [
+                vec![RECORD_OBJECT_NULL_MULTIPLE],
+                0i32.to_le_bytes().to_vec(),
+            ]
+            .concat(),
+        ] {
+            // As a member and as an array element.
+            let mut writer = Writer::new();
+            writer
+                .library(2, "Lib")
+                .class(
+                    1,
+                    "Holder",
+                    &[("a", Member::Object), ("b", Member::Primitive(8))],
+                    Some(2),
+                )
+                .raw(&run)
+                .i32(5);
+            let error = parse(&writer.end(), 0).err().expect("member run");
+            assert!(error.contains("empty run of nulls"), "{error}");
+
+            let mut writer = Writer::new();
+            writer.object_array(1, 2).raw(&run).null();
+            assert!(parse(&writer.end(), 0).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let nest = |depth: i32| {
+            let mut writer = Writer::new();
+            writer.library(2, "Lib").class(
+                1,
+                "Node",
+                &[("child", Member::Class("Node", 2))],
+                Some(2),
+            );
+            for id in 0..depth {
+                writer.class_with_id(id + 10, 1);
+            }
+            writer.null();
+            writer.end()
+        };
+        let stream = parse(&nest(MAX_DEPTH as i32), 0).expect("nesting at the limit");
+        assert_eq!(stream.records.len(), MAX_DEPTH + 1);
+        let error = parse(&nest(MAX_DEPTH as i32 + 1), 0)
+            .err()
+            .expect("nesting past the limit");
+        assert!(error.contains("nested too deeply"), "{error}");
+        assert!(parse(&nest(100_000), 0).is_err());
+    }
+}
@@ -0,0 +1,533 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use crate::{clmesh, dff, paths, textures};
+
+// Meshes whose material has no texture share this material.
+const UNTEXTURED_MATERIAL: &str = "untextured";
+
+/// What `export_dff_to_obj` or `export_clmesh_obj` wrote.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjExport {
+    pub obj_path: String,
+    /// Absent when no `.mtl` was asked for.
+    pub mtl_path: Option<String>,
+    /// One object and group per mesh (a geometry and material of a DFF).
+    pub groups: usize,
+    pub vertices: usize,
+    pub normals: usize,
+    pub uvs: usize,
+    pub faces: usize,
+    pub materials: usize,
+    /// Textures the `.mtl` names but no image was found for.
+    pub missing_textures: Vec<String>,
+}
+
+/// One `.mtl` entry, by texture name (empty when untextured).
+struct Material {
+    color: [u8; 4],
+    /// What `map_Kd` points at.
+    map: String,
+    found: bool,
+}
+
+/// GTA models are Z-up; OBJ importers (Blender's included) expect Y-up and
+/// rotate back on import.
+fn to_y_up([x, y, z]: [f32; 3]) -> [f32; 3] {
+    [x, z, -y]
+}
+
+fn material_name(texture: &str) -> &str {
+    if texture.is_empty() {
+        UNTEXTURED_MATERIAL
+    } else {
+        texture
+    }
+}
+
+/// Stream `meshes` into `out`, one `o`/`g` pair and `usemtl` per mesh.
+/// Returns the vertex, normal, UV and face counts.
+fn write_meshes(
+    out: &mut impl std::io::Write,
+    meshes: &[clmesh::Mesh],
+    mtllib: Option<&str>,
+    z_up: bool,
+) -> std::io::Result<[usize; 4]> {
+    let axes = |p: &[f32]| {
+        let p = [p[0], p[1], p[2]];
+        if z_up {
+            p
+        } else {
+            to_y_up(p)
+        }
+    };
+
+    writeln!(out, "# Exported by Cortex Studio")?;
+    if let Some(mtllib) = mtllib {
+        writeln!(out, "mtllib {mtllib}")?;
+    }
+    let (mut vertices, mut normals, mut uvs, mut faces) = (0, 0, 0, 0);
+    for mesh in meshes {
+        writeln!(out, "o {}", mesh.name)?;
+        writeln!(out, "g {}", mesh.name)?;
+        writeln!(out, "usemtl {}", material_name(&mesh.material))?;
+        for p in mesh.positions.chunks_exact(3) {
+            let [x, y, z] = axes(p);
+            writeln!(out, "v {x} {y} {z}")?;
+        }
+        if let Some(mesh_normals) = &mesh.normals {
+            for n in mesh_normals.chunks_exact(3) {
+                let [x, y, z] = axes(n);
+                writeln!(out, "vn {x} {y} {z}")?;
+            }
+        }
+        if let Some(mesh_uvs) = &mesh.uvs {
+            // RenderWare's V runs down the texture, OBJ's up.
+            for uv in mesh_uvs.chunks_exact(2) {
+                writeln!(out, "vt {} {}", uv[0], 1.0 - uv[1])?;
+            }
+        }
+
+        // OBJ indices are 1-based and shared across the whole file.
+        let has_uvs = mesh.uvs.is_some();
+        let has_normals = mesh.normals.is_some();
+        for triangle in mesh.indices.chunks_exact(3) {
+            write!(out, "f")?;
+            for index in triangle {
+                let index = *index as usize + 1;
+                let v = vertices + index;
+                match (has_uvs, has_normals) {
+                    (true, true) => write!(out, " {v}/{}/{}", uvs + index, normals + index)?,
+                    (true, false) => write!(out, " {v}/{}", uvs + index)?,
+                    (false, true) => write!(out, " {v}//{}", normals + index)?,
+                    (false, false) => write!(out, " {v}")?,
+                }
+            }
+            writeln!(out)?;
+            faces += 1;
+        }
+
+        let count = mesh.positions.len() / 3;
+        vertices += count;
+        if has_normals {
+            normals += count;
+        }
+        if has_uvs {
+            uvs += count;
+        }
+    }
+    out.flush()?;
+    Ok([vertices, normals, uvs, faces])
+}
+
+/// Write `meshes` as an `.obj` at `out_path`, with `materials` (by texture
+/// name) in a `.mtl` next to it when given.
+fn write(
+    meshes: &[clmesh::Mesh],
+    materials: Option<&BTreeMap<String, Material>>,
+    out_path: &Path,
+    z_up: bool,
+) -> Result<ObjExport, String> {
+    let mtl_path = out_path.with_extension("mtl");
+    let mtllib = mtl_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|_| materials.is_some());
+
+    if let Some(parent) = out_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(paths::long(parent))
+            .map_err(|e| format!("Failed to create output dir: {e}"))?;
+    }
+    let file = std::fs::File::create(paths::long(out_path))
+        .map_err(|e| format!("Failed to write OBJ: {e}"))?;
+    let [vertices, normals, uvs, faces] =
+        write_meshes(&mut BufWriter::new(file), meshes, mtllib.as_deref(), z_up)
+            .map_err(|e| format!("Failed to write OBJ: {e}"))?;
+
+    let mut missing_textures = Vec::new();
+    if let Some(materials) = materials {
+        let mut mtl = String::new();
+        let _ = writeln!(mtl, "# Exported by Cortex Studio");
+        for (texture, material) in materials {
+            let [r, g, b, a] = material.color;
+            let _ = writeln!(mtl, "\nnewmtl {}", material_name(texture));
+            let _ = writeln!(
+                mtl,
+                "Kd {:.4} {:.4} {:.4}",
+                f32::from(r) / 255.0,
+                f32::from(g) / 255.0,
+                f32::from(b) / 255.0
+            );
+            let _ = writeln!(mtl, "d {:.4}", f32::from(a) / 255.0);
+            if !texture.is_empty() {
+                let _ = writeln!(mtl, "map_Kd {}", material.map);
+                if !material.found {
+                    missing_textures.push(texture.clone());
+                }
+            }
+        }
+        std::fs::write(paths::long(&mtl_path), mtl)
+            .map_err(|e| format!("Failed to write MTL: {e}"))?;
+    }
+
+    Ok(ObjExport {
+        obj_path: out_path.to_string_lossy().to_string(),
+        mtl_path: materials.map(|_| mtl_path.to_string_lossy().to_string()),
+        groups: meshes.len(),
+        vertices,
+        normals,
+        uvs,
+        faces,
+        materials: materials.map_or(0, BTreeMap::len),
+        missing_textures,
+    })
+}
+
+/// Write `dff` as an `.obj` at `out_path` and a `.mtl` next to it. Groups
+/// and materials follow `dff::to_meshes`; each textured material points at
+/// `<texture>.png` beside the files.
+pub fn export(dff: &dff::Dff, out_path: &Path, z_up: bool) -> Result<ObjExport, String> {
+    let base_name = out_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "model".to_string());
+    let meshes = dff::to_meshes(dff, &base_name);
+    let out_dir = out_path.parent().unwrap_or(Path::new(""));
+
+    // The first material using each texture decides its color.
+    let mut materials = BTreeMap::new();
+    for mesh in &meshes {
+        let color = dff
+            .geometries
+            .iter()
+            .flat_map(|geometry| &geometry.materials)
+            .find(|material| material.texture_name.as_deref().unwrap_or("") == mesh.material)
+            .map(|material| material.color)
+            .unwrap_or([255; 4]);
+        let map = format!("{}.png", mesh.material);
+        let found = paths::long(&out_dir.join(&map)).is_file();
+        materials
+            .entry(mesh.material.clone())
+            .or_insert(Material { color, map, found });
+    }
+    write(&meshes, Some(&materials), out_path, z_up)
+}
+
+/// Write the meshes of a cached `.clmesh` as an `.obj` at `out_path`, with
+/// a `.mtl` when `include_mtl`. Each textured material points at its image
+/// from the parse's `textureFiles`, or `<texture>.png` beside the mesh or
+/// the output.
+pub fn export_clmesh(
+    mesh_path: &Path,
+    out_path: &Path,
+    include_mtl: bool,
+) -> Result<ObjExport, String> {
+    let data = std::fs::read(paths::long(mesh_path))
+        .map_err(|e| format!("Failed to read {}: {e}", mesh_path.display()))?;
+    let meshes = clmesh::read(&data)?;
+    if !include_mtl {
+        return write(&meshes, None, out_path, false);
+    }
+
+    let mesh_dir = mesh_path.parent().unwrap_or(Path::new(""));
+    let out_dir = out_path.parent().unwrap_or(Path::new(""));
+    let recorded = textures::recorded_files(mesh_dir);
+    let mut materials = BTreeMap::new();
+    for mesh in &meshes {
+        if materials.contains_key(&mesh.material) {
+            continue;
+        }
+        let file = textures::find_file(&mesh.material, &recorded, &[mesh_dir, out_dir])
+            .filter(|_| !mesh.material.is_empty());
+        // Images beside the output stay relative so the three files can move
+        // together.
+        let map = match &file {
+            Some(file) if file.parent() == Some(out_dir) => file
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            Some(file) => file.to_string_lossy().to_string(),
+            None => format!("{}.png", mesh.material),
+        };
+        let material = Material {
+            color: [255; 4],
+            map,
+            found: file.is_some(),
+        };
+        materials.insert(mesh.material.clone(), material);
+    }
+    write(&meshes, Some(&materials), out_path, false)
+}
+
+/// A mesh per group of an imported `.obj`, in GTA's axes.
+pub struct ObjImport {
+    pub meshes: Vec<clmesh::Mesh>,
+    pub vertex_count: usize,
+    pub triangle_count: usize,
+    /// How many faces had no normals in the file and got flat ones.
+    pub flat_normals: usize,
+}
+
+/// A group being read: its vertices by `v/vt/vn` triple, or by face for
+/// flat-shaded ones.
+#[derive(Default)]
+struct ImportGroup {
+    name: String,
+    vertices: std::collections::HashMap<(usize, Option<usize>, Option<usize>), u32>,
+    positions: Vec<f32>,
+    normals: Vec<f32>,
+    uvs: Vec<f32>,
+    has_uvs: bool,
+    indices: Vec<u32>,
+}
+
+impl ImportGroup {
+    fn push(&mut self, position: [f32; 3], normal: [f32; 3], uv: Option<[f32; 2]>) -> u32 {
+        self.positions.extend_from_slice(&position);
+        self.normals.extend_from_slice(&normal);
+        self.uvs.extend_from_slice(&uv.unwrap_or_default());
+        self.has_uvs |= uv.is_some();
+        (self.positions.len() / 3 - 1) as u32
+    }
+}
+
+/// Resolve an OBJ index (1-based, or negative counting back from the last
+/// element read so far) against `len` elements.
+fn obj_index(token: &str, len: usize, line: usize) -> Result<usize, String> {
+    let index = token
+        .parse::<i64>()
+        .map_err(|_| format!("Line {line}: `{token}` is not an OBJ index."))?;
+    let resolved = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+    usize::try_from(resolved)
+        .ok()
+        .filter(|resolved| *resolved < len)
+        .ok_or_else(|| format!("Line {line}: index {index} is out of range ({len} defined)."))
+}
+
+/// Read an `.obj`: positions, normals, UVs and faces, with polygons fanned
+/// into triangles and every `g`/`o` group its own mesh (faces before any
+/// group take `default_name`). Materials are ignored. Faces without
+/// normals get their polygon's flat normal. Axes and V are flipped back
+/// from what `export` writes, so a round trip lines up with the original.
+pub fn import(data: &str, default_name: &str) -> Result<ObjImport, String> {
+    let (mut positions, mut normals, mut uvs) = (Vec::new(), Vec::new(), Vec::new());
+    let mut groups: Vec<ImportGroup> = Vec::new();
+    let mut current = None;
+    let mut flat_normals = 0;
+
+    for (number, line) in data.lines().enumerate() {
+        let number = number + 1;
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let mut floats = |count: usize| -> Result<Vec<f32>, String> {
+            let values = tokens
+                .by_ref()
+                .take(count)
+                .map(|token| token.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Line {number}: {e}"))?;
+            if values.len() < count {
+                return Err(format!("Line {number}: `{keyword}` needs {count} numbers."));
+            }
+            Ok(values)
+        };
+        match keyword {
+            "v" => {
+                let v = floats(3)?;
+                positions.push([v[0], -v[2], v[1]]);
+            }
+            "vn" => {
+                let n = floats(3)?;
+                normals.push([n[0], -n[2], n[1]]);
+            }
+            // A lone U is allowed; V then defaults to 0.
+            "vt" => {
+                let u = floats(1)?[0];
+                let v = tokens
+                    .next()
+                    .and_then(|v| v.parse::<f32>().ok())
+                    .unwrap_or(0.0);
+                uvs.push([u, 1.0 - v]);
+            }
+            "g" | "o" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                let name = if name.is_empty() {
+                    default_name.to_string()
+                } else {
+                    name
+                };
+                current = Some(match groups.iter().position(|group| group.name == name) {
+                    Some(index) => index,
+                    None => {
+                        groups.push(ImportGroup {
+                            name,
+                            ..Default::default()
+                        });
+                        groups.len() - 1
+                    }
+                });
+            }
+            "f" => {
+                let mut corners = Vec::new();
+                for corner in tokens {
+                    let mut parts = corner.split('/');
+                    let v = obj_index(parts.next().unwrap_or(""), positions.len(), number)?;
+                    let vt = match parts.next().filter(|part| !part.is_empty()) {
+                        Some(vt) => Some(obj_index(vt, uvs.len(), number)?),
+                        None => None,
+                    };
+                    let vn = match parts.next().filter(|part| !part.is_empty()) {
+                        Some(vn) => Some(obj_index(vn, normals.len(), number)?),
+                        None => None,
+                    };
+                    corners.push((v, vt, vn));
+                }
+                if corners.len() < 3 {
+                    return Err(format!("Line {number}: a face needs at least 3 corners."));
+                }
+                let index = *current.get_or_insert_with(|| {
+                    groups.push(ImportGroup {
+                        name: default_name.to_string(),
+                        ..Default::default()
+                    });
+                    groups.len() - 1
+                });
+                let group = &mut groups[index];
+
+                // Newell's method, so concave and slightly non-planar
+                // polygons still face the right way.
+                let flat = corners.iter().all(|(_, _, vn)| vn.is_none()).then(|| {
+                    let mut normal = [0.0f32; 3];
+                    for (i, (a, _, _)) in corners.iter().enumerate() {
+                        let [ax, ay, az] = positions[*a];
+                        let [bx, by, bz] = positions[corners[(i + 1) % corners.len()].0];
+                        normal[0] += (ay - by) * (az + bz);
+                        normal[1] += (az - bz) * (ax + bx);
+                        normal[2] += (ax - bx) * (ay + by);
+                    }
+                    let length = normal.iter().map(|c| c * c).sum::<f32>().sqrt();
+                    if length > 0.0 {
+                        normal.map(|c| c / length)
+                    } else {
+                        [0.0, 0.0, 1.0]
+                    }
+                });
+                let mut indices = Vec::with_capacity(corners.len());
+                for (v, vt, vn) in corners {
+                    let uv = vt.map(|vt| uvs[vt]);
+                    let index = match flat {
+                        Some(normal) => group.push(positions[v], normal, uv),
+                        None => match group.vertices.get(&(v, vt, vn)) {
+                            Some(index) => *index,
+                            None => {
+                                let normal = vn.map(|vn| normals[vn]).unwrap_or_default();
+                                let index = group.push(positions[v], normal, uv);
+                                group.vertices.insert((v, vt, vn), index);
+                                index
+                            }
+                        },
+                    };
+                    indices.push(index);
+                }
+                if flat.is_some() {
+                    flat_normals += 1;
+                }
+                for i in 1..indices.len() - 1 {
+                    group
+                        .indices
+                        .extend_from_slice(&[indices[0], indices[i], indices[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let meshes = groups
+        .into_iter()
+        .filter(|group| !group.indices.is_empty())
+        .map(|group| clmesh::Mesh {
+            name: group.name,
+            material: String::new(),
+            positions: group.positions,
+            normals: Some(group.normals),
+            uvs: group.has_uvs.then_some(group.uvs),
+            colors: None,
+            indices: group.indices,
+        })
+        .collect::<Vec<_>>();
+    if meshes.is_empty() {
+        return Err("The OBJ has no faces.".to_string());
+    }
+    Ok(ObjImport {
+        vertex_count: meshes.iter().map(|mesh| mesh.positions.len() / 3).sum(),
+        triangle_count: meshes.iter().map(|mesh| mesh.indices.len() / 3).sum(),
+        meshes,
+        flat_normals,
+    })
+}
+
+/// Export a .dff (such as the cached `model.dff` from `convert_yft`) to an
+/// .obj at `out_path` plus a sidecar .mtl naming the material textures,
+/// with one group per geometry and material. Strips are written as
+/// triangle lists. The axes are converted to OBJ's Y-up unless `z_up`
+/// keeps GTA's own. Returns the counts written.
+#[tauri::command]
+pub async fn export_dff_to_obj(
+    dff_path: String,
+    out_path: String,
+    z_up: Option<bool>,
+) -> Result<ObjExport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&dff_path, "dff") {
+            return Err("Only .dff files are supported by export_dff_to_obj".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&dff_path)))
+            .map_err(|e| format!("Failed to read DFF: {e}"))?;
+        let dff = dff::parse(&data)?;
+        let out_path = PathBuf::from(out_path).with_extension("obj");
+        export(&dff, &out_path, z_up.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Failed to join OBJ export task: {e}"))?
+}
+
+/// Export a cached .clmesh (as `parse_yft` writes) to an .obj at `out_path`,
+/// one object and group per mesh with a `usemtl` for its texture, streamed
+/// so large meshes don't build the file in memory. With `include_mtl` a
+/// sidecar .mtl maps each texture to its extracted image; textures with no
+/// image on disk are listed in `missingTextures`. Axes are converted to
+/// OBJ's Y-up.
+#[tauri::command]
+pub async fn export_clmesh_obj(
+    mesh_path: String,
+    out_path: String,
+    include_mtl: Option<bool>,
+) -> Result<ObjExport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&mesh_path, "clmesh") {
+            return Err("Only .clmesh files are supported by export_clmesh_obj".to_string());
+        }
+        let out_path = PathBuf::from(out_path).with_extension("obj");
+        export_clmesh(
+            Path::new(&mesh_path),
+            &out_path,
+            include_mtl.unwrap_or(true),
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to join OBJ export task: {e}"))?
+}
@@ -0,0 +1,52 @@
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+/// Write `contents` over `path` through a temporary file and a rename,
+/// keeping the previous file as `<path>.bak`. `what` names the file in
+/// errors.
+pub fn replace_file(path: &Path, contents: &[u8], what: &str) -> Result<(), String> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let (backup, temp) = (PathBuf::from(backup), PathBuf::from(temp));
+
+    std::fs::copy(long(path), long(&backup))
+        .map_err(|e| format!("Failed to back up {what}: {e}"))?;
+    std::fs::write(long(&temp), contents).map_err(|e| format!("Failed to write {what}: {e}"))?;
+    std::fs::rename(long(&temp), long(path)).map_err(|e| {
+        let _ = std::fs::remove_file(long(&temp));
+        format!("Failed to replace {what}: {e}")
+    })
+}
+
+/// `path` in Windows' extended-length form (`\\?\C:\...`, or
+/// `\\?\UNC\server\share\...` for network paths), which lifts the 260-char
+/// MAX_PATH limit for our own file operations. Relative paths, paths already
+/// in that form and paths on other platforms are returned unchanged.
+pub fn long(path: &Path) -> PathBuf {
+    if !cfg!(target_os = "windows") || !path.is_absolute() {
+        return path.to_path_buf();
+    }
+    let Some(raw) = path.to_str() else {
+        return path.to_path_buf();
+    };
+    if raw.starts_with(r"\\?\") || raw.starts_with(r"\\.\") {
+        return path.to_path_buf();
+    }
+
+    // The prefix turns off Win32 path parsing, so separators must already
+    // be backslashes.
+    let raw = raw.replace('/', "\\");
+    let mut extended = OsString::from(r"\\?\");
+    match raw.strip_prefix(r"\\") {
+        Some(unc) => {
+            extended.push(r"UNC\");
+            extended.push(unc);
+        }
+        None => extended.push(&raw),
+    }
+    PathBuf::from(extended)
+}
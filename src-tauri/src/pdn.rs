@@ -0,0 +1,1281 @@
+use std::{collections::BTreeSet, path::Path};
+
+use crate::{
+    cache::{self, CacheKeyMode},
+    images, nrbf, paths, tasks,
+};
+
+const MAX_PDN_FILE_BYTES: u64 = 128 * 1024 * 1024; // 128 MB
+const MAX_PDN_DIMENSION: u32 = 16_384;
+const MAX_PDN_OUTPUT_BYTES: usize = 384 * 1024 * 1024; // 384 MB RGBA
+const MAX_PDN_INFLATED_CHUNK_BYTES: usize = 64 * 1024 * 1024; // 64 MB/chunk
+const MAX_PDN_TOTAL_INFLATED_BYTES: usize = 768 * 1024 * 1024; // 768 MB total
+
+/// Decode a Paint.NET (.pdn) file into raw RGBA pixel data.
+/// Returns a binary response (see `images::rgba_response`) whose JSON has
+/// width/height, and `pdnVersion` when the file records which Paint.NET
+/// version saved it. With `legacy_base64` it is JSON instead, the pixels
+/// base64-encoded in `rgba_base64`.
+/// Hidden layers are left out and each layer's opacity and blend mode
+/// applied; modes the compositor lacks blend as Normal and are listed in
+/// `downgradedBlendModes`. `layers` has each layer's name, visibility,
+/// opacity and blend mode, bottom to top, when they could be read.
+#[tauri::command]
+pub async fn decode_pdn(
+    path: String,
+    legacy_base64: Option<bool>,
+    app: crate::AppHandle,
+) -> Result<tauri::ipc::Response, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let task = tasks::start(&app, "pdn", Some(&path));
+        if legacy_base64.unwrap_or(false) {
+            let decoded = decode_pdn_blocking(path, &|| task.check())?;
+            return Ok(tauri::ipc::Response::new(decoded.to_string()));
+        }
+        let (decoded, rgba) = composite(&path, &|| task.check())?;
+        Ok(images::rgba_response(&decoded, rgba))
+    })
+    .await
+    .map_err(|e| format!("Failed to join PDN decode task: {e}"))?
+}
+
+/// A decoded document's layers: BGRA surfaces bottom to top and, when one
+/// was read per surface, their properties.
+struct PdnLayers {
+    width: usize,
+    height: usize,
+    /// The Paint.NET version that saved the file, when it says.
+    pdn_version: Option<String>,
+    surfaces: Vec<Vec<u8>>,
+    properties: Option<Vec<LayerProperties>>,
+}
+
+/// Read and decode the document at `path`; `check` is called between pixel
+/// chunks and can abort the decode.
+fn read_layers(path: &str, check: &dyn Fn() -> Result<(), String>) -> Result<PdnLayers, String> {
+    let file_meta = std::fs::metadata(path).map_err(|e| format!("Failed to stat PDN file: {e}"))?;
+    if file_meta.len() > MAX_PDN_FILE_BYTES {
+        return Err(format!(
+            "PDN file exceeds safety limit ({} bytes > {} bytes).",
+            file_meta.len(),
+            MAX_PDN_FILE_BYTES
+        ));
+    }
+
+    let data = crate::read_file_with_retry(
+        Path::new(&path),
+        crate::READ_RETRY_ATTEMPTS,
+        crate::READ_RETRY_DELAY,
+    )
+    .map_err(|e| format!("Failed to read PDN file: {e}"))?;
+    parse_layers(&data, check)
+}
+
+/// `read_layers` on a file already in memory.
+fn parse_layers(data: &[u8], check: &dyn Fn() -> Result<(), String>) -> Result<PdnLayers, String> {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    // Validate PDN3 magic
+    if data.len() < 24 || &data[0..4] != b"PDN3" {
+        return Err("Not a valid Paint.NET file (missing PDN3 magic).".to_string());
+    }
+
+    // The XML header gives the size directly and, from 4.x, the version
+    // that saved the file. The BinaryFormatter document after it has the
+    // layers, and stands in for the size when the XML can't be read; builds
+    // with the `pdn-header-scan` feature then guess it from the raw bytes.
+    let header_end = xml_header_end(data);
+    let header = read_xml_header(data);
+    let document = read_document(data, header_end.unwrap_or(4));
+    let (width, height, guessed) = match (&header, &document) {
+        (Ok(header), _) => (header.width, header.height, false),
+        (Err(_), Ok(document)) => (document.width, document.height, false),
+        (Err(_), Err(error)) => guess_dimensions(data)
+            .map(|(width, height)| (width, height, true))
+            .ok_or_else(|| format!("Could not extract image dimensions from PDN file: {error}"))?,
+    };
+    let pdn_version = header
+        .ok()
+        .and_then(|header| header.version)
+        .or_else(|| document.as_ref().ok()?.version.clone());
+    let (layer_properties, pixels_at) = match document {
+        Ok(document) => (document.layers, document.pixels_at),
+        Err(_) => (Vec::new(), header_end.unwrap_or(24)),
+    };
+
+    if width == 0 || height == 0 || width > MAX_PDN_DIMENSION || height > MAX_PDN_DIMENSION {
+        return Err(format!("Invalid PDN dimensions: {width}x{height}"));
+    }
+    let initial_expected_size = (width as usize)
+        .saturating_mul(height as usize)
+        .saturating_mul(4);
+    if initial_expected_size == 0 || initial_expected_size > MAX_PDN_OUTPUT_BYTES {
+        return Err(format!(
+            "PDN image dimensions exceed safety output limit ({} bytes > {} bytes).",
+            initial_expected_size, MAX_PDN_OUTPUT_BYTES
+        ));
+    }
+
+    // Find and decompress all gzip chunks
+    let mut gzip_offsets = Vec::new();
+    let mut i = pixels_at;
+    while i + 2 < data.len() {
+        if data[i] == 0x1F && data[i + 1] == 0x8B && i + 2 < data.len() && data[i + 2] == 0x08 {
+            gzip_offsets.push(i);
+            i += 18; // skip minimum gzip overhead
+        } else {
+            i += 1;
+        }
+    }
+
+    if gzip_offsets.is_empty() {
+        return Err("No compressed pixel data found in PDN file.".to_string());
+    }
+
+    // Decompress all chunks first.
+    let mut inflated_chunks: Vec<Vec<u8>> = Vec::new();
+    let mut total_inflated_bytes: usize = 0;
+
+    for offset in &gzip_offsets {
+        check()?;
+        let slice = &data[*offset..];
+        let mut decoder = GzDecoder::new(slice);
+        let mut inflated = Vec::new();
+        let mut chunk_buf = [0u8; 8192];
+
+        loop {
+            let bytes_read = match decoder.read(&mut chunk_buf) {
+                Ok(n) => n,
+                Err(_) => {
+                    inflated.clear();
+                    break;
+                }
+            };
+
+            if bytes_read == 0 {
+                break;
+            }
+
+            if inflated.len().saturating_add(bytes_read) > MAX_PDN_INFLATED_CHUNK_BYTES {
+                return Err(format!(
+                    "PDN chunk exceeded inflate safety limit ({} bytes).",
+                    MAX_PDN_INFLATED_CHUNK_BYTES
+                ));
+            }
+
+            if total_inflated_bytes.saturating_add(bytes_read) > MAX_PDN_TOTAL_INFLATED_BYTES {
+                return Err(format!(
+                    "PDN decode exceeded total inflate safety limit ({} bytes).",
+                    MAX_PDN_TOTAL_INFLATED_BYTES
+                ));
+            }
+
+            inflated.extend_from_slice(&chunk_buf[..bytes_read]);
+            total_inflated_bytes += bytes_read;
+        }
+
+        if inflated.is_empty() {
+            continue;
+        }
+
+        inflated_chunks.push(inflated);
+    }
+
+    if inflated_chunks.is_empty() {
+        return Err("Failed to decompress any pixel data from PDN file.".to_string());
+    }
+
+    // Guessed sizes can come out swapped; if chunk alignment strongly
+    // suggests so, correct it.
+    let mut width_usize = width as usize;
+    let mut height_usize = height as usize;
+    if guessed {
+        maybe_swap_pdn_dimensions_by_alignment(
+            &inflated_chunks,
+            &mut width_usize,
+            &mut height_usize,
+        );
+    }
+
+    let expected_size = width_usize.saturating_mul(height_usize).saturating_mul(4);
+    if expected_size == 0 || expected_size > MAX_PDN_OUTPUT_BYTES {
+        return Err("PDN decode produced invalid output dimensions.".to_string());
+    }
+
+    let row_bytes = width_usize.saturating_mul(4);
+    let has_row_misalignment = row_bytes > 0
+        && inflated_chunks
+            .iter()
+            .any(|chunk| chunk.len() >= row_bytes && chunk.len() % row_bytes != 0);
+
+    // If chunks are not row-aligned, strip small per-chunk metadata/header bytes.
+    let mut layers = assemble_pdn_layers(
+        &inflated_chunks,
+        width_usize,
+        height_usize,
+        has_row_misalignment,
+    );
+    if layers.is_empty() {
+        // Fallback to raw concatenation if trimmed assembly yielded nothing.
+        layers = assemble_pdn_layers(&inflated_chunks, width_usize, height_usize, false);
+    }
+    if layers.is_empty() {
+        return Err("Failed to reconstruct any PDN layers from decompressed data.".to_string());
+    }
+
+    // The properties can only be paired with the surfaces when there is one
+    // per layer.
+    let properties = layer_properties;
+    Ok(PdnLayers {
+        width: width_usize,
+        height: height_usize,
+        pdn_version,
+        properties: (properties.len() == layers.len()).then_some(properties),
+        surfaces: layers,
+    })
+}
+
+/// The composite as JSON with the pixels in `rgba_base64`, the way
+/// `decode_pdn` used to return it and `pdn:decoded` events still carry it.
+pub fn decode_pdn_blocking(
+    path: String,
+    check: &dyn Fn() -> Result<(), String>,
+) -> Result<serde_json::Value, String> {
+    let (mut decoded, rgba) = composite(&path, check)?;
+    use base64::Engine;
+    decoded["rgba_base64"] = base64::engine::general_purpose::STANDARD
+        .encode(&rgba)
+        .into();
+    Ok(decoded)
+}
+
+/// Composite a document's layers into RGBA, returned with what's known
+/// about it: `{width, height, pdnVersion, downgradedBlendModes, layers}`.
+fn composite(
+    path: &str,
+    check: &dyn Fn() -> Result<(), String>,
+) -> Result<(serde_json::Value, Vec<u8>), String> {
+    Ok(composite_layers(read_layers(path, check)?))
+}
+
+fn composite_layers(layers: PdnLayers) -> (serde_json::Value, Vec<u8>) {
+    let PdnLayers {
+        width,
+        height,
+        pdn_version,
+        surfaces,
+        properties,
+    } = layers;
+    let expected_size = width * height * 4;
+
+    // Hidden layers (sketches, references) stay out of the composite; when
+    // the properties couldn't be read every layer counts as visible. Blend
+    // modes the compositor lacks fall back to Normal and are reported.
+    let mut downgraded = BTreeSet::new();
+
+    // Composite layers bottom-to-top, converting BGRA → RGBA
+    let mut rgba = vec![0u8; expected_size];
+    for (index, layer) in surfaces.iter().enumerate() {
+        // Fully transparent layers add nothing, so they're skipped too.
+        let properties = properties
+            .as_ref()
+            .and_then(|properties| properties.get(index));
+        if properties.is_some_and(|properties| !properties.visible || properties.opacity == 0) {
+            continue;
+        }
+        let opacity = properties.map_or(255, |properties| u32::from(properties.opacity));
+        let mut mode = properties.map_or(BlendMode::Normal, |properties| properties.blend_mode);
+        if !mode.is_supported() {
+            downgraded.insert(mode.name());
+            mode = BlendMode::Normal;
+        }
+        let len = std::cmp::min(layer.len(), expected_size);
+        let mut j = 0;
+        while j + 3 < len {
+            let pix = (j / 4) * 4;
+            if pix + 3 >= rgba.len() {
+                break;
+            }
+
+            let src_b = layer[j];
+            let src_g = layer[j + 1];
+            let src_r = layer[j + 2];
+            let src_a = ((u32::from(layer[j + 3]) * opacity + 127) / 255) as u8;
+
+            if src_a == 0 {
+                j += 4;
+                continue;
+            }
+
+            let dst_a = rgba[pix + 3];
+            if mode != BlendMode::Normal {
+                blend_pixel(mode, &mut rgba[pix..pix + 4], [src_r, src_g, src_b, src_a]);
+            } else if src_a == 255 || dst_a == 0 {
+                rgba[pix] = src_r;
+                rgba[pix + 1] = src_g;
+                rgba[pix + 2] = src_b;
+                rgba[pix + 3] = src_a;
+            } else {
+                let sa = src_a as f32 / 255.0;
+                let da = dst_a as f32 / 255.0;
+                let out_a = sa + da * (1.0 - sa);
+                if out_a > 0.0 {
+                    rgba[pix] =
+                        ((src_r as f32 * sa + rgba[pix] as f32 * da * (1.0 - sa)) / out_a) as u8;
+                    rgba[pix + 1] = ((src_g as f32 * sa + rgba[pix + 1] as f32 * da * (1.0 - sa))
+                        / out_a) as u8;
+                    rgba[pix + 2] = ((src_b as f32 * sa + rgba[pix + 2] as f32 * da * (1.0 - sa))
+                        / out_a) as u8;
+                    rgba[pix + 3] = (out_a * 255.0) as u8;
+                }
+            }
+
+            j += 4;
+        }
+    }
+
+    let decoded = serde_json::json!({
+        "width": width,
+        "height": height,
+        "pdnVersion": pdn_version,
+        "downgradedBlendModes": downgraded,
+        "layers": properties.as_deref().unwrap_or_default()
+    });
+    (decoded, rgba)
+}
+
+/// What `decode_pdn_to_png` returns.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PdnPng {
+    pub png_path: String,
+    pub width: usize,
+    pub height: usize,
+    /// Whether the PNG was already cached from an earlier call.
+    pub cached: bool,
+}
+
+fn decode_to_png_blocking(
+    path: &str,
+    app: &crate::AppHandle,
+    task: &tasks::TaskHandle,
+) -> Result<PdnPng, String> {
+    let root = cache::pdn_cache_root(app)?;
+    let png_path = root.join(format!(
+        "{}.png",
+        cache::yft_cache_key(path, CacheKeyMode::Fast)?
+    ));
+
+    // A PNG that's there was renamed into place whole; reading its header
+    // is all a hit costs.
+    let cached_size = std::fs::File::open(paths::long(&png_path))
+        .ok()
+        .and_then(|file| {
+            png::Decoder::new(std::io::BufReader::new(file))
+                .read_info()
+                .ok()
+        })
+        .map(|reader| (reader.info().width as usize, reader.info().height as usize));
+    let (width, height, cached) = match cached_size {
+        Some((width, height)) => (width, height, true),
+        None => {
+            let (decoded, rgba) = composite(path, &|| task.check())?;
+            let width = decoded["width"].as_u64().unwrap_or_default() as usize;
+            let height = decoded["height"].as_u64().unwrap_or_default() as usize;
+            std::fs::create_dir_all(paths::long(&root))
+                .map_err(|e| format!("Failed to create PDN cache dir: {e}"))?;
+            images::write_png_atomic(&png_path, width, height, &rgba)
+                .map_err(|e| format!("Failed to write PDN PNG: {e}"))?;
+            (width, height, false)
+        }
+    };
+    cache::record_pdn_use(app, &png_path);
+
+    Ok(PdnPng {
+        png_path: png_path.to_string_lossy().to_string(),
+        width,
+        height,
+        cached,
+    })
+}
+
+/// Composite a Paint.NET (.pdn) file like `decode_pdn`, but into a PNG in
+/// the cache that an `<img>` or texture loader can read, so no pixels cross
+/// IPC. The PNG is keyed by path, mtime and size like the YFT cache, so an
+/// unchanged file is a cache hit, and counts towards the same budget.
+/// Returns `{pngPath, width, height, cached}`.
+#[tauri::command]
+pub async fn decode_pdn_to_png(path: String, app: crate::AppHandle) -> Result<PdnPng, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let task = tasks::start(&app, "pdn", Some(&path));
+        decode_to_png_blocking(&path, &app, &task)
+    })
+    .await
+    .map_err(|e| format!("Failed to join PDN decode task: {e}"))?
+}
+
+/// One layer as `decode_pdn_layers` returns it.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedLayer {
+    pub index: usize,
+    #[serde(flatten)]
+    pub properties: LayerProperties,
+    pub width: usize,
+    pub height: usize,
+    /// Where the layer's RGBA starts among the pixels after the JSON of a
+    /// binary response.
+    pub rgba_offset: Option<usize>,
+    /// The layer's RGBA inline, with `legacy_base64`.
+    pub rgba_base64: Option<String>,
+    /// The file holding the layer's RGBA, with `to_disk`.
+    pub path: Option<String>,
+}
+
+/// Where `decode_pdn_layers` puts the pixels.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LayerOutput {
+    /// After the JSON in a binary response.
+    Binary,
+    Base64,
+    /// Raw RGBA files in the cache.
+    Disk,
+}
+
+/// What `decode_pdn_layers` returns; with `to_disk` the same is written
+/// as `layers.json` next to the pixel files.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedLayers {
+    pub width: usize,
+    pub height: usize,
+    pub pdn_version: Option<String>,
+    pub layers: Vec<DecodedLayer>,
+    pub manifest_path: Option<String>,
+}
+
+/// The layers and, for `LayerOutput::Binary`, their pixels back to back.
+fn decode_layers_blocking(
+    path: &str,
+    output: LayerOutput,
+    app: &crate::AppHandle,
+    task: &tasks::TaskHandle,
+) -> Result<(DecodedLayers, Vec<u8>), String> {
+    use base64::Engine;
+
+    let PdnLayers {
+        width,
+        height,
+        pdn_version,
+        surfaces,
+        properties,
+    } = read_layers(path, &|| task.check())?;
+    let out_dir = if output == LayerOutput::Disk {
+        let key = cache::variant_key(
+            &cache::yft_cache_key(path, CacheKeyMode::Fast)?,
+            "pdn-layers",
+        );
+        let dir = cache::yft_cache_root(app)?.join(&key);
+        std::fs::create_dir_all(paths::long(&dir))
+            .map_err(|e| format!("Failed to create output dir: {e}"))?;
+        cache::record_use(app, &key);
+        Some(dir)
+    } else {
+        None
+    };
+
+    let mut pixels = Vec::new();
+    if output == LayerOutput::Binary {
+        pixels.reserve_exact(surfaces.iter().map(Vec::len).sum());
+    }
+    let mut layers = Vec::with_capacity(surfaces.len());
+    for (index, mut surface) in surfaces.into_iter().enumerate() {
+        task.check()?;
+        for pixel in surface.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        let properties = properties
+            .as_ref()
+            .and_then(|properties| properties.get(index))
+            .cloned()
+            .unwrap_or(LayerProperties {
+                name: None,
+                visible: true,
+                opacity: 255,
+                blend_mode: BlendMode::Normal,
+            });
+        let mut layer = DecodedLayer {
+            index,
+            properties,
+            width,
+            height,
+            rgba_offset: None,
+            rgba_base64: None,
+            path: None,
+        };
+        match (&out_dir, output) {
+            (Some(dir), _) => {
+                let file = dir.join(format!("layer_{index}.rgba"));
+                std::fs::write(paths::long(&file), &surface)
+                    .map_err(|e| format!("Failed to write PDN layer: {e}"))?;
+                layer.path = Some(file.to_string_lossy().to_string());
+            }
+            (None, LayerOutput::Base64) => {
+                layer.rgba_base64 =
+                    Some(base64::engine::general_purpose::STANDARD.encode(&surface));
+            }
+            (None, _) => {
+                layer.rgba_offset = Some(pixels.len());
+                pixels.extend_from_slice(&surface);
+            }
+        }
+        layers.push(layer);
+    }
+
+    let mut decoded = DecodedLayers {
+        width,
+        height,
+        pdn_version,
+        layers,
+        manifest_path: None,
+    };
+    if let Some(dir) = out_dir {
+        let manifest = dir.join("layers.json");
+        decoded.manifest_path = Some(manifest.to_string_lossy().to_string());
+        let json = serde_json::to_vec(&decoded)
+            .map_err(|e| format!("Failed to serialize PDN layers: {e}"))?;
+        std::fs::write(paths::long(&manifest), json)
+            .map_err(|e| format!("Failed to write PDN layers: {e}"))?;
+    }
+    Ok((decoded, pixels))
+}
+
+/// Decode a Paint.NET (.pdn) file into its layers instead of a composite,
+/// bottom to top, each with its name (when recorded), visibility, opacity,
+/// blend mode and its own RGBA pixels, leaving compositing to the caller.
+/// Returns a binary response like `decode_pdn`, the layers' pixels back to
+/// back after the JSON and each layer's start in `rgbaOffset`. With
+/// `to_disk` they are instead written to the cache as raw RGBA files
+/// returned as `path`, with the whole result saved as `manifestPath`; with
+/// `legacy_base64` they are base64 in `rgbaBase64`. Both of those return
+/// plain JSON.
+#[tauri::command]
+pub async fn decode_pdn_layers(
+    path: String,
+    to_disk: Option<bool>,
+    legacy_base64: Option<bool>,
+    app: crate::AppHandle,
+) -> Result<tauri::ipc::Response, String> {
+    let output = if to_disk.unwrap_or(false) {
+        LayerOutput::Disk
+    } else if legacy_base64.unwrap_or(false) {
+        LayerOutput::Base64
+    } else {
+        LayerOutput::Binary
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let task = tasks::start(&app, "pdn", Some(&path));
+        let (decoded, pixels) = decode_layers_blocking(&path, output, &app, &task)?;
+        let json = serde_json::to_value(&decoded)
+            .map_err(|e| format!("Failed to serialize PDN layers: {e}"))?;
+        Ok(match output {
+            LayerOutput::Binary => images::rgba_response(&json, pixels),
+            _ => tauri::ipc::Response::new(json.to_string()),
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to join PDN decode task: {e}"))?
+}
+
+const DOCUMENT_CLASS: &str = "PaintDotNet.Document";
+const LAYER_PROPERTIES_CLASS: &str = "PaintDotNet.LayerProperties";
+const BITMAP_LAYER_PROPERTIES_CLASS: &str = "PaintDotNet.BitmapLayer+BitmapLayerProperties";
+
+/// Paint.NET's layer blend modes, in the order `blendMode` values count.
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Additive,
+    ColorBurn,
+    ColorDodge,
+    Reflect,
+    Glow,
+    Overlay,
+    Difference,
+    Negation,
+    Lighten,
+    Darken,
+    Screen,
+    Xor,
+}
+
+impl BlendMode {
+    const ALL: [Self; 14] = [
+        Self::Normal,
+        Self::Multiply,
+        Self::Additive,
+        Self::ColorBurn,
+        Self::ColorDodge,
+        Self::Reflect,
+        Self::Glow,
+        Self::Overlay,
+        Self::Difference,
+        Self::Negation,
+        Self::Lighten,
+        Self::Darken,
+        Self::Screen,
+        Self::Xor,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Multiply => "Multiply",
+            Self::Additive => "Additive",
+            Self::ColorBurn => "ColorBurn",
+            Self::ColorDodge => "ColorDodge",
+            Self::Reflect => "Reflect",
+            Self::Glow => "Glow",
+            Self::Overlay => "Overlay",
+            Self::Difference => "Difference",
+            Self::Negation => "Negation",
+            Self::Lighten => "Lighten",
+            Self::Darken => "Darken",
+            Self::Screen => "Screen",
+            Self::Xor => "Xor",
+        }
+    }
+
+    fn from_index(index: i64) -> Option<Self> {
+        Self::ALL.get(usize::try_from(index).ok()?).copied()
+    }
+
+    /// From the blend op class 3.x files reference, e.g.
+    /// `PaintDotNet.UserBlendOps+MultiplyBlendOp`.
+    fn from_class(class: &str) -> Option<Self> {
+        let name = class.rsplit(['+', '.']).next()?.strip_suffix("BlendOp")?;
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    /// Whether the compositor has this mode; the rest fall back to Normal.
+    pub fn is_supported(self) -> bool {
+        !matches!(
+            self,
+            Self::ColorBurn
+                | Self::ColorDodge
+                | Self::Reflect
+                | Self::Glow
+                | Self::Negation
+                | Self::Xor
+        )
+    }
+
+    /// The mode's color for `top` over `bottom`, one channel at a time.
+    fn apply(self, bottom: u8, top: u8) -> u8 {
+        let (b, t) = (u32::from(bottom), u32::from(top));
+        let scale = |x: u32, y: u32| (x * y + 127) / 255;
+        let value = match self {
+            Self::Multiply => scale(b, t),
+            Self::Additive => (b + t).min(255),
+            Self::Overlay if b < 128 => scale(2 * t, b),
+            Self::Overlay => 255 - scale(2 * (255 - t), 255 - b),
+            Self::Screen => 255 - scale(255 - b, 255 - t),
+            Self::Darken => b.min(t),
+            Self::Lighten => b.max(t),
+            Self::Difference => b.abs_diff(t),
+            _ => t,
+        };
+        value as u8
+    }
+}
+
+/// Blend `src` (RGBA) onto `dst` the way Paint.NET's UserBlendOps do on
+/// non-premultiplied colors: where both are opaque the mode's color shows,
+/// where only one is, that one does.
+fn blend_pixel(mode: BlendMode, dst: &mut [u8], src: [u8; 4]) {
+    let top = f32::from(src[3]) / 255.0;
+    let bottom = f32::from(dst[3]) / 255.0;
+    let both = bottom * top;
+    let bottom_only = bottom - both;
+    let top_only = top - both;
+    let out_a = bottom_only + top;
+    if out_a <= 0.0 {
+        return;
+    }
+    for (channel, top_color) in dst[..3].iter_mut().zip(src) {
+        let blended = mode.apply(*channel, top_color);
+        *channel = ((f32::from(*channel) * bottom_only
+            + f32::from(top_color) * top_only
+            + f32::from(blended) * both)
+            / out_a)
+            .round() as u8;
+    }
+    dst[3] = (out_a * 255.0).round() as u8;
+}
+
+/// What the document says about one layer.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerProperties {
+    pub name: Option<String>,
+    pub visible: bool,
+    /// 0 to 255, applied on top of each pixel's own alpha.
+    pub opacity: u8,
+    pub blend_mode: BlendMode,
+}
+
+/// What the document's BinaryFormatter header says about the image.
+struct Document {
+    width: u32,
+    height: u32,
+    /// Properties of each entry in the layer list, bottom to top.
+    layers: Vec<LayerProperties>,
+    /// From the document's `savedWith`, which 3.x files write.
+    version: Option<String>,
+    /// Where the header ends and the layer pixels start.
+    pixels_at: usize,
+}
+
+/// Walk the `Document` object at the root of the header stream: its size,
+/// then each entry of its layer list and the properties objects it holds.
+/// The stream starts a marker's length after `from`, where the XML header
+/// ends.
+fn read_document(data: &[u8], from: usize) -> Result<Document, String> {
+    let start = data
+        .get(from..)
+        .and_then(|rest| {
+            rest.windows(nrbf::STREAM_HEADER.len())
+                .position(|window| window == nrbf::STREAM_HEADER)
+        })
+        .map(|at| from + at)
+        .ok_or_else(|| "No document header found in PDN file.".to_string())?;
+    let stream = nrbf::parse(data, start)?;
+    let document = stream
+        .root()
+        .filter(|root| root.class_name == DOCUMENT_CLASS)
+        .ok_or_else(|| "PDN header does not start with a document.".to_string())?;
+
+    let dimension = |name: &str| {
+        document
+            .member(name)
+            .and_then(nrbf::Value::as_int)
+            .and_then(|value| u32::try_from(value).ok())
+            .ok_or_else(|| format!("PDN document has no {name}."))
+    };
+    let width = dimension("width")?;
+    let height = dimension("height")?;
+
+    // The layer list is an ArrayList: an items array, of which the first
+    // `_size` are in use.
+    let list = document
+        .member("layers")
+        .and_then(|value| stream.object(value))
+        .ok_or_else(|| "PDN document has no layer list.".to_string())?;
+    let list_member = |name: &str| {
+        list.members
+            .iter()
+            .find(|(member, _)| member == name || member.ends_with(&format!("+{name}")))
+            .map(|(_, value)| value)
+    };
+    let size = list_member("_size")
+        .and_then(nrbf::Value::as_int)
+        .and_then(|size| usize::try_from(size).ok())
+        .ok_or_else(|| "PDN layer list has no size.".to_string())?;
+    let items = list_member("_items")
+        .and_then(|value| stream.array(value))
+        .filter(|items| items.len() >= size)
+        .ok_or_else(|| "PDN layer list has no items.".to_string())?;
+
+    let layers = items[..size]
+        .iter()
+        .map(|item| {
+            let layer = stream
+                .object(item)
+                .ok_or_else(|| "PDN layer list holds something other than a layer.".to_string())?;
+            Ok(layer_properties(&stream, layer))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // A System.Version.
+    let version = document
+        .member("savedWith")
+        .and_then(|value| stream.object(value))
+        .map(|version| {
+            ["_Major", "_Minor", "_Build", "_Revision"]
+                .iter()
+                .filter_map(|part| version.member(part)?.as_int())
+                .take_while(|part| *part >= 0)
+                .map(|part| part.to_string())
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .filter(|version| !version.is_empty());
+
+    Ok(Document {
+        width,
+        height,
+        layers,
+        version,
+        pixels_at: stream.end,
+    })
+}
+
+/// What the XML header every file opens with says: after the "PDN3" magic,
+/// a 24-bit little-endian length and that much UTF-8 XML, a `<pdnImage>`
+/// element with the size and, from 4.x, the version as attributes.
+struct XmlHeader {
+    width: u32,
+    height: u32,
+    version: Option<String>,
+}
+
+/// Where the XML header ends, if its length fits the file.
+fn xml_header_end(data: &[u8]) -> Option<usize> {
+    let len = u32::from_le_bytes([*data.get(4)?, *data.get(5)?, *data.get(6)?, 0]) as usize;
+    Some(7 + len).filter(|end| *end <= data.len())
+}
+
+fn read_xml_header(data: &[u8]) -> Result<XmlHeader, String> {
+    let end = xml_header_end(data)
+        .ok_or_else(|| "PDN header runs past the end of the file.".to_string())?;
+    let xml = std::str::from_utf8(&data[7..end])
+        .map_err(|e| format!("Failed to read PDN header: {e}"))?;
+    let tag = xml
+        .find("<pdnImage")
+        .map(|start| &xml[start + "<pdnImage".len()..])
+        .and_then(|rest| Some(&rest[..rest.find('>')?]))
+        .ok_or_else(|| "PDN header has no pdnImage element.".to_string())?;
+    let dimension = |name: &str| {
+        xml_attribute(tag, name)
+            .and_then(|value| value.trim().parse::<u32>().ok())
+            .ok_or_else(|| format!("PDN header has no {name}."))
+    };
+    Ok(XmlHeader {
+        width: dimension("width")?,
+        height: dimension("height")?,
+        version: xml_attribute(tag, "savedWithVersion").map(str::to_string),
+    })
+}
+
+/// The value of attribute `name` in the inside of a tag, quoted either way.
+fn xml_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(at) = rest.find(name) {
+        let starts_word = rest[..at].ends_with(char::is_whitespace);
+        rest = &rest[at + name.len()..];
+        let Some(value) = rest.trim_start().strip_prefix('=').filter(|_| starts_word) else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let value = &value[1..];
+        return Some(&value[..value.find(quote)?]);
+    }
+    None
+}
+
+/// A layer's `LayerProperties` (name, visibility, opacity) and, for bitmap
+/// layers, its `BitmapLayerProperties` (blend mode), each found by class
+/// among the objects the layer holds.
+fn layer_properties(stream: &nrbf::Stream, layer: &nrbf::Object) -> LayerProperties {
+    let mut properties = LayerProperties {
+        name: None,
+        visible: true,
+        opacity: 255,
+        blend_mode: BlendMode::Normal,
+    };
+    for (_, value) in &layer.members {
+        let Some(object) = stream.object(value) else {
+            continue;
+        };
+        match object.class_name.as_str() {
+            LAYER_PROPERTIES_CLASS => {
+                properties.name = object
+                    .member("name")
+                    .and_then(|name| stream.string(name))
+                    .map(str::to_string);
+                if let Some(visible) = object.member("visible").and_then(nrbf::Value::as_bool) {
+                    properties.visible = visible;
+                }
+                if let Some(opacity) = object.member("opacity").and_then(nrbf::Value::as_int) {
+                    properties.opacity = opacity.clamp(0, 255) as u8;
+                }
+            }
+            BITMAP_LAYER_PROPERTIES_CLASS => {
+                properties.blend_mode = blend_mode(stream, object).unwrap_or(BlendMode::Normal);
+            }
+            _ => {}
+        }
+    }
+    properties
+}
+
+/// A bitmap layer's blend mode: the `blendMode` enum newer files write, or
+/// the class of the `blendOp` object 3.x files point at.
+fn blend_mode(stream: &nrbf::Stream, properties: &nrbf::Object) -> Option<BlendMode> {
+    if let Some(value) = properties.member("blendMode") {
+        let index = match stream.object(value) {
+            Some(object) => object.member("value__")?.as_int()?,
+            None => value.as_int()?,
+        };
+        return BlendMode::from_index(index);
+    }
+    let op = stream.object(properties.member("blendOp")?)?;
+    BlendMode::from_class(&op.class_name)
+}
+
+/// Guess the size from the raw header bytes, for files whose headers can't
+/// be read. Only builds with the `pdn-header-scan` feature try.
+#[cfg(feature = "pdn-header-scan")]
+fn guess_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    extract_pdn_dimensions(data)
+}
+
+#[cfg(not(feature = "pdn-header-scan"))]
+fn guess_dimensions(_data: &[u8]) -> Option<(u32, u32)> {
+    None
+}
+
+#[cfg(not(feature = "pdn-header-scan"))]
+fn maybe_swap_pdn_dimensions_by_alignment(
+    _chunks: &[Vec<u8>],
+    _width: &mut usize,
+    _height: &mut usize,
+) {
+}
+
+#[cfg(feature = "pdn-header-scan")]
+fn maybe_swap_pdn_dimensions_by_alignment(
+    chunks: &[Vec<u8>],
+    width: &mut usize,
+    height: &mut usize,
+) {
+    if *width == 0 || *height == 0 || *width == *height {
+        return;
+    }
+
+    let row_a = width.saturating_mul(4);
+    let row_b = height.saturating_mul(4);
+    if row_a == 0 || row_b == 0 {
+        return;
+    }
+
+    let aligned_a = chunks
+        .iter()
+        .filter(|chunk| chunk.len() >= row_a && chunk.len() % row_a == 0)
+        .count();
+    let aligned_b = chunks
+        .iter()
+        .filter(|chunk| chunk.len() >= row_b && chunk.len() % row_b == 0)
+        .count();
+
+    if aligned_b > aligned_a && aligned_b >= chunks.len().saturating_div(2) {
+        std::mem::swap(width, height);
+    }
+}
+
+fn assemble_pdn_layers(
+    chunks: &[Vec<u8>],
+    width: usize,
+    height: usize,
+    trim_chunk_headers: bool,
+) -> Vec<Vec<u8>> {
+    let expected_size = width.saturating_mul(height).saturating_mul(4);
+    let row_bytes = width.saturating_mul(4);
+    if expected_size == 0 {
+        return Vec::new();
+    }
+
+    let mut layers: Vec<Vec<u8>> = Vec::new();
+    let mut current_layer: Vec<u8> = Vec::with_capacity(expected_size);
+
+    for chunk in chunks {
+        let payload = if trim_chunk_headers {
+            select_pdn_chunk_payload(chunk, row_bytes)
+        } else {
+            (0, chunk.len())
+        };
+        if payload.1 <= payload.0 || payload.1 > chunk.len() {
+            continue;
+        }
+
+        current_layer.extend_from_slice(&chunk[payload.0..payload.1]);
+
+        while current_layer.len() >= expected_size {
+            let layer = current_layer.drain(..expected_size).collect::<Vec<u8>>();
+            layers.push(layer);
+        }
+    }
+
+    // Keep non-empty trailing data as a partial layer and pad to full image size.
+    if current_layer.len() >= row_bytes.max(1) {
+        current_layer.resize(expected_size, 0);
+        layers.push(current_layer);
+    }
+
+    layers
+}
+
+fn select_pdn_chunk_payload(chunk: &[u8], row_bytes: usize) -> (usize, usize) {
+    if row_bytes == 0 || chunk.len() <= row_bytes {
+        return (0, chunk.len());
+    }
+
+    let max_trim = std::cmp::min(64, chunk.len().saturating_sub(1));
+    let mut best: Option<(usize, usize, usize)> = None;
+
+    for lead in 0..=max_trim {
+        for trail in 0..=max_trim {
+            if lead + trail >= chunk.len() {
+                continue;
+            }
+            let payload_len = chunk.len() - lead - trail;
+            if payload_len < row_bytes || !payload_len.is_multiple_of(row_bytes) {
+                continue;
+            }
+            let rows = payload_len / row_bytes;
+            if rows == 0 || rows > 8192 {
+                continue;
+            }
+            let discarded = lead + trail;
+            match best {
+                None => best = Some((lead, chunk.len() - trail, discarded)),
+                Some((best_start, best_end, best_discarded)) => {
+                    let best_payload_len = best_end.saturating_sub(best_start);
+                    if discarded < best_discarded
+                        || (discarded == best_discarded && payload_len > best_payload_len)
+                    {
+                        best = Some((lead, chunk.len() - trail, discarded));
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some((start, end, _)) = best {
+        return (start, end);
+    }
+
+    let aligned_len = chunk.len() - (chunk.len() % row_bytes);
+    if aligned_len >= row_bytes {
+        return (0, aligned_len);
+    }
+
+    (0, chunk.len())
+}
+
+/// Extract width and height from a PDN file's .NET BinaryFormatter stream.
+#[cfg(feature = "pdn-header-scan")]
+fn extract_pdn_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let mut width: u32 = 0;
+    let mut height: u32 = 0;
+
+    // Scan for "width" and "height" length-prefixed strings
+    let width_needle = b"width";
+    let height_needle = b"height";
+
+    for i in 0..data.len().saturating_sub(10) {
+        // .NET BinaryFormatter writes field names as length-prefixed strings
+        if data[i] == width_needle.len() as u8
+            && i + 1 + width_needle.len() < data.len()
+            && &data[i + 1..i + 1 + width_needle.len()] == width_needle
+        {
+            // Scan forward for a reasonable Int32 value
+            width = scan_for_dimension(data, i + 1 + width_needle.len());
+        }
+        if data[i] == height_needle.len() as u8
+            && i + 1 + height_needle.len() < data.len()
+            && &data[i + 1..i + 1 + height_needle.len()] == height_needle
+        {
+            height = scan_for_dimension(data, i + 1 + height_needle.len());
+        }
+        if width > 0 && height > 0 {
+            return Some((width, height));
+        }
+    }
+
+    // Fallback: scan for dimension pair in the header area
+    for i in 24..std::cmp::min(data.len().saturating_sub(8), 4096) {
+        let a = u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        let b = u32::from_le_bytes([data[i + 4], data[i + 5], data[i + 6], data[i + 7]]);
+        if (16..=16384).contains(&a)
+            && (16..=16384).contains(&b)
+            && a.is_power_of_two()
+            && b.is_power_of_two()
+        {
+            return Some((a, b));
+        }
+    }
+
+    None
+}
+
+/// Scan forward from a position to find a reasonable dimension value.
+#[cfg(feature = "pdn-header-scan")]
+fn scan_for_dimension(data: &[u8], from: usize) -> u32 {
+    for off in from..std::cmp::min(from + 64, data.len().saturating_sub(4)) {
+        let val = u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]]);
+        if (1..=65536).contains(&val)
+            && (val.is_power_of_two() || val.is_multiple_of(64) || val.is_multiple_of(100))
+        {
+            return val;
+        }
+    }
+    for off in from..std::cmp::min(from + 64, data.len().saturating_sub(4)) {
+        let val = u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]]);
+        if (16..=65536).contains(&val) {
+            return val;
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file from `tests/fixtures/pdn`: a malformed one `generate.py`
+    /// writes, or under `paintdotnet/` one Paint.NET saved (see README.md).
+    fn fixture(name: &str) -> Vec<u8> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/pdn")
+            .join(name);
+        std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+    }
+
+    fn decode(name: &str) -> (serde_json::Value, Vec<u8>) {
+        let layers =
+            parse_layers(&fixture(name), &|| Ok(())).unwrap_or_else(|e| panic!("{name}: {e}"));
+        composite_layers(layers)
+    }
+
+    /// Decode `paintdotnet/{name}.pdn` and check the composite is within
+    /// one per channel of Paint.NET's own flattened `{name}.png`.
+    fn matches_flattened(name: &str) -> serde_json::Value {
+        let (decoded, rgba) = decode(&format!("paintdotnet/{name}.pdn"));
+        let png = fixture(&format!("paintdotnet/{name}.png"));
+        let mut reader = png::Decoder::new(std::io::Cursor::new(png))
+            .read_info()
+            .expect(name);
+        let mut expected = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut expected).expect(name);
+        expected.truncate(info.buffer_size());
+        // Paint.NET's auto bit depth drops alpha from opaque images.
+        if info.color_type == png::ColorType::Rgb {
+            expected = expected
+                .chunks(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect();
+        }
+        assert_eq!(rgba.len(), expected.len(), "{name}");
+        assert_eq!(
+            (info.width, info.height),
+            (
+                decoded["width"].as_u64().unwrap() as u32,
+                decoded["height"].as_u64().unwrap() as u32
+            )
+        );
+        for (at, (got, want)) in rgba.iter().zip(&expected).enumerate() {
+            assert!(
+                got.abs_diff(*want) <= 1,
+                "{name}: pixel {} channel {} is {got}, expected {want}",
+                at / 4,
+                at % 4
+            );
+        }
+        decoded
+    }
+
+    #[test]
+    #[ignore = "needs the Paint.NET saves listed in tests/fixtures/pdn/README.md"]
+    fn blend_modes_match_paint_dot_nets_flattened_export() {
+        for mode in ["Multiply", "Overlay", "Screen", "Additive", "Darken"] {
+            let decoded = matches_flattened(&format!("blend-{}", mode.to_lowercase()));
+            assert_eq!(decoded["layers"][1]["blendMode"], mode);
+            assert_eq!(decoded["downgradedBlendModes"], serde_json::json!([]));
+        }
+    }
+
+    #[test]
+    #[ignore = "needs the Paint.NET saves listed in tests/fixtures/pdn/README.md"]
+    fn layer_opacity_matches_paint_dot_nets_flattened_export() {
+        let decoded = matches_flattened("half-opacity");
+        assert_eq!(decoded["layers"][1]["opacity"], 128);
+    }
+
+    #[test]
+    #[ignore = "needs the Paint.NET saves listed in tests/fixtures/pdn/README.md"]
+    fn reads_the_size_and_version_of_3_5_files() {
+        // 3.x writes the version in the document, and blend modes as op
+        // classes.
+        let decoded = matches_flattened("v3.5");
+        assert_eq!(
+            (decoded["width"].as_u64(), decoded["height"].as_u64()),
+            (Some(16), Some(16))
+        );
+        let version = decoded["pdnVersion"].as_str().unwrap();
+        assert!(version.starts_with("3.5."), "{version}");
+        assert_eq!(decoded["layers"][1]["blendMode"], "Multiply");
+    }
+
+    #[test]
+    #[ignore = "needs the Paint.NET saves listed in tests/fixtures/pdn/README.md"]
+    fn reads_the_size_and_version_of_4_x_files() {
+        let decoded = matches_flattened("v4");
+        assert_eq!(
+            (decoded["width"].as_u64(), decoded["height"].as_u64()),
+            (Some(16), Some(16))
+        );
+        let version = decoded["pdnVersion"].as_str().unwrap();
+        assert!(version.starts_with("4."), "{version}");
+        assert_eq!(decoded["layers"][1]["blendMode"], "Multiply");
+    }
+
+    #[test]
+    fn finds_attributes_by_whole_name_in_either_quotes() {
+        let tag =
+            r#" thumbwidth="1" width = '16' savedWithVersionX="2" savedWithVersion="4.3" height=8"#;
+        assert_eq!(xml_attribute(tag, "width"), Some("16"));
+        assert_eq!(xml_attribute(tag, "savedWithVersion"), Some("4.3"));
+        assert_eq!(xml_attribute(tag, "savedWith"), None);
+        assert_eq!(xml_attribute(tag, "thumbwidth"), Some("1"));
+        assert_eq!(xml_attribute(tag, "height"), None);
+        assert_eq!(
+            xml_attribute(r#" title="a width" width="3""#, "width"),
+            Some("3")
+        );
+        assert_eq!(xml_attribute(" layers='2\"", "layers"), None);
+    }
+
+    #[test]
+    fn malformed_files_are_errors() {
+        for (name, expected) in [
+            ("no-magic.pdn", "missing PDN3 magic"),
+            ("truncated-header.pdn", "Could not extract image dimensions"),
+            ("zero-width.pdn", "Invalid PDN dimensions: 0x16"),
+            ("no-pixels.pdn", "No compressed pixel data"),
+            (
+                "truncated-pixels.pdn",
+                "Failed to decompress any pixel data",
+            ),
+        ] {
+            let error = parse_layers(&fixture(name), &|| Ok(())).err().expect(name);
+            assert!(error.contains(expected), "{name}: {error}");
+        }
+        // Nor does any shorter cut decode, or panic.
+        let data = fixture("truncated-pixels.pdn");
+        for len in 0..data.len() {
+            assert!(parse_layers(&data[..len], &|| Ok(())).is_err(), "{len}");
+        }
+    }
+
+    #[test]
+    #[ignore = "needs the Paint.NET saves listed in tests/fixtures/pdn/README.md"]
+    fn hidden_layers_stay_out_of_the_composite() {
+        let decoded = matches_flattened("hidden-layer");
+        assert_eq!(decoded["layers"][1]["name"], "Sketch");
+        assert_eq!(decoded["layers"][1]["visible"], false);
+    }
+}
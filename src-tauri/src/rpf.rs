@@ -0,0 +1,92 @@
+use std::{path::Path, process::Command};
+
+// Bridge exit code for archives that need the game's keys.
+const KEYS_REQUIRED_EXIT_CODE: i32 = 4;
+
+/// Entry tree of an `.rpf` archive, nested archives included: directories
+/// with `children`, files with `size`, `type` (`resource`, `file` or
+/// `archive`) and the `path` to pass to `extract_rpf_entry`.
+///
+/// `gta_dir` is a GTA V install to load the keys for encrypted archives from.
+#[tauri::command]
+pub async fn list_rpf(
+    path: String,
+    gta_dir: Option<String>,
+    app: crate::AppHandle,
+) -> Result<serde_json::Value, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !Path::new(&path).is_file() {
+            return Err(format!("Archive not found: {path}"));
+        }
+        let mut command = bridge_command(&app, gta_dir.as_deref())?;
+        command.arg("--rpf-list").arg(&path);
+        run(command)
+    })
+    .await
+    .map_err(|e| format!("Failed to join RPF listing task: {e}"))?
+}
+
+/// Write one entry of an archive to `dest_dir`, decrypted and decompressed
+/// the way a loose file exported with OpenIV would be, and return its path.
+#[tauri::command]
+pub async fn extract_rpf_entry(
+    archive: String,
+    entry_path: String,
+    dest_dir: String,
+    gta_dir: Option<String>,
+    app: crate::AppHandle,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !Path::new(&archive).is_file() {
+            return Err(format!("Archive not found: {archive}"));
+        }
+        let mut command = bridge_command(&app, gta_dir.as_deref())?;
+        command
+            .arg("--rpf-extract")
+            .arg(&archive)
+            .arg("--entry")
+            .arg(&entry_path)
+            .arg("--dest")
+            .arg(&dest_dir);
+        let result = run(command)?;
+        result
+            .get("path")
+            .and_then(|path| path.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| "CodeWalker bridge did not report the extracted file.".to_string())
+    })
+    .await
+    .map_err(|e| format!("Failed to join RPF extraction task: {e}"))?
+}
+
+fn bridge_command(app: &crate::AppHandle, gta_dir: Option<&str>) -> Result<Command, String> {
+    if !cfg!(target_os = "windows") {
+        return Err("RPF browsing is only supported on Windows in this build.".to_string());
+    }
+    let mut command = Command::new(crate::find_codewalker_bridge(app)?);
+    if let Some(gta_dir) = gta_dir.filter(|dir| !dir.trim().is_empty()) {
+        command.arg("--gta").arg(gta_dir);
+    }
+    Ok(command)
+}
+
+fn run(mut command: Command) -> Result<serde_json::Value, String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run CodeWalker bridge: {e}"))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if output.status.code() == Some(KEYS_REQUIRED_EXIT_CODE) {
+        return Err(
+            "This archive is encrypted with the game's keys. Set your GTA V install folder and try again."
+                .to_string(),
+        );
+    }
+    if !output.status.success() {
+        return Err(format!("CodeWalker bridge failed: {}", stderr.trim()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str::<serde_json::Value>(stdout.trim())
+        .map_err(|e| format!("Failed to parse CodeWalker bridge output: {e}"))
+}
@@ -0,0 +1,144 @@
+use std::{io::Read, path::Path};
+
+use flate2::read::DeflateDecoder;
+
+/// `RSC7` as the little-endian u32 at the start of every resource file.
+pub const MAGIC: u32 = 0x3743_5352;
+const HEADER_LEN: usize = 16;
+// Out of the 16 header bytes; a plain header repeats zeros and flag bytes,
+// ciphertext almost never does.
+const ENCRYPTED_DISTINCT_BYTES: usize = 12;
+// Enough of the decompressed stream to reach the fragment's group and bone
+// names without inflating the whole model.
+const CLASSIFY_SCAN_BYTES: u64 = 4 * 1024 * 1024;
+
+// Names only vehicle fragments carry: their bones and physics groups.
+const VEHICLE_MARKERS: [&[u8]; 6] = [
+    b"chassis",
+    b"bodyshell",
+    b"wheel_lf",
+    b"wheel_rf",
+    b"door_dside_f",
+    b"steeringwheel",
+];
+
+/// What the 16-byte RSC7 header says about a resource.
+#[derive(serde::Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceHeader {
+    pub version: u32,
+    pub system_flags: u32,
+    pub graphics_flags: u32,
+    /// Decompressed size of the virtual (CPU) segment.
+    pub virtual_size: u64,
+    /// Decompressed size of the physical (GPU) segment.
+    pub physical_size: u64,
+}
+
+/// Read the RSC7 header of `path` so files the bridge would only choke on
+/// are turned away with a useful message: resources still encrypted from a
+/// DLC archive, and files that aren't resources at all.
+pub fn sniff(path: &Path) -> Result<ResourceHeader, String> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let mut header = [0u8; HEADER_LEN];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map_err(|e| format!("Failed to read .{extension} header: {e}"))?;
+
+    let word = |offset: usize| {
+        u32::from_le_bytes([
+            header[offset],
+            header[offset + 1],
+            header[offset + 2],
+            header[offset + 3],
+        ])
+    };
+    if word(0) != MAGIC {
+        let mut seen = [false; 256];
+        for byte in header {
+            seen[byte as usize] = true;
+        }
+        let distinct = seen.iter().filter(|seen| **seen).count();
+        return Err(if distinct >= ENCRYPTED_DISTINCT_BYTES {
+            format!(
+                "This .{extension} appears to be encrypted; extract it with OpenIV/CodeWalker from the game install first."
+            )
+        } else {
+            format!("This .{extension} is not an RSC7 resource.")
+        });
+    }
+
+    let system_flags = word(8);
+    let graphics_flags = word(12);
+    Ok(ResourceHeader {
+        version: word(4),
+        system_flags,
+        graphics_flags,
+        virtual_size: segment_size(system_flags),
+        physical_size: segment_size(graphics_flags),
+    })
+}
+
+/// Size encoded in RSC7 segment flags: a base page size shifted by the low
+/// nibble, times the page counts packed into the bits above it.
+fn segment_size(flags: u32) -> u64 {
+    let pages = ((flags >> 27) & 0x1)
+        + (((flags >> 26) & 0x1) << 1)
+        + (((flags >> 25) & 0x1) << 2)
+        + (((flags >> 24) & 0x1) << 3)
+        + (((flags >> 17) & 0x7F) << 4)
+        + (((flags >> 11) & 0x3F) << 5)
+        + (((flags >> 7) & 0xF) << 6)
+        + (((flags >> 5) & 0x3) << 7)
+        + (((flags >> 4) & 0x1) << 8);
+    (0x200u64 << (flags & 0xF)) * u64::from(pages)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum YftClass {
+    Vehicle,
+    Generic,
+}
+
+impl YftClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Vehicle => "vehicle",
+            Self::Generic => "generic",
+        }
+    }
+}
+
+/// Guess whether a .yft is a vehicle from the names in the start of its
+/// decompressed data, so the converter can start with the right settings.
+pub fn classify_yft(path: &Path) -> Result<YftClass, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open YFT: {e}"))?;
+    let mut header = [0u8; HEADER_LEN];
+    file.read_exact(&mut header)
+        .map_err(|e| format!("Failed to read YFT header: {e}"))?;
+    if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) != MAGIC {
+        return Err("Not an RSC7 resource.".to_string());
+    }
+
+    let mut data = Vec::new();
+    // A truncated stream still leaves whatever inflated before the error.
+    let _ = DeflateDecoder::new(file)
+        .take(CLASSIFY_SCAN_BYTES)
+        .read_to_end(&mut data);
+    if data.is_empty() {
+        return Err("Failed to decompress YFT data.".to_string());
+    }
+
+    data.make_ascii_lowercase();
+    let is_vehicle = VEHICLE_MARKERS
+        .iter()
+        .any(|marker| data.windows(marker.len()).any(|window| window == *marker));
+    Ok(if is_vehicle {
+        YftClass::Vehicle
+    } else {
+        YftClass::Generic
+    })
+}
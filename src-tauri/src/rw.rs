@@ -0,0 +1,160 @@
+// Chunk types shared by every RenderWare file.
+pub const CHUNK_STRUCT: u32 = 0x01;
+pub const CHUNK_STRING: u32 = 0x02;
+pub const CHUNK_EXTENSION: u32 = 0x03;
+
+/// One chunk: a 12-byte header (type, size, library id) and its data.
+#[derive(Clone, Copy)]
+pub struct Chunk {
+    pub kind: u32,
+    /// Offset of the chunk header.
+    pub offset: usize,
+    /// Offsets of the chunk's data.
+    pub start: usize,
+    pub end: usize,
+    /// Decoded library version, e.g. `0x36003`.
+    pub version: u32,
+}
+
+pub fn read_u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    if offset + 4 > data.len() {
+        return None;
+    }
+    Some(u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ]))
+}
+
+/// RenderWare packs the library version into the chunk header's library
+/// id; ids without the high half set are the old 3.1-era plain versions.
+fn library_version(library_id: u32) -> u32 {
+    if library_id & 0xFFFF_0000 != 0 {
+        (((library_id >> 14) & 0x3_FF00) + 0x3_0000) | ((library_id >> 16) & 0x3F)
+    } else {
+        library_id << 8
+    }
+}
+
+pub fn chunk_at(data: &[u8], offset: usize, limit: usize) -> Result<Chunk, String> {
+    let header = offset
+        .checked_add(12)
+        .filter(|end| *end <= limit)
+        .ok_or_else(|| format!("Chunk header at offset {offset:#x} runs past {limit:#x}."))?;
+    let word = |at: usize| read_u32_le(data, at).unwrap_or(0);
+    let kind = word(offset);
+    let size = word(offset + 4) as usize;
+    let end = header
+        .checked_add(size)
+        .filter(|end| *end <= limit)
+        .ok_or_else(|| {
+            format!(
+                "Chunk {kind:#x} at offset {offset:#x} claims {size} bytes, past the end of its parent at {limit:#x}."
+            )
+        })?;
+    Ok(Chunk {
+        kind,
+        offset,
+        start: header,
+        end,
+        version: library_version(word(offset + 8)),
+    })
+}
+
+pub fn children(data: &[u8], parent: &Chunk) -> Result<Vec<Chunk>, String> {
+    let mut chunks = Vec::new();
+    let mut offset = parent.start;
+    while offset < parent.end {
+        let chunk = chunk_at(data, offset, parent.end)?;
+        offset = chunk.end;
+        chunks.push(chunk);
+    }
+    Ok(chunks)
+}
+
+pub fn child(chunks: &[Chunk], kind: u32) -> Option<&Chunk> {
+    chunks.iter().find(|chunk| chunk.kind == kind)
+}
+
+/// Bounds-checked reads inside one chunk, with errors naming the offset.
+pub struct Cursor<'a> {
+    data: &'a [u8],
+    offset: usize,
+    end: usize,
+    what: &'static str,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(data: &'a [u8], chunk: &Chunk, what: &'static str) -> Self {
+        Self {
+            data,
+            offset: chunk.start,
+            end: chunk.end,
+            what,
+        }
+    }
+
+    /// Where the next read starts in the file.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.end)
+            .ok_or_else(|| {
+                format!(
+                    "{} is truncated at offset {:#x} (needs {len} more bytes, ends at {:#x}).",
+                    self.what, self.offset, self.end
+                )
+            })?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn f32s(&mut self, count: usize) -> Result<Vec<f32>, String> {
+        let len = count.checked_mul(4).ok_or_else(|| {
+            format!(
+                "{} at offset {:#x} has an impossible count {count}.",
+                self.what, self.offset
+            )
+        })?;
+        Ok(self
+            .take(len)?
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect())
+    }
+}
+
+/// Null-terminated and padded to four bytes; whatever follows the first
+/// null is padding (often leftovers of a longer name) and is dropped. GTA
+/// names have no extension, so none is added or stripped.
+pub fn read_string(data: &[u8], chunk: &Chunk) -> String {
+    c_string(&data[chunk.start..chunk.end])
+}
+
+/// A null-padded name field, such as a texture native's 32-byte names.
+pub fn c_string(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..len]).trim().to_string()
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::SystemTime,
+};
+
+use tauri::{Emitter, Manager, State};
+
+use crate::conversion::{self, ConversionTasks};
+
+/// Long-running backend operations (parses, conversions, batches, PDN
+/// decodes), so the UI can list them and cancel any one of them.
+#[derive(Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<BTreeMap<u64, RegisteredTask>>,
+    next_id: AtomicU64,
+}
+
+struct RegisteredTask {
+    info: TaskInfo,
+    cancelled: Arc<AtomicBool>,
+    // Cache key the task's processes run under in `ConversionTasks`, once
+    // known, so cancelling can kill them.
+    cache_key: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+    pub id: u64,
+    /// `parse`, `conversion`, `batch` or `pdn`.
+    pub kind: &'static str,
+    pub path: Option<String>,
+    /// Unix time in milliseconds.
+    pub started_at: u64,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TaskFinishedPayload {
+    #[serde(flatten)]
+    info: TaskInfo,
+    cancelled: bool,
+}
+
+impl TaskRegistry {
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<u64, RegisteredTask>> {
+        self.tasks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Registration of one running task; dropping it unregisters the task and
+/// emits `task:finished`.
+pub struct TaskHandle {
+    app: crate::AppHandle,
+    id: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Register a task and emit `task:started`.
+pub fn start(app: &crate::AppHandle, kind: &'static str, path: Option<&str>) -> TaskHandle {
+    let registry = app.state::<TaskRegistry>();
+    let id = registry.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let info = TaskInfo {
+        id,
+        kind,
+        path: path.map(str::to_string),
+        started_at: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    };
+    let cancelled = Arc::new(AtomicBool::new(false));
+    registry.lock().insert(
+        id,
+        RegisteredTask {
+            info: info.clone(),
+            cancelled: cancelled.clone(),
+            cache_key: None,
+        },
+    );
+    let _ = app.emit("task:started", info);
+    TaskHandle {
+        app: app.clone(),
+        id,
+        cancelled,
+    }
+}
+
+impl TaskHandle {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// `Err(conversion::CANCELLED)` once the task has been cancelled, for
+    /// checks between steps.
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err(conversion::CANCELLED.to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Tie the task to the cache key its processes are registered under.
+    pub fn attach_cache_key(&self, key: &str) {
+        if let Some(task) = self.app.state::<TaskRegistry>().lock().get_mut(&self.id) {
+            task.cache_key = Some(key.to_string());
+        }
+    }
+
+    pub fn cache_key(&self) -> Option<String> {
+        self.app
+            .state::<TaskRegistry>()
+            .lock()
+            .get(&self.id)
+            .and_then(|task| task.cache_key.clone())
+    }
+}
+
+impl Drop for TaskHandle {
+    fn drop(&mut self) {
+        let removed = self.app.state::<TaskRegistry>().lock().remove(&self.id);
+        if let Some(task) = removed {
+            let _ = self.app.emit(
+                "task:finished",
+                TaskFinishedPayload {
+                    info: task.info,
+                    cancelled: self.is_cancelled(),
+                },
+            );
+        }
+    }
+}
+
+/// Every registered task, oldest first.
+#[tauri::command]
+pub fn list_tasks(registry: State<'_, TaskRegistry>) -> Vec<TaskInfo> {
+    registry
+        .lock()
+        .values()
+        .map(|task| task.info.clone())
+        .collect()
+}
+
+/// Cancel task `id`, killing its running process if it has one. The task
+/// stops at its next check and fails with `cancelled`. Returns `false` if no
+/// such task is running.
+#[tauri::command]
+pub fn cancel_task(
+    id: u64,
+    registry: State<'_, TaskRegistry>,
+    processes: State<'_, ConversionTasks>,
+) -> Result<bool, String> {
+    let cache_key = match registry.lock().get(&id) {
+        Some(task) => {
+            task.cancelled.store(true, Ordering::SeqCst);
+            task.cache_key.clone()
+        }
+        None => return Ok(false),
+    };
+    if let Some(key) = cache_key {
+        processes.cancel(&key)?;
+    }
+    Ok(true)
+}
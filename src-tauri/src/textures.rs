@@ -0,0 +1,189 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use tauri::Manager;
+
+use crate::{
+    cache::{self, CacheKeyMode},
+    conversion::{self, ConversionContext, ConversionTasks},
+};
+
+// What an extraction wrote, so a later call can return it without running
+// the bridge again.
+const MANIFEST_FILE: &str = "textures.json";
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextureFormat {
+    /// The texture as stored in the dictionary.
+    Dds,
+    /// Decoded, for viewers that can't read DDS.
+    #[default]
+    Png,
+}
+
+impl TextureFormat {
+    pub fn parse(format: Option<&str>) -> Result<Self, String> {
+        match format.map(str::trim) {
+            None | Some("") | Some("png") => Ok(Self::Png),
+            Some("dds") => Ok(Self::Dds),
+            Some(other) => Err(format!(
+                "Unknown texture format `{other}` (expected `dds` or `png`)"
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Dds => "dds",
+            Self::Png => "png",
+        }
+    }
+}
+
+/// Cache key and directory for the textures of `ytd` in `format`, next to
+/// the model entries.
+pub fn texture_cache_dir(
+    app: &crate::AppHandle,
+    ytd: &str,
+    format: TextureFormat,
+) -> Result<(String, PathBuf), String> {
+    let key = cache::variant_key(
+        &cache::yft_cache_key(ytd, CacheKeyMode::Fast)?,
+        &format!("textures:{}", format.as_str()),
+    );
+    let dir = cache::yft_cache_root(app)?.join(&key);
+    Ok((key, dir))
+}
+
+/// Write every texture of `ytd` into `out_dir` through the bridge and return
+/// the list of `{name, width, height, format, path}`. A list left by an
+/// earlier run is returned as is while its files are all still there.
+/// `out_dir` is only removed on failure when `owned` (a cache directory).
+pub fn extract(
+    ctx: &ConversionContext,
+    bridge: &Path,
+    ytd: &str,
+    out_dir: &Path,
+    format: TextureFormat,
+    owned: bool,
+) -> Result<serde_json::Value, String> {
+    let manifest = out_dir.join(MANIFEST_FILE);
+    if let Some(cached) = read_manifest(&manifest) {
+        return Ok(cached);
+    }
+
+    std::fs::create_dir_all(out_dir).map_err(|e| format!("Failed to create texture dir: {e}"))?;
+    let mut command = Command::new(bridge);
+    command
+        .arg("--extract-ytd")
+        .arg(ytd)
+        .arg("--dest")
+        .arg(out_dir)
+        .arg("--format")
+        .arg(format.as_str());
+    let cleanup = if owned {
+        vec![out_dir.to_path_buf()]
+    } else {
+        Vec::new()
+    };
+    let textures = crate::run_codewalker_bridge(ctx, command, &cleanup)?;
+    if !textures.is_array() {
+        return Err("CodeWalker bridge did not report the extracted textures.".to_string());
+    }
+    let _ = std::fs::write(&manifest, textures.to_string());
+    Ok(textures)
+}
+
+fn read_manifest(path: &Path) -> Option<serde_json::Value> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let textures = serde_json::from_str::<serde_json::Value>(&contents).ok()?;
+    let complete = textures.as_array()?.iter().all(|texture| {
+        texture
+            .get("path")
+            .and_then(|path| path.as_str())
+            .is_some_and(|path| Path::new(path).is_file())
+    });
+    complete.then_some(textures)
+}
+
+/// The `{name, path}` texture files a parse listed in the `meta.json` of a
+/// cached mesh directory.
+pub fn recorded_files(mesh_dir: &Path) -> Vec<(String, PathBuf)> {
+    let Some(meta) = std::fs::read(crate::paths::long(&mesh_dir.join("meta.json")))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+    else {
+        return Vec::new();
+    };
+    meta.get("textureFiles")
+        .and_then(|files| files.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|file| {
+            let name = file.get("name")?.as_str()?;
+            let path = file.get("path")?.as_str()?;
+            Some((name.to_string(), PathBuf::from(path)))
+        })
+        .collect()
+}
+
+/// Where the image for texture `name` is on disk: its recorded file, else
+/// `<name>.png` in one of `dirs`.
+pub fn find_file(name: &str, recorded: &[(String, PathBuf)], dirs: &[&Path]) -> Option<PathBuf> {
+    recorded
+        .iter()
+        .filter(|(recorded, _)| recorded.eq_ignore_ascii_case(name))
+        .map(|(_, path)| path.clone())
+        .chain(dirs.iter().map(|dir| dir.join(format!("{name}.png"))))
+        .find(|path| crate::paths::long(path).is_file())
+}
+
+/// Extract the textures of a .ytd as `dds` or `png` (the default) files, one
+/// per texture named after it. They go to `out_dir` when given and to the
+/// cache otherwise, keyed like the model cache. Returns
+/// `[{name, width, height, format, sourceFormat, path}]`.
+#[tauri::command]
+pub async fn extract_ytd(
+    path: String,
+    out_dir: Option<String>,
+    format: Option<String>,
+    app: crate::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let format = TextureFormat::parse(format.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "ytd") {
+            return Err("Only .ytd files are supported by extract_ytd".to_string());
+        }
+        if !cfg!(target_os = "windows") {
+            return Err("YTD extraction is only supported on Windows in this build.".to_string());
+        }
+        let bridge = crate::find_codewalker_bridge(&app)?;
+
+        let (key, cache_dir) = texture_cache_dir(&app, &path, format)?;
+        let out_dir = out_dir
+            .filter(|dir| !dir.trim().is_empty())
+            .map(PathBuf::from);
+        let owned = out_dir.is_none();
+        let out_dir = out_dir.unwrap_or(cache_dir);
+
+        let tasks = app.state::<ConversionTasks>();
+        let ctx = ConversionContext {
+            app: &app,
+            tasks: &tasks,
+            key: &key,
+            events: "textures",
+            timeout: conversion::process_timeout(None),
+            verbose: false,
+        };
+        let _in_flight = ctx.claim();
+        let textures = extract(&ctx, &bridge, &path, &out_dir, format, owned)?;
+        if owned {
+            cache::record_use(&app, &key);
+        }
+        Ok(textures)
+    })
+    .await
+    .map_err(|e| format!("Failed to join YTD extraction task: {e}"))?
+}
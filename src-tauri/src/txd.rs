@@ -0,0 +1,1297 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    dxt::{self, Compression},
+    images::{self, DdsPixels},
+    paths,
+    rw::{
+        c_string, child, children, chunk_at, read_u32_le, Chunk, Cursor, CHUNK_EXTENSION,
+        CHUNK_STRUCT,
+    },
+    textures::TextureFormat,
+};
+
+const CHUNK_TEXTURE_NATIVE: u32 = 0x15;
+const CHUNK_TEXTURE_DICTIONARY: u32 = 0x16;
+
+const PLATFORM_D3D8: u32 = 8;
+const PLATFORM_D3D9: u32 = 9;
+
+// Raster format bits.
+const RASTER_FORMAT_MASK: u32 = 0x0F00;
+const RASTER_PAL8: u32 = 0x2000;
+const RASTER_PAL4: u32 = 0x4000;
+
+// Raster formats written for new textures.
+const RASTER_1555: u32 = 0x100;
+const RASTER_565: u32 = 0x200;
+const RASTER_4444: u32 = 0x300;
+const RASTER_8888: u32 = 0x500;
+const RASTER_TYPE_TEXTURE: u8 = 4;
+// Linear filtering, wrapped in both directions; built textures have a
+// single level, so no mipmap filtering.
+const FILTER_LINEAR_WRAP: u32 = 0x1102;
+const D3DFMT_A8R8G8B8: u32 = 21;
+
+// D3D9 stores compressed formats as their FourCC.
+const FOURCC_DXT1: u32 = u32::from_le_bytes(*b"DXT1");
+const FOURCC_DXT3: u32 = u32::from_le_bytes(*b"DXT3");
+const FOURCC_DXT5: u32 = u32::from_le_bytes(*b"DXT5");
+
+/// One texture of a dictionary, as `list_txd` reports it.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TxdTexture {
+    pub name: String,
+    pub mask_name: Option<String>,
+    pub width: u16,
+    pub height: u16,
+    /// `DXT1`, `DXT3`, `DXT5`, `8888`, `888`, `565`, `1555`, `555`, `4444`,
+    /// `LUM8`, `PAL8` or `PAL4`.
+    pub format: String,
+    /// The raw RenderWare raster format.
+    pub raster_format: u32,
+    /// `d3d8` or `d3d9`.
+    pub platform: &'static str,
+    pub mipmap_count: u8,
+    pub has_alpha: bool,
+}
+
+/// The first texture dictionary in `data`.
+fn dictionary(data: &[u8]) -> Result<Chunk, String> {
+    let mut offset = 0;
+    while offset < data.len() {
+        let chunk = chunk_at(data, offset, data.len())?;
+        if chunk.kind == CHUNK_TEXTURE_DICTIONARY {
+            return Ok(chunk);
+        }
+        offset = chunk.end;
+    }
+    Err("The file contains no texture dictionary.".to_string())
+}
+
+/// Every texture native in the first dictionary of a `.txd`, in stored
+/// order. PC (D3D8 and D3D9) layouts only.
+pub fn list(data: &[u8]) -> Result<Vec<TxdTexture>, String> {
+    Ok(natives(data)?
+        .into_iter()
+        .map(|(texture, _)| texture)
+        .collect())
+}
+
+fn natives(data: &[u8]) -> Result<Vec<(TxdTexture, Cursor<'_>)>, String> {
+    let dictionary = dictionary(data)?;
+    children(data, &dictionary)?
+        .iter()
+        .filter(|chunk| chunk.kind == CHUNK_TEXTURE_NATIVE)
+        .map(|chunk| read_native(data, chunk))
+        .collect()
+}
+
+/// The header of a texture native, and a cursor at the palette and mipmap
+/// levels that follow it.
+fn read_native<'a>(data: &'a [u8], chunk: &Chunk) -> Result<(TxdTexture, Cursor<'a>), String> {
+    let parts = children(data, chunk)?;
+    let header = child(&parts, CHUNK_STRUCT).ok_or_else(|| {
+        format!(
+            "Texture native at offset {:#x} has no struct.",
+            chunk.offset
+        )
+    })?;
+    let mut cursor = Cursor::new(data, header, "Texture native struct");
+    let platform_id = cursor.u32()?;
+    let platform = match platform_id {
+        PLATFORM_D3D8 => "d3d8",
+        PLATFORM_D3D9 => "d3d9",
+        other => {
+            return Err(format!(
+                "Texture native at offset {:#x} is for platform {other:#x}; only PC (D3D8 and D3D9) textures are supported.",
+                chunk.offset
+            ))
+        }
+    };
+    cursor.u32()?; // filter and addressing
+    let name = c_string(cursor.take(32)?);
+    let mask_name = Some(c_string(cursor.take(32)?)).filter(|mask| !mask.is_empty());
+    let raster_format = cursor.u32()?;
+    // D3D8 has an alpha flag here, D3D9 the Direct3D format.
+    let alpha_or_d3d_format = cursor.u32()?;
+    let width = cursor.u16()?;
+    let height = cursor.u16()?;
+    cursor.u8()?; // depth
+    let mipmap_count = cursor.u8()?;
+    cursor.u8()?; // raster type
+
+    // D3D8: the DXT type, 0 if uncompressed. D3D9: flags, bit 0 alpha and
+    // bit 3 compressed.
+    let compression_or_flags = cursor.u8()?;
+
+    let (format, has_alpha) = if platform_id == PLATFORM_D3D8 {
+        let format = match compression_or_flags {
+            0 => raster_format_name(raster_format),
+            1 => Some("DXT1"),
+            3 => Some("DXT3"),
+            5 => Some("DXT5"),
+            _ => None,
+        };
+        (format, alpha_or_d3d_format != 0)
+    } else {
+        let format = match alpha_or_d3d_format {
+            FOURCC_DXT1 => Some("DXT1"),
+            FOURCC_DXT3 => Some("DXT3"),
+            FOURCC_DXT5 => Some("DXT5"),
+            _ => raster_format_name(raster_format),
+        };
+        (format, compression_or_flags & 1 != 0)
+    };
+    let format = match format {
+        Some(format) => format.to_string(),
+        None => format!("unknown ({raster_format:#x})"),
+    };
+
+    let texture = TxdTexture {
+        name,
+        mask_name,
+        width,
+        height,
+        format,
+        raster_format,
+        platform,
+        mipmap_count,
+        has_alpha,
+    };
+    Ok((texture, cursor))
+}
+
+/// Mipmap level 0 of the texture named `name` (case-insensitively, like the
+/// game) as RGBA: `(width, height, pixels)`. Decodes DXT1/3/5, 8888, 888 and
+/// PAL8; other formats are an error naming the raster format.
+pub fn decode(data: &[u8], name: &str) -> Result<(usize, usize, Vec<u8>), String> {
+    let (texture, cursor) = natives(data)?
+        .into_iter()
+        .find(|(texture, _)| texture.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("The TXD has no texture named `{name}`."))?;
+    let rgba = decode_native(&texture, cursor)?;
+    Ok((
+        usize::from(texture.width),
+        usize::from(texture.height),
+        rgba,
+    ))
+}
+
+/// Mipmap level 0 of a texture native as RGBA, `cursor` being where
+/// `read_native` left off.
+fn decode_native(texture: &TxdTexture, mut cursor: Cursor) -> Result<Vec<u8>, String> {
+    let (width, height) = (usize::from(texture.width), usize::from(texture.height));
+    let palette = if texture.format == "PAL8" {
+        Some(cursor.take(256 * 4)?)
+    } else {
+        None
+    };
+    let level_size = cursor.u32()? as usize;
+    let level = cursor.take(level_size)?;
+
+    let expected = match texture.format.as_str() {
+        "DXT1" => width.div_ceil(4) * height.div_ceil(4) * 8,
+        "DXT3" | "DXT5" => width.div_ceil(4) * height.div_ceil(4) * 16,
+        "8888" | "888" => width * height * 4,
+        "PAL8" => width * height,
+        _ => {
+            return Err(format!(
+                "Texture `{}` uses raster format {:#x} ({}), which can't be decoded.",
+                texture.name, texture.raster_format, texture.format
+            ))
+        }
+    };
+    if level.len() < expected {
+        return Err(format!(
+            "Texture `{}` has {} bytes of pixel data; a {width}x{height} {} needs {expected}.",
+            texture.name,
+            level.len(),
+            texture.format
+        ));
+    }
+
+    let mut rgba = vec![0u8; width * height * 4];
+    match texture.format.as_str() {
+        "DXT1" | "DXT3" | "DXT5" => dxt::decode(level, &texture.format, width, height, &mut rgba),
+        "8888" | "888" => {
+            // Stored as Direct3D's little-endian ARGB, so BGRA in memory.
+            let opaque = texture.format == "888";
+            for (pixel, bgra) in rgba.chunks_exact_mut(4).zip(level.chunks_exact(4)) {
+                pixel.copy_from_slice(&[
+                    bgra[2],
+                    bgra[1],
+                    bgra[0],
+                    if opaque { 255 } else { bgra[3] },
+                ]);
+            }
+        }
+        _ => {
+            // PAL8; palette entries are RGBA.
+            let palette = palette.unwrap_or_default();
+            for (pixel, index) in rgba.chunks_exact_mut(4).zip(level) {
+                let entry = usize::from(*index) * 4;
+                pixel.copy_from_slice(&palette[entry..entry + 4]);
+            }
+        }
+    }
+    Ok(rgba)
+}
+
+/// The raw data of every mipmap level of a texture native, largest first.
+fn levels<'a>(texture: &TxdTexture, mut cursor: Cursor<'a>) -> Result<Vec<&'a [u8]>, String> {
+    if texture.format == "PAL8" {
+        cursor.take(256 * 4)?;
+    }
+    (0..texture.mipmap_count.max(1))
+        .map(|_| {
+            let size = cursor.u32()? as usize;
+            cursor.take(size)
+        })
+        .collect()
+}
+
+/// One file written by `export_textures`.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedTexture {
+    pub name: String,
+    pub path: String,
+    pub width: u16,
+    pub height: u16,
+    /// The file name had to get a numeric suffix because an earlier texture
+    /// already took it, ignoring case.
+    pub renamed: bool,
+}
+
+/// Write every texture of a `.txd` into `out_dir` as `<name>.png` or
+/// `<name>.dds`. DDS keeps DXT textures' blocks and mipmaps as stored and
+/// holds the other formats as decoded RGBA.
+pub fn export_textures(
+    data: &[u8],
+    out_dir: &Path,
+    format: TextureFormat,
+) -> Result<Vec<ExportedTexture>, String> {
+    let mut used = HashSet::new();
+    let mut exported = Vec::new();
+    for (texture, cursor) in natives(data)? {
+        // Names are stored raw; keep anything that would leave out_dir or
+        // isn't allowed in a file name out of the path.
+        let stem = texture
+            .name
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect::<String>();
+        let stem = match stem.trim_matches('.') {
+            "" => "texture".to_string(),
+            stem => stem.to_string(),
+        };
+        // Windows file names ignore case, so compare lowercased; a suffixed
+        // name can itself collide with a later texture's own name.
+        let mut candidate = stem.clone();
+        let mut suffix = 1;
+        while !used.insert(candidate.to_lowercase()) {
+            suffix += 1;
+            candidate = format!("{stem}_{suffix}");
+        }
+        let renamed = suffix > 1;
+        let stem = candidate;
+
+        let path = out_dir.join(format!("{stem}.{}", format.as_str()));
+        if path.parent() != Some(out_dir) {
+            return Err(format!(
+                "Texture `{}` would be written outside the output folder.",
+                texture.name
+            ));
+        }
+
+        let (width, height) = (usize::from(texture.width), usize::from(texture.height));
+        let fourcc = match texture.format.as_str() {
+            "DXT1" => Some(b"DXT1"),
+            "DXT3" => Some(b"DXT3"),
+            "DXT5" => Some(b"DXT5"),
+            _ => None,
+        };
+        match (format, fourcc) {
+            (TextureFormat::Dds, Some(fourcc)) => {
+                let levels = levels(&texture, cursor)?;
+                images::write_dds(
+                    &path,
+                    width,
+                    height,
+                    DdsPixels::Compressed {
+                        fourcc,
+                        levels: &levels,
+                    },
+                )?;
+            }
+            (TextureFormat::Dds, None) => {
+                let rgba = decode_native(&texture, cursor)?;
+                images::write_dds(&path, width, height, DdsPixels::Rgba(&rgba))?;
+            }
+            (TextureFormat::Png, _) => {
+                let rgba = decode_native(&texture, cursor)?;
+                images::write_png(&path, width, height, &rgba)?;
+            }
+        }
+        exported.push(ExportedTexture {
+            name: texture.name,
+            path: path.to_string_lossy().to_string(),
+            width: texture.width,
+            height: texture.height,
+            renamed,
+        });
+    }
+    Ok(exported)
+}
+
+/// The pixel format a raster format names, palettes first.
+fn raster_format_name(raster_format: u32) -> Option<&'static str> {
+    if raster_format & RASTER_PAL8 != 0 {
+        return Some("PAL8");
+    }
+    if raster_format & RASTER_PAL4 != 0 {
+        return Some("PAL4");
+    }
+    match raster_format & RASTER_FORMAT_MASK {
+        0x100 => Some("1555"),
+        0x200 => Some("565"),
+        0x300 => Some("4444"),
+        0x400 => Some("LUM8"),
+        0x500 => Some("8888"),
+        0x600 => Some("888"),
+        0xA00 => Some("555"),
+        _ => None,
+    }
+}
+
+/// How to store a texture written into a TXD.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TxdCompression {
+    /// Whatever the replaced texture used: DXT when it was compressed (DXT5
+    /// if the new image has alpha, DXT1 otherwise), 8888 when it wasn't.
+    Auto,
+    Dxt1,
+    Dxt5,
+    /// Uncompressed 8888.
+    None,
+}
+
+impl TxdCompression {
+    pub fn parse(compress: Option<&str>) -> Result<Self, String> {
+        match compress.map(str::trim) {
+            None | Some("") | Some("auto") => Ok(Self::Auto),
+            Some("dxt1") => Ok(Self::Dxt1),
+            Some("dxt5") => Ok(Self::Dxt5),
+            Some("none") => Ok(Self::None),
+            Some(other) => Err(format!(
+                "Unknown compression `{other}` (expected `auto`, `dxt1`, `dxt5` or `none`)"
+            )),
+        }
+    }
+}
+
+/// A texture to write as a texture native.
+pub struct NewTexture<'a> {
+    pub name: &'a str,
+    pub mask_name: &'a str,
+    pub filter_flags: u32,
+    pub width: usize,
+    pub height: usize,
+    pub rgba: &'a [u8],
+    pub compression: Option<Compression>,
+}
+
+fn push_chunk(out: &mut Vec<u8>, kind: u32, library_id: u32, body: &[u8]) {
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(&library_id.to_le_bytes());
+    out.extend_from_slice(body);
+}
+
+/// A name field of `len` bytes, always null-terminated.
+fn push_name(out: &mut Vec<u8>, name: &str, len: usize) {
+    let mut field = vec![0u8; len];
+    let bytes = name.as_bytes();
+    let used = bytes.len().min(len - 1);
+    field[..used].copy_from_slice(&bytes[..used]);
+    out.extend_from_slice(&field);
+}
+
+/// A complete texture native chunk for `platform_id` (D3D8 or D3D9) with a
+/// single mipmap level. `extension` is the raw extension chunk to keep, if
+/// any; otherwise an empty one is written.
+pub fn native_chunk(
+    texture: &NewTexture,
+    platform_id: u32,
+    library_id: u32,
+    extension: Option<&[u8]>,
+) -> Vec<u8> {
+    let has_alpha = texture.rgba.chunks_exact(4).any(|pixel| pixel[3] < 255);
+    let (raster_format, depth, pixels) = match texture.compression {
+        Some(compression) => {
+            let raster_format = match compression {
+                Compression::Dxt1 if has_alpha => RASTER_1555,
+                Compression::Dxt1 => RASTER_565,
+                Compression::Dxt5 => RASTER_4444,
+            };
+            let blocks = dxt::encode(texture.rgba, texture.width, texture.height, compression);
+            (raster_format, 16u8, blocks)
+        }
+        None => {
+            // Direct3D's ARGB is BGRA in memory.
+            let bgra = texture
+                .rgba
+                .chunks_exact(4)
+                .flat_map(|p| [p[2], p[1], p[0], p[3]])
+                .collect();
+            (RASTER_8888, 32u8, bgra)
+        }
+    };
+
+    let mut header = Vec::with_capacity(88 + pixels.len() + 4);
+    header.extend_from_slice(&platform_id.to_le_bytes());
+    header.extend_from_slice(&texture.filter_flags.to_le_bytes());
+    push_name(&mut header, texture.name, 32);
+    push_name(&mut header, texture.mask_name, 32);
+    header.extend_from_slice(&raster_format.to_le_bytes());
+    let (format_word, last_byte) = if platform_id == PLATFORM_D3D8 {
+        let compression = match texture.compression {
+            Some(Compression::Dxt1) => 1,
+            Some(Compression::Dxt5) => 5,
+            None => 0,
+        };
+        (u32::from(has_alpha), compression)
+    } else {
+        let d3d_format = match texture.compression {
+            Some(Compression::Dxt1) => FOURCC_DXT1,
+            Some(Compression::Dxt5) => FOURCC_DXT5,
+            None => D3DFMT_A8R8G8B8,
+        };
+        let flags = u8::from(has_alpha)
+            | if texture.compression.is_some() {
+                0x8
+            } else {
+                0
+            };
+        (d3d_format, flags)
+    };
+    header.extend_from_slice(&format_word.to_le_bytes());
+    header.extend_from_slice(&(texture.width as u16).to_le_bytes());
+    header.extend_from_slice(&(texture.height as u16).to_le_bytes());
+    header.extend_from_slice(&[depth, 1, RASTER_TYPE_TEXTURE, last_byte]);
+    header.extend_from_slice(&(pixels.len() as u32).to_le_bytes());
+    header.extend_from_slice(&pixels);
+
+    let mut body = Vec::with_capacity(header.len() + 24);
+    push_chunk(&mut body, CHUNK_STRUCT, library_id, &header);
+    match extension {
+        Some(extension) => body.extend_from_slice(extension),
+        None => push_chunk(&mut body, CHUNK_EXTENSION, library_id, &[]),
+    }
+    let mut chunk = Vec::with_capacity(body.len() + 12);
+    push_chunk(&mut chunk, CHUNK_TEXTURE_NATIVE, library_id, &body);
+    chunk
+}
+
+/// Replace the texture named `name` in `data` with `rgba`, keeping its
+/// name, mask, filtering, platform and extensions. Returns the new file.
+pub fn replace_texture(
+    data: &[u8],
+    name: &str,
+    width: usize,
+    height: usize,
+    rgba: &[u8],
+    compress: TxdCompression,
+) -> Result<Vec<u8>, String> {
+    let dictionary = dictionary(data)?;
+    let natives = children(data, &dictionary)?;
+    let (chunk, texture) = natives
+        .iter()
+        .filter(|chunk| chunk.kind == CHUNK_TEXTURE_NATIVE)
+        .map(|chunk| read_native(data, chunk).map(|(texture, _)| (chunk, texture)))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .find(|(_, texture)| texture.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("The TXD has no texture named `{name}`."))?;
+
+    if (width, height) != (usize::from(texture.width), usize::from(texture.height))
+        && !(width.is_power_of_two() && height.is_power_of_two() && width <= 4096 && height <= 4096)
+    {
+        return Err(format!(
+            "The image is {width}x{height}; a texture whose size changes must be a power of two on each side (up to 4096)."
+        ));
+    }
+
+    let has_alpha = rgba.chunks_exact(4).any(|pixel| pixel[3] < 255);
+    let was_compressed = texture.format.starts_with("DXT");
+    let compression = match compress {
+        TxdCompression::Auto if was_compressed && has_alpha => Some(Compression::Dxt5),
+        TxdCompression::Auto if was_compressed => Some(Compression::Dxt1),
+        TxdCompression::Auto | TxdCompression::None => None,
+        TxdCompression::Dxt1 => Some(Compression::Dxt1),
+        TxdCompression::Dxt5 => Some(Compression::Dxt5),
+    };
+
+    let parts = children(data, chunk)?;
+    let header = child(&parts, CHUNK_STRUCT).ok_or_else(|| {
+        format!(
+            "Texture native at offset {:#x} has no struct.",
+            chunk.offset
+        )
+    })?;
+    let word = |offset: usize| read_u32_le(data, offset).unwrap_or(0);
+    let native = native_chunk(
+        &NewTexture {
+            name: &texture.name,
+            mask_name: texture.mask_name.as_deref().unwrap_or(""),
+            filter_flags: word(header.start + 4),
+            width,
+            height,
+            rgba,
+            compression,
+        },
+        word(header.start),
+        word(chunk.offset + 8),
+        child(&parts, CHUNK_EXTENSION).map(|extension| &data[extension.offset..extension.end]),
+    );
+
+    // The dictionary is the only chunk around the natives, so its size is
+    // the only one to fix up.
+    let mut out = Vec::with_capacity(data.len() + native.len());
+    out.extend_from_slice(&data[..chunk.offset]);
+    out.extend_from_slice(&native);
+    out.extend_from_slice(&data[chunk.end..]);
+    let dictionary_size =
+        (dictionary.end - dictionary.start) - (chunk.end - chunk.offset) + native.len();
+    let size_field = dictionary.offset + 4;
+    out[size_field..size_field + 4].copy_from_slice(&(dictionary_size as u32).to_le_bytes());
+    Ok(out)
+}
+
+/// The game a built dictionary is for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TxdVersion {
+    /// RW 3.6, D3D9 texture natives.
+    SanAndreas,
+    /// RW 3.3, D3D8 texture natives.
+    ViceCity,
+}
+
+impl TxdVersion {
+    pub fn parse(version: Option<&str>) -> Result<Self, String> {
+        match version.map(str::trim) {
+            None | Some("") | Some("sa") => Ok(Self::SanAndreas),
+            Some("vc") => Ok(Self::ViceCity),
+            Some(other) => Err(format!(
+                "Unknown TXD version `{other}` (expected `sa` or `vc`)"
+            )),
+        }
+    }
+
+    fn library_id(self) -> u32 {
+        match self {
+            Self::SanAndreas => 0x1803_FFFF,
+            Self::ViceCity => 0x0C02_FFFF,
+        }
+    }
+
+    fn platform_id(self) -> u32 {
+        match self {
+            Self::SanAndreas => PLATFORM_D3D9,
+            Self::ViceCity => PLATFORM_D3D8,
+        }
+    }
+
+    /// Device id in the dictionary struct.
+    fn device_id(self) -> u16 {
+        match self {
+            Self::SanAndreas => 2,
+            Self::ViceCity => 1,
+        }
+    }
+}
+
+/// What `build_txd` wrote.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuiltTxd {
+    pub path: String,
+    pub texture_count: usize,
+    pub bytes_written: u64,
+}
+
+/// A texture dictionary of every `.png` and `.tga` in `image_dir`, named
+/// after the files. With `compress`, textures with alpha are DXT5 and the
+/// rest DXT1; otherwise all are 8888.
+pub fn build(image_dir: &Path, version: TxdVersion, compress: bool) -> Result<Vec<u8>, String> {
+    let mut files = std::fs::read_dir(paths::long(image_dir))
+        .map_err(|e| format!("Failed to read image folder: {e}"))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && ["png", "tga"]
+                    .iter()
+                    .any(|ext| crate::has_extension(&path.to_string_lossy(), ext))
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    if files.is_empty() {
+        return Err("The folder has no .png or .tga images.".to_string());
+    }
+
+    let library_id = version.library_id();
+    let mut names = HashMap::new();
+    let mut natives = Vec::new();
+    for file in &files {
+        let file_name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let name = file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // The name field is 32 bytes including its terminator.
+        if name.len() > 31 {
+            return Err(format!(
+                "{file_name}: texture names are limited to 31 characters."
+            ));
+        }
+        if let Some(other) = names.insert(name.to_lowercase(), file_name.clone()) {
+            return Err(format!(
+                "{file_name} and {other} would both be named `{name}`."
+            ));
+        }
+
+        let (width, height, rgba) = images::read_image(file)?;
+        if !(width.is_power_of_two() && height.is_power_of_two() && width <= 4096 && height <= 4096)
+        {
+            return Err(format!(
+                "{file_name} is {width}x{height}; textures must be a power of two on each side (up to 4096)."
+            ));
+        }
+        let compression = compress.then(|| {
+            if rgba.chunks_exact(4).any(|pixel| pixel[3] < 255) {
+                Compression::Dxt5
+            } else {
+                Compression::Dxt1
+            }
+        });
+        natives.push(native_chunk(
+            &NewTexture {
+                name: &name,
+                mask_name: "",
+                filter_flags: FILTER_LINEAR_WRAP,
+                width,
+                height,
+                rgba: &rgba,
+                compression,
+            },
+            version.platform_id(),
+            library_id,
+            None,
+        ));
+    }
+
+    let mut header = Vec::with_capacity(4);
+    header.extend_from_slice(&(natives.len() as u16).to_le_bytes());
+    header.extend_from_slice(&version.device_id().to_le_bytes());
+    let mut body = Vec::new();
+    push_chunk(&mut body, CHUNK_STRUCT, library_id, &header);
+    for native in &natives {
+        body.extend_from_slice(native);
+    }
+    push_chunk(&mut body, CHUNK_EXTENSION, library_id, &[]);
+    let mut dictionary = Vec::with_capacity(body.len() + 12);
+    push_chunk(&mut dictionary, CHUNK_TEXTURE_DICTIONARY, library_id, &body);
+    Ok(dictionary)
+}
+
+/// Build a .txd at `out_path` from every .png and .tga in `image_dir`, for
+/// `sa` (the default) or `vc`. With `compress`, images with alpha become
+/// DXT5 and the rest DXT1; otherwise 8888. Every image must be a power of
+/// two on each side and its name at most 31 characters. Returns
+/// `{path, textureCount, bytesWritten}`.
+#[tauri::command]
+pub async fn build_txd(
+    image_dir: String,
+    out_path: String,
+    version: Option<String>,
+    compress: bool,
+) -> Result<BuiltTxd, String> {
+    let version = TxdVersion::parse(version.as_deref())?;
+    if !crate::has_extension(&out_path, "txd") {
+        return Err(format!("build_txd writes a .txd; `{out_path}` isn't one."));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let dictionary = build(Path::new(&image_dir), version, compress)?;
+        let texture_count = list(&dictionary)?.len();
+
+        let out_path = PathBuf::from(out_path);
+        if let Some(parent) = out_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(paths::long(parent))
+                .map_err(|e| format!("Failed to create output dir: {e}"))?;
+        }
+        std::fs::write(paths::long(&out_path), &dictionary)
+            .map_err(|e| format!("Failed to write TXD: {e}"))?;
+        Ok(BuiltTxd {
+            path: out_path.to_string_lossy().to_string(),
+            texture_count,
+            bytes_written: dictionary.len() as u64,
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to join TXD build task: {e}"))?
+}
+
+/// Replace the texture `texture_name` of a .txd with a PNG or TGA image,
+/// stored per `compress`: `auto` (the default), `dxt1`, `dxt5` or `none`.
+/// The image may change the texture's size only to another power of two.
+/// The file is replaced atomically and the original kept as `<txd>.bak`.
+/// Returns the texture as `list_txd` now reports it.
+#[tauri::command]
+pub async fn replace_txd_texture(
+    txd_path: String,
+    texture_name: String,
+    image_path: String,
+    compress: Option<String>,
+) -> Result<TxdTexture, String> {
+    let compress = TxdCompression::parse(compress.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&txd_path, "txd") {
+            return Err("Only .txd files are supported by replace_txd_texture".to_string());
+        }
+        let txd_path = Path::new(&txd_path);
+        let data =
+            std::fs::read(paths::long(txd_path)).map_err(|e| format!("Failed to read TXD: {e}"))?;
+        let (width, height, rgba) = images::read_image(Path::new(&image_path))?;
+        let replaced = replace_texture(&data, &texture_name, width, height, &rgba, compress)?;
+
+        // Make sure the result reads back before it replaces anything.
+        let texture = list(&replaced)?
+            .into_iter()
+            .find(|texture| texture.name.eq_ignore_ascii_case(&texture_name))
+            .ok_or_else(|| "The rewritten TXD lost the texture.".to_string())?;
+        paths::replace_file(txd_path, &replaced, "TXD")?;
+        Ok(texture)
+    })
+    .await
+    .map_err(|e| format!("Failed to join TXD replace task: {e}"))?
+}
+
+/// Write every texture of a .txd into `out_dir` (created if needed) as
+/// `png` (the default) or `dds` files named after the textures. Names that
+/// only differ in case get a numeric suffix and `renamed: true`. Returns
+/// `[{name, path, width, height, renamed}]`.
+#[tauri::command]
+pub async fn export_txd_textures(
+    path: String,
+    out_dir: String,
+    format: Option<String>,
+) -> Result<Vec<ExportedTexture>, String> {
+    let format = TextureFormat::parse(format.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "txd") {
+            return Err("Only .txd files are supported by export_txd_textures".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read TXD: {e}"))?;
+        let out_dir = crate::create_dir(&out_dir)?;
+        export_textures(&data, &out_dir, format)
+    })
+    .await
+    .map_err(|e| format!("Failed to join TXD export task: {e}"))?
+}
+
+/// Decode mipmap level 0 of the texture `texture_name` in a .txd to RGBA,
+/// returned like `decode_pdn` as a binary response with `{width, height}`,
+/// or with `legacy_base64` as `{width, height, rgba_base64}`. Supports
+/// DXT1/3/5, 8888, 888 and PAL8 rasters.
+#[tauri::command]
+pub async fn decode_txd_texture(
+    path: String,
+    texture_name: String,
+    legacy_base64: Option<bool>,
+) -> Result<tauri::ipc::Response, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "txd") {
+            return Err("Only .txd files are supported by decode_txd_texture".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read TXD: {e}"))?;
+        let (width, height, rgba) = decode(&data, &texture_name)?;
+        let mut decoded = serde_json::json!({
+            "width": width,
+            "height": height
+        });
+        if !legacy_base64.unwrap_or(false) {
+            return Ok(images::rgba_response(&decoded, rgba));
+        }
+
+        use base64::Engine;
+        decoded["rgba_base64"] = base64::engine::general_purpose::STANDARD
+            .encode(&rgba)
+            .into();
+        Ok(tauri::ipc::Response::new(decoded.to_string()))
+    })
+    .await
+    .map_err(|e| format!("Failed to join TXD decode task: {e}"))?
+}
+
+/// The textures in a .txd (such as a conversion's `txdPath`), as
+/// `[{name, maskName, width, height, format, rasterFormat, platform,
+/// mipmapCount, hasAlpha}]`. Reads PC D3D8 and D3D9 texture natives.
+#[tauri::command]
+pub async fn list_txd(path: String) -> Result<Vec<TxdTexture>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if !crate::has_extension(&path, "txd") {
+            return Err("Only .txd files are supported by list_txd".to_string());
+        }
+        let data = std::fs::read(paths::long(Path::new(&path)))
+            .map_err(|e| format!("Failed to read TXD: {e}"))?;
+        list(&data)
+    })
+    .await
+    .map_err(|e| format!("Failed to join TXD listing task: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SA: u32 = 0x1803_FFFF;
+
+    /// A dictionary of `natives`, laid out like `build` writes one.
+    fn dictionary_of(natives: &[Vec<u8>]) -> Vec<u8> {
+        let mut body = Vec::new();
+        push_chunk(&mut body, CHUNK_STRUCT, SA, &[natives.len() as u8, 0, 2, 0]);
+        for native in natives {
+            body.extend_from_slice(native);
+        }
+        push_chunk(&mut body, CHUNK_EXTENSION, SA, &[]);
+        let mut dictionary = Vec::new();
+        push_chunk(&mut dictionary, CHUNK_TEXTURE_DICTIONARY, SA, &body);
+        dictionary
+    }
+
+    fn texture<'a>(
+        name: &'a str,
+        size: usize,
+        rgba: &'a [u8],
+        compression: Option<Compression>,
+    ) -> NewTexture<'a> {
+        NewTexture {
+            name,
+            mask_name: "",
+            filter_flags: FILTER_LINEAR_WRAP,
+            width: size,
+            height: size,
+            rgba,
+            compression,
+        }
+    }
+
+    fn solid(size: usize, pixel: [u8; 4]) -> Vec<u8> {
+        pixel.repeat(size * size)
+    }
+
+    /// Every chunk's size field, checked against what it holds.
+    fn assert_sizes(data: &[u8]) {
+        let dictionary = chunk_at(data, 0, data.len()).unwrap();
+        assert_eq!(dictionary.end, data.len());
+        for native in children(data, &dictionary)
+            .unwrap()
+            .iter()
+            .filter(|chunk| chunk.kind == CHUNK_TEXTURE_NATIVE)
+        {
+            let parts = children(data, native).unwrap();
+            assert_eq!(parts.last().unwrap().end, native.end);
+        }
+    }
+
+    #[test]
+    fn replace_keeps_other_textures_and_rewrites_chunk_sizes() {
+        // An extension the replaced texture should keep.
+        let mut extension = Vec::new();
+        push_chunk(&mut extension, CHUNK_EXTENSION, SA, &[1, 2, 3, 4]);
+        let kept = native_chunk(
+            &texture("kept", 4, &solid(4, [1, 2, 3, 255]), None),
+            PLATFORM_D3D9,
+            SA,
+            None,
+        );
+        let original = native_chunk(
+            &texture(
+                "paint",
+                8,
+                &solid(8, [255, 0, 0, 255]),
+                Some(Compression::Dxt1),
+            ),
+            PLATFORM_D3D9,
+            SA,
+            Some(&extension),
+        );
+        let data = dictionary_of(&[kept.clone(), original]);
+
+        // Grows to 16x16 with alpha, so DXT1 becomes DXT5.
+        let size = 16;
+        let rgba: Vec<u8> = (0..size * size)
+            .flat_map(|i| {
+                let (x, y) = (i % size, i / size);
+                let gray = ((x + y) * 8) as u8;
+                [gray, gray, gray, 255 - (x * 8) as u8]
+            })
+            .collect();
+        let out = replace_texture(&data, "PAINT", size, size, &rgba, TxdCompression::Auto).unwrap();
+        assert_sizes(&out);
+
+        let textures = list(&out).unwrap();
+        assert_eq!(textures.len(), 2);
+        assert_eq!(textures[1].name, "paint");
+        assert_eq!(textures[1].format, "DXT5");
+        assert_eq!((textures[1].width, textures[1].height), (16, 16));
+        assert!(textures[1].has_alpha);
+        // The other texture and the replaced one's extension are untouched.
+        let dictionary = chunk_at(&out, 0, out.len()).unwrap();
+        let natives = children(&out, &dictionary).unwrap();
+        assert_eq!(&out[natives[1].offset..natives[1].end], &kept[..]);
+        let parts = children(&out, &natives[2]).unwrap();
+        let extension_chunk = child(&parts, CHUNK_EXTENSION).unwrap();
+        assert_eq!(
+            &out[extension_chunk.offset..extension_chunk.end],
+            &extension[..]
+        );
+
+        let (width, height, decoded) = decode(&out, "paint").unwrap();
+        assert_eq!((width, height), (size, size));
+        for (got, want) in decoded.chunks(4).zip(rgba.chunks(4)) {
+            let error = (0..4)
+                .map(|channel| got[channel].abs_diff(want[channel]))
+                .max();
+            assert!(error <= Some(12), "{got:?} vs {want:?}");
+        }
+
+        // Uncompressed, the pixels come back exactly.
+        let out = replace_texture(&out, "paint", size, size, &rgba, TxdCompression::None).unwrap();
+        assert_sizes(&out);
+        assert_eq!(list(&out).unwrap()[1].format, "8888");
+        assert_eq!(decode(&out, "paint").unwrap().2, rgba);
+    }
+
+    #[test]
+    fn exported_texture_edited_and_replaced_on_disk() {
+        use tauri::async_runtime::block_on;
+
+        let kept = native_chunk(
+            &texture("kept", 4, &solid(4, [1, 2, 3, 255]), None),
+            PLATFORM_D3D9,
+            SA,
+            None,
+        );
+        let paint = native_chunk(
+            &texture("paint", 8, &solid(8, [255, 0, 0, 255]), None),
+            PLATFORM_D3D9,
+            SA,
+            None,
+        );
+        let original = dictionary_of(&[kept, paint]);
+        let dir = tempfile::tempdir().unwrap();
+        let txd = dir.path().join("car.txd");
+        std::fs::write(&txd, &original).unwrap();
+        let text = |path: &Path| path.to_string_lossy().to_string();
+
+        let exported = block_on(export_txd_textures(
+            text(&txd),
+            text(&dir.path().join("export")),
+            None,
+        ))
+        .unwrap();
+        let png = PathBuf::from(&exported[1].path);
+        let (width, height, mut rgba) = images::read_image(&png).unwrap();
+        assert_eq!((width, height), (8, 8));
+        // Paint the left half blue.
+        for (index, pixel) in rgba.chunks_mut(4).enumerate() {
+            if index % width < width / 2 {
+                pixel.copy_from_slice(&[0, 0, 255, 255]);
+            }
+        }
+        images::write_png(&png, width, height, &rgba).unwrap();
+
+        let replaced = block_on(replace_txd_texture(
+            text(&txd),
+            "paint".to_string(),
+            text(&png),
+            Some("none".to_string()),
+        ))
+        .unwrap();
+        assert_eq!(
+            (replaced.name.as_str(), replaced.format.as_str()),
+            ("paint", "8888")
+        );
+
+        let listed = block_on(list_txd(text(&txd))).unwrap();
+        let names: Vec<_> = listed.iter().map(|texture| texture.name.as_str()).collect();
+        assert_eq!(names, ["kept", "paint"]);
+        let data = std::fs::read(&txd).unwrap();
+        assert_eq!(decode(&data, "paint").unwrap().2, rgba);
+        assert_eq!(decode(&data, "kept").unwrap().2, solid(4, [1, 2, 3, 255]));
+        // The original is kept next to it and no temp file is left behind.
+        assert_eq!(
+            std::fs::read(dir.path().join("car.txd.bak")).unwrap(),
+            original
+        );
+        let mut files: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["car.txd", "car.txd.bak", "export"]);
+    }
+
+    #[test]
+    fn build_writes_only_txd_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let images = dir.path().join("images");
+        std::fs::create_dir(&images).unwrap();
+        images::write_png(&images.join("paint.png"), 4, 4, &solid(4, [9, 8, 7, 255])).unwrap();
+        let build = |out: &Path| {
+            tauri::async_runtime::block_on(build_txd(
+                images.to_string_lossy().to_string(),
+                out.to_string_lossy().to_string(),
+                None,
+                false,
+            ))
+        };
+
+        let png = dir.path().join("car.png");
+        let error = build(&png).err().unwrap();
+        assert!(error.contains("car.png"), "{error}");
+        assert!(!png.exists() && !png.with_extension("txd").exists());
+
+        let txd = dir.path().join("car.txd");
+        let built = build(&txd).unwrap();
+        assert_eq!(
+            (built.path.as_str(), built.texture_count),
+            (txd.to_string_lossy().as_ref(), 1)
+        );
+        let data = std::fs::read(&txd).unwrap();
+        assert_eq!(decode(&data, "paint").unwrap().2, solid(4, [9, 8, 7, 255]));
+    }
+
+    #[test]
+    fn export_gives_names_equal_apart_from_case_their_own_files() {
+        let natives: Vec<_> = [("Foo", 1), ("foo", 2), ("foo_2", 3)]
+            .into_iter()
+            .map(|(name, shade)| {
+                native_chunk(
+                    &texture(name, 4, &solid(4, [shade, 0, 0, 255]), None),
+                    PLATFORM_D3D9,
+                    SA,
+                    None,
+                )
+            })
+            .collect();
+        let dir = tempfile::tempdir().unwrap();
+        let exported =
+            export_textures(&dictionary_of(&natives), dir.path(), TextureFormat::Png).unwrap();
+
+        let files: Vec<_> = exported
+            .iter()
+            .map(|texture| {
+                let path = Path::new(&texture.path);
+                (path.file_name().unwrap().to_os_string(), texture.renamed)
+            })
+            .collect();
+        assert_eq!(
+            files,
+            [
+                ("Foo.png".into(), false),
+                ("foo_2.png".into(), true),
+                ("foo_2_2.png".into(), true),
+            ]
+        );
+        // Each file holds its own texture.
+        for (texture, shade) in exported.iter().zip(1..) {
+            let (_, _, rgba) = images::read_image(Path::new(&texture.path)).unwrap();
+            assert_eq!(rgba, solid(4, [shade, 0, 0, 255]), "{}", texture.name);
+        }
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn replace_rejects_unknown_names_and_odd_new_sizes() {
+        let native = native_chunk(
+            &texture("paint", 4, &solid(4, [0, 0, 0, 255]), None),
+            PLATFORM_D3D8,
+            0x0C02_FFFF,
+            None,
+        );
+        let data = dictionary_of(&[native]);
+        let rgba = solid(6, [0, 0, 0, 255]);
+        assert!(replace_texture(&data, "other", 4, 4, &rgba, TxdCompression::Auto).is_err());
+        assert!(replace_texture(&data, "paint", 6, 6, &rgba, TxdCompression::Auto).is_err());
+        // D3D8 keeps its platform and writes the DXT type byte.
+        let out = replace_texture(&data, "paint", 4, 4, &rgba, TxdCompression::Dxt1).unwrap();
+        let textures = list(&out).unwrap();
+        assert_eq!(textures[0].platform, "d3d8");
+        assert_eq!(textures[0].format, "DXT1");
+    }
+
+    /// A D3D9 texture native with one level, for formats `native_chunk`
+    /// doesn't write.
+    fn raw_native(
+        name: &str,
+        raster_format: u32,
+        d3d_format: u32,
+        size: u16,
+        palette: Option<&[u8]>,
+        level: &[u8],
+    ) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend(PLATFORM_D3D9.to_le_bytes());
+        header.extend(FILTER_LINEAR_WRAP.to_le_bytes());
+        push_name(&mut header, name, 32);
+        push_name(&mut header, "", 32);
+        header.extend(raster_format.to_le_bytes());
+        header.extend(d3d_format.to_le_bytes());
+        header.extend(size.to_le_bytes());
+        header.extend(size.to_le_bytes());
+        header.extend([32, 1, RASTER_TYPE_TEXTURE, 1]);
+        header.extend(palette.unwrap_or_default());
+        header.extend((level.len() as u32).to_le_bytes());
+        header.extend(level);
+        let mut body = Vec::new();
+        push_chunk(&mut body, CHUNK_STRUCT, SA, &header);
+        push_chunk(&mut body, CHUNK_EXTENSION, SA, &[]);
+        let mut native = Vec::new();
+        push_chunk(&mut native, CHUNK_TEXTURE_NATIVE, SA, &body);
+        native
+    }
+
+    fn pixels(data: &[u8], name: &str) -> Vec<[u8; 4]> {
+        let (width, height, rgba) = decode(data, name).unwrap();
+        assert_eq!((width, height), (4, 4));
+        rgba.chunks(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+            .collect()
+    }
+
+    #[test]
+    fn decodes_each_format_to_known_pixels() {
+        // Red and blue endpoints, each pixel's index its column. In DXT1,
+        // red first selects four colors and blue first three and clear.
+        let indices = [0xE4; 4];
+        let dxt1_four = [[0x00, 0xF8, 0x1F, 0x00], indices].concat();
+        let dxt1_three = [[0x1F, 0x00, 0x00, 0xF8], indices].concat();
+        // DXT3: each pixel's alpha nibble its index; white color.
+        let dxt3 = [
+            (0..8u8)
+                .map(|i| (2 * i) | ((2 * i + 1) << 4))
+                .collect::<Vec<_>>(),
+            vec![0xFF, 0xFF, 0, 0, 0, 0, 0, 0],
+        ]
+        .concat();
+        // DXT5: alpha from 255 to 0 in eight steps, each pixel's index its
+        // position mod 8.
+        let bits = (0..16u64).fold(0u64, |bits, i| bits | ((i % 8) << (i * 3)));
+        let dxt5 = [
+            vec![255, 0],
+            bits.to_le_bytes()[..6].to_vec(),
+            vec![0xFF, 0xFF, 0, 0, 0, 0, 0, 0],
+        ]
+        .concat();
+        let bgra: Vec<u8> = (0..16u8).flat_map(|i| [i, 100, 200, i * 16]).collect();
+        let mut palette = vec![0u8; 256 * 4];
+        palette[4..8].copy_from_slice(&[10, 20, 30, 40]);
+        palette[1020..].copy_from_slice(&[250, 240, 230, 255]);
+        let indexed: Vec<u8> = (0..16).map(|i| if i % 2 == 0 { 1 } else { 255 }).collect();
+
+        let data = dictionary_of(&[
+            raw_native("dxt1", RASTER_565, FOURCC_DXT1, 4, None, &dxt1_four),
+            raw_native("clear", RASTER_1555, FOURCC_DXT1, 4, None, &dxt1_three),
+            raw_native("dxt3", RASTER_4444, FOURCC_DXT3, 4, None, &dxt3),
+            raw_native("dxt5", RASTER_4444, FOURCC_DXT5, 4, None, &dxt5),
+            raw_native("argb", RASTER_8888, D3DFMT_A8R8G8B8, 4, None, &bgra),
+            raw_native("rgb", 0x600, 22, 4, None, &bgra),
+            raw_native(
+                "indexed",
+                RASTER_PAL8 | RASTER_8888,
+                41,
+                4,
+                Some(&palette),
+                &indexed,
+            ),
+        ]);
+        let formats: Vec<_> = list(&data)
+            .unwrap()
+            .into_iter()
+            .map(|texture| texture.format)
+            .collect();
+        assert_eq!(
+            formats,
+            ["DXT1", "DXT1", "DXT3", "DXT5", "8888", "888", "PAL8"]
+        );
+
+        let four = [
+            [255, 0, 0, 255],
+            [0, 0, 255, 255],
+            [170, 0, 85, 255],
+            [85, 0, 170, 255],
+        ];
+        assert_eq!(pixels(&data, "DXT1"), four.repeat(4));
+        let three = [
+            [0, 0, 255, 255],
+            [255, 0, 0, 255],
+            [127, 0, 127, 255],
+            [0, 0, 0, 0],
+        ];
+        assert_eq!(pixels(&data, "clear"), three.repeat(4));
+        let alphas: Vec<_> = pixels(&data, "dxt3").iter().map(|pixel| pixel[3]).collect();
+        assert_eq!(alphas, (0..16).map(|i| i * 17).collect::<Vec<u8>>());
+        let dxt5 = pixels(&data, "dxt5");
+        let table = [255, 0, 218, 182, 145, 109, 72, 36];
+        for (i, pixel) in dxt5.iter().enumerate() {
+            assert_eq!(*pixel, [255, 255, 255, table[i % 8]], "pixel {i}");
+        }
+        let argb = pixels(&data, "argb");
+        let rgb = pixels(&data, "rgb");
+        for i in 0..16u8 {
+            assert_eq!(argb[usize::from(i)], [200, 100, i, i * 16]);
+            assert_eq!(rgb[usize::from(i)], [200, 100, i, 255]);
+        }
+        let indexed = pixels(&data, "indexed");
+        for (i, pixel) in indexed.iter().enumerate() {
+            let expected = if i % 2 == 0 {
+                [10, 20, 30, 40]
+            } else {
+                [250, 240, 230, 255]
+            };
+            assert_eq!(*pixel, expected);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_short_levels_and_unsupported_formats() {
+        let data = dictionary_of(&[
+            raw_native("short", RASTER_565, FOURCC_DXT1, 8, None, &[0; 8]),
+            raw_native("lum", 0x400, 50, 4, None, &[0; 16]),
+        ]);
+        let short = decode(&data, "short").err().unwrap();
+        assert!(short.contains("needs 32"), "{short}");
+        let lum = decode(&data, "lum").err().unwrap();
+        assert!(lum.contains("LUM8"), "{lum}");
+        assert!(decode(&data, "missing").is_err());
+    }
+}
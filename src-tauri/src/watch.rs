@@ -0,0 +1,2706 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use notify::{
+    event::{ModifyKind, RenameMode},
+    EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+use tauri::{Emitter, EventTarget, Manager, State};
+
+const DEFAULT_WATCH_DEBOUNCE_MS: u64 = 150;
+const DEFAULT_WATCH_RATE_LIMIT_MS: u64 = 250;
+// Repack tools rewrite a dictionary several times per save.
+const DEFAULT_YTD_DEBOUNCE_MS: u64 = 750;
+const DEFAULT_WATCH_EVENT: &str = "texture:update";
+const REARM_ATTEMPTS: u32 = 8;
+const REARM_INTERVAL: Duration = Duration::from_millis(250);
+// How long a rename source waits for its matching destination, and the
+// minimum time temp-file events are held back in case they get renamed away.
+const RENAME_PAIR_WINDOW: Duration = Duration::from_millis(500);
+const DEFAULT_DEDUPE_MAX_BYTES: u64 = 64 * 1024 * 1024; // 64 MB
+
+// Ids used by the legacy single-purpose commands. They share the registry with
+// any ids the frontend picks, so these are effectively reserved names.
+const TEXTURE_WATCH_ID: &str = "texture";
+// Window watches are registered as `window:<label>`, one per webview.
+const WINDOW_WATCH_PREFIX: &str = "window:";
+const MULTI_WATCH_ID: &str = "multi";
+const MODEL_WATCH_ID: &str = "model";
+const MODEL_DIR_WATCH_ID: &str = "model-dir";
+const PDN_LIVE_WATCH_ID: &str = "pdn-live";
+const YTD_WATCH_ID: &str = "ytd";
+
+#[derive(serde::Serialize, Clone)]
+struct WatchPayload {
+    path: String,
+    /// Where the event really came from once symlinks and junctions are
+    /// resolved. Same as `path` when nothing was resolved.
+    resolved_path: String,
+    kind: String,
+    size: Option<u64>,
+    modified_ms: Option<u64>,
+}
+
+impl WatchPayload {
+    /// Stat the path so the frontend can tell whether a write has settled
+    /// without another round trip. A file that vanished mid-event still gets a
+    /// payload, just with `size`/`modified_ms` left empty.
+    fn new(path: &Path, resolved_path: &Path, kind: String) -> Self {
+        let meta = std::fs::metadata(path).ok();
+        Self {
+            path: path.to_string_lossy().to_string(),
+            resolved_path: resolved_path.to_string_lossy().to_string(),
+            kind,
+            size: meta.as_ref().map(|m| m.len()),
+            modified_ms: meta
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64),
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ModelFilePayload {
+    path: String,
+    extension: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ModelParsedPayload {
+    path: String,
+    #[serde(flatten)]
+    result: crate::ParseYftResult,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ModelParseErrorPayload {
+    path: String,
+    error: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct WatchErrorPayload {
+    id: String,
+    path: Option<String>,
+    error: String,
+}
+
+impl WatchErrorPayload {
+    fn new(id: &str, path: Option<&PathBuf>, error: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            path: path.map(|p| p.to_string_lossy().to_string()),
+            error: error.to_string(),
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+struct WatchLostPayload {
+    id: String,
+    path: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct WatchResumedPayload {
+    id: String,
+    changed: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ActiveWatch {
+    id: String,
+    paths: Vec<String>,
+    event_name: String,
+    recursive: bool,
+    paused: bool,
+    last_event_ms: Option<u64>,
+}
+
+/// Counters for the diagnostics panel, so "hot reload is flaky" reports come
+/// with something to look at.
+#[derive(serde::Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct WatchStats {
+    events_received: u64,
+    events_emitted: u64,
+    events_dropped: u64,
+    last_event_ms: Option<u64>,
+    last_error: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchStatsEntry {
+    id: String,
+    #[serde(flatten)]
+    stats: WatchStats,
+}
+
+#[derive(Clone)]
+struct WatchConfig {
+    /// Roots as the caller supplied them.
+    paths: Vec<PathBuf>,
+    /// Canonical form of each root, which is what actually gets watched.
+    /// notify watches a junction itself rather than what it points to, so
+    /// edits behind it would otherwise never produce events.
+    resolved: HashMap<PathBuf, PathBuf>,
+    event_name: String,
+    recursive: bool,
+    debounce: Duration,
+    /// Minimum gap between two emits for the same path. Zero disables it.
+    rate_limit: Duration,
+    extensions: Vec<String>,
+    /// File-name globs as given by the caller, kept for saving the session.
+    ignore: Vec<String>,
+    ignore_set: GlobSet,
+    dedupe_by_hash: bool,
+    dedupe_max_bytes: u64,
+    /// Emit `model:added`/`model:removed` for supported model files instead
+    /// of plain updates on `event_name`.
+    announce_models: bool,
+    /// Decode changed `.pdn` files in the backend and emit `pdn:decoded`
+    /// instead of plain updates on `event_name`.
+    decode_pdn: bool,
+    /// Re-run `parse_yft` in the background for changed .yft files and emit
+    /// `model:parsed`/`model:parse_error`.
+    auto_parse: bool,
+    /// Label of the webview that owns this watch. Its events go only to that
+    /// window, and the watch is dropped when the window is destroyed.
+    window: Option<String>,
+}
+
+impl WatchConfig {
+    fn new(paths: Vec<PathBuf>, event_name: &str) -> Self {
+        Self {
+            paths,
+            resolved: HashMap::new(),
+            event_name: event_name.to_string(),
+            recursive: false,
+            debounce: Duration::from_millis(DEFAULT_WATCH_DEBOUNCE_MS),
+            rate_limit: Duration::from_millis(DEFAULT_WATCH_RATE_LIMIT_MS),
+            extensions: Vec::new(),
+            ignore: Vec::new(),
+            ignore_set: GlobSet::empty(),
+            dedupe_by_hash: false,
+            dedupe_max_bytes: DEFAULT_DEDUPE_MAX_BYTES,
+            announce_models: false,
+            decode_pdn: false,
+            auto_parse: false,
+            window: None,
+        }
+    }
+
+    /// Resolve a root and remember its canonical form, falling back to the
+    /// path as given when it can't be canonicalized (UNC quirks and the like).
+    fn resolve_root(&mut self, path: &Path) -> PathBuf {
+        let target = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.resolved.insert(path.to_path_buf(), target.clone());
+        target
+    }
+
+    /// The path notify is actually watching for `root`.
+    fn watch_target(&self, root: &Path) -> PathBuf {
+        self.resolved
+            .get(root)
+            .cloned()
+            .unwrap_or_else(|| root.to_path_buf())
+    }
+
+    /// Map an event path under a resolved root back onto the root the caller
+    /// supplied, so payloads use the frontend's own paths.
+    fn to_original(&self, path: &Path) -> PathBuf {
+        for (root, target) in &self.resolved {
+            if let Ok(rest) = path.strip_prefix(target) {
+                return if rest.as_os_str().is_empty() {
+                    root.clone()
+                } else {
+                    root.join(rest)
+                };
+            }
+        }
+        path.to_path_buf()
+    }
+
+    fn to_resolved(&self, path: &Path) -> PathBuf {
+        for (root, target) in &self.resolved {
+            if let Ok(rest) = path.strip_prefix(root) {
+                return if rest.as_os_str().is_empty() {
+                    target.clone()
+                } else {
+                    target.join(rest)
+                };
+            }
+        }
+        path.to_path_buf()
+    }
+}
+
+/// On-disk form of a `WatchConfig`, used to restore watches across restarts.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedWatch {
+    id: String,
+    paths: Vec<String>,
+    event_name: String,
+    recursive: bool,
+    debounce_ms: u64,
+    #[serde(default = "default_rate_limit_ms")]
+    rate_limit_ms: u64,
+    #[serde(default)]
+    extensions: Vec<String>,
+    #[serde(default)]
+    ignore: Vec<String>,
+    #[serde(default)]
+    dedupe_by_hash: bool,
+    #[serde(default = "default_dedupe_max_bytes")]
+    dedupe_max_bytes: u64,
+    #[serde(default)]
+    announce_models: bool,
+    #[serde(default)]
+    decode_pdn: bool,
+    #[serde(default)]
+    auto_parse: bool,
+}
+
+fn default_dedupe_max_bytes() -> u64 {
+    DEFAULT_DEDUPE_MAX_BYTES
+}
+
+fn default_rate_limit_ms() -> u64 {
+    DEFAULT_WATCH_RATE_LIMIT_MS
+}
+
+impl SavedWatch {
+    fn from_config(id: &str, config: &WatchConfig) -> Self {
+        Self {
+            id: id.to_string(),
+            paths: config
+                .paths
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+            event_name: config.event_name.clone(),
+            recursive: config.recursive,
+            debounce_ms: config.debounce.as_millis() as u64,
+            rate_limit_ms: config.rate_limit.as_millis() as u64,
+            extensions: config.extensions.clone(),
+            ignore: config.ignore.clone(),
+            dedupe_by_hash: config.dedupe_by_hash,
+            dedupe_max_bytes: config.dedupe_max_bytes,
+            announce_models: config.announce_models,
+            decode_pdn: config.decode_pdn,
+            auto_parse: config.auto_parse,
+        }
+    }
+
+    fn into_config(self, paths: Vec<PathBuf>) -> Result<WatchConfig, String> {
+        let ignore_set = compile_ignore_patterns(&self.ignore)?;
+        Ok(WatchConfig {
+            paths,
+            resolved: HashMap::new(),
+            event_name: self.event_name,
+            recursive: self.recursive,
+            debounce: Duration::from_millis(self.debounce_ms),
+            rate_limit: Duration::from_millis(self.rate_limit_ms),
+            extensions: self.extensions,
+            ignore: self.ignore,
+            ignore_set,
+            dedupe_by_hash: self.dedupe_by_hash,
+            dedupe_max_bytes: self.dedupe_max_bytes,
+            announce_models: self.announce_models,
+            decode_pdn: self.decode_pdn,
+            auto_parse: self.auto_parse,
+            window: None,
+        })
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct WatchSession {
+    #[serde(default)]
+    auto_restore: bool,
+    #[serde(default)]
+    watches: Vec<SavedWatch>,
+}
+
+/// Per-path outcome of starting a watch that tolerates individual failures.
+#[derive(serde::Serialize, Default)]
+struct WatchStartReport {
+    watched: Vec<String>,
+    failed: Vec<FailedWatchPath>,
+}
+
+#[derive(serde::Serialize)]
+struct FailedWatchPath {
+    path: String,
+    error: String,
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct WatchRestoreReport {
+    restored: Vec<RestoredWatch>,
+    skipped: Vec<SkippedWatchPath>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoredWatch {
+    id: String,
+    paths: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkippedWatchPath {
+    id: String,
+    path: Option<String>,
+    reason: String,
+}
+
+/// State shared between a registry entry, its notify callback and its
+/// debounce thread.
+struct WatchShared {
+    id: String,
+    config: Mutex<WatchConfig>,
+    rearming: Mutex<HashSet<PathBuf>>,
+    last_events: Mutex<HashMap<PathBuf, u64>>,
+    paused: AtomicBool,
+    touched_while_paused: Mutex<HashSet<PathBuf>>,
+    content_hashes: Mutex<HashMap<PathBuf, u64>>,
+    /// Bumped for every queued PDN decode and on shutdown; a decode whose
+    /// generation is stale by the time it finishes is dropped.
+    decode_generation: AtomicU64,
+    /// Paths with an auto-parse running, mapped to whether another run has
+    /// been queued behind it.
+    parse_jobs: Mutex<HashMap<PathBuf, bool>>,
+    stats: Mutex<WatchStats>,
+}
+
+impl WatchShared {
+    fn new(id: &str, config: WatchConfig) -> Arc<Self> {
+        Arc::new(Self {
+            id: id.to_string(),
+            config: Mutex::new(config),
+            rearming: Mutex::new(HashSet::new()),
+            last_events: Mutex::new(HashMap::new()),
+            paused: AtomicBool::new(false),
+            touched_while_paused: Mutex::new(HashSet::new()),
+            content_hashes: Mutex::new(HashMap::new()),
+            decode_generation: AtomicU64::new(0),
+            parse_jobs: Mutex::new(HashMap::new()),
+            stats: Mutex::new(WatchStats::default()),
+        })
+    }
+
+    fn is_root(&self, path: &Path) -> bool {
+        self.config
+            .lock()
+            .map(|config| config.paths.iter().any(|root| root == path))
+            .unwrap_or(false)
+    }
+
+    /// Watched roots that `event` removes or renames away, which have to be
+    /// re-armed or reported lost.
+    fn removed_roots(&self, event: &notify::Event) -> Vec<PathBuf> {
+        if !is_removal(&event.kind) {
+            return Vec::new();
+        }
+        event
+            .paths
+            .iter()
+            .filter(|path| self.is_root(path))
+            .cloned()
+            .collect()
+    }
+
+    fn accepts(&self, path: &Path) -> bool {
+        self.config
+            .lock()
+            .map(|config| {
+                (config.extensions.is_empty() || has_watched_extension(path, &config.extensions))
+                    && !is_ignored(path, &config.ignore_set)
+            })
+            .unwrap_or(true)
+    }
+
+    fn record_received(&self) {
+        if let Ok(mut stats) = self.stats.lock() {
+            stats.events_received += 1;
+            stats.last_event_ms = Some(now_ms());
+        }
+    }
+
+    fn record_dropped(&self) {
+        if let Ok(mut stats) = self.stats.lock() {
+            stats.events_dropped += 1;
+        }
+    }
+
+    /// Remember the error for `get_watch_stats` and forward it as `watch:error`.
+    fn report_error(&self, app: &crate::AppHandle, path: Option<&PathBuf>, error: &str) {
+        self.record_error(error);
+        emit_watch_error(app, &self.id, path, error);
+    }
+
+    fn record_error(&self, error: &str) {
+        if let Ok(mut stats) = self.stats.lock() {
+            stats.last_error = Some(error.to_string());
+        }
+    }
+
+    /// While paused, remember which paths changed instead of emitting.
+    /// Returns `true` when the event was swallowed.
+    fn record_if_paused(&self, path: &Path) -> bool {
+        if !self.paused.load(Ordering::SeqCst) {
+            return false;
+        }
+        if self.accepts(path) {
+            if let Ok(mut touched) = self.touched_while_paused.lock() {
+                touched.insert(path.to_path_buf());
+            }
+        }
+        true
+    }
+
+    /// With `dedupe_by_hash`, compare the file's content hash against the last
+    /// one seen and report whether it actually changed. Oversized, unreadable
+    /// or deleted files always count as changed.
+    fn content_changed(&self, path: &Path) -> bool {
+        let (enabled, max_bytes) = match self.config.lock() {
+            Ok(config) => (config.dedupe_by_hash, config.dedupe_max_bytes),
+            Err(_) => return true,
+        };
+        if !enabled {
+            return true;
+        }
+
+        let mut hashes = match self.content_hashes.lock() {
+            Ok(hashes) => hashes,
+            Err(_) => return true,
+        };
+        match hash_file_contents(path, max_bytes) {
+            Some(hash) => hashes.insert(path.to_path_buf(), hash) != Some(hash),
+            None => {
+                hashes.remove(path);
+                true
+            }
+        }
+    }
+
+    /// Record the current hash of every watched root that is a plain file, so
+    /// the first mtime-only touch after starting is already suppressed.
+    fn seed_content_hashes(&self) {
+        let (roots, max_bytes) = match self.config.lock() {
+            Ok(config) if config.dedupe_by_hash => (config.paths.clone(), config.dedupe_max_bytes),
+            _ => return,
+        };
+        for root in roots.iter().filter(|p| p.is_file()) {
+            if let Some(hash) = hash_file_contents(root, max_bytes) {
+                if let Ok(mut hashes) = self.content_hashes.lock() {
+                    hashes.insert(root.clone(), hash);
+                }
+            }
+        }
+    }
+
+    /// Announce model files arriving in or leaving a watched folder. Anything
+    /// that isn't a create/rename-in or a removal (plain edits) is ignored.
+    fn announce_model(&self, app: &crate::AppHandle, path: &Path, kind: &str) {
+        let path_str = path.to_string_lossy().to_string();
+        if !crate::is_supported_open_model(&path_str) {
+            return;
+        }
+
+        let event_name = if !path.exists() {
+            "model:removed"
+        } else if is_arrival_kind(kind) {
+            "model:added"
+        } else {
+            return;
+        };
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+
+        if self
+            .emit(
+                app,
+                event_name,
+                ModelFilePayload {
+                    path: path_str,
+                    extension,
+                },
+            )
+            .is_ok()
+        {
+            if let Ok(mut last) = self.last_events.lock() {
+                last.insert(path.to_path_buf(), now_ms());
+            }
+        }
+    }
+
+    fn emit_update(&self, app: &crate::AppHandle, event_name: &str, path: PathBuf, kind: String) {
+        let resolved = self
+            .config
+            .lock()
+            .map(|config| config.to_resolved(&path))
+            .unwrap_or_else(|_| path.clone());
+        if self
+            .emit(app, event_name, WatchPayload::new(&path, &resolved, kind))
+            .is_ok()
+        {
+            if let Ok(mut last) = self.last_events.lock() {
+                last.insert(path, now_ms());
+            }
+        }
+    }
+
+    /// Re-parse a changed .yft on a background thread. Only one parse per path
+    /// runs at a time; changes arriving meanwhile queue exactly one follow-up,
+    /// which picks up the latest state of the file.
+    fn queue_auto_parse(self: &Arc<Self>, app: &crate::AppHandle, path: &Path) {
+        let path_str = path.to_string_lossy().to_string();
+        if !crate::is_yft(&path_str) {
+            return;
+        }
+
+        match self.parse_jobs.lock() {
+            Ok(mut jobs) => {
+                if let Some(queued) = jobs.get_mut(path) {
+                    *queued = true;
+                    return;
+                }
+                jobs.insert(path.to_path_buf(), false);
+            }
+            Err(_) => return,
+        }
+
+        let shared = self.clone();
+        let app = app.clone();
+        let path = path.to_path_buf();
+        std::thread::spawn(move || loop {
+            match crate::parse_model_blocking(
+                crate::BridgeModel::Yft,
+                path_str.clone(),
+                // Same as `parse_yft`'s defaults, so both land on one entry.
+                crate::ParseOptions {
+                    merge_hi: true,
+                    ..Default::default()
+                },
+                &app,
+            ) {
+                Ok(result) => {
+                    let _ = shared.emit(
+                        &app,
+                        "model:parsed",
+                        ModelParsedPayload {
+                            path: path_str.clone(),
+                            result,
+                        },
+                    );
+                }
+                Err(error) => {
+                    let _ = shared.emit(
+                        &app,
+                        "model:parse_error",
+                        ModelParseErrorPayload {
+                            path: path_str.clone(),
+                            error,
+                        },
+                    );
+                }
+            }
+
+            let mut jobs = match shared.parse_jobs.lock() {
+                Ok(jobs) => jobs,
+                Err(_) => return,
+            };
+            match jobs.get_mut(&path) {
+                Some(queued) if *queued => *queued = false,
+                _ => {
+                    jobs.remove(&path);
+                    return;
+                }
+            }
+        });
+    }
+
+    /// Emit to the owning window if there is one, otherwise app-wide.
+    fn emit<S: serde::Serialize + Clone>(
+        &self,
+        app: &crate::AppHandle,
+        event_name: &str,
+        payload: S,
+    ) -> tauri::Result<()> {
+        let window = self
+            .config
+            .lock()
+            .ok()
+            .and_then(|config| config.window.clone());
+        let result = match window {
+            Some(label) => app.emit_to(EventTarget::webview_window(label), event_name, payload),
+            None => app.emit(event_name, payload),
+        };
+        if result.is_ok() {
+            if let Ok(mut stats) = self.stats.lock() {
+                stats.events_emitted += 1;
+            }
+        }
+        result
+    }
+}
+
+struct WatchEntry {
+    watcher: RecommendedWatcher,
+    shared: Arc<WatchShared>,
+}
+
+impl WatchEntry {
+    fn shutdown(mut self) {
+        let paths: Vec<PathBuf> = self
+            .shared
+            .config
+            .lock()
+            .map(|config| {
+                config
+                    .paths
+                    .iter()
+                    .map(|path| config.watch_target(path))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for path in &paths {
+            let _ = self.watcher.unwatch(path);
+        }
+        // Abort whatever PDN decode is still running.
+        self.shared.decode_generation.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Every live watch, keyed by a caller-chosen id. Starting a watch with an id
+/// that is already registered replaces it.
+#[derive(Default)]
+pub struct WatcherRegistry {
+    watches: Mutex<HashMap<String, WatchEntry>>,
+}
+
+impl WatcherRegistry {
+    fn start(&self, app: &crate::AppHandle, id: &str, config: WatchConfig) -> Result<(), String> {
+        self.start_with_stats(app, id, config, false)
+    }
+
+    /// Like `start`, but with `keep_stats` the counters of the watch being
+    /// replaced carry over to the new one instead of starting from zero.
+    fn start_with_stats(
+        &self,
+        app: &crate::AppHandle,
+        id: &str,
+        mut config: WatchConfig,
+        keep_stats: bool,
+    ) -> Result<(), String> {
+        let mut watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        let mut previous_stats = None;
+        if let Some(existing) = watches.remove(id) {
+            if keep_stats {
+                previous_stats = existing.shared.stats.lock().ok().map(|stats| stats.clone());
+            }
+            existing.shutdown();
+        }
+
+        if config.paths.is_empty() {
+            return Ok(());
+        }
+
+        let mode = watch_mode(config.recursive);
+        let paths = config.paths.clone();
+        let targets: Vec<PathBuf> = paths.iter().map(|path| config.resolve_root(path)).collect();
+        let shared = WatchShared::new(id, config);
+        if let Some(stats) = previous_stats {
+            if let Ok(mut current) = shared.stats.lock() {
+                *current = stats;
+            }
+        }
+        let mut watcher = create_debounced_watcher(app.clone(), shared.clone())?;
+
+        for (path, target) in paths.iter().zip(&targets) {
+            if let Err(e) = watcher.watch(target, mode) {
+                shared.report_error(app, Some(path), &e.to_string());
+                return Err(e.to_string());
+            }
+        }
+
+        watches.insert(id.to_string(), WatchEntry { watcher, shared });
+        Ok(())
+    }
+
+    /// Replace the watch under `id`, attempting every path and keeping the
+    /// ones that could be watched. If none could, the previous watch is still
+    /// gone and nothing is registered in its place.
+    fn start_each(
+        &self,
+        app: &crate::AppHandle,
+        id: &str,
+        config: WatchConfig,
+    ) -> Result<WatchStartReport, String> {
+        let mut watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        if let Some(existing) = watches.remove(id) {
+            existing.shutdown();
+        }
+
+        let mut report = WatchStartReport::default();
+        if config.paths.is_empty() {
+            return Ok(report);
+        }
+
+        let mode = watch_mode(config.recursive);
+        let paths = config.paths.clone();
+        let shared = WatchShared::new(id, config);
+        let mut watcher = create_debounced_watcher(app.clone(), shared.clone())?;
+
+        // Roots armed earlier in the loop can already fire events, and the
+        // callback takes the config lock, so `watch` runs without it (see
+        // `add_paths`).
+        let targets: Vec<PathBuf> = {
+            let mut config = shared
+                .config
+                .lock()
+                .map_err(|_| "watch config lock failed".to_string())?;
+            paths.iter().map(|path| config.resolve_root(path)).collect()
+        };
+        let mut unwatched = Vec::new();
+        for (path, target) in paths.into_iter().zip(targets) {
+            match watcher.watch(&target, mode) {
+                Ok(()) => report.watched.push(path.to_string_lossy().to_string()),
+                Err(e) => {
+                    report.failed.push(FailedWatchPath {
+                        path: path.to_string_lossy().to_string(),
+                        error: e.to_string(),
+                    });
+                    unwatched.push(path);
+                }
+            }
+        }
+        if !unwatched.is_empty() {
+            let mut config = shared
+                .config
+                .lock()
+                .map_err(|_| "watch config lock failed".to_string())?;
+            config.paths.retain(|p| !unwatched.contains(p));
+            for path in &unwatched {
+                config.resolved.remove(path);
+            }
+        }
+
+        if !report.watched.is_empty() {
+            watches.insert(id.to_string(), WatchEntry { watcher, shared });
+        }
+        Ok(report)
+    }
+
+    /// Add paths to an existing watch in place, creating it from `template` if
+    /// it isn't registered yet. Paths that are already watched are skipped;
+    /// the rest are each attempted and reported like `start_each`. A new
+    /// watch is only registered once one of its paths could be watched.
+    fn add_paths(
+        &self,
+        app: &crate::AppHandle,
+        id: &str,
+        paths: Vec<PathBuf>,
+        template: WatchConfig,
+    ) -> Result<WatchStartReport, String> {
+        let mut watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        let mut created = None;
+        let entry = match watches.get_mut(id) {
+            Some(entry) => entry,
+            None => {
+                let shared = WatchShared::new(
+                    id,
+                    WatchConfig {
+                        paths: Vec::new(),
+                        ..template
+                    },
+                );
+                let watcher = create_debounced_watcher(app.clone(), shared.clone())?;
+                created.insert(WatchEntry { watcher, shared })
+            }
+        };
+
+        // notify's `watch` waits on its event loop, which may be inside our
+        // callback waiting for the config lock, so that lock is only held to
+        // resolve the targets and to record the outcome.
+        let (mode, targets) = {
+            let mut config = entry
+                .shared
+                .config
+                .lock()
+                .map_err(|_| "watch config lock failed".to_string())?;
+            let mut targets: Vec<(PathBuf, PathBuf)> = Vec::new();
+            for path in paths {
+                if config.paths.contains(&path) || targets.iter().any(|(p, _)| *p == path) {
+                    continue;
+                }
+                let target = config.resolve_root(&path);
+                targets.push((path, target));
+            }
+            (watch_mode(config.recursive), targets)
+        };
+
+        let mut report = WatchStartReport::default();
+        let mut watched = Vec::new();
+        let mut unwatched = Vec::new();
+        for (path, target) in targets {
+            match entry.watcher.watch(&target, mode) {
+                Ok(()) => {
+                    report.watched.push(path.to_string_lossy().to_string());
+                    watched.push(path);
+                }
+                Err(e) => {
+                    entry.shared.report_error(app, Some(&path), &e.to_string());
+                    report.failed.push(FailedWatchPath {
+                        path: path.to_string_lossy().to_string(),
+                        error: e.to_string(),
+                    });
+                    unwatched.push(path);
+                }
+            }
+        }
+        {
+            let mut config = entry
+                .shared
+                .config
+                .lock()
+                .map_err(|_| "watch config lock failed".to_string())?;
+            for path in &unwatched {
+                config.resolved.remove(path);
+            }
+            config.paths.extend(watched);
+        }
+
+        if let Some(entry) = created {
+            if !report.watched.is_empty() {
+                watches.insert(id.to_string(), entry);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Unwatch the given paths without touching the rest of the watch. Paths
+    /// that aren't being watched are ignored.
+    fn remove_paths(&self, id: &str, paths: Vec<PathBuf>) -> Result<(), String> {
+        let mut watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        if let Some(entry) = watches.get_mut(id) {
+            // As in `add_paths`, `unwatch` runs without the config lock.
+            let targets: Vec<(PathBuf, PathBuf)> = {
+                let config = entry
+                    .shared
+                    .config
+                    .lock()
+                    .map_err(|_| "watch config lock failed".to_string())?;
+                paths
+                    .into_iter()
+                    .filter(|path| config.paths.contains(path))
+                    .map(|path| {
+                        let target = config.watch_target(&path);
+                        (path, target)
+                    })
+                    .collect()
+            };
+            for (_, target) in &targets {
+                let _ = entry.watcher.unwatch(target);
+            }
+            let mut config = entry
+                .shared
+                .config
+                .lock()
+                .map_err(|_| "watch config lock failed".to_string())?;
+            for (path, _) in targets {
+                config.resolved.remove(&path);
+                config.paths.retain(|p| *p != path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn paths(&self, id: &str) -> Result<Vec<PathBuf>, String> {
+        let watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        Ok(watches
+            .get(id)
+            .and_then(|entry| entry.shared.config.lock().ok())
+            .map(|config| config.paths.clone())
+            .unwrap_or_default())
+    }
+
+    /// Re-subscribe a root whose file handle went stale after a save replaced
+    /// it. Returns `false` when the watch was stopped or replaced meanwhile.
+    fn rearm(&self, shared: &Arc<WatchShared>, path: &Path) -> Result<bool, String> {
+        let mut watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        let entry = match watches.get_mut(&shared.id) {
+            Some(entry) if Arc::ptr_eq(&entry.shared, shared) => entry,
+            _ => return Ok(false),
+        };
+        let (recursive, target) = entry
+            .shared
+            .config
+            .lock()
+            .map(|config| (config.recursive, config.watch_target(path)))
+            .map_err(|_| "watch config lock failed".to_string())?;
+
+        let _ = entry.watcher.unwatch(&target);
+        entry
+            .watcher
+            .watch(&target, watch_mode(recursive))
+            .map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
+    fn shared(&self, id: &str) -> Result<Arc<WatchShared>, String> {
+        let watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        watches
+            .get(id)
+            .map(|entry| entry.shared.clone())
+            .ok_or_else(|| format!("No active watch with id `{id}`"))
+    }
+
+    fn snapshot(&self) -> Result<Vec<SavedWatch>, String> {
+        let watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        // Window-owned watches are left out; their window is gone by the next
+        // launch and it will ask for its own watch again.
+        let mut saved: Vec<SavedWatch> = watches
+            .values()
+            .filter_map(|entry| {
+                let config = entry.shared.config.lock().ok()?;
+                if config.window.is_some() {
+                    return None;
+                }
+                Some(SavedWatch::from_config(&entry.shared.id, &config))
+            })
+            .collect();
+        saved.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(saved)
+    }
+
+    fn describe(&self) -> Result<Vec<ActiveWatch>, String> {
+        let watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        let mut active: Vec<ActiveWatch> = watches
+            .values()
+            .filter_map(|entry| {
+                let config = entry.shared.config.lock().ok()?;
+                let last_event_ms = entry
+                    .shared
+                    .last_events
+                    .lock()
+                    .ok()
+                    .and_then(|last| last.values().copied().max());
+                Some(ActiveWatch {
+                    id: entry.shared.id.clone(),
+                    paths: config
+                        .paths
+                        .iter()
+                        .map(|p| p.to_string_lossy().to_string())
+                        .collect(),
+                    event_name: config.event_name.clone(),
+                    recursive: config.recursive,
+                    paused: entry.shared.paused.load(Ordering::SeqCst),
+                    last_event_ms,
+                })
+            })
+            .collect();
+        active.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(active)
+    }
+
+    fn stats(&self) -> Result<Vec<WatchStatsEntry>, String> {
+        let watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        let mut entries: Vec<WatchStatsEntry> = watches
+            .values()
+            .filter_map(|entry| {
+                let stats = entry.shared.stats.lock().ok()?;
+                Some(WatchStatsEntry {
+                    id: entry.shared.id.clone(),
+                    stats: stats.clone(),
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(entries)
+    }
+
+    /// Zero the counters of one watch, or of every watch when `id` is `None`.
+    fn reset_stats(&self, id: Option<&str>) -> Result<(), String> {
+        let watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        for entry in watches.values() {
+            if id.is_some_and(|id| id != entry.shared.id) {
+                continue;
+            }
+            if let Ok(mut stats) = entry.shared.stats.lock() {
+                *stats = WatchStats::default();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn stop(&self, id: &str) -> Result<(), String> {
+        let mut watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        if let Some(entry) = watches.remove(id) {
+            entry.shutdown();
+        }
+
+        Ok(())
+    }
+
+    /// Stop every watch owned by the window with this label.
+    fn stop_window(&self, label: &str) -> Result<(), String> {
+        let mut watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        let owned: Vec<String> = watches
+            .iter()
+            .filter(|(_, entry)| {
+                entry
+                    .shared
+                    .config
+                    .lock()
+                    .map(|config| config.window.as_deref() == Some(label))
+                    .unwrap_or(false)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in owned {
+            if let Some(entry) = watches.remove(&id) {
+                entry.shutdown();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn stop_all(&self) -> Result<(), String> {
+        let mut watches = self
+            .watches
+            .lock()
+            .map_err(|_| "watcher registry lock failed".to_string())?;
+
+        for (_, entry) in watches.drain() {
+            entry.shutdown();
+        }
+
+        Ok(())
+    }
+}
+
+fn watch_mode(recursive: bool) -> RecursiveMode {
+    if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Validate a caller-supplied event name, falling back to `texture:update`.
+/// Tauri only accepts alphanumerics plus `-`, `/`, `:` and `_` in event
+/// names, so anything else (whitespace included) is rejected up front rather
+/// than failing silently on every emit.
+fn resolve_event_name(event_name: Option<String>) -> Result<String, String> {
+    let name = match event_name {
+        Some(name) => name,
+        None => return Ok(DEFAULT_WATCH_EVENT.to_string()),
+    };
+    if name.is_empty() {
+        return Err("Watch event name must not be empty".to_string());
+    }
+    if let Some(bad) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '/' | ':' | '_')))
+    {
+        return Err(format!(
+            "Invalid character {bad:?} in watch event name `{name}`"
+        ));
+    }
+    Ok(name)
+}
+
+fn debounce_duration(debounce_ms: Option<u64>) -> Duration {
+    Duration::from_millis(debounce_ms.unwrap_or(DEFAULT_WATCH_DEBOUNCE_MS))
+}
+
+fn rate_limit_duration(rate_limit_ms: Option<u64>) -> Duration {
+    Duration::from_millis(rate_limit_ms.unwrap_or(DEFAULT_WATCH_RATE_LIMIT_MS))
+}
+
+/// Trim and de-duplicate the requested paths, dropping blank entries.
+fn collect_watch_paths(paths: Vec<String>) -> Vec<PathBuf> {
+    let mut unique: HashSet<PathBuf> = HashSet::new();
+    for raw in paths {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        unique.insert(PathBuf::from(trimmed));
+    }
+    unique.into_iter().collect()
+}
+
+/// Lowercase the requested extensions and strip any leading dot so `".DDS"`
+/// and `"dds"` behave the same. An empty result means "no filter".
+fn normalize_watch_extensions(extensions: Vec<String>) -> Vec<String> {
+    extensions
+        .into_iter()
+        .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect()
+}
+
+fn has_watched_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            extensions
+                .iter()
+                .any(|wanted| ext.eq_ignore_ascii_case(wanted))
+        })
+        .unwrap_or(false)
+}
+
+/// Compile the caller's ignore globs once, when the watch starts. A pattern
+/// that doesn't parse is an error rather than a filter that silently matches
+/// nothing.
+fn compile_ignore_patterns(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| format!("Invalid ignore pattern `{pattern}`: {e}"))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Failed to compile ignore patterns: {e}"))
+}
+
+/// Ignore globs are matched against the file name only, so `Thumbs.db`
+/// matches in any watched folder.
+fn is_ignored(path: &Path, ignore_set: &GlobSet) -> bool {
+    !ignore_set.is_empty()
+        && path
+            .file_name()
+            .map(|name| ignore_set.is_match(name))
+            .unwrap_or(false)
+}
+
+/// Trim the requested ignore globs, dropping blank entries.
+fn collect_ignore_patterns(ignore: Option<Vec<String>>) -> Vec<String> {
+    ignore
+        .unwrap_or_default()
+        .into_iter()
+        .map(|pattern| pattern.trim().to_string())
+        .filter(|pattern| !pattern.is_empty())
+        .collect()
+}
+
+/// Hash a file's bytes in 1 MB blocks. Returns `None` for files over
+/// `max_bytes` or that can't be read, so hashing never stalls the watcher on
+/// huge inputs.
+fn hash_file_contents(path: &Path, max_bytes: u64) -> Option<u64> {
+    use std::hash::Hasher;
+    use std::io::Read;
+
+    let meta = std::fs::metadata(path).ok()?;
+    if !meta.is_file() || meta.len() > max_bytes {
+        return None;
+    }
+
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buf).ok()?;
+        if read == 0 {
+            break;
+        }
+        hasher.write(&buf[..read]);
+    }
+    Some(hasher.finish())
+}
+
+/// Coalesced kinds that mean a file appeared at the path.
+fn is_arrival_kind(kind: &str) -> bool {
+    kind.starts_with("Create") || kind == "Modify(Name(To))" || kind == "replaced"
+}
+
+fn is_removal(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))
+    )
+}
+
+fn emit_watch_error(app: &crate::AppHandle, id: &str, path: Option<&PathBuf>, error: &str) {
+    let _ = app.emit("watch:error", WatchErrorPayload::new(id, path, error));
+}
+
+/// Create a watcher whose events are coalesced per path before being emitted.
+/// Editors typically fire several events for one save (data, metadata, close),
+/// and reading the file between them picks up a half-written texture, so every
+/// path has to stay quiet for `debounce` before its last event kind is emitted.
+///
+/// Errors from notify are forwarded as `watch:error`. A watched root that is
+/// removed or renamed is re-armed (see `schedule_rearm`), and reported as
+/// `watch:lost` only if it never comes back.
+fn create_debounced_watcher(
+    app: crate::AppHandle,
+    shared: Arc<WatchShared>,
+) -> Result<RecommendedWatcher, String> {
+    let (tx, rx) = mpsc::channel::<RawWatchEvent>();
+    let callback_app = app.clone();
+    let callback_shared = shared.clone();
+
+    let watcher = notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
+        let mut event = match res {
+            Ok(event) => event,
+            Err(error) => {
+                callback_shared.report_error(
+                    &callback_app,
+                    error.paths.first(),
+                    &error.to_string(),
+                );
+                return;
+            }
+        };
+        callback_shared.record_received();
+        // Everything past this point works in terms of the caller's paths.
+        if let Ok(config) = callback_shared.config.lock() {
+            for path in event.paths.iter_mut() {
+                *path = config.to_original(path);
+            }
+        }
+
+        for path in callback_shared.removed_roots(&event) {
+            schedule_rearm(
+                callback_app.clone(),
+                callback_shared.clone(),
+                path,
+                tx.clone(),
+            );
+        }
+
+        if let Some(raw) = RawWatchEvent::classify(&event) {
+            if callback_shared.record_if_paused(raw.subject()) {
+                callback_shared.record_dropped();
+                return;
+            }
+            let _ = tx.send(raw);
+        }
+    })
+    .map_err(|e| e.to_string())?;
+
+    // The thread exits once the watcher (and with it the sender) is dropped.
+    std::thread::spawn(move || run_watch_debouncer(rx, app, shared));
+
+    Ok(watcher)
+}
+
+/// Editors save by writing a temp file and renaming it over the target, which
+/// leaves the original subscription pointing at a file that no longer exists.
+/// Poll for the path to reappear, re-watch it and feed a synthetic `modified`
+/// event through the debouncer. If it stays gone, report the watch as lost.
+fn schedule_rearm(
+    app: crate::AppHandle,
+    shared: Arc<WatchShared>,
+    path: PathBuf,
+    tx: mpsc::Sender<RawWatchEvent>,
+) {
+    match shared.rearming.lock() {
+        Ok(mut rearming) => {
+            if !rearming.insert(path.clone()) {
+                return;
+            }
+        }
+        Err(_) => return,
+    }
+
+    let removed_at = now_ms();
+
+    std::thread::spawn(move || {
+        let mut rearmed = false;
+        for _ in 0..REARM_ATTEMPTS {
+            std::thread::sleep(REARM_INTERVAL);
+            if !path.exists() {
+                continue;
+            }
+            match app.state::<WatcherRegistry>().rearm(&shared, &path) {
+                Ok(true) => {
+                    rearmed = true;
+                    // Skip the synthetic event if the save was already
+                    // reported (e.g. as a collapsed `replaced` rename).
+                    let already_reported = shared
+                        .last_events
+                        .lock()
+                        .map(|last| last.get(&path).is_some_and(|at| *at >= removed_at))
+                        .unwrap_or(false);
+                    if !already_reported {
+                        let _ = tx.send(RawWatchEvent::Rearmed(path.clone()));
+                    }
+                    break;
+                }
+                Ok(false) => {
+                    // The watch was stopped or replaced; nothing to re-arm.
+                    rearmed = true;
+                    break;
+                }
+                Err(_) => continue,
+            }
+        }
+
+        if !rearmed {
+            let _ = app.emit(
+                "watch:lost",
+                WatchLostPayload {
+                    id: shared.id.clone(),
+                    path: path.to_string_lossy().to_string(),
+                },
+            );
+        }
+
+        if let Ok(mut rearming) = shared.rearming.lock() {
+            rearming.remove(&path);
+        }
+    });
+}
+
+fn run_watch_debouncer(
+    rx: mpsc::Receiver<RawWatchEvent>,
+    app: crate::AppHandle,
+    shared: Arc<WatchShared>,
+) {
+    let config = match shared.config.lock() {
+        Ok(config) => config.clone(),
+        Err(_) => return,
+    };
+    let mut coalescer = WatchCoalescer::new(config.debounce, config.rate_limit);
+    shared.seed_content_hashes();
+    let pdn_decoder = config
+        .decode_pdn
+        .then(|| spawn_pdn_decoder(app.clone(), shared.clone()));
+
+    loop {
+        let received = match coalescer.next_deadline() {
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+
+        match received {
+            Ok(raw) => coalescer.push(raw, Instant::now()),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        for (path, kind) in coalescer.take_ready(Instant::now()) {
+            // The extension filter is applied here rather than in the notify
+            // callback so a temp file's rename can still be paired with the
+            // watched file it replaces.
+            if !shared.accepts(&path) {
+                shared.record_dropped();
+                continue;
+            }
+            // Events already queued when the watch was paused are held back too.
+            if shared.record_if_paused(&path) {
+                shared.record_dropped();
+                continue;
+            }
+            if config.announce_models {
+                shared.announce_model(&app, &path, &kind);
+                continue;
+            }
+            if !shared.content_changed(&path) {
+                shared.record_dropped();
+                continue;
+            }
+            if let Some(decoder) = &pdn_decoder {
+                if path.is_file() {
+                    let generation = shared.decode_generation.fetch_add(1, Ordering::SeqCst) + 1;
+                    let _ = decoder.send((path, generation));
+                }
+                continue;
+            }
+            if config.auto_parse && path.is_file() {
+                shared.queue_auto_parse(&app, &path);
+            }
+            shared.emit_update(&app, &config.event_name, path, kind);
+        }
+    }
+}
+
+/// Run `decode_pdn` off the debounce thread so a slow decode never holds up
+/// other events. Requests that pile up while a decode is running collapse to
+/// the newest one. Once something newer is queued or the watch is stopped,
+/// the running decode gives up at its next layer and isn't emitted.
+fn spawn_pdn_decoder(
+    app: crate::AppHandle,
+    shared: Arc<WatchShared>,
+) -> mpsc::Sender<(PathBuf, u64)> {
+    let (tx, rx) = mpsc::channel::<(PathBuf, u64)>();
+
+    // Exits once the debounce thread drops the sender.
+    std::thread::spawn(move || {
+        while let Ok(mut request) = rx.recv() {
+            while let Ok(newer) = rx.try_recv() {
+                request = newer;
+            }
+            let (path, generation) = request;
+
+            let path_str = path.to_string_lossy().to_string();
+            let task = crate::tasks::start(&app, "pdn", Some(&path_str));
+            let result = decode_live_pdn(&shared, &task, path_str, generation);
+            drop(task);
+            if shared.decode_generation.load(Ordering::SeqCst) != generation {
+                continue;
+            }
+
+            match result {
+                Ok(mut decoded) => {
+                    if let Some(fields) = decoded.as_object_mut() {
+                        fields.insert(
+                            "path".to_string(),
+                            serde_json::Value::String(path.to_string_lossy().to_string()),
+                        );
+                    }
+                    if shared.emit(&app, "pdn:decoded", decoded).is_ok() {
+                        if let Ok(mut last) = shared.last_events.lock() {
+                            last.insert(path, now_ms());
+                        }
+                    }
+                }
+                Err(error) => shared.report_error(&app, Some(&path), &error),
+            }
+        }
+    });
+
+    tx
+}
+
+/// Decode the PDN queued as `generation`, aborting between layers when the
+/// task is cancelled or the generation goes stale.
+fn decode_live_pdn(
+    shared: &WatchShared,
+    task: &crate::tasks::TaskHandle,
+    path: String,
+    generation: u64,
+) -> Result<serde_json::Value, String> {
+    crate::pdn::decode_pdn_blocking(path, &|| {
+        task.check()?;
+        if shared.decode_generation.load(Ordering::SeqCst) != generation {
+            return Err(crate::conversion::CANCELLED.to_string());
+        }
+        Ok(())
+    })
+}
+
+/// A notify event reduced to what the coalescer cares about. Renames are kept
+/// apart from other changes so a temp-file save can be folded into a single
+/// `replaced` event for the file it overwrote.
+enum RawWatchEvent {
+    Change {
+        path: PathBuf,
+        kind: String,
+    },
+    RenameFrom(PathBuf),
+    RenameTo(PathBuf),
+    RenameBoth {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// Synthetic change after a stale root was re-watched.
+    Rearmed(PathBuf),
+}
+
+impl RawWatchEvent {
+    fn classify(event: &notify::Event) -> Option<Self> {
+        let first = event.paths.first()?.clone();
+        let raw = match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => Self::RenameFrom(first),
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => Self::RenameTo(first),
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() >= 2 => {
+                Self::RenameBoth {
+                    from: first,
+                    to: event.paths[1].clone(),
+                }
+            }
+            _ => Self::Change {
+                path: first,
+                kind: format!("{:?}", event.kind),
+            },
+        };
+        Some(raw)
+    }
+
+    /// The path a listener would consider changed by this event.
+    fn subject(&self) -> &Path {
+        match self {
+            Self::Change { path, .. }
+            | Self::RenameFrom(path)
+            | Self::RenameTo(path)
+            | Self::Rearmed(path) => path,
+            Self::RenameBoth { to, .. } => to,
+        }
+    }
+}
+
+/// Temp names editors and browsers write before renaming over the real file.
+fn is_temp_save_path(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name.to_ascii_lowercase(),
+        None => return false,
+    };
+    name.starts_with("~$") || name.ends_with(".tmp") || name.ends_with(".crdownload")
+}
+
+/// Per-path debounce, rate limiting and rename pairing, kept free of Tauri so
+/// the event sequences are easy to reason about in isolation.
+///
+/// The rate limit is per path: a file flushed in many small chunks is held to
+/// one emit per `rate_limit`, with its latest kind still delivered once the
+/// window opens, while other paths go out on their own schedule.
+struct WatchCoalescer {
+    debounce: Duration,
+    rate_limit: Duration,
+    pending: HashMap<PathBuf, (String, Instant)>,
+    last_rename_from: Option<(PathBuf, Instant)>,
+    last_emitted: HashMap<PathBuf, Instant>,
+}
+
+impl WatchCoalescer {
+    fn new(debounce: Duration, rate_limit: Duration) -> Self {
+        Self {
+            debounce,
+            rate_limit,
+            pending: HashMap::new(),
+            last_rename_from: None,
+            last_emitted: HashMap::new(),
+        }
+    }
+
+    /// When a pending event for `path` may go out: after its debounce hold,
+    /// and no sooner than `rate_limit` after the previous emit for the path.
+    fn due_at(&self, path: &Path, seen: Instant) -> Instant {
+        let debounced = seen + self.hold_for(path);
+        match self.last_emitted.get(path) {
+            Some(emitted) => debounced.max(*emitted + self.rate_limit),
+            None => debounced,
+        }
+    }
+
+    fn hold_for(&self, path: &Path) -> Duration {
+        if is_temp_save_path(path) {
+            self.debounce.max(RENAME_PAIR_WINDOW)
+        } else {
+            self.debounce
+        }
+    }
+
+    fn push(&mut self, raw: RawWatchEvent, now: Instant) {
+        match raw {
+            RawWatchEvent::Change { path, kind } => {
+                self.pending.insert(path, (kind, now));
+            }
+            RawWatchEvent::RenameFrom(path) => {
+                self.pending
+                    .insert(path.clone(), ("Modify(Name(From))".to_string(), now));
+                self.last_rename_from = Some((path, now));
+            }
+            RawWatchEvent::RenameTo(to) => {
+                let paired = self
+                    .last_rename_from
+                    .take()
+                    .filter(|(_, seen)| now.duration_since(*seen) <= RENAME_PAIR_WINDOW);
+                match paired {
+                    Some((from, _)) => self.collapse_rename(&from, to, now),
+                    None => {
+                        self.pending
+                            .insert(to, ("Modify(Name(To))".to_string(), now));
+                    }
+                }
+            }
+            RawWatchEvent::RenameBoth { from, to } => {
+                if self
+                    .last_rename_from
+                    .as_ref()
+                    .map(|(pending_from, _)| *pending_from == from)
+                    .unwrap_or(false)
+                {
+                    self.last_rename_from = None;
+                }
+                self.collapse_rename(&from, to, now);
+            }
+            RawWatchEvent::Rearmed(path) => {
+                // Don't let the synthetic event mask a more specific kind
+                // (such as `replaced`) that is still waiting to go out.
+                self.pending
+                    .entry(path)
+                    .or_insert_with(|| ("modified".to_string(), now));
+            }
+        }
+    }
+
+    /// Drop everything queued for the rename source and report the
+    /// destination once as `replaced`, overriding any remove/create noise
+    /// already pending for it.
+    fn collapse_rename(&mut self, from: &Path, to: PathBuf, now: Instant) {
+        self.pending.remove(from);
+        self.pending.insert(to, ("replaced".to_string(), now));
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        let pending = self
+            .pending
+            .iter()
+            .map(|(path, (_, seen))| self.due_at(path, *seen));
+        let rename = self
+            .last_rename_from
+            .as_ref()
+            .map(|(_, seen)| *seen + RENAME_PAIR_WINDOW);
+        pending.chain(rename).min()
+    }
+
+    fn take_ready(&mut self, now: Instant) -> Vec<(PathBuf, String)> {
+        if self
+            .last_rename_from
+            .as_ref()
+            .map(|(_, seen)| now.duration_since(*seen) > RENAME_PAIR_WINDOW)
+            .unwrap_or(false)
+        {
+            self.last_rename_from = None;
+        }
+
+        let ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(path, (_, seen))| now >= self.due_at(path, *seen))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        // Forget emit times once they can no longer hold anything back.
+        let rate_limit = self.rate_limit;
+        self.last_emitted
+            .retain(|_, emitted| now.duration_since(*emitted) < rate_limit);
+
+        ready
+            .into_iter()
+            .filter_map(|path| {
+                let (kind, _) = self.pending.remove(&path)?;
+                if !self.rate_limit.is_zero() {
+                    self.last_emitted.insert(path.clone(), now);
+                }
+                Some((path, kind))
+            })
+            .collect()
+    }
+}
+
+/// Create or replace the watch registered under `id`.
+///
+/// Older frontends call this with just `path`; that maps onto the `texture`
+/// id emitting `texture:update`, which is what this command used to do.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn start_watch(
+    id: Option<String>,
+    path: Option<String>,
+    paths: Option<Vec<String>>,
+    event_name: Option<String>,
+    recursive: Option<bool>,
+    debounce_ms: Option<u64>,
+    rate_limit_ms: Option<u64>,
+    extensions: Option<Vec<String>>,
+    ignore: Option<Vec<String>>,
+    dedupe_by_hash: Option<bool>,
+    dedupe_max_bytes: Option<u64>,
+    keep_stats: Option<bool>,
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<(), String> {
+    let id = id.unwrap_or_else(|| TEXTURE_WATCH_ID.to_string());
+    let mut requested = paths.unwrap_or_default();
+    requested.extend(path);
+
+    let event_name = resolve_event_name(event_name)?;
+    let mut config = WatchConfig::new(collect_watch_paths(requested), &event_name);
+    config.recursive = recursive.unwrap_or(false);
+    config.debounce = debounce_duration(debounce_ms);
+    config.rate_limit = rate_limit_duration(rate_limit_ms);
+    config.extensions = normalize_watch_extensions(extensions.unwrap_or_default());
+    config.ignore = collect_ignore_patterns(ignore);
+    config.ignore_set = compile_ignore_patterns(&config.ignore)?;
+    config.dedupe_by_hash = dedupe_by_hash.unwrap_or(false);
+    config.dedupe_max_bytes = dedupe_max_bytes.unwrap_or(DEFAULT_DEDUPE_MAX_BYTES);
+
+    state.start_with_stats(&app, &id, config, keep_stats.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn stop_watch(id: Option<String>, state: State<WatcherRegistry>) -> Result<(), String> {
+    state.stop(id.as_deref().unwrap_or(TEXTURE_WATCH_ID))
+}
+
+#[tauri::command]
+pub fn stop_all_watches(state: State<WatcherRegistry>) -> Result<(), String> {
+    state.stop_all()
+}
+
+/// Describe every live watch for the diagnostics panel.
+#[tauri::command]
+pub fn list_active_watches(state: State<WatcherRegistry>) -> Result<serde_json::Value, String> {
+    serde_json::to_value(state.describe()?).map_err(|e| e.to_string())
+}
+
+/// Per-watch event counters and the last error, for diagnosing flaky reloads.
+#[tauri::command]
+pub fn get_watch_stats(state: State<WatcherRegistry>) -> Result<serde_json::Value, String> {
+    serde_json::to_value(state.stats()?).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn reset_watch_stats(id: Option<String>, state: State<WatcherRegistry>) -> Result<(), String> {
+    state.reset_stats(id.as_deref())
+}
+
+/// Stop emitting for a watch without tearing down the underlying watcher.
+/// Changes made while paused are remembered and reported on resume.
+#[tauri::command]
+pub fn pause_watch(id: String, state: State<WatcherRegistry>) -> Result<(), String> {
+    let shared = state.shared(&id)?;
+    if !shared.paused.swap(true, Ordering::SeqCst) {
+        if let Ok(mut touched) = shared.touched_while_paused.lock() {
+            touched.clear();
+        }
+    }
+    Ok(())
+}
+
+/// Resume a paused watch and emit `watch:resumed` listing the paths that
+/// changed meanwhile. With `flush_on_resume`, each of those paths also gets
+/// one synthetic update on the watch's own event so the frontend reloads them.
+#[tauri::command]
+pub fn resume_watch(
+    id: String,
+    flush_on_resume: Option<bool>,
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<Vec<String>, String> {
+    let shared = state.shared(&id)?;
+    if !shared.paused.swap(false, Ordering::SeqCst) {
+        return Ok(Vec::new());
+    }
+
+    let mut changed: Vec<PathBuf> = shared
+        .touched_while_paused
+        .lock()
+        .map(|mut touched| touched.drain().collect())
+        .unwrap_or_default();
+    changed.sort();
+
+    if flush_on_resume.unwrap_or(false) {
+        let event_name = shared
+            .config
+            .lock()
+            .map(|config| config.event_name.clone())
+            .map_err(|_| "watch config lock failed".to_string())?;
+        for path in &changed {
+            shared.emit_update(&app, &event_name, path.clone(), "modified".to_string());
+        }
+    }
+
+    let changed: Vec<String> = changed
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let _ = app.emit(
+        "watch:resumed",
+        WatchResumedPayload {
+            id,
+            changed: changed.clone(),
+        },
+    );
+
+    Ok(changed)
+}
+
+/// Watch a file for the calling window only. Each webview (main window,
+/// popped-out texture inspector, ...) gets its own watch, and its events are
+/// delivered to that window rather than broadcast.
+#[tauri::command]
+pub fn start_window_watch(
+    path: String,
+    debounce_ms: Option<u64>,
+    window: tauri::WebviewWindow<crate::Runtime>,
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<(), String> {
+    let mut config = WatchConfig::new(vec![PathBuf::from(&path)], DEFAULT_WATCH_EVENT);
+    config.debounce = debounce_duration(debounce_ms);
+    config.window = Some(window.label().to_string());
+
+    state.start(&app, &window_watch_id(window.label()), config)
+}
+
+#[tauri::command]
+pub fn stop_window_watch(
+    window: tauri::WebviewWindow<crate::Runtime>,
+    state: State<WatcherRegistry>,
+) -> Result<(), String> {
+    state.stop(&window_watch_id(window.label()))
+}
+
+fn window_watch_id(label: &str) -> String {
+    format!("{WINDOW_WATCH_PREFIX}{label}")
+}
+
+/// Called from the builder's window event handler: drop every watch the
+/// destroyed window owned so its watcher threads and file handles go with it.
+/// When the last window goes (the app may live on in the tray), the global
+/// watches are stopped as well, leaving the watched folders deletable.
+///
+/// This runs on `Destroyed` rather than `CloseRequested`, since a close can
+/// still be cancelled or turned into hiding the window.
+pub fn on_window_destroyed(window: &tauri::Window<crate::Runtime>) {
+    let registry = window.state::<WatcherRegistry>();
+    let _ = registry.stop_window(window.label());
+
+    if is_last_window(window.webview_windows().keys(), window.label()) {
+        let _ = registry.stop_all();
+    }
+}
+
+/// Whether `closing` is the only window left among `open`.
+fn is_last_window<'a>(open: impl IntoIterator<Item = &'a String>, closing: &str) -> bool {
+    open.into_iter().all(|label| label == closing)
+}
+
+/// Watch several paths under the `multi` id. Paths are attempted one by one
+/// and reported as `{ watched, failed }`, so one missing file doesn't take
+/// the others down with it.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn start_multi_watch(
+    paths: Vec<String>,
+    event_name: Option<String>,
+    debounce_ms: Option<u64>,
+    rate_limit_ms: Option<u64>,
+    recursive: Option<bool>,
+    extensions: Option<Vec<String>>,
+    ignore: Option<Vec<String>>,
+    dedupe_by_hash: Option<bool>,
+    dedupe_max_bytes: Option<u64>,
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<serde_json::Value, String> {
+    let event_name = resolve_event_name(event_name)?;
+    let mut config = WatchConfig::new(collect_watch_paths(paths), &event_name);
+    config.recursive = recursive.unwrap_or(false);
+    config.debounce = debounce_duration(debounce_ms);
+    config.rate_limit = rate_limit_duration(rate_limit_ms);
+    config.extensions = normalize_watch_extensions(extensions.unwrap_or_default());
+    config.ignore = collect_ignore_patterns(ignore);
+    config.ignore_set = compile_ignore_patterns(&config.ignore)?;
+    config.dedupe_by_hash = dedupe_by_hash.unwrap_or(false);
+    config.dedupe_max_bytes = dedupe_max_bytes.unwrap_or(DEFAULT_DEDUPE_MAX_BYTES);
+
+    let report = state.start_each(&app, MULTI_WATCH_ID, config)?;
+    serde_json::to_value(report).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn stop_multi_watch(state: State<WatcherRegistry>) -> Result<(), String> {
+    state.stop(MULTI_WATCH_ID)
+}
+
+#[tauri::command]
+pub fn add_watch_paths(
+    paths: Vec<String>,
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<serde_json::Value, String> {
+    let report = state.add_paths(
+        &app,
+        MULTI_WATCH_ID,
+        collect_watch_paths(paths),
+        WatchConfig::new(Vec::new(), DEFAULT_WATCH_EVENT),
+    )?;
+    serde_json::to_value(report).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_watch_paths(paths: Vec<String>, state: State<WatcherRegistry>) -> Result<(), String> {
+    state.remove_paths(MULTI_WATCH_ID, collect_watch_paths(paths))
+}
+
+#[tauri::command]
+pub fn list_multi_watch_paths(state: State<WatcherRegistry>) -> Result<Vec<String>, String> {
+    Ok(state
+        .paths(MULTI_WATCH_ID)?
+        .into_iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
+}
+
+/// Watch a model file and emit `model:update` when it changes. With
+/// `auto_parse`, a changed .yft is also re-parsed in the background and the
+/// result emitted as `model:parsed` (the `parse_yft` result plus `path`) or
+/// `model:parse_error`.
+#[tauri::command]
+pub fn start_model_watch(
+    path: String,
+    debounce_ms: Option<u64>,
+    recursive: Option<bool>,
+    auto_parse: Option<bool>,
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<(), String> {
+    let mut config = WatchConfig::new(vec![PathBuf::from(&path)], "model:update");
+    config.recursive = recursive.unwrap_or(false);
+    config.debounce = debounce_duration(debounce_ms);
+    config.auto_parse = auto_parse.unwrap_or(false);
+
+    state.start(&app, MODEL_WATCH_ID, config)
+}
+
+#[tauri::command]
+pub fn stop_model_watch(state: State<WatcherRegistry>) -> Result<(), String> {
+    state.stop(MODEL_WATCH_ID)
+}
+
+/// Watch a folder (recursively) for model files being added or removed, e.g.
+/// vehicles extracted from OpenIV, so the UI can offer to open them.
+#[tauri::command]
+pub fn start_model_dir_watch(
+    path: String,
+    debounce_ms: Option<u64>,
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<(), String> {
+    let dir = PathBuf::from(path.trim());
+    if !dir.is_dir() {
+        return Err(format!("Not a directory: {}", dir.display()));
+    }
+
+    let mut config = WatchConfig::new(vec![dir], "model:added");
+    config.recursive = true;
+    config.debounce = debounce_duration(debounce_ms);
+    config.announce_models = true;
+
+    state.start(&app, MODEL_DIR_WATCH_ID, config)
+}
+
+#[tauri::command]
+pub fn stop_model_dir_watch(state: State<WatcherRegistry>) -> Result<(), String> {
+    state.stop(MODEL_DIR_WATCH_ID)
+}
+
+/// Watch a .pdn and decode it in the backend on every save, emitting
+/// `pdn:decoded` with the same fields `decode_pdn` returns plus `path`. This
+/// saves the frontend the event → invoke → decode round trip while painting.
+#[tauri::command]
+pub fn start_pdn_live_watch(
+    path: String,
+    debounce_ms: Option<u64>,
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<(), String> {
+    let file = PathBuf::from(path.trim());
+    if !file.is_file() {
+        return Err(format!("Not a file: {}", file.display()));
+    }
+
+    let mut config = WatchConfig::new(vec![file], "pdn:decoded");
+    config.debounce = debounce_duration(debounce_ms);
+    config.decode_pdn = true;
+
+    state.start(&app, PDN_LIVE_WATCH_ID, config)
+}
+
+#[tauri::command]
+pub fn stop_pdn_live_watch(state: State<WatcherRegistry>) -> Result<(), String> {
+    state.stop(PDN_LIVE_WATCH_ID)
+}
+
+/// Watch the texture dictionary next to the open model and emit `ytd:update`
+/// (path, size, mtime) once a repack has settled.
+#[tauri::command]
+pub fn start_ytd_watch(
+    ytd_path: String,
+    debounce_ms: Option<u64>,
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<(), String> {
+    let file = PathBuf::from(ytd_path.trim());
+    let is_ytd = file
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("ytd"))
+        .unwrap_or(false);
+    if !is_ytd {
+        return Err("Only .ytd files are supported by start_ytd_watch".to_string());
+    }
+    if !file.is_file() {
+        return Err(format!("Not a file: {}", file.display()));
+    }
+
+    let mut config = WatchConfig::new(vec![file], "ytd:update");
+    config.debounce = Duration::from_millis(debounce_ms.unwrap_or(DEFAULT_YTD_DEBOUNCE_MS));
+
+    state.start(&app, YTD_WATCH_ID, config)
+}
+
+#[tauri::command]
+pub fn stop_ytd_watch(state: State<WatcherRegistry>) -> Result<(), String> {
+    state.stop(YTD_WATCH_ID)
+}
+
+fn watch_session_path(app: &crate::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve config dir: {e}"))?
+        .join("cortex-labs")
+        .join("watches.json"))
+}
+
+fn read_watch_session(app: &crate::AppHandle) -> Result<WatchSession, String> {
+    let session_path = watch_session_path(app)?;
+    if !session_path.exists() {
+        return Ok(WatchSession::default());
+    }
+    let contents = std::fs::read_to_string(&session_path)
+        .map_err(|e| format!("Failed to read watch session: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse watch session: {e}"))
+}
+
+/// Re-create saved watches, skipping paths that no longer exist. A watch
+/// whose paths are all gone is skipped entirely.
+fn restore_session(
+    app: &crate::AppHandle,
+    registry: &WatcherRegistry,
+    session: WatchSession,
+) -> WatchRestoreReport {
+    let mut report = WatchRestoreReport::default();
+
+    for saved in session.watches {
+        let mut existing: Vec<PathBuf> = Vec::new();
+        for raw in &saved.paths {
+            let path = PathBuf::from(raw);
+            if path.exists() {
+                existing.push(path);
+            } else {
+                report.skipped.push(SkippedWatchPath {
+                    id: saved.id.clone(),
+                    path: Some(raw.clone()),
+                    reason: "path no longer exists".to_string(),
+                });
+            }
+        }
+        if existing.is_empty() {
+            continue;
+        }
+
+        let id = saved.id.clone();
+        let paths: Vec<String> = existing
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        match saved
+            .into_config(existing)
+            .and_then(|config| registry.start(app, &id, config))
+        {
+            Ok(()) => report.restored.push(RestoredWatch { id, paths }),
+            Err(error) => report.skipped.push(SkippedWatchPath {
+                id,
+                path: None,
+                reason: error,
+            }),
+        }
+    }
+
+    report
+}
+
+/// Called from `.setup()`: restore the last session if it was saved with
+/// `auto_restore`. A missing, unreadable or corrupt session file is ignored
+/// so it can never keep the app from launching.
+pub fn auto_restore_watch_session(app: &crate::AppHandle) {
+    let session = match read_watch_session(app) {
+        Ok(session) if session.auto_restore => session,
+        _ => return,
+    };
+    let registry = app.state::<WatcherRegistry>();
+    let _ = restore_session(app, &registry, session);
+}
+
+/// Save every active watch so it can be restored after a restart. With
+/// `auto_restore`, the session is re-created automatically on launch.
+#[tauri::command]
+pub fn save_watch_session(
+    auto_restore: Option<bool>,
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<usize, String> {
+    let session = WatchSession {
+        auto_restore: auto_restore.unwrap_or(false),
+        watches: state.snapshot()?,
+    };
+    let session_path = watch_session_path(&app)?;
+    if let Some(parent) = session_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir: {e}"))?;
+    }
+    let json = serde_json::to_string_pretty(&session).map_err(|e| e.to_string())?;
+    std::fs::write(&session_path, json)
+        .map_err(|e| format!("Failed to write watch session: {e}"))?;
+
+    Ok(session.watches.len())
+}
+
+#[tauri::command]
+pub fn restore_watch_session(
+    app: crate::AppHandle,
+    state: State<WatcherRegistry>,
+) -> Result<serde_json::Value, String> {
+    let session = read_watch_session(&app)?;
+    let report = restore_session(&app, &state, session);
+    serde_json::to_value(report).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, RemoveKind};
+    use tauri::Listener;
+
+    fn event(kind: EventKind, paths: &[&Path]) -> notify::Event {
+        paths.iter().fold(notify::Event::new(kind), |event, path| {
+            event.add_path(path.to_path_buf())
+        })
+    }
+
+    fn push_all(coalescer: &mut WatchCoalescer, start: Instant, events: &[notify::Event]) {
+        for (i, event) in events.iter().enumerate() {
+            let raw = RawWatchEvent::classify(event).unwrap();
+            coalescer.push(raw, start + Duration::from_millis(i as u64 * 5));
+        }
+    }
+
+    #[test]
+    fn temp_file_rename_is_one_replaced_event() {
+        let target = Path::new("/textures/foo.dds");
+        let temp = Path::new("/textures/foo.dds.tmp");
+        let start = Instant::now();
+        let mut coalescer = WatchCoalescer::new(Duration::from_millis(150), Duration::ZERO);
+        push_all(
+            &mut coalescer,
+            start,
+            &[
+                event(EventKind::Create(CreateKind::File), &[temp]),
+                event(
+                    EventKind::Modify(ModifyKind::Data(DataChange::Any)),
+                    &[temp],
+                ),
+                event(EventKind::Remove(RemoveKind::File), &[target]),
+                event(
+                    EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+                    &[temp],
+                ),
+                event(
+                    EventKind::Modify(ModifyKind::Name(RenameMode::To)),
+                    &[target],
+                ),
+            ],
+        );
+
+        // Nothing goes out while the target is still inside its debounce.
+        assert!(coalescer
+            .take_ready(start + Duration::from_millis(100))
+            .is_empty());
+        let deadline = coalescer.next_deadline().unwrap();
+        assert_eq!(
+            coalescer.take_ready(deadline),
+            vec![(target.to_path_buf(), "replaced".to_string())]
+        );
+        assert!(coalescer.next_deadline().is_none());
+        assert!(coalescer
+            .take_ready(start + Duration::from_secs(10))
+            .is_empty());
+    }
+
+    #[test]
+    fn rename_with_both_paths_is_one_replaced_event() {
+        let target = Path::new("/textures/foo.dds");
+        let temp = Path::new("/textures/~$foo.dds");
+        let start = Instant::now();
+        let mut coalescer = WatchCoalescer::new(Duration::from_millis(150), Duration::ZERO);
+        push_all(
+            &mut coalescer,
+            start,
+            &[
+                event(EventKind::Create(CreateKind::File), &[temp]),
+                event(
+                    EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                    &[temp, target],
+                ),
+            ],
+        );
+
+        assert_eq!(
+            coalescer.take_ready(start + Duration::from_secs(10)),
+            vec![(target.to_path_buf(), "replaced".to_string())]
+        );
+    }
+
+    #[test]
+    fn rename_outside_the_pair_window_is_not_collapsed() {
+        let from = Path::new("/textures/old.dds");
+        let to = Path::new("/textures/new.dds");
+        let start = Instant::now();
+        let mut coalescer = WatchCoalescer::new(Duration::from_millis(150), Duration::ZERO);
+        coalescer.push(RawWatchEvent::RenameFrom(from.to_path_buf()), start);
+        coalescer.push(
+            RawWatchEvent::RenameTo(to.to_path_buf()),
+            start + RENAME_PAIR_WINDOW + Duration::from_millis(1),
+        );
+
+        let mut ready = coalescer.take_ready(start + Duration::from_secs(10));
+        ready.sort();
+        assert_eq!(
+            ready,
+            vec![
+                (to.to_path_buf(), "Modify(Name(To))".to_string()),
+                (from.to_path_buf(), "Modify(Name(From))".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rate_limit_holds_a_path_but_not_its_neighbours() {
+        let busy = Path::new("/textures/busy.dds");
+        let other = Path::new("/textures/other.dds");
+        let start = Instant::now();
+        let debounce = Duration::from_millis(10);
+        let mut coalescer = WatchCoalescer::new(debounce, Duration::from_millis(250));
+        let modified = |path: &Path| RawWatchEvent::Change {
+            path: path.to_path_buf(),
+            kind: "modified".to_string(),
+        };
+
+        coalescer.push(modified(busy), start);
+        assert_eq!(coalescer.take_ready(start + debounce).len(), 1);
+        let again = start + Duration::from_millis(20);
+        coalescer.push(modified(busy), again);
+        coalescer.push(modified(other), again);
+        assert_eq!(
+            coalescer.take_ready(again + debounce),
+            vec![(other.to_path_buf(), "modified".to_string())]
+        );
+        assert_eq!(
+            coalescer.next_deadline(),
+            Some(start + debounce + Duration::from_millis(250))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_root_maps_events_back_to_the_callers_path() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let real = dir.join("real");
+        let link = dir.join("link");
+        std::fs::create_dir(&real).unwrap();
+        std::fs::write(real.join("a.dds"), b"dds").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let mut config = WatchConfig::new(vec![link.clone()], DEFAULT_WATCH_EVENT);
+        let canonical = std::fs::canonicalize(&real).unwrap();
+        assert_eq!(config.resolve_root(&link), canonical);
+        assert_eq!(config.watch_target(&link), canonical);
+        assert_eq!(config.to_original(&canonical), link);
+        assert_eq!(
+            config.to_original(&canonical.join("a.dds")),
+            link.join("a.dds")
+        );
+        assert_eq!(
+            config.to_resolved(&link.join("a.dds")),
+            canonical.join("a.dds")
+        );
+        assert_eq!(
+            config.to_original(Path::new("/elsewhere")),
+            Path::new("/elsewhere")
+        );
+
+        let payload = WatchPayload::new(
+            &link.join("a.dds"),
+            &config.to_resolved(&link.join("a.dds")),
+            "modified".to_string(),
+        );
+        assert_eq!(payload.path, link.join("a.dds").to_string_lossy());
+        assert_eq!(
+            payload.resolved_path,
+            canonical.join("a.dds").to_string_lossy()
+        );
+        assert_eq!(payload.size, Some(3));
+
+        let missing = dir.join("missing");
+        assert_eq!(config.resolve_root(&missing), missing);
+        assert_eq!(config.watch_target(&missing), missing);
+    }
+
+    /// A mock app with a window per label and, besides a global texture
+    /// watch, one watch owned by each window.
+    fn app_with_window_watches(
+        dir: &Path,
+        labels: &[&str],
+    ) -> (
+        tauri::App<crate::Runtime>,
+        Vec<tauri::WebviewWindow<crate::Runtime>>,
+    ) {
+        let app = mock_app();
+        let windows = labels
+            .iter()
+            .map(|label| {
+                tauri::WebviewWindowBuilder::new(&app, *label, Default::default())
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+        let owners = labels.iter().map(|label| Some(*label)).chain([None]);
+        for owner in owners {
+            let id = owner.map_or(TEXTURE_WATCH_ID.to_string(), |label| {
+                format!("window:{label}")
+            });
+            let mut config = WatchConfig::new(vec![dir.to_path_buf()], DEFAULT_WATCH_EVENT);
+            config.window = owner.map(str::to_string);
+            app.state::<WatcherRegistry>()
+                .start(app.handle(), &id, config)
+                .unwrap();
+        }
+        (app, windows)
+    }
+
+    fn watch_ids(app: &tauri::App<crate::Runtime>) -> Vec<String> {
+        let registry = app.state::<WatcherRegistry>();
+        let mut ids: Vec<String> = registry.watches.lock().unwrap().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    fn watch_state(app: &tauri::App<crate::Runtime>, id: &str) -> std::sync::Weak<WatchShared> {
+        Arc::downgrade(&app.state::<WatcherRegistry>().watches.lock().unwrap()[id].shared)
+    }
+
+    /// The debounce thread holds on to the shared state until the watcher
+    /// (and with it the sender) is dropped, so a dead `Weak` means the notify
+    /// watcher is gone too.
+    fn wait_dropped(state: &std::sync::Weak<WatchShared>) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while state.strong_count() > 0 {
+            assert!(Instant::now() < deadline, "watch state still alive");
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn closing_a_window_stops_only_its_watches() {
+        let dir = tempfile::tempdir().unwrap();
+        let (app, windows) = app_with_window_watches(dir.path(), &["main", "inspector"]);
+        let inspector = watch_state(&app, "window:inspector");
+        let others = [
+            watch_state(&app, "window:main"),
+            watch_state(&app, TEXTURE_WATCH_ID),
+        ];
+
+        on_window_destroyed(&windows[1].as_ref().window());
+        assert_eq!(watch_ids(&app), [TEXTURE_WATCH_ID, "window:main"]);
+        wait_dropped(&inspector);
+        assert!(others.iter().all(|state| state.strong_count() > 0));
+    }
+
+    #[test]
+    fn closing_the_last_window_stops_every_watch() {
+        let dir = tempfile::tempdir().unwrap();
+        let (app, windows) = app_with_window_watches(dir.path(), &["main"]);
+        let states = [
+            watch_state(&app, "window:main"),
+            watch_state(&app, TEXTURE_WATCH_ID),
+        ];
+
+        on_window_destroyed(&windows[0].as_ref().window());
+        assert!(watch_ids(&app).is_empty());
+        for state in &states {
+            wait_dropped(state);
+        }
+    }
+
+    #[test]
+    fn notify_errors_become_watch_error_payloads() {
+        let shared = WatchShared::new("texture", WatchConfig::new(Vec::new(), DEFAULT_WATCH_EVENT));
+        let error = notify::Error::path_not_found().add_path(PathBuf::from("/gone/foo.dds"));
+        shared.record_error(&error.to_string());
+        let payload = WatchErrorPayload::new(&shared.id, error.paths.first(), &error.to_string());
+
+        assert_eq!(
+            serde_json::to_value(payload).unwrap(),
+            serde_json::json!({
+                "id": "texture",
+                "path": "/gone/foo.dds",
+                "error": error.to_string(),
+            })
+        );
+        assert_eq!(
+            shared.stats.lock().unwrap().last_error.as_deref(),
+            Some(error.to_string().as_str())
+        );
+    }
+
+    #[test]
+    fn only_removing_a_root_triggers_rearm() {
+        let root = Path::new("/textures");
+        let child = Path::new("/textures/foo.dds");
+        let shared = WatchShared::new(
+            "texture",
+            WatchConfig::new(vec![root.to_path_buf()], DEFAULT_WATCH_EVENT),
+        );
+
+        let remove = |path| event(EventKind::Remove(RemoveKind::Any), &[path]);
+        assert_eq!(shared.removed_roots(&remove(root)), [root]);
+        assert_eq!(
+            shared.removed_roots(&event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+                &[root]
+            )),
+            [root]
+        );
+        assert!(shared.removed_roots(&remove(child)).is_empty());
+        assert!(shared
+            .removed_roots(&event(
+                EventKind::Modify(ModifyKind::Data(DataChange::Any)),
+                &[root]
+            ))
+            .is_empty());
+    }
+
+    #[test]
+    fn deleting_a_watched_dir_reports_it_lost() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("deleted");
+        std::fs::create_dir(&dir).unwrap();
+        let app = mock_app();
+        let (tx, rx) = mpsc::channel();
+        for event in ["watch:lost", "watch:error"] {
+            let tx = tx.clone();
+            app.listen(event, move |payload| {
+                let _ = tx.send((event, payload.payload().to_string()));
+            });
+        }
+
+        let mut config = WatchConfig::new(vec![dir.clone()], DEFAULT_WATCH_EVENT);
+        let target = config.resolve_root(&dir);
+        let shared = WatchShared::new("texture", config);
+        let mut watcher = create_debounced_watcher(app.handle().clone(), shared).unwrap();
+        watcher.watch(&target, RecursiveMode::NonRecursive).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // The root never comes back, so every re-arm attempt fails first.
+        let timeout = REARM_INTERVAL * (REARM_ATTEMPTS + 8);
+        let (event, payload) = rx
+            .recv_timeout(timeout)
+            .unwrap_or_else(|_| panic!("no watch:lost for {}", dir.display()));
+        assert_eq!(event, "watch:lost", "{payload}");
+        let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(
+            payload,
+            serde_json::json!({ "id": "texture", "path": dir.to_string_lossy() })
+        );
+    }
+
+    fn mock_app() -> tauri::App<crate::Runtime> {
+        tauri::test::mock_builder()
+            .manage(WatcherRegistry::default())
+            .manage(crate::tasks::TaskRegistry::default())
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .unwrap()
+    }
+
+    fn start_multi(app: &tauri::App<crate::Runtime>, paths: &[&Path]) -> serde_json::Value {
+        start_multi_watch(
+            paths
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            app.handle().clone(),
+            app.state(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn multi_watch_keeps_existing_paths_and_reports_missing_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.dds");
+        let b = dir.path().join("b.dds");
+        std::fs::write(&a, b"dds").unwrap();
+        std::fs::write(&b, b"dds").unwrap();
+        let missing = dir.path().join("missing.dds");
+        let gone = dir.path().join("gone").join("c.dds");
+        let app = mock_app();
+
+        // Paths are deduplicated through a set, so compare them sorted.
+        let report = start_multi(&app, &[&a, &missing, &b, &gone]);
+        let text = |path: &Path| path.to_string_lossy().to_string();
+        let mut watched: Vec<&str> = report["watched"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|path| path.as_str().unwrap())
+            .collect();
+        watched.sort();
+        assert_eq!(watched, [text(&a), text(&b)]);
+        let failed = report["failed"].as_array().unwrap();
+        let mut failed_paths: Vec<&str> = failed
+            .iter()
+            .map(|failure| failure["path"].as_str().unwrap())
+            .collect();
+        failed_paths.sort();
+        assert_eq!(failed_paths, [text(&gone), text(&missing)]);
+        for failure in failed {
+            assert!(!failure["error"].as_str().unwrap().is_empty(), "{failure}");
+        }
+
+        let active = app.state::<WatcherRegistry>().describe().unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, MULTI_WATCH_ID);
+        let mut active_paths = active[0].paths.clone();
+        active_paths.sort();
+        assert_eq!(active_paths, [text(&a), text(&b)]);
+
+        // With nothing watchable the previous watch is gone and nothing
+        // replaces it.
+        let report = start_multi(&app, &[&missing]);
+        assert_eq!(report["watched"], serde_json::json!([]));
+        assert_eq!(report["failed"].as_array().unwrap().len(), 1);
+        assert!(app
+            .state::<WatcherRegistry>()
+            .describe()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn stopping_the_pdn_live_watch_aborts_its_decode() {
+        let dir = tempfile::tempdir().unwrap();
+        let pdn = dir.path().join("livery.pdn");
+        // Its pixels are cut short, so a decode that gets as far as the first
+        // gzip chunk fails there.
+        let fixture =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/pdn/truncated-pixels.pdn");
+        std::fs::copy(fixture, &pdn).unwrap();
+        let path = pdn.to_string_lossy().to_string();
+        let app = mock_app();
+        start_pdn_live_watch(path.clone(), None, app.handle().clone(), app.state()).unwrap();
+        let shared = app.state::<WatcherRegistry>().watches.lock().unwrap()[PDN_LIVE_WATCH_ID]
+            .shared
+            .clone();
+
+        // A decode queued the way the debouncer queues one.
+        let generation = shared.decode_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let task = crate::tasks::start(app.handle(), "pdn", Some(&path));
+        let error = decode_live_pdn(&shared, &task, path.clone(), generation).unwrap_err();
+        assert!(error.contains("decompress"), "{error}");
+
+        // Once the watch is stopped the same decode gives up before it
+        // inflates anything.
+        stop_pdn_live_watch(app.state()).unwrap();
+        let error = decode_live_pdn(&shared, &task, path, generation).unwrap_err();
+        assert_eq!(error, crate::conversion::CANCELLED);
+    }
+}
@@ -0,0 +1,57 @@
+#!/usr/bin/env python3
+"""Writes the .clmesh fixtures the clmesh.rs tests read.
+
+valid.clmesh is laid out the way the CodeWalker bridge writes it: a body
+mesh with normals and UVs and a glass mesh with positions only.
+truncated.clmesh is the same file cut off inside the glass mesh's
+positions, as an interrupted bridge run leaves it.
+
+Run from this directory: python3 generate.py
+"""
+
+import struct
+
+FLAG_NORMALS = 0x1
+FLAG_UVS = 0x2
+
+
+def string(value):
+    data = value.encode("utf-8")
+    return struct.pack("<H", len(data)) + data
+
+
+def mesh(name, material, positions, indices, normals=None, uvs=None):
+    flags = (FLAG_NORMALS if normals else 0) | (FLAG_UVS if uvs else 0)
+    out = string(name) + string(material)
+    out += struct.pack("<IIB", len(positions), len(indices), flags)
+    for attribute in (positions, normals or [], uvs or []):
+        for vertex in attribute:
+            out += struct.pack("<%df" % len(vertex), *vertex)
+    out += struct.pack("<%dI" % len(indices), *indices)
+    return out
+
+
+def main():
+    body = mesh(
+        "body",
+        "vehicle_paint",
+        [(-1.0, -2.0, 0.0), (1.0, -2.0, 0.0), (1.0, 2.0, 0.5), (-1.0, 2.0, 0.5)],
+        [0, 1, 2, 0, 2, 3],
+        normals=[(0.0, 0.0, 1.0)] * 4,
+        uvs=[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+    )
+    glass = mesh(
+        "glass",
+        "vehicle_glass",
+        [(-0.5, 0.0, 1.0), (0.5, 0.0, 1.0), (0.0, 1.0, 1.5)],
+        [0, 1, 2],
+    )
+    data = b"CLM1" + struct.pack("<HH", 1, 2) + body + glass
+    with open("valid.clmesh", "wb") as f:
+        f.write(data)
+    with open("truncated.clmesh", "wb") as f:
+        f.write(data[: len(data) - 20])
+
+
+if __name__ == "__main__":
+    main()
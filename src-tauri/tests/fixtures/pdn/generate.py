@@ -0,0 +1,254 @@
+#!/usr/bin/env python3
+"""Writes the malformed and truncated .pdn fixtures the pdn.rs tests read.
+Files that should decode are saved by Paint.NET itself; README.md lists them.
+
+Each case starts from a file laid out the way this tree expects Paint.NET
+to save one: the "PDN3" magic, the XML header, the BinaryFormatter
+document, then every layer's BGRA pixels in gzip chunks.
+
+Run from this directory: python3 generate.py
+"""
+
+import gzip
+import struct
+
+WIDTH = HEIGHT = 16
+
+
+class Nrbf:
+    """Just enough of a BinaryFormatter writer for a Paint.NET document."""
+
+    def __init__(self):
+        self.out = bytearray(b"\x00\x01\x00\x00\x00\xff\xff\xff\xff\x01\x00\x00\x00\x00\x00\x00\x00")
+        self.layouts = {}
+        self.next_id = 100
+
+    def id(self):
+        self.next_id += 1
+        return self.next_id
+
+    def u8(self, value):
+        self.out.append(value)
+
+    def i32(self, value):
+        self.out += struct.pack("<i", value)
+
+    def string(self, text):
+        data = text.encode()
+        length = len(data)
+        while True:
+            byte = length & 0x7F
+            length >>= 7
+            if length:
+                self.u8(byte | 0x80)
+            else:
+                self.u8(byte)
+                break
+        self.out += data
+
+    def library(self, library_id, name):
+        self.u8(12)
+        self.i32(library_id)
+        self.string(name)
+
+    def object_string(self, object_id, text):
+        self.u8(6)
+        self.i32(object_id)
+        self.string(text)
+
+    def reference(self, object_id):
+        self.u8(9)
+        self.i32(object_id)
+
+    def null(self):
+        self.u8(10)
+
+    def begin(self, object_id, class_name, members, library):
+        """A class record up to its member values. `members` are
+        (name, kind, extra): kind 0 with a primitive type, 1 string,
+        2 object, 3 with a system class name, 4 with a class name."""
+        if class_name in self.layouts:
+            self.u8(1)
+            self.i32(object_id)
+            self.i32(self.layouts[class_name])
+            return
+        self.layouts[class_name] = object_id
+        self.u8(5 if library else 4)
+        self.i32(object_id)
+        self.string(class_name)
+        self.i32(len(members))
+        for name, _, _ in members:
+            self.string(name)
+        for _, kind, _ in members:
+            self.u8(kind)
+        for _, kind, extra in members:
+            if kind == 0:
+                self.u8(extra)
+            elif kind == 3:
+                self.string(extra)
+            elif kind == 4:
+                self.string(extra)
+                self.i32(library)
+        if library:
+            self.i32(library)
+
+
+BOOL, BYTE, INT32, INT64 = 1, 2, 8, 9
+LIBRARY = 2
+
+
+def document(layers):
+    """The document for `layers`, each a dict with a name, all visible,
+    opaque and Normal."""
+    w = Nrbf()
+    w.library(LIBRARY, "PaintDotNet.Data, Version=3.36.0.0")
+    w.begin(1, "PaintDotNet.Document", [
+        ("layers", 4, "PaintDotNet.LayerList"),
+        ("width", 0, INT32),
+        ("height", 0, INT32),
+        ("savedWith", 3, "System.Version"),
+        ("userMetaData", 2, None),
+    ], LIBRARY)
+    w.reference(2)
+    w.i32(WIDTH)
+    w.i32(HEIGHT)
+    w.null()
+    w.null()
+
+    w.begin(2, "PaintDotNet.LayerList", [
+        ("parent", 4, "PaintDotNet.Document"),
+        ("ArrayList+_items", 2, None),
+        ("ArrayList+_size", 0, INT32),
+        ("ArrayList+_version", 0, INT32),
+    ], LIBRARY)
+    w.reference(1)
+    w.reference(4)
+    w.i32(len(layers))
+    w.i32(len(layers))
+
+    # ArrayList's backing array has spare capacity past _size.
+    w.u8(16)
+    w.i32(4)
+    w.i32(len(layers) + 2)
+    layer_ids = [w.id() for _ in layers]
+    for layer_id in layer_ids:
+        w.reference(layer_id)
+    w.u8(13)
+    w.u8(2)
+
+    for layer_id, layer in zip(layer_ids, layers):
+        properties, bitmap_properties, surface = w.id(), w.id(), w.id()
+        w.begin(layer_id, "PaintDotNet.BitmapLayer", [
+            ("properties", 4, "PaintDotNet.BitmapLayer+BitmapLayerProperties"),
+            ("surface", 4, "PaintDotNet.Surface"),
+            ("Layer+isDisposed", 0, BOOL),
+            ("Layer+width", 0, INT32),
+            ("Layer+height", 0, INT32),
+            ("Layer+properties", 4, "PaintDotNet.LayerProperties"),
+        ], LIBRARY)
+        w.reference(bitmap_properties)
+        w.reference(surface)
+        w.u8(0)
+        w.i32(WIDTH)
+        w.i32(HEIGHT)
+        w.reference(properties)
+
+        name = w.id()
+        w.begin(properties, "PaintDotNet.LayerProperties", [
+            ("name", 1, None),
+            ("userMetaData", 2, None),
+            ("visible", 0, BOOL),
+            ("isBackground", 0, BOOL),
+            ("opacity", 0, BYTE),
+        ], LIBRARY)
+        w.object_string(name, layer["name"])
+        w.null()
+        w.u8(1)
+        w.u8(1 if layer_id == layer_ids[0] else 0)
+        w.u8(255)
+
+        w.begin(bitmap_properties, "PaintDotNet.BitmapLayer+BitmapLayerProperties", [
+            ("blendMode", 4, "PaintDotNet.LayerBlendMode"),
+        ], LIBRARY)
+        w.begin(w.id(), "PaintDotNet.LayerBlendMode", [("value__", 0, INT32)], LIBRARY)
+        w.i32(0)
+
+        block = w.id()
+        w.begin(surface, "PaintDotNet.Surface", [
+            ("width", 0, INT32),
+            ("height", 0, INT32),
+            ("stride", 0, INT32),
+            ("scan0", 4, "PaintDotNet.MemoryBlock"),
+        ], LIBRARY)
+        w.i32(WIDTH)
+        w.i32(HEIGHT)
+        w.i32(WIDTH * 4)
+        w.reference(block)
+        w.begin(block, "PaintDotNet.MemoryBlock", [
+            ("length64", 0, INT64),
+            ("hasParent", 0, BOOL),
+            ("deferred", 0, BOOL),
+        ], LIBRARY)
+        w.out += struct.pack("<q", WIDTH * HEIGHT * 4)
+        w.u8(0)
+        w.u8(1)
+
+    w.u8(11)
+    return bytes(w.out)
+
+
+def pdn(layers):
+    """A whole file; each layer's `pixels` are RGBA rows."""
+    xml = ('<pdnImage width="%d" height="%d" layers="%d" savedWithVersion="4.3.12.8894">'
+           '<custom><thumb png="" /></custom></pdnImage>'
+           % (WIDTH, HEIGHT, len(layers))).encode()
+    out = bytearray(b"PDN3")
+    out += struct.pack("<I", len(xml))[:3]
+    out += xml
+    out += b"\x00\x01"
+    out += document(layers)
+    for layer in layers:
+        bgra = bytearray()
+        for r, g, b, a in layer["pixels"]:
+            bgra += bytes((b, g, r, a))
+        # The deferred surface data: a format byte and chunk size, then
+        # numbered, length-prefixed gzip chunks of it.
+        chunk = len(bgra) // 2
+        out += b"\x01" + struct.pack(">I", chunk)
+        for number, at in enumerate(range(0, len(bgra), chunk)):
+            payload = gzip.compress(bytes(bgra[at:at + chunk]), mtime=0)
+            out += struct.pack(">II", number, len(payload)) + payload
+    return bytes(out)
+
+
+def solid(color):
+    return [color] * (WIDTH * HEIGHT)
+
+
+def malformed():
+    """Each case's name and bytes; every one must decode to an error."""
+    layers = [
+        {"name": "Background", "pixels": solid((255, 0, 0, 255))},
+        {"name": "Top", "pixels": solid((0, 0, 255, 255))},
+    ]
+    whole = pdn(layers)
+    first_chunk = whole.index(b"\x1f\x8b\x08")
+    yield "no-magic.pdn", b"PDN2" + whole[4:]
+    # Cut inside the XML, so neither it nor the document can be read.
+    yield "truncated-header.pdn", whole[:64]
+    # Same length, so the header length still fits.
+    yield "zero-width.pdn", whole.replace(b'width="16"', b'width="0" ', 1)
+    # The header and document are whole; the pixels are missing or stop
+    # partway into the first gzip chunk.
+    yield "no-pixels.pdn", whole[:first_chunk]
+    yield "truncated-pixels.pdn", whole[:first_chunk + 16]
+
+
+def main():
+    for name, data in malformed():
+        with open(name, "wb") as f:
+            f.write(data)
+
+
+if __name__ == "__main__":
+    main()
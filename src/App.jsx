@@ -426,8 +426,8 @@ function App({ shellTab, isActive = true, onRenameTab, settingsVersion, defaultT
 
   const modelExtensions =
     textureMode === "eup" || textureMode === "multi"
-      ? ["yft", "clmesh", "dff", "ydd"]
-      : ["yft", "clmesh", "dff"];
+      ? ["yft", "clmesh", "dff", "ydd", "ydr"]
+      : ["yft", "clmesh", "dff", "ydr"];
   const modelDropLabel = modelExtensions.map((ext) => `.${ext}`).join(" / ");
 
   const loadModel = useCallback(
@@ -1052,7 +1052,7 @@ function App({ shellTab, isActive = true, onRenameTab, settingsVersion, defaultT
     }
   };
 
-  const modelExtsDual = ["yft", "clmesh", "dff", "ydd"];
+  const modelExtsDual = ["yft", "clmesh", "dff", "ydd", "ydr"];
   const textureExtsDual = ["png", "jpg", "jpeg", "webp", "avif", "bmp", "gif", "tga", "dds", "tif", "tiff", "psd", "ai", "pdn"];
 
   // Material texture upload handler
@@ -1461,7 +1461,7 @@ function App({ shellTab, isActive = true, onRenameTab, settingsVersion, defaultT
         }
 
         const ok = await invoke("start_multi_watch", { paths }).then(
-          () => true,
+          (report) => (report?.watched?.length ?? 0) > 0,
           () => false,
         );
 
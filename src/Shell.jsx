@@ -196,7 +196,7 @@ export default function Shell() {
     lastExternalFileOpenRef.current = { path: filePath, at: now };
 
     const lower = filePath.toLowerCase();
-    if (!(lower.endsWith(".yft") || lower.endsWith(".ydd") || lower.endsWith(".dff") || lower.endsWith(".clmesh"))) {
+    if (!(lower.endsWith(".yft") || lower.endsWith(".ydd") || lower.endsWith(".ydr") || lower.endsWith(".dff") || lower.endsWith(".clmesh"))) {
       return;
     }
 
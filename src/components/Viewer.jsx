@@ -4,6 +4,7 @@ import { OrbitControls } from "three/examples/jsm/controls/OrbitControls";
 import { DDSLoader } from "three/examples/jsm/loaders/DDSLoader";
 import { TGALoader } from "three/examples/jsm/loaders/TGALoader";
 import { DFFLoader } from "dff-loader";
+import { invoke } from "@tauri-apps/api/core";
 import { readFile } from "@tauri-apps/plugin-fs";
 import { parseYft } from "../lib/yft";
 import { parseDDS } from "../lib/dds";
@@ -1180,6 +1181,31 @@ function ViewerComponent({
               return;
             }
             object.userData.sourceFormat = "ydd";
+          } else if (extension === "ydr") {
+            let parsed = null;
+            try {
+              parsed = await invoke("parse_ydr", { path: modelPath });
+            } catch (err) {
+              console.error("[YDR] Parse error:", err);
+              onModelErrorRef.current?.("YDR parsing failed.");
+              return;
+            }
+            if (cancelled) return;
+            let bytes = null;
+            try {
+              bytes = await readFile(parsed.meshPath);
+            } catch {
+              onModelErrorRef.current?.("Failed to read mesh cache.");
+              return;
+            }
+            if (cancelled) return;
+            const meshes = parseClmesh(bytes);
+            if (!meshes || meshes.length === 0) {
+              onModelErrorRef.current?.("YDR parsed but no mesh data was generated.");
+              return;
+            }
+            object = buildClmeshObject(meshes);
+            object.userData.sourceFormat = "ydr";
           } else if (extension === "clmesh") {
             let bytes = null;
             try {
@@ -1201,31 +1201,19 @@ function isTauriRuntimeAvailable() {
   );
 }
 
-function yieldToMainThread() {
-  return new Promise((resolve) => {
-    if (typeof window !== "undefined" && typeof window.requestAnimationFrame === "function") {
-      window.requestAnimationFrame(() => resolve());
-      return;
-    }
-    setTimeout(resolve, 0);
-  });
-}
-
-async function decodeBase64ToUint8Array(base64Value) {
-  if (!base64Value || typeof base64Value !== "string") return null;
-  const binary = atob(base64Value);
-  const bytes = new Uint8Array(binary.length);
-  const chunkSize = 1024 * 1024;
-  for (let start = 0; start < binary.length; start += chunkSize) {
-    const end = Math.min(start + chunkSize, binary.length);
-    for (let index = start; index < end; index += 1) {
-      bytes[index] = binary.charCodeAt(index);
-    }
-    if (end < binary.length) {
-      await yieldToMainThread();
-    }
-  }
-  return bytes;
+/**
+ * Split a binary pixel response from the backend: a 4-byte little-endian
+ * length, that much JSON (width, height, ...), then the raw RGBA.
+ *
+ * @param {ArrayBuffer} buffer
+ * @returns {{ meta: object, rgba: Uint8Array } | null}
+ */
+function readRgbaResponse(buffer) {
+  if (!(buffer instanceof ArrayBuffer) || buffer.byteLength < 4) return null;
+  const metaLength = new DataView(buffer).getUint32(0, true);
+  if (4 + metaLength > buffer.byteLength) return null;
+  const meta = JSON.parse(new TextDecoder().decode(new Uint8Array(buffer, 4, metaLength)));
+  return { meta, rgba: new Uint8Array(buffer, 4 + metaLength) };
 }
 
 async function decodePdnViaWorker(bytes) {
@@ -1298,10 +1286,10 @@ async function decodePdnViaTauri(filePath) {
   }
 
   pdnLog("debug", "Attempting Tauri decode fallback", { filePath });
-  const payload = await invoke("decode_pdn", { path: filePath });
-  const width = Number(payload?.width || 0);
-  const height = Number(payload?.height || 0);
-  const rgba = await decodeBase64ToUint8Array(payload?.rgba_base64);
+  const response = readRgbaResponse(await invoke("decode_pdn", { path: filePath }));
+  const width = Number(response?.meta?.width || 0);
+  const height = Number(response?.meta?.height || 0);
+  const rgba = response?.rgba ?? null;
 
   pdnLog("debug", "Tauri decode response received", {
     width,
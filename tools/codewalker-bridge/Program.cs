@@ -11,15 +11,72 @@ public static class Program
     private const string Magic = "CLM1";
     private const ushort Version = 1;
 
+    private static readonly (string Level, Func<DrawableBase, DrawableModel[]?> Models)[] LodLevels =
+    {
+        ("high", d => d.DrawableModels?.High),
+        ("med", d => d.DrawableModels?.Med),
+        ("low", d => d.DrawableModels?.Low),
+        ("vlow", d => d.DrawableModels?.VLow),
+    };
+
+    private static bool _jenkIndexLoaded;
+
     public static int Main(string[] args)
     {
         Console.OutputEncoding = Encoding.UTF8;
+        return HasFlag(args, "--server") ? BridgeServer.Serve(Run) : Run(args);
+    }
+
+    // One invocation's worth of work: the result goes to stdout, errors to
+    // stderr, and the return value is the exit code.
+    public static int Run(string[] args)
+    {
+        if (HasFlag(args, "--version"))
+        {
+            Console.WriteLine($"CodeWalkerBridge {VersionOf(typeof(Program))} (CodeWalker.Core {VersionOf(typeof(YftFile))})");
+            return 0;
+        }
+
+        var extractYtd = GetArg(args, "--extract-ytd");
+        if (extractYtd != null)
+        {
+            var dest = GetArg(args, "--dest");
+            if (string.IsNullOrWhiteSpace(dest))
+            {
+                Console.Error.WriteLine("Usage: CodeWalkerBridge --extract-ytd <file.ytd> --dest <dir> [--format dds|png]");
+                return 2;
+            }
+            try
+            {
+                LoadJenkIndexStrings();
+                return TextureExtractor.Extract(extractYtd, dest, GetArg(args, "--format") ?? "png");
+            }
+            catch (Exception ex)
+            {
+                Console.Error.WriteLine(ex.ToString());
+                return 1;
+            }
+        }
+
+        var rpfList = GetArg(args, "--rpf-list");
+        var rpfExtract = GetArg(args, "--rpf-extract");
+        if (rpfList != null || rpfExtract != null)
+        {
+            return RunRpfCommand(args, rpfList, rpfExtract);
+        }
 
         var inputPath = GetArg(args, "--input");
         var outputPath = GetArg(args, "--output");
+        var ytdPath = GetArg(args, "--ytd");
+        var type = (GetArg(args, "--type") ?? "yft").ToLowerInvariant();
+        var drawableName = GetArg(args, "--drawable");
+        var writeLods = HasFlag(args, "--lods");
+        var writeSkeleton = HasFlag(args, "--skeleton");
+        var writeCollision = HasFlag(args, "--collision");
+        var writeDamaged = HasFlag(args, "--damaged");
         if (string.IsNullOrWhiteSpace(inputPath) || string.IsNullOrWhiteSpace(outputPath))
         {
-            Console.Error.WriteLine("Usage: CodeWalkerBridge --input <file.yft> --output <file.clmesh>");
+            Console.Error.WriteLine("Usage: CodeWalkerBridge --input <file> --output <file.clmesh> [--type yft|ydd|ydr] [--drawable <name>] [--ytd <file.ytd>] [--lods] [--skeleton] [--collision] [--damaged]");
             return 2;
         }
 
@@ -27,25 +84,94 @@ public static class Program
         {
             LoadJenkIndexStrings();
             var data = File.ReadAllBytes(inputPath);
-            var yft = new YftFile();
-            yft.Load(data);
+            var baseName = Path.GetFileNameWithoutExtension(inputPath);
+            var meta = new Dictionary<string, object?>();
 
-            var meshes = ExtractMeshes(yft, Path.GetFileNameWithoutExtension(inputPath));
+            List<DrawableBase> drawables;
+            Bounds? collisionBound = null;
+            var damagedDrawables = new List<DrawableBase>();
+            switch (type)
+            {
+                case "yft":
+                {
+                    var yft = new YftFile();
+                    yft.Load(data);
+                    drawables = GetFragmentDrawables(yft);
+                    collisionBound = yft.Fragment?.PhysicsLODGroup?.PhysicsLOD1?.Bound ?? yft.Fragment?.Bound;
+                    damagedDrawables = GetDamagedDrawables(yft);
+                    break;
+                }
+                case "ydd":
+                {
+                    var ydd = new YddFile();
+                    ydd.Load(data);
+                    var named = GetDictionaryDrawables(ydd);
+                    meta["drawables"] = named.Select(d => d.Name).ToList();
+
+                    if (!string.IsNullOrWhiteSpace(drawableName))
+                    {
+                        var selected = named.FirstOrDefault(d => d.Matches(drawableName));
+                        if (selected == null)
+                        {
+                            Console.Error.WriteLine($"Drawable '{drawableName}' was not found in the YDD.");
+                            return 3;
+                        }
+                        named = new List<NamedDrawable> { selected };
+                        meta["drawable"] = selected.Name;
+                    }
+
+                    drawables = named.Select(d => d.Drawable).ToList();
+                    break;
+                }
+                case "ydr":
+                {
+                    var ydr = new YdrFile();
+                    ydr.Load(data);
+                    if (ydr.Drawable == null)
+                    {
+                        Console.Error.WriteLine("The YDR contains no drawable.");
+                        return 3;
+                    }
+                    var embedded = ydr.Drawable.ShaderGroup?.TextureDictionary?.Textures?.data_items;
+                    meta["embeddedTextures"] = embedded != null && embedded.Length > 0;
+                    drawables = new List<DrawableBase> { ydr.Drawable };
+                    break;
+                }
+                default:
+                    Console.Error.WriteLine($"Unsupported --type '{type}'.");
+                    return 2;
+            }
+
+            var meshes = ExtractDrawableMeshes(drawables, baseName);
             if (meshes.Count == 0)
             {
-                Console.Error.WriteLine("No meshes were extracted from the YFT.");
+                Console.Error.WriteLine($"No meshes were extracted from the {type.ToUpperInvariant()}.");
                 return 3;
             }
 
             WriteClmesh(outputPath, meshes);
+            meta["lods"] = ExtractLods(drawables, baseName, writeLods ? Path.GetDirectoryName(outputPath) : null);
+            if (writeSkeleton)
+            {
+                meta["skeleton"] = ExtractSkeleton(drawables, meshes, Path.ChangeExtension(outputPath, ".skel.json"));
+            }
+            if (writeDamaged)
+            {
+                meta["damaged"] = ExtractDamaged(damagedDrawables, baseName, Path.GetDirectoryName(outputPath) ?? ".");
+            }
+            if (writeCollision)
+            {
+                meta["collision"] = ExtractCollision(collisionBound, baseName, Path.GetDirectoryName(outputPath) ?? ".");
+            }
 
-            var meta = new
+            meta["meshCount"] = meshes.Count;
+            meta["vertexCount"] = meshes.Sum(m => m.VertexCount);
+            meta["indexCount"] = meshes.Sum(m => m.IndexCount);
+            meta["materialCount"] = meshes.Select(m => m.MaterialName).Where(n => !string.IsNullOrEmpty(n)).Distinct().Count();
+            if (!string.IsNullOrWhiteSpace(ytdPath))
             {
-                meshCount = meshes.Count,
-                vertexCount = meshes.Sum(m => m.VertexCount),
-                indexCount = meshes.Sum(m => m.IndexCount),
-                materialCount = meshes.Select(m => m.MaterialName).Where(n => !string.IsNullOrEmpty(n)).Distinct().Count()
-            };
+                meta["textures"] = ResolveTextures(meshes, ytdPath);
+            }
             Console.WriteLine(JsonSerializer.Serialize(meta));
             return 0;
         }
@@ -56,6 +182,42 @@ public static class Program
         }
     }
 
+    private static int RunRpfCommand(string[] args, string? listPath, string? extractPath)
+    {
+        var gtaDir = GetArg(args, "--gta");
+        try
+        {
+            if (listPath != null)
+            {
+                return RpfCommands.List(listPath, gtaDir);
+            }
+
+            var entry = GetArg(args, "--entry");
+            var dest = GetArg(args, "--dest");
+            if (string.IsNullOrWhiteSpace(entry) || string.IsNullOrWhiteSpace(dest))
+            {
+                Console.Error.WriteLine("Usage: CodeWalkerBridge --rpf-extract <archive.rpf> --entry <path> --dest <dir> [--gta <folder>]");
+                return 2;
+            }
+            return RpfCommands.Extract(extractPath!, entry, dest, gtaDir);
+        }
+        catch (Exception ex)
+        {
+            Console.Error.WriteLine(ex.ToString());
+            return 1;
+        }
+    }
+
+    private static string VersionOf(Type type)
+    {
+        var assembly = type.Assembly;
+        var informational = assembly
+            .GetCustomAttributes(typeof(System.Reflection.AssemblyInformationalVersionAttribute), false)
+            .OfType<System.Reflection.AssemblyInformationalVersionAttribute>()
+            .FirstOrDefault()?.InformationalVersion;
+        return informational ?? assembly.GetName().Version?.ToString() ?? "unknown";
+    }
+
     private static string? GetArg(string[] args, string name)
     {
         for (var i = 0; i < args.Length - 1; i += 1)
@@ -68,6 +230,11 @@ public static class Program
 
     private static void LoadJenkIndexStrings()
     {
+        // A server process handles many requests; the index only needs
+        // building once.
+        if (_jenkIndexLoaded) return;
+        _jenkIndexLoaded = true;
+
         var baseDir = AppContext.BaseDirectory;
         var stringsPath = Path.Combine(baseDir, "strings.txt");
         if (!File.Exists(stringsPath)) return;
@@ -80,11 +247,15 @@ public static class Program
         }
     }
 
-    private static List<MeshData> ExtractMeshes(YftFile yft, string baseName)
+    private static bool HasFlag(string[] args, string name)
+    {
+        return args.Any(arg => string.Equals(arg, name, StringComparison.OrdinalIgnoreCase));
+    }
+
+    private static List<DrawableBase> GetFragmentDrawables(YftFile yft)
     {
-        var meshes = new List<MeshData>();
         var fragment = yft.Fragment;
-        if (fragment == null) return meshes;
+        if (fragment == null) return new List<DrawableBase>();
 
         var drawables = new List<DrawableBase>();
         if (fragment.Drawable != null) drawables.Add(fragment.Drawable);
@@ -96,15 +267,110 @@ public static class Program
             }
         }
 
+        return drawables;
+    }
+
+    // The deformed drawables the game swaps in for broken parts (each physics
+    // child's second drawable).
+    private static List<DrawableBase> GetDamagedDrawables(YftFile yft)
+    {
+        var children = yft.Fragment?.PhysicsLODGroup?.PhysicsLOD1?.Children?.data_items;
+        if (children == null) return new List<DrawableBase>();
+        return children
+            .Where(child => child?.Drawable2 != null)
+            .Select(child => (DrawableBase)child.Drawable2)
+            .ToList();
+    }
+
+    // Writes model_damaged.clmesh into outputDir and returns its counts, or
+    // null when the fragment has no damaged geometry.
+    private static object? ExtractDamaged(List<DrawableBase> drawables, string baseName, string outputDir)
+    {
+        var meshes = ExtractDrawableMeshes(drawables, $"{baseName}_damaged");
+        if (meshes.Count == 0) return null;
+
+        const string file = "model_damaged.clmesh";
+        WriteClmesh(Path.Combine(outputDir, file), meshes);
+        return new
+        {
+            file,
+            drawableCount = drawables.Count,
+            meshCount = meshes.Count,
+            vertexCount = meshes.Sum(m => m.VertexCount)
+        };
+    }
+
+    // Per-level counts for every LOD that has geometry. With an output
+    // directory, each level is also written as model_<level>.clmesh there.
+    private static List<object> ExtractLods(List<DrawableBase> drawables, string baseName, string? outputDir)
+    {
+        var lods = new List<object>();
+        foreach (var (level, models) in LodLevels)
+        {
+            var meshes = ExtractDrawableMeshes(drawables, baseName, models);
+            if (meshes.Count == 0) continue;
+
+            string? file = null;
+            if (outputDir != null)
+            {
+                file = $"model_{level}.clmesh";
+                WriteClmesh(Path.Combine(outputDir, file), meshes);
+            }
+
+            lods.Add(new
+            {
+                level,
+                meshCount = meshes.Count,
+                vertexCount = meshes.Sum(m => m.VertexCount),
+                polyCount = meshes.Sum(m => m.IndexCount) / 3,
+                file
+            });
+        }
+        return lods;
+    }
+
+    // Drawables of a .ydd in file order, named from the string index when the
+    // hash is known and as hex otherwise.
+    private static List<NamedDrawable> GetDictionaryDrawables(YddFile ydd)
+    {
+        var result = new List<NamedDrawable>();
+        var drawables = ydd.DrawableDict?.Drawables?.data_items;
+        var hashes = ydd.DrawableDict?.Hashes;
+        if (drawables == null) return result;
+
+        for (var i = 0; i < drawables.Length; i += 1)
+        {
+            var drawable = drawables[i];
+            if (drawable == null) continue;
+            var hash = hashes != null && i < hashes.Length ? hashes[i] : 0u;
+            var name = JenkIndex.TryGetString(hash);
+            if (string.IsNullOrEmpty(name)) name = $"0x{hash:X8}";
+            result.Add(new NamedDrawable(name, hash, drawable));
+        }
+        return result;
+    }
+
+    // Meshes of every model in the drawables, or only the models picked by
+    // `selectModels` (one LOD level).
+    private static List<MeshData> ExtractDrawableMeshes(IEnumerable<DrawableBase> drawables, string baseName, Func<DrawableBase, DrawableModel[]?>? selectModels = null)
+    {
+        var meshes = new List<MeshData>();
         var drawableIndex = 0;
         foreach (var drawable in drawables)
         {
-            if (drawable.AllModels == null || drawable.AllModels.Length == 0)
+            DrawableModel[] models;
+            if (selectModels != null)
             {
-                drawable.BuildAllModels();
+                models = selectModels(drawable) ?? Array.Empty<DrawableModel>();
+            }
+            else
+            {
+                if (drawable.AllModels == null || drawable.AllModels.Length == 0)
+                {
+                    drawable.BuildAllModels();
+                }
+                models = drawable.AllModels ?? Array.Empty<DrawableModel>();
             }
-
-            var models = drawable.AllModels ?? Array.Empty<DrawableModel>();
             for (var modelIndex = 0; modelIndex < models.Length; modelIndex += 1)
             {
                 var model = models[modelIndex];
@@ -129,6 +395,100 @@ public static class Program
         return meshes;
     }
 
+    // Bone hierarchy with bind transforms of the first drawable that has a
+    // skeleton. Per-vertex bone indices and weights go to a sidecar JSON file,
+    // keyed by mesh name, since the clmesh format has no room for them.
+    private static object? ExtractSkeleton(List<DrawableBase> drawables, List<MeshData> meshes, string sidecarPath)
+    {
+        var skeleton = drawables.Select(d => d.Skeleton).FirstOrDefault(s => s != null);
+        var bones = skeleton?.Bones?.Items;
+        if (bones == null || bones.Length == 0) return null;
+
+        var skin = meshes
+            .Where(m => m.BoneIndices != null && m.BoneWeights != null)
+            .Select(m => new { name = m.Name, boneIndices = m.BoneIndices, boneWeights = m.BoneWeights })
+            .ToList();
+        File.WriteAllText(sidecarPath, JsonSerializer.Serialize(new { meshes = skin }));
+
+        return new
+        {
+            bones = bones.Select((bone, index) => new
+            {
+                index,
+                name = bone.Name ?? string.Empty,
+                parentIndex = (int)bone.ParentIndex,
+                translation = new[] { bone.Translation.X, bone.Translation.Y, bone.Translation.Z },
+                rotation = new[] { bone.Rotation.X, bone.Rotation.Y, bone.Rotation.Z, bone.Rotation.W },
+                tag = (int)bone.Tag
+            }).ToList(),
+            skinnedMeshCount = skin.Count,
+            sidecar = Path.GetFileName(sidecarPath)
+        };
+    }
+
+    // Writes collision.clmesh (bound geometry triangles, one mesh per child and
+    // material) and collision.json (every primitive with its material, plus
+    // counts) into outputDir. Returns the counts, or null without bounds.
+    private static object? ExtractCollision(Bounds? root, string baseName, string outputDir)
+    {
+        if (root == null) return null;
+
+        var collector = new CollisionCollector(baseName);
+        if (root is BoundComposite composite && composite.Children?.data_items != null)
+        {
+            var children = composite.Children.data_items;
+            var transforms = composite.ChildrenTransformation1;
+            for (var i = 0; i < children.Length; i += 1)
+            {
+                if (children[i] == null) continue;
+                var transform = transforms != null && i < transforms.Length ? transforms[i] : Matrix.Identity;
+                collector.Add(children[i], i, transform);
+            }
+        }
+        else
+        {
+            collector.Add(root, 0, Matrix.Identity);
+        }
+
+        WriteClmesh(Path.Combine(outputDir, "collision.clmesh"), collector.Meshes);
+        var counts = collector.Counts();
+        File.WriteAllText(Path.Combine(outputDir, "collision.json"), JsonSerializer.Serialize(new
+        {
+            counts,
+            materials = collector.Materials.OrderBy(n => n).ToList(),
+            primitives = collector.Primitives
+        }));
+        return counts;
+    }
+
+    // Four bone indices (into the skeleton, via the geometry's bone id
+    // table) and weights per vertex, or nulls if the geometry isn't skinned.
+    private static (int[]?, float[]?) ReadSkin(DrawableGeometry geom, VertexData data, VertexDeclaration info, int vertexCount)
+    {
+        if (!HasComponent(info, VertexSemantics.BlendWeights) || !HasComponent(info, VertexSemantics.BlendIndices))
+        {
+            return (null, null);
+        }
+
+        var boneIds = geom.BoneIds;
+        var indices = new int[vertexCount * 4];
+        var weights = new float[vertexCount * 4];
+        for (var v = 0; v < vertexCount; v += 1)
+        {
+            var blendIndices = data.GetColour(v, (int)VertexSemantics.BlendIndices);
+            var blendWeights = data.GetColour(v, (int)VertexSemantics.BlendWeights);
+            var localIndices = new[] { blendIndices.R, blendIndices.G, blendIndices.B, blendIndices.A };
+            var localWeights = new[] { blendWeights.R, blendWeights.G, blendWeights.B, blendWeights.A };
+            for (var k = 0; k < 4; k += 1)
+            {
+                var local = localIndices[k];
+                indices[v * 4 + k] = boneIds != null && local < boneIds.Length ? boneIds[local] : local;
+                weights[v * 4 + k] = localWeights[k] / 255f;
+            }
+        }
+        return (indices, weights);
+    }
+
     private static MeshData? ExtractMesh(DrawableGeometry geom, string baseName, int drawableIndex, int modelIndex, int geomIndex)
     {
         var vertexData = geom.VertexData;
@@ -180,7 +540,69 @@ public static class Program
         }
 
         var meshName = $"{baseName}_d{drawableIndex}_m{modelIndex}_g{geomIndex}";
-        return new MeshData(meshName, materialName, positions, normals, uvs, indices);
+        var (boneIndices, boneWeights) = ReadSkin(geom, vertexData, info, vertexCount);
+        return new MeshData(meshName, materialName, positions, normals, uvs, indices, GetTextureNames(geom))
+        {
+            BoneIndices = boneIndices,
+            BoneWeights = boneWeights
+        };
+    }
+
+    private static List<string> GetTextureNames(DrawableGeometry geom)
+    {
+        var names = new List<string>();
+        var parameters = geom.Shader?.ParametersList?.Parameters;
+        if (parameters == null) return names;
+        foreach (var parameter in parameters)
+        {
+            if (parameter?.Data is not TextureBase texture) continue;
+            var name = texture.Name;
+            if (!string.IsNullOrEmpty(name)) names.Add(name);
+        }
+        return names;
+    }
+
+    // Looks up every texture the materials reference in the given dictionary,
+    // so the UI knows which names it can bind and which are missing.
+    private static object ResolveTextures(List<MeshData> meshes, string ytdPath)
+    {
+        var ytd = new YtdFile();
+        ytd.Load(File.ReadAllBytes(ytdPath));
+        var available = new HashSet<string>(StringComparer.OrdinalIgnoreCase);
+        foreach (var texture in ytd.TextureDict?.Textures?.data_items ?? Array.Empty<Texture>())
+        {
+            if (!string.IsNullOrEmpty(texture?.Name)) available.Add(texture.Name);
+        }
+
+        var materials = new Dictionary<string, List<string>>();
+        var missing = new HashSet<string>(StringComparer.OrdinalIgnoreCase);
+        foreach (var mesh in meshes)
+        {
+            if (!materials.TryGetValue(mesh.MaterialName, out var resolved))
+            {
+                resolved = new List<string>();
+                materials[mesh.MaterialName] = resolved;
+            }
+            foreach (var name in mesh.TextureNames)
+            {
+                if (available.Contains(name))
+                {
+                    if (!resolved.Contains(name)) resolved.Add(name);
+                }
+                else
+                {
+                    missing.Add(name);
+                }
+            }
+        }
+
+        return new
+        {
+            ytdPath,
+            textureCount = available.Count,
+            materials,
+            missing = missing.OrderBy(n => n).ToList()
+        };
     }
 
     private static bool HasComponent(VertexDeclaration info, VertexSemantics semantic)
@@ -361,9 +783,161 @@ public static class Program
     }
 }
 
+// Flattens a bound tree into triangle meshes and a primitive list, with
+// child transforms applied so everything is in fragment space.
+public sealed class CollisionCollector
+{
+    private readonly string _baseName;
+    private readonly Dictionary<string, int> _counts = new()
+    {
+        ["box"] = 0,
+        ["sphere"] = 0,
+        ["capsule"] = 0,
+        ["cylinder"] = 0,
+        ["triangle"] = 0
+    };
+
+    public CollisionCollector(string baseName)
+    {
+        _baseName = baseName;
+    }
+
+    public List<MeshData> Meshes { get; } = new();
+    public List<object> Primitives { get; } = new();
+    public HashSet<string> Materials { get; } = new();
+
+    public Dictionary<string, int> Counts() => new(_counts);
+
+    public void Add(Bounds bound, int child, Matrix transform)
+    {
+        switch (bound)
+        {
+            case BoundGeometry geometry:
+                AddGeometry(geometry, child, transform);
+                break;
+            case BoundBox:
+                AddPrimitive("box", child, MaterialName(bound.MaterialIndex, null), new
+                {
+                    min = ToArray(Vector3.TransformCoordinate(bound.BoxMin, transform)),
+                    max = ToArray(Vector3.TransformCoordinate(bound.BoxMax, transform))
+                });
+                break;
+            case BoundSphere:
+                AddPrimitive("sphere", child, MaterialName(bound.MaterialIndex, null), new
+                {
+                    center = ToArray(Vector3.TransformCoordinate(bound.SphereCenter, transform)),
+                    radius = bound.SphereRadius
+                });
+                break;
+            case BoundCapsule:
+            case BoundCylinder:
+                AddPrimitive(bound is BoundCapsule ? "capsule" : "cylinder", child, MaterialName(bound.MaterialIndex, null), new
+                {
+                    min = ToArray(Vector3.TransformCoordinate(bound.BoxMin, transform)),
+                    max = ToArray(Vector3.TransformCoordinate(bound.BoxMax, transform)),
+                    radius = bound.SphereRadius
+                });
+                break;
+        }
+    }
+
+    private void AddGeometry(BoundGeometry geometry, int child, Matrix transform)
+    {
+        var polygons = geometry.Polygons;
+        if (polygons == null) return;
+
+        Vector3 Vertex(int index) => Vector3.TransformCoordinate(geometry.GetVertexPos(index), transform);
+
+        var triangles = new Dictionary<string, List<Vector3>>();
+        foreach (var polygon in polygons)
+        {
+            var material = MaterialName(polygon.MaterialIndex, geometry);
+            switch (polygon)
+            {
+                case BoundPolygonTriangle tri:
+                    if (!triangles.TryGetValue(material, out var corners))
+                    {
+                        corners = new List<Vector3>();
+                        triangles[material] = corners;
+                    }
+                    corners.Add(Vertex(tri.vertIndex1));
+                    corners.Add(Vertex(tri.vertIndex2));
+                    corners.Add(Vertex(tri.vertIndex3));
+                    _counts["triangle"] += 1;
+                    Materials.Add(material);
+                    break;
+                case BoundPolygonSphere sphere:
+                    AddPrimitive("sphere", child, material, new
+                    {
+                        center = ToArray(Vertex(sphere.sphereIndex)),
+                        radius = sphere.sphereRadius
+                    });
+                    break;
+                case BoundPolygonCapsule capsule:
+                    AddPrimitive("capsule", child, material, new
+                    {
+                        start = ToArray(Vertex(capsule.capsuleIndex1)),
+                        end = ToArray(Vertex(capsule.capsuleIndex2)),
+                        radius = capsule.capsuleRadius
+                    });
+                    break;
+                case BoundPolygonCylinder cylinder:
+                    AddPrimitive("cylinder", child, material, new
+                    {
+                        start = ToArray(Vertex(cylinder.cylinderIndex1)),
+                        end = ToArray(Vertex(cylinder.cylinderIndex2)),
+                        radius = cylinder.cylinderRadius
+                    });
+                    break;
+                case BoundPolygonBox box:
+                    AddPrimitive("box", child, material, new
+                    {
+                        corners = new[] { box.boxIndex1, box.boxIndex2, box.boxIndex3, box.boxIndex4 }
+                            .Select(index => ToArray(Vertex(index)))
+                            .ToList()
+                    });
+                    break;
+            }
+        }
+
+        foreach (var (material, corners) in triangles)
+        {
+            var positions = new float[corners.Count * 3];
+            var indices = new uint[corners.Count];
+            for (var i = 0; i < corners.Count; i += 1)
+            {
+                positions[i * 3] = corners[i].X;
+                positions[i * 3 + 1] = corners[i].Y;
+                positions[i * 3 + 2] = corners[i].Z;
+                indices[i] = (uint)i;
+            }
+            Meshes.Add(new MeshData($"{_baseName}_col{child}_{Meshes.Count}", material, positions, null, null, indices, new List<string>()));
+        }
+    }
+
+    private void AddPrimitive(string type, int child, string material, object shape)
+    {
+        _counts[type] += 1;
+        Materials.Add(material);
+        Primitives.Add(new { type, child, material, shape });
+    }
+
+    private static string MaterialName(int index, BoundGeometry? geometry)
+    {
+        var materials = geometry?.Materials;
+        if (materials != null && index >= 0 && index < materials.Length)
+        {
+            return materials[index].Type.ToString();
+        }
+        return new BoundsMaterialType { Index = (byte)index }.ToString();
+    }
+
+    private static float[] ToArray(Vector3 value) => new[] { value.X, value.Y, value.Z };
+}
+
 public sealed class MeshData
 {
-    public MeshData(string name, string materialName, float[] positions, float[]? normals, float[]? uvs, uint[] indices)
+    public MeshData(string name, string materialName, float[] positions, float[]? normals, float[]? uvs, uint[] indices, List<string> textureNames)
     {
         Name = name;
         MaterialName = materialName;
@@ -371,6 +945,7 @@ public sealed class MeshData
         Normals = normals;
         Uvs = uvs;
         Indices = indices;
+        TextureNames = textureNames;
     }
 
     public string Name { get; }
@@ -379,7 +954,32 @@ public sealed class MeshData
     public float[]? Normals { get; }
     public float[]? Uvs { get; }
     public uint[] Indices { get; }
+    public List<string> TextureNames { get; }
+    public int[]? BoneIndices { get; init; }
+    public float[]? BoneWeights { get; init; }
 
     public int VertexCount => Positions.Length / 3;
     public int IndexCount => Indices.Length;
 }
+
+public sealed class NamedDrawable
+{
+    public NamedDrawable(string name, uint hash, DrawableBase drawable)
+    {
+        Name = name;
+        Hash = hash;
+        Drawable = drawable;
+    }
+
+    public string Name { get; }
+    public uint Hash { get; }
+    public DrawableBase Drawable { get; }
+
+    // Accepts the resolved name, its hex form, or any string that hashes to it.
+    public bool Matches(string query)
+    {
+        if (string.Equals(Name, query, StringComparison.OrdinalIgnoreCase)) return true;
+        if (string.Equals($"0x{Hash:X8}", query, StringComparison.OrdinalIgnoreCase)) return true;
+        return JenkHash.GenHash(query.ToLowerInvariant()) == Hash;
+    }
+}
@@ -0,0 +1,169 @@
+using System.Text.Json;
+using CodeWalker.GameFiles;
+
+namespace CodeWalkerBridge;
+
+// `--rpf-list` and `--rpf-extract`: archive browsing for files that haven't
+// been exported with OpenIV. Nested archives are walked in place.
+public static class RpfCommands
+{
+    // Exit code for archives that can't be opened without the game's keys.
+    public const int KeysRequired = 4;
+
+    public static int List(string archivePath, string? gtaDir)
+    {
+        var rpf = Open(archivePath, gtaDir, out var exitCode);
+        if (rpf == null) return exitCode;
+
+        Console.WriteLine(JsonSerializer.Serialize(new
+        {
+            name = rpf.Name,
+            encryption = rpf.Encryption.ToString(),
+            root = DescribeDirectory(rpf, rpf.Root)
+        }));
+        return 0;
+    }
+
+    public static int Extract(string archivePath, string entryPath, string destDir, string? gtaDir)
+    {
+        var rpf = Open(archivePath, gtaDir, out var exitCode);
+        if (rpf == null) return exitCode;
+
+        var wanted = NormalizeEntryPath(entryPath);
+        var entry = AllFiles(rpf).FirstOrDefault(e => string.Equals(RelativePath(rpf, e), wanted, StringComparison.OrdinalIgnoreCase));
+        if (entry == null)
+        {
+            Console.Error.WriteLine($"Entry '{entryPath}' was not found in {rpf.Name}.");
+            return 3;
+        }
+
+        // Resources come back with their RSC7 header, the same bytes a loose
+        // file exported with OpenIV has.
+        var data = entry.File.ExtractFile(entry);
+        if (data == null)
+        {
+            Console.Error.WriteLine($"Entry '{entryPath}' could not be extracted.");
+            return 1;
+        }
+
+        Directory.CreateDirectory(destDir);
+        var outputPath = Path.Combine(destDir, entry.Name);
+        File.WriteAllBytes(outputPath, data);
+        Console.WriteLine(JsonSerializer.Serialize(new { path = outputPath, size = data.Length }));
+        return 0;
+    }
+
+    private static RpfFile? Open(string archivePath, string? gtaDir, out int exitCode)
+    {
+        exitCode = 0;
+        if (!string.IsNullOrWhiteSpace(gtaDir))
+        {
+            GTA5Keys.LoadFromPath(gtaDir);
+        }
+
+        var rpf = new RpfFile(archivePath, Path.GetFileName(archivePath));
+        var errors = new List<string>();
+        try
+        {
+            rpf.ScanStructure(_ => { }, errors.Add);
+        }
+        catch (Exception) when (NeedsKeys(rpf))
+        {
+            errors.Add("encrypted");
+        }
+
+        if (NeedsKeys(rpf))
+        {
+            Console.Error.WriteLine("This archive is encrypted with the game's keys. Point the app at your GTA V install folder so they can be loaded.");
+            exitCode = KeysRequired;
+            return null;
+        }
+        if (rpf.Root == null)
+        {
+            Console.Error.WriteLine($"Failed to read {Path.GetFileName(archivePath)}: {string.Join("; ", errors)}");
+            exitCode = 1;
+            return null;
+        }
+        return rpf;
+    }
+
+    private static bool NeedsKeys(RpfFile rpf)
+    {
+        return rpf.Encryption switch
+        {
+            RpfEncryption.AES => GTA5Keys.PC_AES_KEY == null,
+            RpfEncryption.NG => GTA5Keys.PC_NG_KEYS == null,
+            _ => false
+        };
+    }
+
+    private static object DescribeDirectory(RpfFile rpf, RpfDirectoryEntry directory)
+    {
+        var children = new List<object>();
+        foreach (var child in directory.Directories.OrderBy(d => d.Name, StringComparer.OrdinalIgnoreCase))
+        {
+            children.Add(DescribeDirectory(rpf, child));
+        }
+        foreach (var file in directory.Files.OrderBy(f => f.Name, StringComparer.OrdinalIgnoreCase))
+        {
+            var nested = rpf.Children?.FirstOrDefault(c => c.ParentFileEntry == file);
+            if (nested?.Root != null)
+            {
+                var archive = (Dictionary<string, object?>)DescribeDirectory(nested, nested.Root);
+                archive["name"] = file.Name;
+                archive["type"] = "archive";
+                archive["size"] = (long)file.GetFileSize();
+                children.Add(archive);
+                continue;
+            }
+
+            children.Add(new Dictionary<string, object?>
+            {
+                ["name"] = file.Name,
+                ["path"] = RelativePath(rpf, file),
+                ["type"] = file is RpfResourceFileEntry ? "resource" : "file",
+                ["extension"] = Path.GetExtension(file.Name).TrimStart('.').ToLowerInvariant(),
+                ["size"] = (long)file.GetFileSize()
+            });
+        }
+
+        return new Dictionary<string, object?>
+        {
+            ["name"] = directory.Name,
+            ["path"] = RelativePath(rpf, directory),
+            ["type"] = "directory",
+            ["children"] = children
+        };
+    }
+
+    private static IEnumerable<RpfFileEntry> AllFiles(RpfFile rpf)
+    {
+        foreach (var entry in rpf.AllEntries.OfType<RpfFileEntry>())
+        {
+            yield return entry;
+        }
+        foreach (var child in rpf.Children ?? new List<RpfFile>())
+        {
+            foreach (var entry in AllFiles(child))
+            {
+                yield return entry;
+            }
+        }
+    }
+
+    // Entry paths inside the root archive, e.g. `x64\vehicles.rpf\adder.yft`.
+    // CodeWalker's own paths start with the root archive's name.
+    private static string RelativePath(RpfFile rpf, RpfEntry entry)
+    {
+        var root = rpf;
+        while (root.Parent != null) root = root.Parent;
+        var path = entry.Path ?? string.Empty;
+        var prefix = root.Name + "\\";
+        return NormalizeEntryPath(path.StartsWith(prefix, StringComparison.OrdinalIgnoreCase) ? path[prefix.Length..] : path);
+    }
+
+    private static string NormalizeEntryPath(string path)
+    {
+        return path.Replace('/', '\\').Trim('\\');
+    }
+}
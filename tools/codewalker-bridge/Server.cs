@@ -0,0 +1,74 @@
+using System.Text;
+using System.Text.Json;
+
+namespace CodeWalkerBridge;
+
+// `--server`: newline-delimited JSON requests on stdin, one response line per
+// request on stdout. Each request's own output is captured so nothing but
+// responses ever reaches the real stdout.
+public static class BridgeServer
+{
+    public static int Serve(Func<string[], int> run)
+    {
+        var output = Console.Out;
+        var errors = Console.Error;
+        // Requests are UTF-8 JSON; Console.In would decode them with the
+        // OEM code page and garble non-ASCII paths.
+        using var input = new StreamReader(Console.OpenStandardInput(), new UTF8Encoding(false));
+
+        string? line;
+        while ((line = input.ReadLine()) != null)
+        {
+            if (string.IsNullOrWhiteSpace(line)) continue;
+
+            long? id = null;
+            object response;
+            try
+            {
+                using var request = JsonDocument.Parse(line);
+                var root = request.RootElement;
+                if (root.TryGetProperty("id", out var idElement) && idElement.TryGetInt64(out var parsedId))
+                {
+                    id = parsedId;
+                }
+                var op = root.TryGetProperty("op", out var opElement) ? opElement.GetString() : null;
+
+                if (op == "shutdown") break;
+                if (op != "parse")
+                {
+                    response = new { id, exitCode = 2, stdout = "", stderr = $"Unknown op '{op}'." };
+                }
+                else
+                {
+                    var args = root.TryGetProperty("args", out var argsElement)
+                        ? argsElement.EnumerateArray().Select(arg => arg.GetString() ?? string.Empty).ToArray()
+                        : Array.Empty<string>();
+                    response = Handle(run, args, id);
+                }
+            }
+            catch (Exception ex)
+            {
+                response = new { id, exitCode = 1, stdout = "", stderr = ex.ToString() };
+            }
+            finally
+            {
+                Console.SetOut(output);
+                Console.SetError(errors);
+            }
+
+            output.WriteLine(JsonSerializer.Serialize(response));
+            output.Flush();
+        }
+        return 0;
+    }
+
+    private static object Handle(Func<string[], int> run, string[] args, long? id)
+    {
+        var stdout = new StringWriter();
+        var stderr = new StringWriter();
+        Console.SetOut(stdout);
+        Console.SetError(stderr);
+        var exitCode = run(args);
+        return new { id, exitCode, stdout = stdout.ToString(), stderr = stderr.ToString() };
+    }
+}
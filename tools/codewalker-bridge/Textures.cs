@@ -0,0 +1,145 @@
+using System.IO.Compression;
+using System.Text.Json;
+using CodeWalker.GameFiles;
+using CodeWalker.Utils;
+
+namespace CodeWalkerBridge;
+
+// `--extract-ytd`: one file per texture of a dictionary, as the DDS stored in
+// the game files or decoded to PNG for viewers that can't read DDS.
+public static class TextureExtractor
+{
+    public static int Extract(string ytdPath, string destDir, string format)
+    {
+        format = format.ToLowerInvariant();
+        if (format != "dds" && format != "png")
+        {
+            Console.Error.WriteLine($"Unsupported --format '{format}' (expected dds or png).");
+            return 2;
+        }
+
+        var ytd = new YtdFile();
+        ytd.Load(File.ReadAllBytes(ytdPath));
+        var textures = ytd.TextureDict?.Textures?.data_items;
+        if (textures == null || textures.Length == 0)
+        {
+            Console.Error.WriteLine("The YTD contains no textures.");
+            return 3;
+        }
+
+        Directory.CreateDirectory(destDir);
+        var results = new List<object>();
+        foreach (var texture in textures)
+        {
+            if (texture == null || string.IsNullOrEmpty(texture.Name)) continue;
+
+            var fileName = $"{SafeFileName(texture.Name)}.{format}";
+            var path = Path.Combine(destDir, fileName);
+            if (format == "dds")
+            {
+                File.WriteAllBytes(path, DDSIO.GetDDSFile(texture));
+            }
+            else
+            {
+                WritePng(path, texture.Width, texture.Height, DDSIO.GetPixels(texture, 0));
+            }
+
+            results.Add(new
+            {
+                name = texture.Name,
+                width = (int)texture.Width,
+                height = (int)texture.Height,
+                format,
+                sourceFormat = texture.Format.ToString(),
+                path
+            });
+        }
+
+        Console.WriteLine(JsonSerializer.Serialize(results));
+        return 0;
+    }
+
+    private static string SafeFileName(string name)
+    {
+        var invalid = Path.GetInvalidFileNameChars();
+        return new string(name.Select(c => invalid.Contains(c) ? '_' : c).ToArray());
+    }
+
+    // Minimal RGBA PNG writer for the BGRA pixels CodeWalker decodes to.
+    private static void WritePng(string path, int width, int height, byte[] bgra)
+    {
+        var raw = new byte[height * (width * 4 + 1)];
+        for (var y = 0; y < height; y += 1)
+        {
+            var row = y * (width * 4 + 1);
+            raw[row] = 0; // no filter
+            for (var x = 0; x < width; x += 1)
+            {
+                var src = (y * width + x) * 4;
+                var dst = row + 1 + x * 4;
+                raw[dst] = bgra[src + 2];
+                raw[dst + 1] = bgra[src + 1];
+                raw[dst + 2] = bgra[src];
+                raw[dst + 3] = bgra[src + 3];
+            }
+        }
+
+        using var compressed = new MemoryStream();
+        using (var zlib = new ZLibStream(compressed, CompressionLevel.Fastest, true))
+        {
+            zlib.Write(raw, 0, raw.Length);
+        }
+
+        using var stream = new FileStream(path, FileMode.Create, FileAccess.Write, FileShare.None);
+        stream.Write(new byte[] { 0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A });
+
+        var header = new byte[13];
+        WriteBigEndian(header, 0, (uint)width);
+        WriteBigEndian(header, 4, (uint)height);
+        header[8] = 8; // bit depth
+        header[9] = 6; // RGBA
+        WriteChunk(stream, "IHDR", header);
+        WriteChunk(stream, "IDAT", compressed.ToArray());
+        WriteChunk(stream, "IEND", Array.Empty<byte>());
+    }
+
+    private static void WriteChunk(Stream stream, string type, byte[] data)
+    {
+        var length = new byte[4];
+        WriteBigEndian(length, 0, (uint)data.Length);
+        stream.Write(length);
+
+        var typeBytes = System.Text.Encoding.ASCII.GetBytes(type);
+        stream.Write(typeBytes);
+        stream.Write(data);
+
+        var crc = new byte[4];
+        WriteBigEndian(crc, 0, Crc32(typeBytes, data));
+        stream.Write(crc);
+    }
+
+    private static void WriteBigEndian(byte[] buffer, int offset, uint value)
+    {
+        buffer[offset] = (byte)(value >> 24);
+        buffer[offset + 1] = (byte)(value >> 16);
+        buffer[offset + 2] = (byte)(value >> 8);
+        buffer[offset + 3] = (byte)value;
+    }
+
+    private static uint Crc32(byte[] first, byte[] second)
+    {
+        var crc = 0xFFFFFFFFu;
+        foreach (var bytes in new[] { first, second })
+        {
+            foreach (var b in bytes)
+            {
+                crc ^= b;
+                for (var k = 0; k < 8; k += 1)
+                {
+                    crc = (crc & 1) != 0 ? (crc >> 1) ^ 0xEDB88320u : crc >> 1;
+                }
+            }
+        }
+        return crc ^ 0xFFFFFFFFu;
+    }
+}